@@ -0,0 +1,78 @@
+//! A grid of points drifting diagonally behind the level select screen, like the grid
+//! of the board but never still. With low graphics it's drawn but doesn't move.
+
+use crate::{
+    color, layer, level_select::LevelSelectScreen, settings::GraphicsQuality, GameState,
+    MainCamera, GRID_SIZE,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use itertools::Itertools;
+
+/// How far the grid drifts each second, in board units along each axis.
+const BACKDROP_SPEED: f32 = 6.0;
+/// How many grid cells the backdrop reaches out from the camera, enough to fill wide
+/// windows with some to spare.
+const BACKDROP_CELLS: IVec2 = IVec2::new(30, 20);
+
+pub struct BackdropPlugin;
+impl Plugin for BackdropPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::LevelSelect), spawn_backdrop_system);
+        app.add_systems(
+            Update,
+            scroll_backdrop_system.run_if(in_state(GameState::LevelSelect)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct Backdrop;
+
+fn spawn_backdrop_system(mut commands: Commands) {
+    let points = (-BACKDROP_CELLS.x..=BACKDROP_CELLS.x)
+        .cartesian_product(-BACKDROP_CELLS.y..=BACKDROP_CELLS.y)
+        .fold(GeometryBuilder::new(), |builder, (x, y)| {
+            builder.add(&shapes::Circle {
+                radius: 2.5,
+                center: Vec2::new(x as f32, y as f32) * GRID_SIZE,
+            })
+        });
+
+    commands.spawn((
+        ShapeBundle {
+            path: points.build(),
+            transform: Transform::from_xyz(0.0, 0.0, layer::GRID),
+            ..default()
+        },
+        Fill::color(color::GRID),
+        Backdrop,
+        LevelSelectScreen,
+    ));
+}
+
+fn scroll_backdrop_system(
+    time: Res<Time>,
+    quality: Res<GraphicsQuality>,
+    q_camera: Query<&Transform, (With<MainCamera>, Without<Backdrop>)>,
+    mut q_backdrop: Query<&mut Transform, With<Backdrop>>,
+) {
+    let Ok(camera) = q_camera.get_single() else {
+        return;
+    };
+
+    let drift = match *quality {
+        GraphicsQuality::High => (time.elapsed_secs() * BACKDROP_SPEED).rem_euclid(GRID_SIZE),
+        GraphicsQuality::Low => 0.0,
+    };
+
+    // Keeping to whole cells around the camera means the grid wraps around seamlessly.
+    let center = (camera.translation.truncate() / GRID_SIZE).round() * GRID_SIZE;
+    let translation = (center + Vec2::new(drift, -drift)).extend(layer::GRID);
+
+    for mut transform in q_backdrop.iter_mut() {
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
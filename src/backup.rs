@@ -0,0 +1,238 @@
+//! Moving progress between installs. The whole save file can be exported to
+//! `pixie_wrangler.save.ron` and imported again somewhere else, either merged with the
+//! progress there or replacing it.
+
+use crate::{
+    color,
+    level::Level,
+    restart::Restart,
+    save::{SaveFile, SaveResources},
+    GameState, Handles,
+};
+use bevy::{
+    prelude::*,
+    reflect::{
+        serde::{TypedReflectDeserializer, TypedReflectSerializer},
+        TypeRegistry,
+    },
+};
+use serde::de::DeserializeSeed;
+
+const BACKUP_PATH: &str = "pixie_wrangler.save.ron";
+
+pub struct BackupPlugin;
+impl Plugin for BackupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImportedLabel>();
+        app.add_systems(
+            Update,
+            (backup_button_system, imported_label_system).run_if(in_state(GameState::LevelSelect)),
+        );
+    }
+}
+
+/// The import button that was pressed, which says so once the level select screen has
+/// been built again.
+#[derive(Resource, Default)]
+struct ImportedLabel(Option<BackupButton>);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum BackupButton {
+    Export,
+    /// Imports, keeping the best scores from either save.
+    Merge,
+    /// Imports, throwing away everything saved here.
+    Replace,
+}
+
+impl BackupButton {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Export => "EXPORT SAVE",
+            Self::Merge => "IMPORT AND MERGE",
+            Self::Replace => "IMPORT AND REPLACE",
+        }
+    }
+}
+
+pub fn spawn_backup_buttons(parent: &mut ChildBuilder, font: &TextFont) {
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            column_gap: Val::Px(10.),
+            ..default()
+        })
+        .with_children(|parent| {
+            for button in [
+                BackupButton::Export,
+                BackupButton::Merge,
+                BackupButton::Replace,
+            ] {
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                            ..default()
+                        },
+                        BackgroundColor(color::UI_NORMAL_BUTTON),
+                        button,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(button.label()),
+                            font.clone(),
+                            TextColor(color::UI_BUTTON_TEXT),
+                        ));
+                    });
+            }
+        });
+}
+
+fn backup_button_system(
+    q_interaction: Query<(&Interaction, &BackupButton, &Children), Changed<Interaction>>,
+    mut q_text: Query<&mut Text>,
+    mut save: SaveResources,
+    registry: Res<AppTypeRegistry>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    mut imported_label: ResMut<ImportedLabel>,
+    mut restart: Restart,
+) {
+    for (_, button, children) in q_interaction
+        .iter()
+        .filter(|(i, _, _)| **i == Interaction::Pressed)
+    {
+        let registry = registry.read();
+
+        let result = match button {
+            BackupButton::Export => {
+                to_ron(&save.save_file(), &registry).and_then(|ron| write_backup(&ron))
+            }
+            BackupButton::Merge | BackupButton::Replace => read_backup()
+                .and_then(|ron| from_ron(&ron, &registry))
+                .map(|mut imported| {
+                    let dropped = drop_invalid_solutions(&mut imported, &handles, &levels);
+                    if dropped > 0 {
+                        warn!("Dropped {} invalid solutions from the import", dropped);
+                    }
+
+                    if matches!(button, BackupButton::Merge) {
+                        let mut merged = save.save_file();
+                        merged.merge(imported);
+                        save.set(merged);
+                    } else {
+                        save.set(imported);
+                    }
+
+                    // Start the level select screen over so that it shows the new scores.
+                    restart.set(GameState::LevelSelect);
+                    imported_label.0 = Some(*button);
+                }),
+        };
+
+        let label = match (button, result) {
+            (BackupButton::Export, Ok(())) => "SAVE EXPORTED",
+            (_, Ok(())) => "SAVE IMPORTED",
+            (BackupButton::Export, Err(e)) => {
+                warn!("Failed to export save: {}", e);
+                "EXPORT FAILED"
+            }
+            (_, Err(e)) => {
+                warn!("Failed to import save: {}", e);
+                "IMPORT FAILED"
+            }
+        };
+
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0 = label.to_string();
+        }
+    }
+}
+
+fn imported_label_system(
+    mut imported_label: ResMut<ImportedLabel>,
+    q_buttons: Query<(&BackupButton, &Children), Added<BackupButton>>,
+    mut q_text: Query<&mut Text>,
+) {
+    let Some(pressed) = imported_label.0 else {
+        return;
+    };
+
+    // The old screen's buttons go on the way out, so wait for the new ones.
+    let Some((_, children)) = q_buttons.iter().find(|(b, _)| **b == pressed) else {
+        return;
+    };
+
+    let mut iter = q_text.iter_many_mut(children);
+    while let Some(mut text) = iter.fetch_next() {
+        text.0 = "SAVE IMPORTED".to_string();
+    }
+    imported_label.0 = None;
+}
+
+pub(crate) fn to_ron(save_file: &SaveFile, registry: &TypeRegistry) -> Result<String, String> {
+    let serializer = TypedReflectSerializer::new(save_file, registry);
+    ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) fn from_ron(ron: &str, registry: &TypeRegistry) -> Result<SaveFile, String> {
+    let mut deserializer = ron::de::Deserializer::from_str(ron).map_err(|e| e.to_string())?;
+    let value = TypedReflectDeserializer::of::<SaveFile>(registry)
+        .deserialize(&mut deserializer)
+        .map_err(|e| e.to_string())?;
+
+    SaveFile::from_reflect(&*value).ok_or_else(|| "not a save file".to_string())
+}
+
+/// Removes solutions for known levels with roads that don't fit there, for instance
+/// because the level has changed since. Returns how many were removed.
+fn drop_invalid_solutions(
+    save_file: &mut SaveFile,
+    handles: &Handles,
+    levels: &Assets<Level>,
+) -> usize {
+    let before = save_file.solutions.0.len() + save_file.best_solutions.0.len();
+
+    for number in 1..=handles.levels.len() as u32 {
+        let Some(level) = levels.get(&handles.levels[number as usize - 1]) else {
+            continue;
+        };
+
+        let id = handles.level_id(number);
+        for solutions in [&mut save_file.solutions.0, &mut save_file.best_solutions.0] {
+            if solutions
+                .get(&id)
+                .is_some_and(|s| !s.segments.iter().all(|r| level.fits(r)))
+            {
+                solutions.remove(&id);
+            }
+        }
+    }
+
+    before - save_file.solutions.0.len() - save_file.best_solutions.0.len()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_backup(ron: &str) -> Result<(), String> {
+    std::fs::write(BACKUP_PATH, ron).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_backup() -> Result<String, String> {
+    std::fs::read_to_string(BACKUP_PATH).map_err(|e| e.to_string())
+}
+
+// TODO the web build has no file system. This could go through a download and a file
+// picker instead.
+#[cfg(target_arch = "wasm32")]
+fn write_backup(_ron: &str) -> Result<(), String> {
+    Err("not supported on the web".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_backup() -> Result<String, String> {
+    Err("not supported on the web".to_string())
+}
@@ -0,0 +1,168 @@
+use crate::{
+    level::Level,
+    sandbox::Sandbox,
+    save::LevelMetadata,
+    sim::{SimulationState, SimulationTimeline},
+    update_score_system, AfterUpdate, DrawingState, Handles, RoadSegment, ScoreCalc, SelectedLevel,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+/// Solutions made from fewer segments than this earn the "lean" badge.
+pub const LEAN_SEGMENTS: usize = 10;
+
+pub struct BadgePlugin;
+impl Plugin for BadgePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            AfterUpdate,
+            update_badges_system
+                .after(update_score_system)
+                .in_set(ScoreCalc),
+        );
+    }
+}
+
+/// Optional constraints that a level's solutions have been completed under.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct LevelBadges {
+    /// Only the first layer was used on a level that offers more than one.
+    pub flat: bool,
+    /// Fewer than `LEAN_SEGMENTS` road segments were used.
+    pub lean: bool,
+}
+
+impl LevelBadges {
+    pub fn earned<'a>(level: &Level, segments: impl IntoIterator<Item = &'a RoadSegment>) -> Self {
+        let mut count = 0;
+        let mut flat = true;
+
+        for segment in segments {
+            count += 1;
+            flat &= segment.layer == 1;
+        }
+
+        Self {
+            flat: flat && level.layers > 1,
+            lean: count < LEAN_SEGMENTS,
+        }
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.flat |= other.flat;
+        self.lean |= other.lean;
+    }
+
+    pub fn any(&self) -> bool {
+        self.flat || self.lean
+    }
+
+    pub fn label(&self) -> String {
+        [(self.flat, "FLAT"), (self.lean, "LEAN")]
+            .iter()
+            .filter(|(earned, _)| *earned)
+            .map(|(_, label)| *label)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Whether a finished run counts towards badges.
+#[derive(SystemParam)]
+pub struct BadgeEligibility<'w> {
+    drawing_state: Res<'w, DrawingState>,
+    sandbox: Res<'w, Sandbox>,
+    timeline: Res<'w, SimulationTimeline>,
+}
+
+impl BadgeEligibility<'_> {
+    /// Like best scores, runs where the roads were changed part way through don't count,
+    /// and neither do sandbox runs. Badges also need every pixie delivered.
+    pub fn eligible(&self) -> bool {
+        !self.drawing_state.edited_while_running
+            && !self.sandbox.enabled
+            && self.timeline.explosions.is_empty()
+    }
+
+    /// The badges earned by the finished run, or none if it doesn't count.
+    pub fn earned<'a>(
+        &self,
+        level: &Level,
+        segments: impl IntoIterator<Item = &'a RoadSegment>,
+    ) -> LevelBadges {
+        if !self.eligible() {
+            return LevelBadges::default();
+        }
+
+        LevelBadges::earned(level, segments)
+    }
+}
+
+fn update_badges_system(
+    sim_state: Res<SimulationState>,
+    eligibility: BadgeEligibility,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    mut metadata: ResMut<LevelMetadata>,
+    q_segments: Query<&RoadSegment>,
+) {
+    if !sim_state.is_changed() {
+        return;
+    }
+
+    if *sim_state != SimulationState::Finished {
+        return;
+    }
+
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    let earned = eligibility.earned(level, q_segments.iter());
+    if !earned.any() {
+        return;
+    }
+
+    metadata
+        .0
+        .entry(handles.level_id(selected_level.0))
+        .or_default()
+        .badges
+        .merge(earned);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{level, seg};
+
+    fn segments(n: usize, layer: u32) -> Vec<RoadSegment> {
+        (0..n)
+            .map(|i| seg((i as f32, 0.0), (i as f32 + 1.0, 0.0), layer))
+            .collect()
+    }
+
+    #[test]
+    fn flat_requires_multiple_layers() {
+        let segs = segments(20, 1);
+        assert!(LevelBadges::earned(&level(2), &segs).flat);
+        assert!(!LevelBadges::earned(&level(1), &segs).flat);
+    }
+
+    #[test]
+    fn upper_layer_is_not_flat() {
+        let mut segs = segments(20, 1);
+        segs.extend(segments(1, 2));
+        assert!(!LevelBadges::earned(&level(3), &segs).flat);
+    }
+
+    #[test]
+    fn lean() {
+        assert!(LevelBadges::earned(&level(1), &segments(LEAN_SEGMENTS - 1, 1)).lean);
+        assert!(!LevelBadges::earned(&level(1), &segments(LEAN_SEGMENTS, 1)).lean);
+    }
+}
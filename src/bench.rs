@@ -0,0 +1,211 @@
+//! A development tool for measuring simulation performance on a real level, so that
+//! slowdowns can be reproduced and compared between changes.
+//!
+//! Enabled with the `benchmark` feature, and run with a level and optionally a number
+//! of ticks, like `cargo run --release --features benchmark -- 3 2000`. The level can
+//! be a level number, which runs the solution in the save file, or a share code. Once
+//! the pixies are released, the simulation is stepped as fast as it can go within a
+//! single frame, so that drawing doesn't count towards the timings. A summary is
+//! logged, per-tick timings are written to `benchmark.csv`, and the app exits.
+
+use std::fmt::Write;
+
+use crate::{
+    controls::{Action, ActionPressed},
+    pixie::Pixie,
+    save::Solutions,
+    share::decode,
+    sim::{run_simulation, SimulationSchedule, SimulationState, SimulationSteps},
+    GameState, Handles, PathfindingState, PixieCount, SelectedLevel,
+};
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    utils::{Duration, Instant},
+};
+
+const DEFAULT_TICKS: u32 = 3600;
+/// How many frames to wait for the roads to make a valid solution before giving up.
+const SETTLE_FRAMES: u32 = 30;
+
+const OUTPUT_PATH: &str = "benchmark.csv";
+
+pub struct BenchmarkPlugin;
+impl Plugin for BenchmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Benchmark::from_args(std::env::args().skip(1)));
+
+        app.add_systems(OnEnter(GameState::LevelSelect), bench_setup_system);
+        app.add_systems(
+            Update,
+            (
+                bench_release_system.run_if(in_state(GameState::Playing)),
+                bench_run_system.after(run_simulation),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Benchmark {
+    level: String,
+    ticks: u32,
+    started: bool,
+    frames: u32,
+    released: bool,
+    finished: bool,
+}
+
+impl Benchmark {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        Self {
+            level: args.next().unwrap_or_else(|| "1".to_string()),
+            ticks: args
+                .next()
+                .and_then(|ticks| ticks.parse().ok())
+                .unwrap_or(DEFAULT_TICKS),
+            ..default()
+        }
+    }
+}
+
+struct TickTiming {
+    duration: Duration,
+    pixies: usize,
+}
+
+fn bench_setup_system(
+    mut bench: ResMut<Benchmark>,
+    handles: Res<Handles>,
+    mut solutions: ResMut<Solutions>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if bench.started {
+        return;
+    }
+    bench.started = true;
+
+    let number = if let Ok(number) = bench.level.parse::<u32>() {
+        Some(number).filter(|n| (1..=handles.levels.len() as u32).contains(n))
+    } else {
+        match decode(&bench.level) {
+            Ok((id, solution)) => {
+                let number = (1..=handles.levels.len() as u32).find(|n| handles.level_id(*n) == id);
+                if number.is_some() {
+                    solutions.0.insert(id, solution);
+                }
+                number
+            }
+            Err(e) => {
+                error!("Couldn't read the share code: {}", e.reason());
+                exit.send(AppExit::error());
+                return;
+            }
+        }
+    };
+
+    let Some(number) = number else {
+        error!("No such level: {}", bench.level);
+        exit.send(AppExit::error());
+        return;
+    };
+
+    info!("Benchmarking level {} for {} ticks", number, bench.ticks);
+
+    selected_level.0 = number;
+    next_state.set(GameState::Playing);
+}
+
+fn bench_release_system(
+    mut bench: ResMut<Benchmark>,
+    pathfinding: Res<PathfindingState>,
+    mut actions: EventWriter<ActionPressed>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if bench.released {
+        return;
+    }
+
+    if pathfinding.valid {
+        actions.send(ActionPressed(Action::Release));
+        bench.released = true;
+        return;
+    }
+
+    bench.frames += 1;
+    if bench.frames > SETTLE_FRAMES {
+        error!("The solution for this level doesn't connect every terminus");
+        exit.send(AppExit::error());
+    }
+}
+
+fn bench_run_system(world: &mut World) {
+    let bench = world.resource::<Benchmark>();
+    if !bench.released || bench.finished {
+        return;
+    }
+    let ticks = bench.ticks;
+
+    if *world.resource::<SimulationState>() != SimulationState::Running {
+        return;
+    }
+
+    let mut timings = Vec::with_capacity(ticks as usize);
+    let mut q_pixies = world.query_filtered::<(), With<Pixie>>();
+
+    while timings.len() < ticks as usize
+        && *world.resource::<SimulationState>() == SimulationState::Running
+    {
+        world.resource_mut::<SimulationSteps>().advance();
+
+        let start = Instant::now();
+        world.run_schedule(SimulationSchedule);
+        let duration = start.elapsed();
+
+        timings.push(TickTiming {
+            duration,
+            pixies: q_pixies.iter(world).count(),
+        });
+    }
+
+    report(&timings, world.resource::<PixieCount>().0);
+
+    world.resource_mut::<Benchmark>().finished = true;
+    world.send_event(AppExit::Success);
+}
+
+fn report(timings: &[TickTiming], delivered: u32) {
+    let mut micros: Vec<_> = timings.iter().map(|t| t.duration.as_micros()).collect();
+    micros.sort_unstable();
+
+    let percentile = |p: usize| {
+        micros
+            .get((micros.len() * p / 100).min(micros.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default()
+    };
+    let total: u128 = micros.iter().sum();
+
+    info!(
+        "{} ticks in {}ms: median {}us, p99 {}us, max {}us, peak {} pixies, {} delivered",
+        timings.len(),
+        total / 1000,
+        percentile(50),
+        percentile(99),
+        micros.last().copied().unwrap_or_default(),
+        timings.iter().map(|t| t.pixies).max().unwrap_or_default(),
+        delivered
+    );
+
+    let mut csv = "tick,micros,pixies\n".to_string();
+    for (i, tick) in timings.iter().enumerate() {
+        let _ = writeln!(csv, "{},{},{}", i, tick.duration.as_micros(), tick.pixies);
+    }
+
+    match std::fs::write(OUTPUT_PATH, csv) {
+        Ok(_) => info!("Wrote {} tick timings to {}", timings.len(), OUTPUT_PATH),
+        Err(e) => error!("Failed to write {}: {}", OUTPUT_PATH, e),
+    }
+}
@@ -0,0 +1,94 @@
+//! A faint copy of the roads that got the best score on the level, shown under the
+//! roads being drawn so that it's easy to see where the current attempt differs.
+//! Toggled with V.
+
+use crate::{
+    color,
+    controls::Action,
+    layer,
+    save::{BestSolutions, InputMap},
+    sim::SimulationState,
+    DrawingInput, GameState, Handles, SelectedLevel, WIDE_ROAD_STROKE,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+const BEST_LAYOUT_ALPHA: f32 = 0.25;
+
+pub struct BestLayoutPlugin;
+impl Plugin for BestLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BestLayout>();
+
+        app.add_systems(Update, best_layout_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            draw_best_layout_system
+                .after(best_layout_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct BestLayout {
+    /// Whether the best layout is shown while editing. Kept between levels.
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct BestLayoutLine;
+
+fn best_layout_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut best_layout: ResMut<BestLayout>,
+) {
+    if input_map.just_pressed(Action::BestLayout, &keyboard_input) {
+        best_layout.enabled = !best_layout.enabled;
+    }
+}
+
+fn draw_best_layout_system(
+    mut commands: Commands,
+    best_layout: Res<BestLayout>,
+    best_solutions: Res<BestSolutions>,
+    sim_state: Res<SimulationState>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    q_lines: Query<Entity, With<BestLayoutLine>>,
+) {
+    if !best_layout.is_changed()
+        && !best_solutions.is_changed()
+        && !sim_state.is_changed()
+        && !selected_level.is_changed()
+    {
+        return;
+    }
+
+    for entity in q_lines.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if !best_layout.enabled || *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    let Some(solution) = best_solutions.0.get(&handles.level_id(selected_level.0)) else {
+        return;
+    };
+
+    for segment in solution.segments.iter() {
+        let color = color::FINISHED_ROAD[segment.layer as usize - 1].with_alpha(BEST_LAYOUT_ALPHA);
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Line(segment.points.0, segment.points.1)),
+                transform: Transform::from_xyz(0.0, 0.0, layer::GHOST_ROAD),
+                ..default()
+            },
+            Stroke::new(color, if segment.wide { WIDE_ROAD_STROKE } else { 2.0 }),
+            BestLayoutLine,
+        ));
+    }
+}
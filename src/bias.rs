@@ -0,0 +1,227 @@
+//! A tool for nudging pixies onto or off particular roads. Clicking a road cycles it
+//! between normal, avoided, and preferred, which scales its length when pixies look
+//! for the shortest path. Handy when several paths are equally short and the pixies
+//! all pile onto one of them.
+
+use crate::{
+    color, hud::Hud, layer, segment_near_point, sim::SimulationState, AfterUpdate, Collider,
+    ColliderLayer, DrawingInteraction, DrawingMode, DrawingMouseMovement, DrawingState, GameState,
+    MainCamera, MouseState, RoadGraph, RoadSegment, NET_RIPPING_HIT_RADIUS,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use serde::Deserialize;
+
+/// Radius of the marker drawn in the middle of roads with a bias.
+const MARKER_RADIUS: f32 = 4.0;
+
+pub struct BiasPlugin;
+impl Plugin for BiasPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BiasState>();
+
+        app.add_systems(
+            Update,
+            bias_mouse_movement_system.in_set(DrawingMouseMovement),
+        );
+        app.add_systems(
+            Update,
+            (bias_mouse_click_system, draw_bias_hover_system)
+                .chain()
+                .in_set(DrawingInteraction),
+        );
+        app.add_systems(
+            AfterUpdate,
+            bias_markers_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// How pixies feel about a road.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Deserialize)]
+pub enum RouteBias {
+    #[default]
+    Normal,
+    Avoid,
+    Prefer,
+}
+
+impl RouteBias {
+    /// How much longer the road seems to pixies looking for the shortest path.
+    pub fn multiplier(&self) -> f32 {
+        match self {
+            Self::Normal => 1.0,
+            Self::Avoid => 3.0,
+            Self::Prefer => 0.5,
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            Self::Normal => Self::Avoid,
+            Self::Avoid => Self::Prefer,
+            Self::Prefer => Self::Normal,
+        }
+    }
+
+    fn color(&self) -> Option<Color> {
+        match self {
+            Self::Normal => None,
+            Self::Avoid => Some(color::UI_GREY_RED),
+            Self::Prefer => Some(color::UI_PRESSED_BUTTON),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BiasButton;
+#[derive(Component)]
+struct BiasHoverLine;
+#[derive(Component)]
+struct BiasMarker;
+
+#[derive(Resource, Default)]
+struct BiasState {
+    /// The road under the cursor, which will change when clicked.
+    hovered: Option<(Entity, (Vec2, Vec2))>,
+}
+
+fn bias_mouse_movement_system(
+    drawing_state: Res<DrawingState>,
+    mouse: Res<MouseState>,
+    mut bias: ResMut<BiasState>,
+    sim_state: Res<SimulationState>,
+    q_colliders: Query<(&Parent, &Collider, &ColliderLayer)>,
+    q_segments: Query<&RoadSegment>,
+    q_projection: Query<&OrthographicProjection, With<MainCamera>>,
+) {
+    if !matches!(drawing_state.mode, DrawingMode::Biasing)
+        || *sim_state != SimulationState::NotStarted
+    {
+        if bias.hovered.is_some() {
+            bias.hovered = None;
+        }
+        return;
+    }
+
+    if !mouse.is_changed() && !drawing_state.is_changed() {
+        return;
+    }
+
+    let scale = q_projection.get_single().map_or(1.0, |p| p.scale);
+
+    let hovered = segment_near_point(mouse.position, NET_RIPPING_HIT_RADIUS * scale, &q_colliders)
+        .and_then(|entity| q_segments.get(entity).ok().map(|s| (entity, s.points)));
+
+    if bias.hovered != hovered {
+        bias.hovered = hovered;
+    }
+}
+
+fn bias_mouse_click_system(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mouse: Res<MouseState>,
+    hud: Res<Hud>,
+    drawing_state: Res<DrawingState>,
+    sim_state: Res<SimulationState>,
+    bias: Res<BiasState>,
+    mut graph: ResMut<RoadGraph>,
+    mut q_segments: Query<&mut RoadSegment>,
+    q_window: Query<&Window>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    if mouse.window_position.y > window.resolution.height() - hud.bar_height() {
+        return;
+    }
+
+    if !matches!(drawing_state.mode, DrawingMode::Biasing) {
+        return;
+    }
+
+    if *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some((entity, _)) = bias.hovered else {
+        return;
+    };
+
+    let Ok(mut segment) = q_segments.get_mut(entity) else {
+        return;
+    };
+
+    segment.bias = segment.bias.next();
+
+    // The graph itself is the same, but the paths through it and the saved solution
+    // need updating.
+    graph.set_changed();
+}
+
+fn draw_bias_hover_system(
+    mut commands: Commands,
+    bias: Res<BiasState>,
+    q_lines: Query<Entity, With<BiasHoverLine>>,
+) {
+    if !bias.is_changed() {
+        return;
+    }
+
+    for entity in q_lines.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if let Some((_, (a, b))) = bias.hovered {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Line(a, b)),
+                transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                ..default()
+            },
+            Stroke::new(color::UI_WHITE, 2.0),
+            BiasHoverLine,
+        ));
+    }
+}
+
+fn bias_markers_system(
+    mut commands: Commands,
+    graph: Res<RoadGraph>,
+    q_segments: Query<&RoadSegment>,
+    q_markers: Query<Entity, With<BiasMarker>>,
+) {
+    if !graph.is_changed() {
+        return;
+    }
+
+    for entity in q_markers.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    for segment in q_segments.iter() {
+        let Some(color) = segment.bias.color() else {
+            continue;
+        };
+
+        let middle = (segment.points.0 + segment.points.1) / 2.0;
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Circle {
+                    radius: MARKER_RADIUS,
+                    center: middle,
+                }),
+                transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                ..default()
+            },
+            Fill::color(color),
+            BiasMarker,
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+//! Bridges where roads on different layers cross. The road on the lower layer is
+//! interrupted under the upper one, so it's clear which passes over which.
+
+use crate::{
+    collision::{bounding_boxes_overlap, segment_intersection},
+    color, layer, AfterUpdate, GameState, RoadGraph, RoadSegment, WIDE_ROAD_STROKE,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+/// How far the gap in the lower road extends along the upper road, either side of
+/// the crossing.
+const BRIDGE_HALF_LENGTH: f32 = 10.0;
+/// How much wider than the upper road the gap is.
+const BRIDGE_MARGIN: f32 = 8.0;
+
+pub struct BridgesPlugin;
+impl Plugin for BridgesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            AfterUpdate,
+            bridges_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct Bridge;
+
+fn bridges_system(
+    mut commands: Commands,
+    graph: Res<RoadGraph>,
+    q_segments: Query<&RoadSegment>,
+    q_bridges: Query<Entity, With<Bridge>>,
+) {
+    if !graph.is_changed() {
+        return;
+    }
+
+    for entity in q_bridges.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let segments: Vec<_> = q_segments.iter().collect();
+
+    for (i, upper) in segments.iter().enumerate() {
+        for lower in segments[i + 1..].iter() {
+            if upper.layer == lower.layer || !bounding_boxes_overlap(upper.points, lower.points) {
+                continue;
+            }
+
+            // Layer one is drawn on top.
+            let (upper, lower) = if upper.layer < lower.layer {
+                (upper, lower)
+            } else {
+                (lower, upper)
+            };
+
+            let Some(point) = segment_intersection(
+                upper.points.0,
+                upper.points.1,
+                lower.points.0,
+                lower.points.1,
+            ) else {
+                continue;
+            };
+
+            let direction = (upper.points.1 - upper.points.0).normalize();
+            let upper_width = if upper.wide { WIDE_ROAD_STROKE } else { 2.0 };
+
+            commands.spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Line(
+                        point - direction * BRIDGE_HALF_LENGTH,
+                        point + direction * BRIDGE_HALF_LENGTH,
+                    )),
+                    // Between the two roads, so the upper one is drawn over the gap.
+                    transform: Transform::from_xyz(
+                        0.0,
+                        0.0,
+                        layer::ROAD - upper.layer as f32 - 0.25,
+                    ),
+                    ..default()
+                },
+                Stroke::new(color::BACKGROUND, upper_width + BRIDGE_MARGIN),
+                Bridge,
+            ));
+        }
+    }
+}
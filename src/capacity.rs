@@ -0,0 +1,84 @@
+//! Terminuses that can only collect pixies so quickly. Each one with a `capacity` takes
+//! at most that many pixies per second, and pixies that arrive faster than that wait
+//! at its door, with the ones behind them queueing back along the road.
+
+use crate::sim::SIMULATION_TIMESTEP;
+use bevy::{prelude::*, utils::HashMap};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Intake {
+    /// Pixies per second.
+    rate: f32,
+    /// How much of the next pixie the terminus is ready for. It can take one when this
+    /// reaches 1.0, and never saves up for more than one.
+    ready: f32,
+}
+
+/// How ready each terminus with a capacity is to collect another pixie, keyed by its
+/// rounded position.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct TerminusCapacity(HashMap<IVec2, Intake>);
+
+impl TerminusCapacity {
+    /// Starts over with terminuses at each point that take the given number of pixies
+    /// per second. Each one is ready for its first pixie.
+    pub fn reset(&mut self, terminuses: impl IntoIterator<Item = (Vec2, f32)>) {
+        self.0 = terminuses
+            .into_iter()
+            .map(|(point, rate)| (point.round().as_ivec2(), Intake { rate, ready: 1.0 }))
+            .collect();
+    }
+
+    /// Moves time on by `delta` seconds.
+    pub fn refill(&mut self, delta: f32) {
+        for intake in self.0.values_mut() {
+            intake.ready = (intake.ready + intake.rate * delta).min(1.0);
+        }
+    }
+
+    /// Whether the terminus at `point` will take a pixie now. Terminuses without a
+    /// capacity always will.
+    pub fn accept(&mut self, point: Vec2) -> bool {
+        let Some(intake) = self.0.get_mut(&point.round().as_ivec2()) else {
+            return true;
+        };
+
+        if intake.ready < 1.0 {
+            return false;
+        }
+
+        intake.ready -= 1.0;
+        true
+    }
+}
+
+pub fn refill_capacity_system(mut capacity: ResMut<TerminusCapacity>) {
+    capacity.refill(SIMULATION_TIMESTEP);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intake() {
+        let door = Vec2::new(48.0, 0.0);
+        let mut capacity = TerminusCapacity::default();
+        capacity.reset([(door, 2.0)]);
+
+        assert!(capacity.accept(door));
+        assert!(!capacity.accept(door));
+
+        capacity.refill(0.25);
+        assert!(!capacity.accept(door));
+        capacity.refill(0.25);
+        assert!(capacity.accept(door));
+
+        // Waiting around doesn't save up for a rush of pixies later.
+        capacity.refill(10.0);
+        assert!(capacity.accept(door));
+        assert!(!capacity.accept(door));
+
+        assert!(capacity.accept(Vec2::ZERO));
+    }
+}
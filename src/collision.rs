@@ -17,26 +17,42 @@ pub fn point_segment_collision(p: Vec2, a: Vec2, b: Vec2) -> SegmentCollision {
         return SegmentCollision::Connecting;
     }
 
-    let diff = b - a;
-    let len2 = diff.length_squared();
-    if len2 == 0.0 {
+    if a == b {
         return SegmentCollision::Touching;
     }
 
-    let d1 = p - a;
-    let d2 = b - a;
-
-    let t = (d1.dot(d2) / len2).clamp(0.0, 1.0);
-    let proj = a + t * diff;
-    let dist = p.distance(proj);
-
-    if dist <= 0.0001 {
+    if point_segment_distance(p, a, b) <= 0.0001 {
         SegmentCollision::Touching
     } else {
         SegmentCollision::None
     }
 }
 
+/// Returns the shortest distance between the point `p` and the segment `a`-`b`.
+pub fn point_segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let diff = b - a;
+    let len2 = diff.length_squared();
+    if len2 == 0.0 {
+        return p.distance(a);
+    }
+
+    let t = ((p - a).dot(diff) / len2).clamp(0.0, 1.0);
+    let proj = a + t * diff;
+
+    p.distance(proj)
+}
+
+/// Whether the bounding boxes of two segments overlap, with a little slack so that
+/// anything `segment_collision` or `point_segment_collision` could report is kept.
+pub fn bounding_boxes_overlap(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+    const SLACK: f32 = 0.001;
+
+    let (a_min, a_max) = (a.0.min(a.1), a.0.max(a.1));
+    let (b_min, b_max) = (b.0.min(b.1), b.0.max(b.1));
+
+    a_min.cmple(b_max + SLACK).all() && b_min.cmple(a_max + SLACK).all()
+}
+
 // for reference, this is helpful
 // https://github.com/pgkelley4/line-segments-intersect/blob/master/js/line-segments-intersect.js
 // but we're differing pretty wildly in how we choose to deal with colinearities, and
@@ -105,6 +121,43 @@ pub fn segment_collision(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> SegmentColli
     SegmentCollision::None
 }
 
+/// The point where two segments cross, if they are `Intersecting`.
+pub fn segment_intersection(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<Vec2> {
+    if !matches!(
+        segment_collision(a1, a2, b1, b2),
+        SegmentCollision::Intersecting
+    ) {
+        return None;
+    }
+
+    let da = a2 - a1;
+    let db = b2 - b1;
+    let t = (b1 - a1).perp_dot(db) / da.perp_dot(db);
+
+    Some(a1 + da * t)
+}
+
+/// Whether any part of the segment `a`-`b` is inside or on the edge of `rect`.
+pub fn segment_rect_collision(a: Vec2, b: Vec2, rect: Rect) -> bool {
+    if rect.contains(a) || rect.contains(b) {
+        return true;
+    }
+
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+    ];
+
+    (0..4).any(|i| {
+        !matches!(
+            segment_collision(a, b, corners[i], corners[(i + 1) % 4]),
+            SegmentCollision::None
+        )
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,6 +217,16 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn pointseg_distance() {
+        let a = Vec2::new(0.0, 0.0);
+        let b = Vec2::new(10.0, 0.0);
+
+        assert_eq!(point_segment_distance(Vec2::new(5.0, 3.0), a, b), 3.0);
+        assert_eq!(point_segment_distance(Vec2::new(14.0, 3.0), a, b), 5.0);
+        assert_eq!(point_segment_distance(Vec2::new(-4.0, 0.0), a, b), 4.0);
+    }
+
     #[test]
     fn pointseg_collinear() {
         // collinear horizontal
@@ -314,4 +377,86 @@ mod tests {
             SegmentCollision::None
         ));
     }
+
+    #[test]
+    fn segseg_intersection() {
+        // X
+        assert_eq!(
+            segment_intersection(
+                Vec2::new(-10.0, -10.0),
+                Vec2::new(10.0, 10.0),
+                Vec2::new(-10.0, 10.0),
+                Vec2::new(10.0, -10.0),
+            ),
+            Some(Vec2::ZERO)
+        );
+        // T
+        assert_eq!(
+            segment_intersection(
+                Vec2::new(-10.0, 0.0),
+                Vec2::new(10.0, 0.0),
+                Vec2::new(0.0, 0.0),
+                Vec2::new(0.0, -10.0),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn bounding_boxes() {
+        let seg = (Vec2::new(0.0, 0.0), Vec2::new(2.0, 2.0));
+
+        assert!(bounding_boxes_overlap(
+            seg,
+            (Vec2::new(2.0, 0.0), Vec2::new(0.0, 2.0))
+        ));
+        // touching corners
+        assert!(bounding_boxes_overlap(
+            seg,
+            (Vec2::new(2.0, 2.0), Vec2::new(3.0, 3.0))
+        ));
+        // points
+        assert!(bounding_boxes_overlap(
+            seg,
+            (Vec2::new(1.0, 1.0), Vec2::new(1.0, 1.0))
+        ));
+        assert!(!bounding_boxes_overlap(
+            seg,
+            (Vec2::new(3.0, 0.0), Vec2::new(4.0, 2.0))
+        ));
+    }
+
+    #[test]
+    fn segment_rect() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+
+        // inside
+        assert!(segment_rect_collision(
+            Vec2::new(2.0, 2.0),
+            Vec2::new(4.0, 4.0),
+            rect
+        ));
+        // crossing through without an end inside
+        assert!(segment_rect_collision(
+            Vec2::new(-5.0, 5.0),
+            Vec2::new(15.0, 5.0),
+            rect
+        ));
+        // along an edge
+        assert!(segment_rect_collision(
+            Vec2::new(-5.0, 10.0),
+            Vec2::new(15.0, 10.0),
+            rect
+        ));
+        assert!(!segment_rect_collision(
+            Vec2::new(-5.0, 12.0),
+            Vec2::new(15.0, 12.0),
+            rect
+        ));
+        assert!(!segment_rect_collision(
+            Vec2::new(-5.0, 0.0),
+            Vec2::new(0.0, -5.0),
+            rect
+        ));
+    }
 }
@@ -10,14 +10,6 @@ pub const DRAWING_ROAD: [Color; 3] = [
     Color::srgb(0.102, 0.298, 0.125),
     Color::srgb(0.102, 0.298, 0.298),
 ];
-pub const PIXIE: [Srgba; 6] = [
-    bevy::color::palettes::css::AQUAMARINE,
-    bevy::color::palettes::css::DEEP_PINK,
-    bevy::color::palettes::css::ORANGE,
-    bevy::color::palettes::css::PURPLE,
-    bevy::color::palettes::css::DARK_GREEN,
-    bevy::color::palettes::css::YELLOW,
-];
 
 pub const BACKGROUND: Color = Color::srgb(0.05, 0.066, 0.09);
 pub const GRID: Color = Color::srgb(0.086, 0.105, 0.133);
@@ -26,6 +18,15 @@ pub const OBSTACLE: Color = Color::srgb(0.086, 0.105, 0.133);
 pub const BOTTOM_BAR_BACKGROUND: Color = Color::srgb(0.09, 0.11, 0.13);
 pub const DIALOG_BACKGROUND: Color = Color::srgb(0.2, 0.2, 0.2);
 pub const OVERLAY: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+pub const HEATMAP_COOL: Color = Color::srgba(1.0, 0.9, 0.2, 0.3);
+pub const HEATMAP_HOT: Color = Color::srgba(1.0, 0.2, 0.1, 0.8);
+pub const NETS: [Color; 4] = [
+    Color::srgba(0.95, 0.45, 0.85, 0.8),
+    Color::srgba(1.0, 0.75, 0.2, 0.8),
+    Color::srgba(0.4, 0.9, 1.0, 0.8),
+    Color::srgba(0.6, 1.0, 0.4, 0.8),
+];
+pub const NET_UNCONNECTED: Color = Color::srgba(0.788, 0.82, 0.851, 0.3);
 
 pub const UI_WHITE: Color = Color::srgb(0.788, 0.82, 0.851);
 pub const UI_GREY_RED: Color = Color::srgb(1.0, 0.341, 0.341);
@@ -0,0 +1,151 @@
+//! Checking saved roads against the level they were drawn on. Solutions remember a hash
+//! of the level's layout, so when an update moves an obstacle or a terminus, the saved
+//! roads are loaded as best they can be: any that no longer fit are dropped rather than
+//! restored on top of something, and the player is told what happened.
+//!
+//! Solutions saved before the hash existed have none, and are trusted as they are.
+
+use crate::{
+    level::Level,
+    playing_enter_system,
+    save::{BestSolutions, Solutions},
+    GameState, Handles, SelectedLevel,
+};
+use bevy::prelude::*;
+
+pub struct SolutionCompatPlugin;
+impl Plugin for SolutionCompatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelChanged>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            (
+                check_solution_system.before(playing_enter_system),
+                level_changed_text_system.after(playing_enter_system),
+            ),
+        );
+    }
+}
+
+/// What changed about the current level since its roads were saved.
+#[derive(Resource, Default)]
+struct LevelChanged {
+    /// The saved roads were drawn on an older layout.
+    roads: bool,
+    dropped: usize,
+    best: bool,
+}
+
+/// Whether roads saved with `saved` were drawn on a different layout than `current`.
+fn is_stale(saved: u64, current: u64) -> bool {
+    saved != 0 && saved != current
+}
+
+fn check_solution_system(
+    mut changed: ResMut<LevelChanged>,
+    mut solutions: ResMut<Solutions>,
+    best_solutions: Res<BestSolutions>,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+) {
+    *changed = LevelChanged::default();
+
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    let id = handles.level_id(selected_level.0);
+    let hash = level.layout_hash();
+
+    changed.best = best_solutions
+        .0
+        .get(&id)
+        .is_some_and(|s| is_stale(s.level_hash, hash));
+
+    // Checked without borrowing mutably, so that saves aren't written for nothing.
+    if !solutions
+        .0
+        .get(&id)
+        .is_some_and(|s| is_stale(s.level_hash, hash))
+    {
+        return;
+    }
+
+    let Some(solution) = solutions.0.get_mut(&id) else {
+        return;
+    };
+
+    let before = solution.segments.len();
+    solution.segments.retain(|segment| level.fits(segment));
+    solution.level_hash = hash;
+
+    changed.roads = true;
+    changed.dropped = before - solution.segments.len();
+
+    warn!(
+        "Level {id} has changed since its roads were saved. {} of {before} no longer fit.",
+        changed.dropped
+    );
+}
+
+fn level_changed_text_system(
+    mut commands: Commands,
+    changed: Res<LevelChanged>,
+    handles: Res<Handles>,
+) {
+    let mut lines = vec![];
+
+    if changed.roads {
+        lines.push("THIS LEVEL HAS CHANGED SINCE YOUR ROADS WERE SAVED".to_string());
+        if changed.dropped > 0 {
+            lines.push(format!(
+                "{} ROADS NO LONGER FIT AND WERE REMOVED",
+                changed.dropped
+            ));
+        }
+    }
+    if changed.best {
+        lines.push("YOUR BEST SCORE WAS SET BEFORE THIS LEVEL CHANGED".to_string());
+    }
+
+    if lines.is_empty() {
+        return;
+    }
+
+    // Below any level warnings.
+    commands.spawn((
+        Text::new(lines.join("\n")),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(bevy::color::palettes::css::RED.into()),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(34.),
+            left: Val::Px(0.),
+            right: Val::Px(0.),
+            ..default()
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale() {
+        assert!(!is_stale(0, 42));
+        assert!(!is_stale(42, 42));
+        assert!(is_stale(41, 42));
+    }
+}
@@ -0,0 +1,263 @@
+//! Where pixies got stuck during the last run. Every simulation step, each pixie adds
+//! to the count of the road it's on, and pixies that start braking add a braking
+//! event. Once the run is over, the roads can be shown as a heatmap. Toggled with T.
+//!
+//! Pixies follow paths whose pieces can run along several roads in a row, in either
+//! direction, so each piece is split onto the roads along it.
+
+use crate::{
+    collision::{point_segment_distance, segment_collision, SegmentCollision},
+    color,
+    controls::Action,
+    layer, playing_enter_system,
+    save::InputMap,
+    sim::SimulationState,
+    DrawingInput, GameState, RoadSegment,
+};
+use bevy::{color::Mix, prelude::*, utils::HashMap};
+use bevy_prototype_lyon::prelude::*;
+
+/// How many steps spent on a road a single braking event counts as.
+const BRAKING_WEIGHT: f32 = 30.0;
+const HEATMAP_WIDTH: f32 = 6.0;
+
+pub struct CongestionPlugin;
+impl Plugin for CongestionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Congestion>();
+        app.init_resource::<Heatmap>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            reset_congestion_system.after(playing_enter_system),
+        );
+        app.add_systems(Update, heatmap_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            draw_heatmap_system
+                .after(heatmap_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct SegmentCongestion {
+    pub points: (Vec2, Vec2),
+    /// Simulation steps spent on the road, summed over every pixie.
+    pub occupancy: u32,
+    /// Times a pixie started braking on the road.
+    pub braking: u32,
+}
+
+/// A road's rounded end points, the same whichever way round they're given.
+type RoadKey = (IVec2, IVec2);
+
+fn road_key(points: (Vec2, Vec2)) -> RoadKey {
+    let (a, b) = (points.0.round().as_ivec2(), points.1.round().as_ivec2());
+    if a.to_array() <= b.to_array() {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Congestion for each road that pixies drove on.
+#[derive(Resource, Default, Clone)]
+pub struct Congestion {
+    roads: HashMap<RoadKey, SegmentCongestion>,
+    /// The roads along each path piece that pixies have driven on, and its layer.
+    pieces: HashMap<(RoadKey, u32), Vec<(Vec2, Vec2)>>,
+}
+
+impl Congestion {
+    /// Counts a step by a pixie at `position` on `piece` of its path. `roads` are only
+    /// looked through the first time a piece is driven on.
+    pub fn record<'a>(
+        &mut self,
+        piece: &RoadSegment,
+        position: Vec2,
+        braked: bool,
+        roads: impl IntoIterator<Item = &'a RoadSegment>,
+    ) {
+        let along = self
+            .pieces
+            .entry((road_key(piece.points), piece.layer))
+            .or_insert_with(|| {
+                roads
+                    .into_iter()
+                    .filter(|road| {
+                        road.layer == piece.layer
+                            && matches!(
+                                segment_collision(
+                                    piece.points.0,
+                                    piece.points.1,
+                                    road.points.0,
+                                    road.points.1
+                                ),
+                                SegmentCollision::Overlapping
+                            )
+                    })
+                    .map(|road| road.points)
+                    .collect()
+            });
+
+        // The piece itself stands in for a road that has since been ripped up.
+        let points = along
+            .iter()
+            .min_by(|a, b| {
+                point_segment_distance(position, a.0, a.1)
+                    .total_cmp(&point_segment_distance(position, b.0, b.1))
+            })
+            .copied()
+            .unwrap_or(piece.points);
+
+        let entry = self
+            .roads
+            .entry(road_key(points))
+            .or_insert_with(|| SegmentCongestion {
+                points,
+                ..default()
+            });
+        entry.occupancy += 1;
+        if braked {
+            entry.braking += 1;
+        }
+    }
+
+    /// Each road with how congested it was, from 0.0 for the least to 1.0 for the most.
+    /// Roads are scored by time spent on them per unit of length, so that long roads
+    /// don't look congested just for being long.
+    pub fn heat(&self) -> Vec<((Vec2, Vec2), f32)> {
+        let scores: Vec<_> = self
+            .roads
+            .values()
+            .map(|s| {
+                let length = s.points.0.distance(s.points.1).max(1.0);
+                let score = (s.occupancy as f32 + s.braking as f32 * BRAKING_WEIGHT) / length;
+                (s.points, score)
+            })
+            .collect();
+
+        let max = scores.iter().map(|(_, s)| *s).fold(0.0, f32::max);
+        if max <= 0.0 {
+            return vec![];
+        }
+
+        scores
+            .into_iter()
+            .map(|(points, score)| (points, score / max))
+            .collect()
+    }
+
+    /// Forgets which roads are along each path piece, for when roads are added or
+    /// ripped up part way through a run.
+    pub fn roads_changed(&mut self) {
+        self.pieces.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.roads.clear();
+        self.pieces.clear();
+    }
+}
+
+#[derive(Resource, Default)]
+struct Heatmap {
+    /// Whether the heatmap is shown after a run. Kept between levels.
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct HeatmapLine;
+
+fn reset_congestion_system(mut congestion: ResMut<Congestion>) {
+    congestion.clear();
+}
+
+fn heatmap_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut heatmap: ResMut<Heatmap>,
+) {
+    if input_map.just_pressed(Action::Heatmap, &keyboard_input) {
+        heatmap.enabled = !heatmap.enabled;
+    }
+}
+
+fn draw_heatmap_system(
+    mut commands: Commands,
+    heatmap: Res<Heatmap>,
+    congestion: Res<Congestion>,
+    sim_state: Res<SimulationState>,
+    q_lines: Query<Entity, With<HeatmapLine>>,
+) {
+    if !heatmap.is_changed() && !sim_state.is_changed() {
+        return;
+    }
+
+    for entity in q_lines.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if !heatmap.enabled || *sim_state != SimulationState::Finished {
+        return;
+    }
+
+    for ((a, b), heat) in congestion.heat() {
+        let color = color::HEATMAP_COOL.mix(&color::HEATMAP_HOT, heat);
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Line(a, b)),
+                transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                ..default()
+            },
+            Stroke::new(color, HEATMAP_WIDTH),
+            HeatmapLine,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::seg;
+
+    #[test]
+    fn heat() {
+        let mut congestion = Congestion::default();
+        assert!(congestion.heat().is_empty());
+
+        let short = seg((0.0, 0.0), (10.0, 0.0), 1);
+        let long = seg((10.0, 0.0), (50.0, 0.0), 1);
+        let roads = [short.clone(), long.clone()];
+
+        // A path piece along both roads, driven in both directions.
+        let piece = seg((0.0, 0.0), (50.0, 0.0), 1);
+        let back = seg((50.0, 0.0), (0.0, 0.0), 1);
+
+        for _ in 0..5 {
+            congestion.record(&piece, Vec2::new(5.0, 0.0), false, &roads);
+            congestion.record(&back, Vec2::new(5.0, 0.0), false, &roads);
+            congestion.record(&piece, Vec2::new(30.0, 0.0), false, &roads);
+            congestion.record(&back, Vec2::new(30.0, 0.0), false, &roads);
+        }
+        assert_eq!(congestion.heat().len(), 2);
+
+        let heat = |congestion: &Congestion, segment: &RoadSegment| {
+            congestion
+                .heat()
+                .iter()
+                .find(|(points, _)| *points == segment.points)
+                .map(|(_, heat)| *heat)
+        };
+
+        // The same time spent on a longer road is less congested.
+        assert_eq!(heat(&congestion, &short), Some(1.0));
+        assert_eq!(heat(&congestion, &long), Some(0.25));
+
+        congestion.record(&back, Vec2::new(30.0, 0.0), true, &roads);
+        assert_eq!(heat(&congestion, &long), Some(1.0));
+    }
+}
@@ -0,0 +1,535 @@
+//! Rebindable keys, and a cursor that can be moved with the keyboard so that the
+//! whole game can be played without a mouse.
+//!
+//! Bindings are changed from the settings panel. Only the keys that
+//! differ from the defaults are saved, so actions added later get their default key.
+
+use crate::{
+    color, mouse_movement_system, playing_enter_system, save::InputMap, share::ARENA_HALF_SIZE,
+    sim::SimulationPaused, touch::click, DrawingInput, GameState, MainCamera, MouseState,
+    GRID_SIZE,
+};
+use bevy::prelude::*;
+
+/// How long an arrow key must be held before the cursor starts repeating, in seconds.
+const REPEAT_DELAY: f32 = 0.4;
+/// How often the cursor moves while an arrow key is held, in seconds.
+const REPEAT_INTERVAL: f32 = 0.08;
+
+pub struct ControlsPlugin;
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>();
+        app.init_resource::<KeyboardCursor>();
+        app.add_event::<ActionPressed>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            reset_controls_system.after(playing_enter_system),
+        );
+        app.add_systems(OnEnter(GameState::LevelSelect), reset_controls_system);
+        app.add_systems(
+            Update,
+            (
+                keyboard_cursor_system.after(mouse_movement_system),
+                action_key_system,
+            )
+                .in_set(DrawingInput),
+        );
+        app.add_systems(
+            Update,
+            (
+                binding_button_system,
+                rebind_key_system.after(DrawingInput),
+                binding_text_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing).or(in_state(GameState::LevelSelect))),
+        );
+    }
+}
+
+/// Something the player can do with a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum Action {
+    Layer1,
+    Layer2,
+    Layer3,
+    SwapLayer,
+    Arcs,
+    Wide,
+    Rip,
+    RipSegment,
+    Drag,
+    Bias,
+    Cancel,
+    Continue,
+    Release,
+    Reset,
+    Speed,
+    FastForward,
+    Back,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    Place,
+    Preview,
+    Ghost,
+    Legend,
+    Hud,
+    Slots,
+    Heatmap,
+    Nets,
+    History,
+    BestLayout,
+    Sandbox,
+    PassTurn,
+    NextButton,
+    PreviousLevel,
+    NextLevel,
+    Diagnostics,
+}
+
+impl Action {
+    pub const ALL: [Action; 37] = [
+        Action::Layer1,
+        Action::Layer2,
+        Action::Layer3,
+        Action::SwapLayer,
+        Action::Arcs,
+        Action::Wide,
+        Action::Rip,
+        Action::RipSegment,
+        Action::Drag,
+        Action::Bias,
+        Action::Cancel,
+        Action::Continue,
+        Action::Release,
+        Action::Reset,
+        Action::Speed,
+        Action::FastForward,
+        Action::Back,
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::Place,
+        Action::Preview,
+        Action::Ghost,
+        Action::Legend,
+        Action::Hud,
+        Action::Slots,
+        Action::Heatmap,
+        Action::Nets,
+        Action::History,
+        Action::BestLayout,
+        Action::Sandbox,
+        Action::PassTurn,
+        Action::NextButton,
+        Action::PreviousLevel,
+        Action::NextLevel,
+        Action::Diagnostics,
+    ];
+
+    pub fn default_key(&self) -> KeyCode {
+        match self {
+            Action::Layer1 => KeyCode::Digit1,
+            Action::Layer2 => KeyCode::Digit2,
+            Action::Layer3 => KeyCode::Digit3,
+            Action::SwapLayer => KeyCode::KeyX,
+            Action::Arcs => KeyCode::KeyA,
+            Action::Wide => KeyCode::KeyW,
+            Action::Rip => KeyCode::KeyR,
+            Action::RipSegment => KeyCode::ControlLeft,
+            Action::Drag => KeyCode::KeyM,
+            Action::Bias => KeyCode::KeyB,
+            Action::Cancel => KeyCode::Escape,
+            Action::Continue => KeyCode::KeyC,
+            Action::Release => KeyCode::Space,
+            Action::Reset => KeyCode::Delete,
+            Action::Speed => KeyCode::KeyF,
+            Action::FastForward => KeyCode::ShiftLeft,
+            Action::Back => KeyCode::Backspace,
+            Action::CursorUp => KeyCode::ArrowUp,
+            Action::CursorDown => KeyCode::ArrowDown,
+            Action::CursorLeft => KeyCode::ArrowLeft,
+            Action::CursorRight => KeyCode::ArrowRight,
+            Action::Place => KeyCode::Enter,
+            Action::Preview => KeyCode::KeyP,
+            Action::Ghost => KeyCode::KeyG,
+            Action::Legend => KeyCode::KeyL,
+            Action::Hud => KeyCode::KeyH,
+            Action::Slots => KeyCode::KeyS,
+            Action::Heatmap => KeyCode::KeyT,
+            Action::Nets => KeyCode::KeyN,
+            Action::History => KeyCode::KeyI,
+            Action::BestLayout => KeyCode::KeyV,
+            Action::Sandbox => KeyCode::KeyK,
+            Action::PassTurn => KeyCode::KeyE,
+            Action::NextButton => KeyCode::Tab,
+            Action::PreviousLevel => KeyCode::PageUp,
+            Action::NextLevel => KeyCode::PageDown,
+            Action::Diagnostics => KeyCode::F3,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Action::Layer1 => "LAYER 1",
+            Action::Layer2 => "LAYER 2",
+            Action::Layer3 => "LAYER 3",
+            Action::SwapLayer => "SWAP LAYER",
+            Action::Arcs => "ARCS",
+            Action::Wide => "WIDE ROADS",
+            Action::Rip => "RIP NETS",
+            Action::RipSegment => "RIP ONE ROAD",
+            Action::Drag => "MOVE ROADS",
+            Action::Bias => "ROUTE BIAS",
+            Action::Cancel => "CANCEL",
+            Action::Continue => "CONTINUE ROAD",
+            Action::Release => "RELEASE",
+            Action::Reset => "RESET",
+            Action::Speed => "SPEED",
+            Action::FastForward => "FAST FORWARD",
+            Action::Back => "BACK",
+            Action::CursorUp => "CURSOR UP",
+            Action::CursorDown => "CURSOR DOWN",
+            Action::CursorLeft => "CURSOR LEFT",
+            Action::CursorRight => "CURSOR RIGHT",
+            Action::Place => "PLACE",
+            Action::Preview => "PREVIEW",
+            Action::Ghost => "GHOST",
+            Action::Legend => "LEGEND",
+            Action::Hud => "COMPACT HUD",
+            Action::Slots => "SLOTS",
+            Action::Heatmap => "HEATMAP",
+            Action::Nets => "NETS",
+            Action::History => "HISTORY",
+            Action::BestLayout => "BEST LAYOUT",
+            Action::Sandbox => "SANDBOX",
+            Action::PassTurn => "PASS TURN",
+            Action::NextButton => "NEXT BUTTON",
+            Action::PreviousLevel => "PREV LEVEL",
+            Action::NextLevel => "NEXT LEVEL",
+            Action::Diagnostics => "DIAGNOSTICS",
+        }
+    }
+}
+
+impl InputMap {
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.0
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_key())
+    }
+
+    pub fn pressed(&self, action: Action, input: &ButtonInput<KeyCode>) -> bool {
+        input.pressed(self.key(action))
+    }
+
+    pub fn just_pressed(&self, action: Action, input: &ButtonInput<KeyCode>) -> bool {
+        input.just_pressed(self.key(action))
+    }
+
+    /// Binds `key` to `action`. Whatever was bound to `key` before gets the key that
+    /// `action` had, so every action keeps a key.
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        let old = self.key(action);
+
+        if let Some(other) = Action::ALL
+            .into_iter()
+            .find(|a| *a != action && self.key(*a) == key)
+        {
+            self.set(other, old);
+        }
+
+        self.set(action, key);
+    }
+
+    fn set(&mut self, action: Action, key: KeyCode) {
+        if key == action.default_key() {
+            self.0.remove(&action);
+        } else {
+            self.0.insert(action, key);
+        }
+    }
+}
+
+/// A short name for a key, for the bindings list.
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    let name = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name);
+    name.to_uppercase()
+}
+
+/// Sent when a key is pressed for one of the actions that buttons in the bottom bar
+/// also perform. These only happen while the board is taking input, so that they
+/// don't fire while typing a slot name or using the pause menu.
+#[derive(Event)]
+pub struct ActionPressed(pub Action);
+
+#[derive(Component)]
+struct BindingButton(Action);
+#[derive(Component)]
+struct ResetBindingsButton;
+
+/// The action waiting for a key to be pressed.
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+#[derive(Resource, Default)]
+struct KeyboardCursor {
+    /// Time until the held arrow keys move the cursor again.
+    repeat: f32,
+}
+
+fn reset_controls_system(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+pub fn not_rebinding(rebinding: Res<Rebinding>) -> bool {
+    rebinding.0.is_none()
+}
+
+fn action_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut events: EventWriter<ActionPressed>,
+) {
+    for action in [Action::Release, Action::Reset, Action::Speed, Action::Back] {
+        if input_map.just_pressed(action, &keyboard_input) {
+            events.send(ActionPressed(action));
+        }
+    }
+}
+
+fn keyboard_cursor_system(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut cursor: ResMut<KeyboardCursor>,
+    mut mouse: ResMut<MouseState>,
+    mut mouse_input: ResMut<ButtonInput<MouseButton>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let directions = [
+        (Action::CursorUp, IVec2::Y),
+        (Action::CursorDown, IVec2::NEG_Y),
+        (Action::CursorLeft, IVec2::NEG_X),
+        (Action::CursorRight, IVec2::X),
+    ];
+
+    let step: IVec2 = directions
+        .iter()
+        .filter(|(action, _)| input_map.pressed(*action, &keyboard_input))
+        .map(|(_, direction)| *direction)
+        .sum();
+
+    let moved = if directions
+        .iter()
+        .any(|(action, _)| input_map.just_pressed(*action, &keyboard_input))
+    {
+        cursor.repeat = REPEAT_DELAY;
+        true
+    } else if step != IVec2::ZERO {
+        cursor.repeat -= time.delta_secs();
+        if cursor.repeat <= 0.0 {
+            cursor.repeat += REPEAT_INTERVAL;
+            true
+        } else {
+            false
+        }
+    } else {
+        false
+    };
+
+    if moved && step != IVec2::ZERO {
+        let half = Vec2::new(ARENA_HALF_SIZE.0 as f32, ARENA_HALF_SIZE.1 as f32) * GRID_SIZE;
+        let snapped = (mouse.snapped + step.as_vec2() * GRID_SIZE).clamp(-half, half);
+
+        mouse.snapped = snapped;
+        mouse.position = snapped;
+
+        // Clicks in the bottom bar are ignored by the board, so keep the window
+        // position in step too.
+        if let Ok((camera, camera_transform)) = q_camera.get_single() {
+            if let Ok(position) = camera.world_to_viewport(camera_transform, snapped.extend(0.0)) {
+                mouse.window_position = position;
+            }
+        }
+    }
+
+    if input_map.just_pressed(Action::Place, &keyboard_input) {
+        click(&mut mouse_input);
+    }
+}
+
+/// Adds the list of bindings to a settings panel.
+pub fn spawn_bindings(parent: &mut ChildBuilder, input_map: &InputMap, font: &TextFont) {
+    let font = TextFont {
+        font_size: 18.0,
+        ..font.clone()
+    };
+
+    parent
+        .spawn(Node {
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::auto(4),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.),
+            row_gap: Val::Px(4.),
+            ..default()
+        })
+        .with_children(|parent| {
+            for action in Action::ALL {
+                parent.spawn((
+                    Text::new(action.label()),
+                    font.clone(),
+                    TextColor(color::UI_WHITE),
+                ));
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            justify_content: JustifyContent::Center,
+                            padding: UiRect::axes(Val::Px(10.), Val::Px(2.)),
+                            ..default()
+                        },
+                        BackgroundColor(color::UI_NORMAL_BUTTON),
+                        BindingButton(action),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(key_label(input_map.key(action))),
+                            font.clone(),
+                            TextColor(color::UI_BUTTON_TEXT),
+                        ));
+                    });
+            }
+        });
+
+    parent
+        .spawn((
+            Button,
+            Node {
+                align_self: AlignSelf::Center,
+                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                ..default()
+            },
+            BackgroundColor(color::UI_NORMAL_BUTTON),
+            ResetBindingsButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("DEFAULT KEYS"),
+                font.clone(),
+                TextColor(color::UI_BUTTON_TEXT),
+            ));
+        });
+}
+
+fn binding_button_system(
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+    q_binding: Query<(&Interaction, &BindingButton), Changed<Interaction>>,
+    q_reset: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+) {
+    for (_, button) in q_binding
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        rebinding.0 = Some(button.0);
+    }
+
+    for _ in q_reset.iter().filter(|i| **i == Interaction::Pressed) {
+        rebinding.0 = None;
+        input_map.0.clear();
+    }
+}
+
+fn rebind_key_system(
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    paused: Res<SimulationPaused>,
+    state: Res<State<GameState>>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+
+    // While playing, the bindings are only shown in the pause menu.
+    if *state.get() == GameState::Playing && !paused.0 {
+        rebinding.0 = None;
+        return;
+    }
+
+    let Some(key) = keyboard_input.get_just_pressed().next() else {
+        return;
+    };
+
+    // Escape gives up on rebinding rather than closing the menu.
+    if *key != KeyCode::Escape {
+        input_map.bind(action, *key);
+    }
+
+    rebinding.0 = None;
+}
+
+fn binding_text_system(
+    rebinding: Res<Rebinding>,
+    input_map: Res<InputMap>,
+    q_button: Query<(&BindingButton, &Children)>,
+    q_added: Query<(), Added<BindingButton>>,
+    mut q_text: Query<&mut Text>,
+) {
+    if !rebinding.is_changed() && !input_map.is_changed() && q_added.is_empty() {
+        return;
+    }
+
+    for (button, children) in q_button.iter() {
+        let label = if rebinding.0 == Some(button.0) {
+            "...".to_string()
+        } else {
+            key_label(input_map.key(button.0))
+        };
+
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            if text.0 != label {
+                text.0.clone_from(&label);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_labels() {
+        assert_eq!(key_label(KeyCode::KeyA), "A");
+        assert_eq!(key_label(KeyCode::Digit1), "1");
+        assert_eq!(key_label(KeyCode::PageUp), "PAGEUP");
+    }
+
+    #[test]
+    fn bind_swaps() {
+        let mut input_map = InputMap::default();
+
+        input_map.bind(Action::Release, KeyCode::KeyR);
+        assert_eq!(input_map.key(Action::Release), KeyCode::KeyR);
+        assert_eq!(input_map.key(Action::Rip), KeyCode::Space);
+
+        // Moving both back only leaves the defaults, which aren't saved.
+        input_map.bind(Action::Rip, KeyCode::KeyR);
+        assert_eq!(input_map.key(Action::Release), KeyCode::Space);
+        assert!(input_map.0.is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+//! Converters change the color of pixies that drive over them. A pixie that leaves an
+//! emitter as one color can be collected as another, as long as its road takes it over
+//! the right converters on the way.
+//!
+//! A road counts as driving over a converter when the converter is anywhere along it,
+//! not counting the point where the road starts, so that a converter where two roads
+//! meet only converts once.
+
+use crate::{
+    collision::point_segment_distance,
+    layer,
+    level::{Converter, Level},
+    playing_enter_system,
+    theme::Theme,
+    GameState, Handles, SelectedLevel,
+};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_prototype_lyon::prelude::*;
+use petgraph::{
+    stable_graph::{EdgeReference, NodeIndex, StableUnGraph},
+    visit::EdgeRef,
+};
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+/// How close to a road a converter must be to be on it.
+const ON_ROAD: f32 = 0.5;
+const CONVERTER_RADIUS: f32 = 8.0;
+
+pub struct ConverterPlugin;
+impl Plugin for ConverterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_converters_system.after(playing_enter_system),
+        );
+    }
+}
+
+impl Converter {
+    fn on_road(&self, from: Vec2, to: Vec2) -> bool {
+        point_segment_distance(self.point, from, to) < ON_ROAD
+            && self.point.distance(from) >= ON_ROAD
+    }
+}
+
+/// The color of a pixie of `color` after driving in a straight line from `from` to
+/// `to`, over any converters on the way.
+pub fn convert_along(converters: &[Converter], from: Vec2, to: Vec2, color: u32) -> u32 {
+    let mut passed: Vec<_> = converters.iter().filter(|c| c.on_road(from, to)).collect();
+    passed.sort_by(|a, b| {
+        a.point
+            .distance_squared(from)
+            .total_cmp(&b.point.distance_squared(from))
+    });
+
+    passed
+        .iter()
+        .fold(color, |color, c| if c.from == color { c.to } else { color })
+}
+
+/// Every color that a pixie of `color` could be turned into, including `color` itself.
+pub fn reachable_colors(converters: &[Converter], color: u32) -> HashSet<u32> {
+    let mut reachable: HashSet<u32> = [color].into_iter().collect();
+    let mut stack = vec![color];
+
+    while let Some(color) = stack.pop() {
+        for converter in converters.iter().filter(|c| c.from == color) {
+            if reachable.insert(converter.to) {
+                stack.push(converter.to);
+            }
+        }
+    }
+
+    reachable
+}
+
+#[derive(PartialEq)]
+struct Visit {
+    cost: f32,
+    node: NodeIndex,
+    color: u32,
+}
+impl Eq for Visit {}
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so that the heap gives the cheapest first.
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.node.cmp(&self.node))
+            .then_with(|| other.color.cmp(&self.color))
+    }
+}
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Like `petgraph::algo::astar`, but keeping track of the color of a pixie as it
+/// drives, so that the path found delivers a pixie that left `start` as `start_color`
+/// to `goal` as `goal_color`. `convert` gives the color after following an edge.
+pub fn find_path<N>(
+    graph: &StableUnGraph<N, f32>,
+    start: NodeIndex,
+    goal: NodeIndex,
+    start_color: u32,
+    goal_color: u32,
+    mut edge_cost: impl FnMut(EdgeReference<f32>) -> f32,
+    mut convert: impl FnMut(NodeIndex, NodeIndex, u32) -> u32,
+) -> Option<(f32, Vec<NodeIndex>)> {
+    let mut costs = HashMap::default();
+    let mut came_from = HashMap::default();
+    let mut heap = BinaryHeap::new();
+
+    costs.insert((start, start_color), 0.0);
+    heap.push(Visit {
+        cost: 0.0,
+        node: start,
+        color: start_color,
+    });
+
+    while let Some(Visit { cost, node, color }) = heap.pop() {
+        if node == goal && color == goal_color {
+            let mut path = vec![node];
+            let mut state = (node, color);
+            while let Some(previous) = came_from.get(&state) {
+                state = *previous;
+                path.push(state.0);
+            }
+            path.reverse();
+
+            return Some((cost, path));
+        }
+
+        if costs.get(&(node, color)).is_some_and(|c| cost > *c) {
+            continue;
+        }
+
+        for edge in graph.edges(node) {
+            let next = edge.target();
+            let next_color = convert(node, next, color);
+            let next_cost = cost + edge_cost(edge);
+
+            if costs
+                .get(&(next, next_color))
+                .is_some_and(|c| next_cost >= *c)
+            {
+                continue;
+            }
+
+            costs.insert((next, next_color), next_cost);
+            came_from.insert((next, next_color), (node, color));
+            heap.push(Visit {
+                cost: next_cost,
+                node: next,
+                color: next_color,
+            });
+        }
+    }
+
+    None
+}
+
+fn spawn_converters_system(
+    mut commands: Commands,
+    levels: Res<Assets<Level>>,
+    handles: Res<Handles>,
+    selected_level: Res<SelectedLevel>,
+    theme: Res<Theme>,
+) {
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    for converter in level.converters.iter() {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::RegularPolygon {
+                    sides: 4,
+                    feature: shapes::RegularPolygonFeature::Radius(CONVERTER_RADIUS),
+                    ..default()
+                }),
+                transform: Transform::from_translation(converter.point.extend(layer::TERMINUS)),
+                ..default()
+            },
+            Fill::color(theme.pixie(converter.to)),
+            Stroke::new(theme.pixie(converter.from), 3.0),
+            converter.clone(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converter(x: f32, from: u32, to: u32) -> Converter {
+        Converter {
+            point: Vec2::new(x, 0.0),
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn convert_in_order() {
+        let converters = [converter(20.0, 1, 2), converter(10.0, 0, 1)];
+        let from = Vec2::ZERO;
+        let to = Vec2::new(30.0, 0.0);
+
+        assert_eq!(convert_along(&converters, from, to, 0), 2);
+        assert_eq!(convert_along(&converters, to, from, 0), 1);
+        // Not counted where the road starts.
+        assert_eq!(convert_along(&converters, Vec2::new(10.0, 0.0), to, 0), 0);
+        assert_eq!(convert_along(&converters, from, Vec2::new(10.0, 0.0), 0), 1);
+    }
+
+    #[test]
+    fn reachable() {
+        let converters = [
+            converter(0.0, 0, 1),
+            converter(0.0, 1, 2),
+            converter(0.0, 3, 0),
+        ];
+
+        assert_eq!(
+            reachable_colors(&converters, 0),
+            [0, 1, 2].into_iter().collect()
+        );
+        assert_eq!(reachable_colors(&converters, 2), [2].into_iter().collect());
+    }
+
+    #[test]
+    fn path_through_converter() {
+        // 0 - 1 - 2, with a longer way round from 0 to 2 through 3 that converts.
+        let mut graph = StableUnGraph::<(), f32>::default();
+        let nodes: Vec<_> = (0..4).map(|_| graph.add_node(())).collect();
+        graph.add_edge(nodes[0], nodes[1], 1.0);
+        graph.add_edge(nodes[1], nodes[2], 1.0);
+        graph.add_edge(nodes[0], nodes[3], 2.0);
+        graph.add_edge(nodes[3], nodes[2], 2.0);
+
+        let convert =
+            |_: NodeIndex, to: NodeIndex, color: u32| if to == nodes[3] { 5 } else { color };
+
+        let same = find_path(&graph, nodes[0], nodes[2], 0, 0, |e| *e.weight(), convert);
+        assert_eq!(same, Some((2.0, vec![nodes[0], nodes[1], nodes[2]])));
+
+        let converted = find_path(&graph, nodes[0], nodes[2], 0, 5, |e| *e.weight(), convert);
+        assert_eq!(converted, Some((4.0, vec![nodes[0], nodes[3], nodes[2]])));
+
+        assert!(find_path(&graph, nodes[0], nodes[2], 0, 6, |e| *e.weight(), convert).is_none());
+    }
+}
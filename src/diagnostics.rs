@@ -0,0 +1,163 @@
+//! An overlay of performance numbers, toggled with F3, so that reports of lag can say
+//! how bad it is and where. It shows the frame rate, how many simulation ticks ran
+//! each frame, how many pixies and entities there are, and how long the pixie grid
+//! takes to keep up to date.
+
+use crate::{
+    color,
+    controls::{not_rebinding, Action},
+    pixie::{Pixie, PixieGrid},
+    save::InputMap,
+    sim::SimulationSteps,
+    Handles,
+};
+use bevy::{
+    diagnostic::{
+        Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, EntityCountDiagnosticsPlugin,
+        FrameTimeDiagnosticsPlugin, RegisterDiagnostic,
+    },
+    prelude::*,
+};
+
+const SIM_TICKS: DiagnosticPath = DiagnosticPath::const_new("sim_ticks_per_frame");
+const PIXIES: DiagnosticPath = DiagnosticPath::const_new("pixies");
+const GRID_UPDATE_TIME: DiagnosticPath = DiagnosticPath::const_new("pixie_grid_update_time");
+
+pub struct DiagnosticsOverlayPlugin;
+impl Plugin for DiagnosticsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((FrameTimeDiagnosticsPlugin, EntityCountDiagnosticsPlugin));
+        app.register_diagnostic(Diagnostic::new(SIM_TICKS))
+            .register_diagnostic(Diagnostic::new(PIXIES))
+            .register_diagnostic(Diagnostic::new(GRID_UPDATE_TIME).with_suffix("ms"));
+
+        app.init_resource::<DiagnosticsOverlay>();
+
+        // After the simulation has run for the frame.
+        app.add_systems(PostUpdate, measure_system);
+        app.add_systems(
+            Update,
+            (toggle_overlay_system.run_if(not_rebinding), overlay_system).chain(),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct DiagnosticsOverlay {
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct DiagnosticsText;
+
+/// A smoothed value to show, or a dash before there is one.
+fn reading(value: Option<f64>, precision: usize) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{v:.precision$}"))
+}
+
+fn measure_system(
+    mut diagnostics: Diagnostics,
+    mut last_step: Local<u32>,
+    steps: Res<SimulationSteps>,
+    mut grid: ResMut<PixieGrid>,
+    q_pixies: Query<(), With<Pixie>>,
+) {
+    // Steps start over with each run, and go back when rewinding.
+    let step = steps.step();
+    let ticks = step.saturating_sub(*last_step);
+    *last_step = step;
+
+    let grid_time = std::mem::take(&mut grid.update_time);
+
+    diagnostics.add_measurement(&SIM_TICKS, || ticks as f64);
+    diagnostics.add_measurement(&PIXIES, || q_pixies.iter().count() as f64);
+    diagnostics.add_measurement(&GRID_UPDATE_TIME, || grid_time.as_secs_f64() * 1000.0);
+}
+
+fn toggle_overlay_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut overlay: ResMut<DiagnosticsOverlay>,
+) {
+    if input_map.just_pressed(Action::Diagnostics, &keyboard_input) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+fn overlay_system(
+    mut commands: Commands,
+    overlay: Res<DiagnosticsOverlay>,
+    store: Res<DiagnosticsStore>,
+    handles: Res<Handles>,
+    mut q_text: Query<(Entity, &mut Text), With<DiagnosticsText>>,
+) {
+    if !overlay.enabled {
+        for (entity, _) in q_text.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let value = |path: &DiagnosticPath| store.get(path).and_then(|d| d.smoothed());
+
+    let text = [
+        format!(
+            "FPS {} ({}MS)",
+            reading(value(&FrameTimeDiagnosticsPlugin::FPS), 0),
+            reading(value(&FrameTimeDiagnosticsPlugin::FRAME_TIME), 1)
+        ),
+        format!("TICKS/FRAME {}", reading(value(&SIM_TICKS), 1)),
+        format!("PIXIES {}", reading(value(&PIXIES), 0)),
+        format!(
+            "ENTITIES {}",
+            reading(value(&EntityCountDiagnosticsPlugin::ENTITY_COUNT), 0)
+        ),
+        format!("GRID {}MS", reading(value(&GRID_UPDATE_TIME), 2)),
+    ]
+    .join("\n");
+
+    // Leaving a screen despawns everything on it, so the overlay comes back if it was
+    // taken along with it.
+    if let Ok((_, mut current)) = q_text.get_single_mut() {
+        if current.0 != text {
+            current.0 = text;
+        }
+        return;
+    }
+
+    let Some(font) = handles.fonts.first() else {
+        return;
+    };
+
+    commands.spawn((
+        Text::new(text),
+        TextFont {
+            font: font.clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(color::UI_WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(10.),
+            padding: UiRect::all(Val::Px(6.)),
+            ..default()
+        },
+        BackgroundColor(color::DIALOG_BACKGROUND.with_alpha(0.8)),
+        GlobalZIndex(2),
+        DiagnosticsText,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readings() {
+        assert_eq!(reading(None, 1), "-");
+        assert_eq!(reading(Some(16.66), 1), "16.7");
+        assert_eq!(reading(Some(59.6), 0), "60");
+    }
+}
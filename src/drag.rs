@@ -0,0 +1,406 @@
+//! A tool for grabbing the end of a road, or a junction where several roads meet, and
+//! moving it to another grid point without ripping and redrawing the roads.
+
+use crate::{
+    collision::{point_segment_collision, segment_collision, SegmentCollision},
+    color,
+    hud::Hud,
+    layer,
+    level::ObstacleLayers,
+    sim::SimulationState,
+    Collider, ColliderLayer, DrawingInteraction, DrawingMode, DrawingMouseMovement, DrawingState,
+    MouseState, RoadGraph, RoadSegment, SegmentGraphNodes,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct DragPlugin;
+impl Plugin for DragPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DragState>();
+
+        app.add_systems(
+            Update,
+            drag_mouse_movement_system.in_set(DrawingMouseMovement),
+        );
+        app.add_systems(
+            Update,
+            (drag_mouse_click_system, draw_drag_system)
+                .chain()
+                .in_set(DrawingInteraction),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct DragButton;
+#[derive(Component)]
+struct DragLine;
+
+#[derive(Resource, Default)]
+pub struct DragState {
+    /// The junction under the cursor, which will be picked up when clicked.
+    hovered: Option<Junction>,
+    /// The junction that was picked up, and will be dropped at `target`.
+    dragging: Option<Junction>,
+    target: Vec2,
+    valid: bool,
+}
+
+/// A point where one or more road segments end.
+#[derive(Clone, Debug, PartialEq)]
+struct Junction {
+    point: Vec2,
+    ends: Vec<SegmentEnd>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct SegmentEnd {
+    entity: Entity,
+    /// Whether the junction is at the segment's first point.
+    first: bool,
+    /// The other end of the segment, which stays put.
+    fixed: Vec2,
+    layer: u32,
+}
+
+impl Junction {
+    /// The segments that would result from moving the junction to `target`.
+    fn moved(&self, target: Vec2) -> Vec<((Vec2, Vec2), u32)> {
+        self.ends
+            .iter()
+            .map(|end| ((end.fixed, target), end.layer))
+            .collect()
+    }
+}
+
+fn drag_mouse_movement_system(
+    drawing_state: Res<DrawingState>,
+    mouse: Res<MouseState>,
+    mut drag: ResMut<DragState>,
+    sim_state: Res<SimulationState>,
+    graph: Res<RoadGraph>,
+    q_segments: Query<(Entity, &RoadSegment)>,
+    q_colliders: Query<(&Parent, &Collider, &ColliderLayer)>,
+    q_obstacle_layers: Query<&ObstacleLayers>,
+) {
+    if !matches!(drawing_state.mode, DrawingMode::Dragging)
+        || *sim_state != SimulationState::NotStarted
+    {
+        if drag.hovered.is_some() || drag.dragging.is_some() {
+            *drag = DragState::default();
+        }
+        return;
+    }
+
+    if !mouse.is_changed() && !drawing_state.is_changed() && !graph.is_changed() {
+        return;
+    }
+
+    let Some(junction) = drag.dragging.as_ref() else {
+        let hovered = find_junction(mouse.snapped, &q_segments, &q_colliders);
+        if drag.hovered != hovered {
+            drag.hovered = hovered;
+        }
+        return;
+    };
+
+    let valid = mouse.snapped == junction.point
+        || valid_move(
+            &junction.moved(mouse.snapped),
+            q_colliders
+                .iter()
+                .filter(|(parent, _, _)| junction.ends.iter().all(|e| e.entity != parent.get()))
+                .map(|(parent, collider, layer)| {
+                    (
+                        collider,
+                        layer.0,
+                        q_obstacle_layers.get(parent.get()).ok().copied(),
+                    )
+                }),
+        );
+
+    drag.target = mouse.snapped;
+    drag.valid = valid;
+}
+
+fn find_junction(
+    point: Vec2,
+    q_segments: &Query<(Entity, &RoadSegment)>,
+    q_colliders: &Query<(&Parent, &Collider, &ColliderLayer)>,
+) -> Option<Junction> {
+    // Roads that end at a terminus stay attached to it.
+    if q_colliders
+        .iter()
+        .any(|(_, collider, _)| matches!(collider, Collider::Point(p) if *p == point))
+    {
+        return None;
+    }
+
+    let ends: Vec<_> = q_segments
+        .iter()
+        .filter_map(|(entity, segment)| {
+            let (first, fixed) = if segment.points.0 == point {
+                (true, segment.points.1)
+            } else if segment.points.1 == point {
+                (false, segment.points.0)
+            } else {
+                return None;
+            };
+
+            Some(SegmentEnd {
+                entity,
+                first,
+                fixed,
+                layer: segment.layer,
+            })
+        })
+        .collect();
+
+    (!ends.is_empty()).then_some(Junction { point, ends })
+}
+
+/// Whether the `moved` segments and their layers can be placed among `colliders` and
+/// their layers, which should not include the colliders of the segments being moved.
+/// Obstacle colliders may only block some layers.
+///
+/// The moved segments must stay straight or diagonal, and may only touch other
+/// things at their fixed ends, where they were already connected.
+fn valid_move<'a>(
+    moved: &[((Vec2, Vec2), u32)],
+    colliders: impl IntoIterator<Item = (&'a Collider, u32, Option<ObstacleLayers>)>,
+) -> bool {
+    for ((fixed, target), _) in moved {
+        let diff = *target - *fixed;
+        if diff == Vec2::ZERO || (diff.x != 0.0 && diff.y != 0.0 && diff.x.abs() != diff.y.abs()) {
+            return false;
+        }
+    }
+
+    for (i, (a, _)) in moved.iter().enumerate() {
+        for (b, _) in moved.iter().skip(i + 1) {
+            if matches!(
+                segment_collision(a.0, a.1, b.0, b.1),
+                SegmentCollision::Overlapping
+            ) {
+                return false;
+            }
+        }
+    }
+
+    colliders
+        .into_iter()
+        .all(|(collider, layer, obstacle_layers)| {
+            moved.iter().all(|((fixed, target), moved_layer)| {
+                if obstacle_layers.is_some_and(|l| !l.blocks(*moved_layer)) {
+                    return true;
+                }
+
+                match collider {
+                    Collider::Segment(s) => match segment_collision(s.0, s.1, *fixed, *target) {
+                        SegmentCollision::None => true,
+                        SegmentCollision::Intersecting => layer != 0 && layer != *moved_layer,
+                        SegmentCollision::Connecting | SegmentCollision::ConnectingParallel => {
+                            layer != 0
+                                && matches!(
+                                    point_segment_collision(*fixed, s.0, s.1),
+                                    SegmentCollision::Connecting
+                                )
+                                && matches!(
+                                    point_segment_collision(*target, s.0, s.1),
+                                    SegmentCollision::None
+                                )
+                        }
+                        SegmentCollision::Touching | SegmentCollision::Overlapping => false,
+                    },
+                    Collider::Point(p) => match point_segment_collision(*p, *fixed, *target) {
+                        SegmentCollision::None => true,
+                        SegmentCollision::Connecting => *p == *fixed,
+                        _ => false,
+                    },
+                }
+            })
+        })
+}
+
+fn drag_mouse_click_system(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mouse: Res<MouseState>,
+    hud: Res<Hud>,
+    drawing_state: Res<DrawingState>,
+    mut drag: ResMut<DragState>,
+    sim_state: Res<SimulationState>,
+    mut graph: ResMut<RoadGraph>,
+    q_window: Query<&Window>,
+    mut q_segments: Query<(&mut RoadSegment, &mut Path, &SegmentGraphNodes, &Children)>,
+    mut q_colliders: Query<&mut Collider>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    if mouse.window_position.y > window.resolution.height() - hud.bar_height() {
+        return;
+    }
+
+    if !matches!(drawing_state.mode, DrawingMode::Dragging) {
+        return;
+    }
+
+    if *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(junction) = drag.dragging.clone() else {
+        if let Some(hovered) = drag.hovered.take() {
+            drag.target = hovered.point;
+            drag.valid = true;
+            drag.dragging = Some(hovered);
+        }
+        return;
+    };
+
+    if !drag.valid {
+        return;
+    }
+
+    drag.dragging = None;
+
+    if drag.target == junction.point {
+        return;
+    }
+
+    for end in junction.ends.iter() {
+        let Ok((mut segment, mut path, nodes, children)) = q_segments.get_mut(end.entity) else {
+            continue;
+        };
+
+        if end.first {
+            segment.points.0 = drag.target;
+        } else {
+            segment.points.1 = drag.target;
+        }
+
+        *path = GeometryBuilder::build_as(&shapes::Line(segment.points.0, segment.points.1));
+
+        for child in children.iter() {
+            if let Ok(mut collider) = q_colliders.get_mut(*child) {
+                *collider = Collider::Segment(segment.points);
+            }
+        }
+
+        graph.graph.update_edge(
+            nodes.0,
+            nodes.1,
+            (segment.points.0 - segment.points.1).length(),
+        );
+    }
+}
+
+fn draw_drag_system(
+    mut commands: Commands,
+    drag: Res<DragState>,
+    q_lines: Query<Entity, With<DragLine>>,
+) {
+    if !drag.is_changed() {
+        return;
+    }
+
+    for entity in q_lines.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if let Some(junction) = drag.hovered.as_ref() {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Circle {
+                    radius: 8.0,
+                    ..default()
+                }),
+                transform: Transform::from_translation(junction.point.extend(layer::ROAD_OVERLAY)),
+                ..default()
+            },
+            Stroke::new(bevy::color::palettes::css::ORANGE, 2.0),
+            DragLine,
+        ));
+    }
+
+    let Some(junction) = drag.dragging.as_ref() else {
+        return;
+    };
+
+    for ((a, b), segment_layer) in junction.moved(drag.target) {
+        let color = if drag.valid {
+            color::DRAWING_ROAD[segment_layer as usize - 1]
+        } else {
+            bevy::color::palettes::css::RED.into()
+        };
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Line(a, b)),
+                transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                ..default()
+            },
+            Stroke::new(color, 2.0),
+            DragLine,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_stay_straight_or_diagonal() {
+        let fixed = Vec2::new(0.0, 0.0);
+
+        assert!(valid_move(&[((fixed, Vec2::new(0.0, 2.0)), 1)], []));
+        assert!(valid_move(&[((fixed, Vec2::new(2.0, 2.0)), 1)], []));
+        assert!(!valid_move(&[((fixed, Vec2::new(1.0, 2.0)), 1)], []));
+        assert!(!valid_move(&[((fixed, fixed), 1)], []));
+    }
+
+    #[test]
+    fn moves_respect_colliders() {
+        let moved = [((Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0)), 1)];
+        let crossing = Collider::Segment((Vec2::new(1.0, -1.0), Vec2::new(1.0, 1.0)));
+        let neighbor = Collider::Segment((Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0)));
+        let target_terminus = Collider::Point(Vec2::new(2.0, 0.0));
+
+        assert!(valid_move(&moved, [(&crossing, 2, None)]));
+        assert!(!valid_move(&moved, [(&crossing, 1, None)]));
+        assert!(!valid_move(&moved, [(&crossing, 0, None)]));
+        assert!(valid_move(
+            &moved,
+            [(&crossing, 0, Some(ObstacleLayers::new(&[2])))]
+        ));
+        assert!(valid_move(&moved, [(&neighbor, 1, None)]));
+        assert!(!valid_move(&moved, [(&target_terminus, 0, None)]));
+    }
+
+    #[test]
+    fn junction_segments_cannot_overlap() {
+        let target = Vec2::new(2.0, 0.0);
+
+        assert!(valid_move(
+            &[
+                ((Vec2::new(0.0, 0.0), target), 1),
+                ((Vec2::new(2.0, 2.0), target), 1)
+            ],
+            []
+        ));
+        assert!(!valid_move(
+            &[
+                ((Vec2::new(0.0, 0.0), target), 1),
+                ((Vec2::new(1.0, 0.0), target), 1)
+            ],
+            []
+        ));
+    }
+}
@@ -0,0 +1,244 @@
+//! Erasing single roads. Holding the right mouse button and dragging over roads removes
+//! each one it touches, rather than the whole net like the net ripping tool. Whatever
+//! met at the erased road's ends stays connected, and two straight roads left meeting
+//! end to end are joined back into one.
+
+use crate::{
+    hud::Hud,
+    segment_near_point,
+    sfx::{PlaySfx, Sfx},
+    sim::SimulationState,
+    spawn_road_segment, Collider, ColliderLayer, DrawingInteraction, LineDrawingState, MainCamera,
+    MouseState, RoadGraph, RoadSegment, SegmentGraphNodes, NET_RIPPING_HIT_RADIUS,
+};
+use bevy::prelude::*;
+use itertools::Itertools;
+use petgraph::stable_graph::NodeIndex;
+
+pub struct ErasePlugin;
+impl Plugin for ErasePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, erase_mouse_system.in_set(DrawingInteraction));
+    }
+}
+
+fn erase_mouse_system(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mouse: Res<MouseState>,
+    hud: Res<Hud>,
+    line_state: Res<LineDrawingState>,
+    sim_state: Res<SimulationState>,
+    mut graph: ResMut<RoadGraph>,
+    mut sfx: EventWriter<PlaySfx>,
+    q_colliders: Query<(&Parent, &Collider, &ColliderLayer)>,
+    q_segments: Query<(&RoadSegment, &SegmentGraphNodes)>,
+    q_window: Query<&Window>,
+    q_projection: Query<&OrthographicProjection, With<MainCamera>>,
+) {
+    if !mouse_input.pressed(MouseButton::Right) {
+        return;
+    }
+
+    if !mouse_input.just_pressed(MouseButton::Right) && !mouse.is_changed() {
+        return;
+    }
+
+    if *sim_state != SimulationState::NotStarted || line_state.drawing {
+        return;
+    }
+
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    if mouse.window_position.y > window.resolution.height() - hud.bar_height() {
+        return;
+    }
+
+    let scale = q_projection.get_single().map_or(1.0, |p| p.scale);
+
+    let Some(entity) =
+        segment_near_point(mouse.position, NET_RIPPING_HIT_RADIUS * scale, &q_colliders)
+    else {
+        return;
+    };
+
+    let Ok((segment, nodes)) = q_segments.get(entity) else {
+        return;
+    };
+
+    // Anything that met this road at one of its ends should still meet after it's gone.
+    let ends = [(segment.points.0, nodes.0), (segment.points.1, nodes.1)].map(|(point, node)| {
+        let neighbors: Vec<_> = graph
+            .graph
+            .neighbors(node)
+            .filter(|n| *n != nodes.0 && *n != nodes.1)
+            .unique()
+            .collect();
+        (point, neighbors)
+    });
+
+    graph.graph.remove_node(nodes.0);
+    graph.graph.remove_node(nodes.1);
+    commands.entity(entity).despawn_recursive();
+
+    for (_, neighbors) in ends.iter() {
+        for (a, b) in neighbors
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| neighbors[i + 1..].iter().map(move |b| (*a, *b)))
+        {
+            if graph.graph.find_edge(a, b).is_none() {
+                graph.graph.add_edge(a, b, 0.0);
+            }
+        }
+    }
+
+    for (point, neighbors) in ends.iter() {
+        rejoin(&mut commands, &mut graph, *point, neighbors, &q_segments);
+    }
+
+    sfx.send(PlaySfx(Sfx::Rip));
+}
+
+/// If exactly two roads are left meeting at `point` and nothing else is there, replaces
+/// them with a single road when they line up.
+fn rejoin(
+    commands: &mut Commands,
+    graph: &mut RoadGraph,
+    point: Vec2,
+    neighbors: &[NodeIndex],
+    q_segments: &Query<(&RoadSegment, &SegmentGraphNodes)>,
+) {
+    let [a, b] = neighbors else {
+        return;
+    };
+
+    // Already joined up at the other end.
+    if !graph.graph.contains_node(*a) || !graph.graph.contains_node(*b) {
+        return;
+    }
+
+    let entities = [a, b].map(|node| graph.graph.node_weight(*node).copied());
+    let [Some(entity_a), Some(entity_b)] = entities else {
+        return;
+    };
+
+    // The same road looping back on itself.
+    if entity_a == entity_b {
+        return;
+    }
+
+    // Fails for a terminus.
+    let (Ok((segment_a, nodes_a)), Ok((segment_b, nodes_b))) =
+        (q_segments.get(entity_a), q_segments.get(entity_b))
+    else {
+        return;
+    };
+
+    let Some(joined) = join(segment_a, segment_b, point) else {
+        return;
+    };
+
+    let far_node = |segment: &RoadSegment, nodes: &SegmentGraphNodes| {
+        if segment.points.0 == point {
+            nodes.1
+        } else {
+            nodes.0
+        }
+    };
+    let far_a = far_node(segment_a, nodes_a);
+    let far_b = far_node(segment_b, nodes_b);
+
+    let neighbors_a: Vec<_> = graph.graph.neighbors(far_a).collect();
+    let neighbors_b: Vec<_> = graph.graph.neighbors(far_b).collect();
+
+    for node in [nodes_a.0, nodes_a.1, nodes_b.0, nodes_b.1] {
+        graph.graph.remove_node(node);
+    }
+    commands.entity(entity_a).despawn_recursive();
+    commands.entity(entity_b).despawn_recursive();
+
+    let (_, start_node, end_node) = spawn_road_segment(commands, graph, joined);
+
+    for neighbor in neighbors_a {
+        if graph.graph.contains_node(neighbor) {
+            graph.graph.add_edge(neighbor, start_node, 0.0);
+        }
+    }
+    for neighbor in neighbors_b {
+        if graph.graph.contains_node(neighbor) {
+            graph.graph.add_edge(end_node, neighbor, 0.0);
+        }
+    }
+}
+
+/// The single road covering `a` and `b`, which meet at `point`, if they're straight
+/// continuations of each other on the same layer.
+fn join(a: &RoadSegment, b: &RoadSegment, point: Vec2) -> Option<RoadSegment> {
+    let far = |segment: &RoadSegment| {
+        if segment.points.0 == point {
+            Some(segment.points.1)
+        } else if segment.points.1 == point {
+            Some(segment.points.0)
+        } else {
+            None
+        }
+    };
+
+    let (start, end) = (far(a)?, far(b)?);
+
+    let dir_a = (point - start).normalize_or_zero();
+    let dir_b = (end - point).normalize_or_zero();
+
+    if a.layer != b.layer
+        || a.wide != b.wide
+        || a.bias != b.bias
+        || dir_a.perp_dot(dir_b).abs() >= 1e-4
+        || dir_a.dot(dir_b) <= 0.0
+    {
+        return None;
+    }
+
+    Some(RoadSegment {
+        points: (start, end),
+        ..a.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::seg;
+
+    #[test]
+    fn join_straight() {
+        let point = Vec2::new(1.0, 0.0);
+
+        // Either end of either road may be the one at the joint.
+        let joined = join(
+            &seg((0.0, 0.0), (1.0, 0.0), 1),
+            &seg((3.0, 0.0), (1.0, 0.0), 1),
+            point,
+        );
+        assert_eq!(
+            joined.map(|s| s.points),
+            Some((Vec2::ZERO, Vec2::new(3.0, 0.0)))
+        );
+
+        // Corners and layer changes stay as they are.
+        assert!(join(
+            &seg((0.0, 0.0), (1.0, 0.0), 1),
+            &seg((1.0, 0.0), (1.0, 1.0), 1),
+            point
+        )
+        .is_none());
+        assert!(join(
+            &seg((0.0, 0.0), (1.0, 0.0), 1),
+            &seg((1.0, 0.0), (2.0, 0.0), 2),
+            point
+        )
+        .is_none());
+    }
+}
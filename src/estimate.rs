@@ -0,0 +1,247 @@
+//! A quick analytic estimate of how long a run will take and what it might score,
+//! cheap enough to update after every edit. It ignores most pixie interactions, so
+//! it is only a rough guide.
+
+use crate::{
+    collision::{segment_collision, SegmentCollision},
+    level::{EmitterSchedule, Level, ScoreWeights, Terminus},
+    lines::corner_angle,
+    pixie::{corner_speed_limit, PIXIE_ACCELERATION, PIXIE_DECELERATION, PIXIE_MAX_SPEED},
+    sim::{EmissionStrategy, SimulationSettings, SimulationState},
+    AfterUpdate, Cost, Handles, PathfindingState, RoadSegment, ScoreUi, SelectedLevel,
+};
+use bevy::{prelude::*, utils::HashMap};
+
+/// Extra travel time, as a fraction of the time spent on them, for stretches of road
+/// shared with another path.
+const SHARED_ROAD_SLOWDOWN: f32 = 0.5;
+
+pub struct EstimatePlugin;
+impl Plugin for EstimatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(AfterUpdate, update_estimate_text_system.in_set(ScoreUi));
+    }
+}
+
+#[derive(Component)]
+pub struct EstimateText;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub duration: f32,
+    pub pixies: u32,
+}
+
+impl Estimate {
+    pub fn score(&self, cost: u32, weights: &ScoreWeights) -> u32 {
+        weights.score(self.pixies, cost, self.duration)
+    }
+}
+
+/// Estimates a run from the emitting terminus and path of each emitter, in the order
+/// the emitters are spawned, how they take turns, and the schedule of each emitting
+/// terminus.
+pub fn estimate_run<'a>(
+    paths: impl IntoIterator<Item = (Entity, &'a [RoadSegment])>,
+    strategy: EmissionStrategy,
+    schedule: impl Fn(Entity) -> EmitterSchedule,
+) -> Estimate {
+    let paths: Vec<_> = paths.into_iter().collect();
+
+    let mut counts: HashMap<Entity, u32> = HashMap::default();
+    for (start, _) in paths.iter() {
+        *counts.entry(*start).or_default() += 1;
+    }
+
+    let mut is: HashMap<Entity, u32> = HashMap::default();
+    let mut duration: f32 = 0.0;
+    let mut total_pixies = 0;
+
+    for (index, (start, path)) in paths.iter().enumerate() {
+        let i = is.entry(*start).or_default();
+        let count = counts[start];
+        let schedule = schedule(*start);
+        let pixies = schedule.pixies / count;
+
+        // mirrors the emitter staggering in `spawn_emitters`
+        let last_emitted = schedule.delay
+            + strategy.release_time(pixies.saturating_sub(1), *i, count, schedule.interval);
+
+        let others = paths
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .map(|(_, (_, other_path))| *other_path);
+
+        duration = duration.max(last_emitted + travel_time(path, others));
+        total_pixies += pixies;
+        *i += 1;
+    }
+
+    Estimate {
+        duration,
+        pixies: total_pixies,
+    }
+}
+
+/// Estimates how long a single pixie takes to travel `path`, given the other paths
+/// that might share some of its roads.
+fn travel_time<'a>(path: &[RoadSegment], others: impl Iterator<Item = &'a [RoadSegment]>) -> f32 {
+    let others: Vec<_> = others.flatten().collect();
+
+    let mut time = 0.0;
+
+    for segment in path.iter() {
+        let length = segment.points.0.distance(segment.points.1);
+        let mut segment_time = length / PIXIE_MAX_SPEED;
+
+        let shared = others.iter().any(|other| {
+            other.layer == segment.layer
+                && matches!(
+                    segment_collision(
+                        segment.points.0,
+                        segment.points.1,
+                        other.points.0,
+                        other.points.1
+                    ),
+                    SegmentCollision::Overlapping
+                )
+        });
+        if shared {
+            segment_time *= 1.0 + SHARED_ROAD_SLOWDOWN;
+        }
+
+        time += segment_time;
+    }
+
+    for (a, b) in path.iter().zip(path.iter().skip(1)) {
+        let angle = corner_angle(a.points.0, b.points.0, b.points.1).to_degrees();
+
+        if let Some((max_speed, _)) = corner_speed_limit(angle) {
+            time += corner_penalty(max_speed);
+        }
+    }
+
+    time
+}
+
+/// Time lost by slowing down to `speed` for a corner and getting back up to speed,
+/// compared to driving the same distance at full speed.
+fn corner_penalty(speed: f32) -> f32 {
+    let dv = PIXIE_MAX_SPEED - speed;
+    let dv2 = PIXIE_MAX_SPEED.powi(2) - speed.powi(2);
+
+    let time = dv / PIXIE_DECELERATION + dv / PIXIE_ACCELERATION;
+    let distance = dv2 / (2.0 * PIXIE_DECELERATION) + dv2 / (2.0 * PIXIE_ACCELERATION);
+
+    time - distance / PIXIE_MAX_SPEED
+}
+
+fn update_estimate_text_system(
+    pathfinding: Res<PathfindingState>,
+    sim_state: Res<SimulationState>,
+    simulation_settings: Res<SimulationSettings>,
+    cost: Res<Cost>,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    q_terminuses: Query<&Terminus>,
+    mut q_text: Query<&mut Text, With<EstimateText>>,
+) {
+    if !pathfinding.is_changed()
+        && !sim_state.is_changed()
+        && !simulation_settings.is_changed()
+        && !cost.is_changed()
+    {
+        return;
+    }
+
+    let level = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h));
+
+    let text = if let Some(level) =
+        level.filter(|_| pathfinding.valid && *sim_state == SimulationState::NotStarted)
+    {
+        let estimate = estimate_run(
+            pathfinding
+                .paths
+                .iter()
+                .map(|(_, start, path)| (*start, path.as_ref())),
+            simulation_settings.emission,
+            |entity| {
+                q_terminuses.get(entity).map_or_else(
+                    |_| Terminus::default().schedule(level.pixies),
+                    |t| t.schedule(level.pixies),
+                )
+            },
+        );
+
+        format!(
+            "~ŧ{:.1} ~Æ{}",
+            estimate.duration,
+            estimate.score(cost.0, &level.scoring)
+        )
+    } else {
+        "".to_string()
+    };
+
+    for mut estimate_text in q_text.iter_mut() {
+        estimate_text.0.clone_from(&text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::seg;
+    use crate::PIXIES_PER_TERMINUS;
+
+    #[test]
+    fn straight_path() {
+        let path = [seg((0.0, 0.0), (600.0, 0.0), 1)];
+        assert_eq!(travel_time(&path, std::iter::empty()), 10.0);
+    }
+
+    #[test]
+    fn corners_and_sharing_are_slower() {
+        let straight = [seg((0.0, 0.0), (600.0, 0.0), 1)];
+        let bent = [
+            seg((0.0, 0.0), (300.0, 0.0), 1),
+            seg((300.0, 0.0), (300.0, 300.0), 1),
+        ];
+
+        assert!(travel_time(&bent, std::iter::empty()) > 10.0);
+        assert!(travel_time(&straight, [&straight[..]].into_iter()) > 10.0);
+    }
+
+    #[test]
+    fn staggered_emitters() {
+        let a = Entity::from_raw(0);
+        let path = [seg((0.0, 0.0), (600.0, 0.0), 1)];
+
+        let schedule = |_| Terminus::default().schedule(PIXIES_PER_TERMINUS);
+        let turns = EmissionStrategy::RoundRobin;
+
+        let one = estimate_run([(a, &path[..])], turns, schedule);
+        let two = estimate_run([(a, &path[..]), (a, &path[..])], turns, schedule);
+
+        assert_eq!(one.pixies, PIXIES_PER_TERMINUS);
+        assert_eq!(two.pixies, PIXIES_PER_TERMINUS);
+        assert!(two.duration >= one.duration);
+
+        let delayed = Terminus {
+            pixies: Some(10),
+            delay: 5.0,
+            ..default()
+        };
+        let three = estimate_run([(a, &path[..])], turns, |_| {
+            delayed.schedule(PIXIES_PER_TERMINUS)
+        });
+
+        // Waits for the delay, then the last of ten pixies leaves nine intervals later.
+        assert_eq!(three.pixies, 10);
+        assert!((three.duration - (5.0 + 9.0 * 0.4 + 10.0)).abs() < 1e-3);
+    }
+}
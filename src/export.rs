@@ -0,0 +1,183 @@
+//! Saving a picture of the finished board to share. The play area is rendered again by
+//! a second camera into an image, without any of the UI on top, and written out as a
+//! PNG. On the web, the browser downloads it instead.
+
+use crate::{color, viewport::PLAY_AREA, Cursor, GameState, Handles, SelectedLevel};
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{RenderTarget, ScalingMode},
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        view::screenshot::{save_to_disk, Screenshot, ScreenshotCaptured},
+    },
+};
+
+/// Pixels in the image per unit on the board.
+const EXPORT_SCALE: f32 = 2.0;
+
+pub struct ExportPlugin;
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImageExport>();
+
+        app.add_systems(
+            Update,
+            (export_button_system, capture_system)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(OnExit(GameState::Playing), cancel_export_system);
+    }
+}
+
+#[derive(Component)]
+pub struct ExportImageButton;
+#[derive(Component)]
+pub struct ExportImageText;
+#[derive(Component)]
+struct ExportCamera;
+
+/// An image being rendered, waiting to be captured.
+#[derive(Resource, Default)]
+struct ImageExport {
+    pending: Option<PendingExport>,
+}
+
+struct PendingExport {
+    image: Handle<Image>,
+    camera: Entity,
+    file_name: String,
+}
+
+/// The name of the file a picture of the level with `id` is saved as.
+pub fn export_file_name(id: &str) -> String {
+    format!("pixie_wrangler.{}.png", id.replace('/', "-"))
+}
+
+fn export_button_system(
+    mut commands: Commands,
+    mut export: ResMut<ImageExport>,
+    mut images: ResMut<Assets<Image>>,
+    handles: Res<Handles>,
+    selected_level: Res<SelectedLevel>,
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<ExportImageButton>)>,
+    q_cursor: Query<Entity, With<Cursor>>,
+    mut q_text: Query<&mut Text, With<ExportImageText>>,
+) {
+    if !q_interaction.iter().any(|i| *i == Interaction::Pressed) || export.pending.is_some() {
+        return;
+    }
+
+    let size = (PLAY_AREA.size() * EXPORT_SCALE).as_uvec2();
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_DST
+        | TextureUsages::COPY_SRC
+        | TextureUsages::RENDER_ATTACHMENT;
+    let image = images.add(image);
+
+    // UI only goes to the window's camera, so this one sees just the board.
+    let camera = commands
+        .spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(image.clone()),
+                clear_color: ClearColorConfig::Custom(color::BACKGROUND),
+                order: -1,
+                ..default()
+            },
+            OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: PLAY_AREA.width(),
+                    height: PLAY_AREA.height(),
+                },
+                ..OrthographicProjection::default_2d()
+            },
+            Transform::from_translation(PLAY_AREA.center().extend(0.0)),
+            Msaa::Sample4,
+            ExportCamera,
+        ))
+        .id();
+
+    // The cursor is drawn on the board, but isn't part of the picture. It comes back
+    // when the mouse moves.
+    for entity in q_cursor.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    export.pending = Some(PendingExport {
+        image,
+        camera,
+        file_name: export_file_name(&handles.level_id(selected_level.0)),
+    });
+
+    for mut text in q_text.iter_mut() {
+        text.0 = "SAVING".to_string();
+    }
+}
+
+/// Captures the image a frame after the camera was added, once it has been rendered to.
+fn capture_system(
+    mut commands: Commands,
+    mut export: ResMut<ImageExport>,
+    q_added: Query<(), Added<ExportCamera>>,
+) {
+    if !q_added.is_empty() {
+        return;
+    }
+
+    let Some(pending) = export.pending.take() else {
+        return;
+    };
+
+    let camera = pending.camera;
+
+    info!("Saving an image of the board to {}", pending.file_name);
+
+    commands
+        .spawn(Screenshot::image(pending.image))
+        .observe(save_to_disk(pending.file_name))
+        .observe(
+            move |_: Trigger<ScreenshotCaptured>,
+                  mut commands: Commands,
+                  mut q_text: Query<&mut Text, With<ExportImageText>>| {
+                if let Some(mut entity) = commands.get_entity(camera) {
+                    entity.despawn();
+                }
+
+                for mut text in q_text.iter_mut() {
+                    text.0 = "SAVED".to_string();
+                }
+            },
+        );
+}
+
+/// Everything on the board, including the camera, is despawned when leaving it.
+fn cancel_export_system(mut export: ResMut<ImageExport>) {
+    export.pending = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names() {
+        assert_eq!(export_file_name("3"), "pixie_wrangler.3.png");
+        assert_eq!(
+            export_file_name("custom/spaghetti"),
+            "pixie_wrangler.custom-spaghetti.png"
+        );
+    }
+}
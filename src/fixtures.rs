@@ -0,0 +1,33 @@
+//! Roads and levels for tests, so that each test module doesn't build its own.
+
+use crate::{bias::RouteBias, level::Level, RoadSegment, PIXIES_PER_TERMINUS};
+use bevy::prelude::*;
+
+/// A narrow road from `a` to `b` with no route bias.
+pub fn seg(a: (f32, f32), b: (f32, f32), layer: u32) -> RoadSegment {
+    RoadSegment {
+        points: (Vec2::from(a), Vec2::from(b)),
+        layer,
+        wide: false,
+        bias: RouteBias::default(),
+    }
+}
+
+/// A level with `layers` layers and nothing on them, to fill in with struct update
+/// syntax.
+pub fn level(layers: u32) -> Level {
+    Level {
+        name: "Test".to_string(),
+        name_position: Vec2::ZERO,
+        layers,
+        terminuses: vec![],
+        obstacles: vec![],
+        converters: vec![],
+        star_thresholds: vec![],
+        via_cost: 0.0,
+        max_segment_length: None,
+        scoring: default(),
+        pixies: PIXIES_PER_TERMINUS,
+        tutorial: vec![],
+    }
+}
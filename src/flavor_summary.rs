@@ -0,0 +1,155 @@
+//! A breakdown of the run by pixie color, shown while the simulation is running, so
+//! that a net that is losing pixies stands out from the ones that are doing fine.
+
+use crate::{
+    color,
+    level::Level,
+    pixie::Pixie,
+    playing_enter_system,
+    sim::{SimulationState, SimulationTimeline},
+    theme::Theme,
+    GameState, Handles, SelectedLevel, BOTTOM_BAR_HEIGHT,
+};
+use bevy::{prelude::*, utils::HashMap};
+use itertools::Itertools;
+
+pub struct FlavorSummaryPlugin;
+impl Plugin for FlavorSummaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_flavor_summary_system.after(playing_enter_system),
+        );
+        app.add_systems(
+            Update,
+            update_flavor_summary_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct FlavorSummary;
+
+#[derive(Component)]
+struct FlavorSummaryText(u32);
+
+fn spawn_flavor_summary_system(
+    mut commands: Commands,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
+    theme: Res<Theme>,
+) {
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    let colors: Vec<_> = level
+        .terminuses
+        .iter()
+        .flat_map(|t| t.emits.iter().chain(t.collects.iter()))
+        .map(|f| f.color)
+        .chain(level.converters.iter().map(|c| c.to))
+        .unique()
+        .sorted()
+        .collect();
+
+    let text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 16.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(BOTTOM_BAR_HEIGHT + 10.),
+                left: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            BackgroundColor(color::DIALOG_BACKGROUND.with_alpha(0.8)),
+            FlavorSummary,
+        ))
+        .with_children(|parent| {
+            for color in colors {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Node {
+                                width: Val::Px(12.),
+                                height: Val::Px(12.),
+                                ..default()
+                            },
+                            BackgroundColor(theme.pixie(color).into()),
+                        ));
+
+                        parent.spawn((
+                            Text::default(),
+                            text_font.clone(),
+                            TextColor(color::UI_WHITE),
+                            FlavorSummaryText(color),
+                        ));
+                    });
+            }
+        });
+}
+
+fn update_flavor_summary_system(
+    sim_state: Res<SimulationState>,
+    timeline: Res<SimulationTimeline>,
+    q_pixies: Query<&Pixie>,
+    mut q_summary: Query<&mut Node, With<FlavorSummary>>,
+    mut q_text: Query<(&mut Text, &FlavorSummaryText)>,
+) {
+    let display = if *sim_state == SimulationState::NotStarted {
+        Display::None
+    } else {
+        Display::Flex
+    };
+
+    for mut node in q_summary.iter_mut() {
+        if node.display != display {
+            node.display = display;
+        }
+    }
+
+    if display == Display::None {
+        return;
+    }
+
+    let mut in_flight: HashMap<u32, u32> = HashMap::default();
+    for pixie in q_pixies.iter() {
+        *in_flight.entry(pixie.flavor.color).or_default() += 1;
+    }
+
+    for (mut text, FlavorSummaryText(color)) in q_text.iter_mut() {
+        let tally = timeline.flavors.get(color).copied().unwrap_or_default();
+
+        let label = format!(
+            "EMITTED {}  IN FLIGHT {}  DELIVERED {}  EXPLODED {}",
+            tally.emitted,
+            in_flight.get(color).copied().unwrap_or_default(),
+            tally.delivered,
+            tally.exploded
+        );
+
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
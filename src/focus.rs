@@ -0,0 +1,266 @@
+//! Moving between buttons with the keyboard. Tab focuses each button in turn, or the
+//! one before with shift held. Once a button has focus, the arrow keys move to the
+//! nearest button in that direction, Enter presses it and Escape lets go. The focused
+//! button has a ring around it.
+//!
+//! Buttons whose text is just a symbol or a letter are also given a name for screen
+//! readers.
+
+use crate::{
+    color,
+    controls::{not_rebinding, Action},
+    save::InputMap,
+};
+use bevy::{
+    a11y::{AccessibilityNode, AccessibilitySystem},
+    prelude::*,
+    ui::UiSystem,
+};
+
+const FOCUS_RING_WIDTH: f32 = 2.0;
+
+pub struct FocusPlugin;
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonFocus>();
+
+        // Before anything else sees the keys, so that the ones used here don't also
+        // move the cursor or open the pause menu.
+        app.add_systems(
+            PreUpdate,
+            (focus_key_system, focus_ring_system)
+                .chain()
+                .after(UiSystem::Focus)
+                .run_if(not_rebinding),
+        );
+        app.add_systems(
+            PostUpdate,
+            accessible_name_system.after(AccessibilitySystem::Update),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ButtonFocus {
+    focused: Option<Entity>,
+    /// A button pressed with the keyboard last frame, to be let go of.
+    pressed: Option<Entity>,
+}
+
+/// A name for screen readers, for buttons whose text doesn't say what they do.
+#[derive(Component)]
+pub struct AccessibleName(pub String);
+
+impl AccessibleName {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+}
+
+#[derive(Component)]
+struct FocusRing;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FocusStep {
+    Next,
+    Previous,
+    /// The nearest button in a direction on the screen, where y points down.
+    Toward(Vec2),
+}
+
+/// The button to focus after `current`, given the position of each button on the
+/// screen. Buttons are in reading order for `Next` and `Previous`, wrapping around at
+/// either end.
+pub fn next_focus(
+    buttons: &[(Entity, Vec2)],
+    current: Option<Entity>,
+    step: FocusStep,
+) -> Option<Entity> {
+    let mut ordered = buttons.to_vec();
+    ordered.sort_by(|(_, a), (_, b)| {
+        a.y.round()
+            .total_cmp(&b.y.round())
+            .then(a.x.total_cmp(&b.x))
+    });
+
+    let index = current.and_then(|c| ordered.iter().position(|(e, _)| *e == c));
+
+    match (step, index) {
+        (FocusStep::Next, Some(i)) => ordered.get((i + 1) % ordered.len()).map(|(e, _)| *e),
+        (FocusStep::Previous, Some(i)) => ordered
+            .get((i + ordered.len() - 1) % ordered.len())
+            .map(|(e, _)| *e),
+        (FocusStep::Next | FocusStep::Toward(_), None) => ordered.first().map(|(e, _)| *e),
+        (FocusStep::Previous, None) => ordered.last().map(|(e, _)| *e),
+        (FocusStep::Toward(direction), Some(i)) => {
+            let from = ordered[i].1;
+
+            // Buttons straight ahead are preferred over closer ones off to the side.
+            ordered
+                .iter()
+                .filter_map(|(e, p)| {
+                    let offset = *p - from;
+                    let along = offset.dot(direction);
+                    let across = offset.perp_dot(direction).abs();
+                    (along > 0.0 && across <= along * 2.0).then_some((*e, along + across * 2.0))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(e, _)| e)
+        }
+    }
+}
+
+fn focus_key_system(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut focus: ResMut<ButtonFocus>,
+    mut q_interaction: Query<&mut Interaction, With<Button>>,
+    q_buttons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &ComputedNode,
+            &InheritedVisibility,
+        ),
+        With<Button>,
+    >,
+) {
+    // Everything has seen last frame's press by now.
+    if let Some(entity) = focus.pressed.take() {
+        if let Ok(mut interaction) = q_interaction.get_mut(entity) {
+            interaction.set_if_neq(Interaction::None);
+        }
+    }
+
+    let buttons: Vec<_> = q_buttons
+        .iter()
+        .filter(|(_, _, node, visibility)| visibility.get() && node.size() != Vec2::ZERO)
+        .map(|(entity, transform, _, _)| (entity, transform.translation().truncate()))
+        .collect();
+
+    // Buttons come and go with menus and screens.
+    if let Some(focused) = focus.focused {
+        if !buttons.iter().any(|(e, _)| *e == focused) {
+            focus.focused = None;
+        }
+    }
+
+    let mut used = vec![];
+
+    if input_map.just_pressed(Action::NextButton, &keyboard_input) {
+        let step = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            FocusStep::Previous
+        } else {
+            FocusStep::Next
+        };
+
+        focus.focused = next_focus(&buttons, focus.focused, step);
+        used.push(Action::NextButton);
+    }
+
+    let Some(focused) = focus.focused else {
+        for action in used {
+            keyboard_input.reset(input_map.key(action));
+        }
+        return;
+    };
+
+    let directions = [
+        (Action::CursorUp, Vec2::NEG_Y),
+        (Action::CursorDown, Vec2::Y),
+        (Action::CursorLeft, Vec2::NEG_X),
+        (Action::CursorRight, Vec2::X),
+    ];
+    for (action, direction) in directions {
+        if input_map.just_pressed(action, &keyboard_input) {
+            if let Some(next) = next_focus(&buttons, Some(focused), FocusStep::Toward(direction)) {
+                focus.focused = Some(next);
+            }
+        }
+
+        // Held arrows would otherwise carry on moving the board's cursor.
+        used.push(action);
+    }
+
+    if input_map.just_pressed(Action::Place, &keyboard_input) {
+        if let Ok(mut interaction) = q_interaction.get_mut(focused) {
+            *interaction = Interaction::Pressed;
+            focus.pressed = Some(focused);
+        }
+        used.push(Action::Place);
+    }
+
+    if input_map.just_pressed(Action::Cancel, &keyboard_input) {
+        focus.focused = None;
+        used.push(Action::Cancel);
+    }
+
+    for action in used {
+        keyboard_input.reset(input_map.key(action));
+    }
+}
+
+fn focus_ring_system(
+    mut commands: Commands,
+    focus: Res<ButtonFocus>,
+    q_rings: Query<Entity, With<FocusRing>>,
+) {
+    for entity in q_rings.iter().filter(|e| Some(*e) != focus.focused) {
+        commands.entity(entity).remove::<(Outline, FocusRing)>();
+    }
+
+    if let Some(focused) = focus.focused.filter(|f| !q_rings.contains(*f)) {
+        commands.entity(focused).insert((
+            Outline::new(
+                Val::Px(FOCUS_RING_WIDTH),
+                Val::Px(FOCUS_RING_WIDTH),
+                color::UI_WHITE,
+            ),
+            FocusRing,
+        ));
+    }
+}
+
+/// Bevy names buttons after their text, so this runs afterwards to replace that.
+fn accessible_name_system(
+    mut q_nodes: Query<(&AccessibleName, &mut AccessibilityNode), Changed<AccessibilityNode>>,
+) {
+    for (name, mut node) in q_nodes.iter_mut() {
+        node.set_label(name.0.as_str());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_order() {
+        let a = Entity::from_raw(0);
+        let b = Entity::from_raw(1);
+        let c = Entity::from_raw(2);
+        let d = Entity::from_raw(3);
+
+        // a b c
+        //     d
+        let buttons = [
+            (c, Vec2::new(200.0, 0.0)),
+            (a, Vec2::new(0.0, 0.0)),
+            (d, Vec2::new(200.0, 100.0)),
+            (b, Vec2::new(100.0, 0.0)),
+        ];
+
+        assert_eq!(next_focus(&buttons, None, FocusStep::Next), Some(a));
+        assert_eq!(next_focus(&buttons, Some(b), FocusStep::Next), Some(c));
+        assert_eq!(next_focus(&buttons, Some(d), FocusStep::Next), Some(a));
+        assert_eq!(next_focus(&buttons, Some(a), FocusStep::Previous), Some(d));
+
+        let right = FocusStep::Toward(Vec2::X);
+        let down = FocusStep::Toward(Vec2::Y);
+        assert_eq!(next_focus(&buttons, Some(a), right), Some(b));
+        assert_eq!(next_focus(&buttons, Some(c), right), None);
+        assert_eq!(next_focus(&buttons, Some(c), down), Some(d));
+        assert_eq!(next_focus(&buttons, Some(b), down), Some(d));
+        assert!(next_focus(&[], None, FocusStep::Next).is_none());
+    }
+}
@@ -0,0 +1,142 @@
+//! Friends' best scores, shown next to the player's own as a line to beat.
+//!
+//! The export button on the level select screen writes the player's best scores to
+//! `pixie_wrangler.scores.ron`. Friends can drop that file into their
+//! `assets/friends/` folder, renamed to something like `alex.scores.ron`.
+
+use std::collections::HashMap;
+
+use crate::{color, save::BestScores, GameState};
+use bevy::{asset::LoadedFolder, ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+const FRIENDS_FOLDER: &str = "friends";
+const EXPORT_PATH: &str = "pixie_wrangler.scores.ron";
+const EXTENSION: &str = ".scores.ron";
+
+pub struct FriendsPlugin;
+impl Plugin for FriendsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FriendsFolder>();
+
+        app.add_systems(OnEnter(GameState::Loading), load_friends_system);
+        app.add_systems(
+            Update,
+            export_scores_button_system.run_if(in_state(GameState::LevelSelect)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct ExportScoresButton;
+
+#[derive(Serialize, Deserialize, Asset, TypePath, Default, Debug)]
+pub struct FriendScores {
+    pub scores: HashMap<String, u32>,
+}
+
+/// The folder of friends' scores. It's optional, and can't be loaded on the web.
+#[derive(Resource, Default)]
+struct FriendsFolder(Option<Handle<LoadedFolder>>);
+
+/// Looks up friends' scores from whichever files have loaded.
+#[derive(SystemParam)]
+pub struct Friends<'w> {
+    folder: Res<'w, FriendsFolder>,
+    folders: Res<'w, Assets<LoadedFolder>>,
+    scores: Res<'w, Assets<FriendScores>>,
+}
+
+impl Friends<'_> {
+    /// The name of the friend with the best score on the level with the given id, and
+    /// their score.
+    pub fn best(&self, level_id: &str) -> Option<(String, u32)> {
+        let folder = self.folder.0.as_ref().and_then(|h| self.folders.get(h))?;
+
+        folder
+            .handles
+            .iter()
+            .filter_map(|h| {
+                let name = friend_name(&h.path()?.to_string());
+                let scores = self
+                    .scores
+                    .get(&h.clone().try_typed::<FriendScores>().ok()?)?;
+                Some((name, *scores.scores.get(level_id)?))
+            })
+            .max_by_key(|(_, score)| *score)
+    }
+}
+
+/// A friend's name, from the path of their scores file.
+fn friend_name(path: &str) -> String {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    file.strip_suffix(EXTENSION).unwrap_or(file).to_uppercase()
+}
+
+/// The color of a friend's score, which fades once `mine` has caught up with it.
+pub fn friend_color(score: u32, mine: Option<u32>) -> Color {
+    if mine.is_some_and(|m| m >= score) {
+        Srgba::gray(0.5).into()
+    } else {
+        color::UI_GREY_RED
+    }
+}
+
+fn load_friends_system(mut folder: ResMut<FriendsFolder>, asset_server: Res<AssetServer>) {
+    folder.0 = Some(asset_server.load_folder(FRIENDS_FOLDER));
+}
+
+fn export_scores_button_system(
+    q_interaction: Query<
+        (&Interaction, &Children),
+        (Changed<Interaction>, With<ExportScoresButton>),
+    >,
+    mut q_text: Query<&mut Text>,
+    best_scores: Res<BestScores>,
+) {
+    for (_, children) in q_interaction
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        let label = match export_scores(&best_scores) {
+            Ok(()) => "SCORES EXPORTED",
+            Err(e) => {
+                warn!("Failed to export scores: {}", e);
+                "EXPORT FAILED"
+            }
+        };
+
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0 = label.to_string();
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export_scores(best_scores: &BestScores) -> Result<(), String> {
+    let scores = FriendScores {
+        scores: best_scores.0.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+    };
+
+    let ron = ron::ser::to_string_pretty(&scores, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+
+    std::fs::write(EXPORT_PATH, ron).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn export_scores(_best_scores: &BestScores) -> Result<(), String> {
+    Err("not supported on the web".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn friend_names() {
+        assert_eq!(friend_name("friends/alex.scores.ron"), "ALEX");
+        assert_eq!(friend_name("friends/nested/sam.scores.ron"), "SAM");
+    }
+}
@@ -0,0 +1,136 @@
+//! A faint, looping replay of the previous run, shown while editing the board so that
+//! it's easy to see where pixies got stuck.
+
+use crate::{
+    controls::Action, pixie::PIXIE_RADIUS, playing_enter_system, replay::ReplayRecording,
+    save::InputMap, sim::SimulationState, theme::Theme, DrawingInput, GameState, SelectedLevel,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+const GHOST_ALPHA: f32 = 0.2;
+
+pub struct GhostPlugin;
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GhostRun>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            reset_ghost_system.after(playing_enter_system),
+        );
+        app.add_systems(Update, ghost_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            ghost_system
+                .after(ghost_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct GhostRun {
+    /// Whether the ghost is shown. Toggled with G, and kept between levels.
+    enabled: bool,
+    time: f32,
+    /// Reusable pixie entities for displaying frames.
+    pool: Vec<Entity>,
+}
+
+#[derive(Component)]
+struct GhostPixie;
+
+fn reset_ghost_system(mut ghost: ResMut<GhostRun>) {
+    // Entities from the previous level were despawned on the way out.
+    ghost.pool.clear();
+    ghost.time = 0.0;
+}
+
+fn ghost_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut ghost: ResMut<GhostRun>,
+) {
+    if input_map.just_pressed(Action::Ghost, &keyboard_input) {
+        ghost.enabled = !ghost.enabled;
+    }
+}
+
+fn ghost_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ghost: ResMut<GhostRun>,
+    recording: Res<ReplayRecording>,
+    sim_state: Res<SimulationState>,
+    selected_level: Res<SelectedLevel>,
+    theme: Res<Theme>,
+    mut q_pixies: Query<(&mut Transform, &mut Fill, &mut Visibility), With<GhostPixie>>,
+) {
+    let duration = recording.duration();
+
+    let frame = if ghost.enabled
+        && *sim_state == SimulationState::NotStarted
+        && recording.level == selected_level.0
+        && duration > 0.0
+    {
+        ghost.time = (ghost.time + time.delta_secs()) % duration;
+        recording.frame_at(ghost.time)
+    } else {
+        None
+    };
+
+    let pixies = frame.map_or(&[][..], |f| &f.pixies[..]);
+
+    let shape = shapes::RegularPolygon {
+        sides: 6,
+        feature: shapes::RegularPolygonFeature::Radius(PIXIE_RADIUS),
+        ..shapes::RegularPolygon::default()
+    };
+
+    while ghost.pool.len() < pixies.len() {
+        let (translation, flavor) = pixies[ghost.pool.len()];
+
+        let entity = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shape),
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                Fill::color(ghost_color(&theme, flavor)),
+                GhostPixie,
+            ))
+            .id();
+
+        ghost.pool.push(entity);
+    }
+
+    for (i, entity) in ghost.pool.iter().enumerate() {
+        let Ok((mut transform, mut fill, mut visibility)) = q_pixies.get_mut(*entity) else {
+            continue;
+        };
+
+        let Some((translation, flavor)) = pixies.get(i) else {
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+            }
+            continue;
+        };
+
+        transform.translation = *translation;
+
+        let color = ghost_color(&theme, *flavor);
+        if fill.color != color {
+            fill.color = color;
+        }
+
+        if *visibility != Visibility::Inherited {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn ghost_color(theme: &Theme, flavor: u32) -> Color {
+    theme.pixie(flavor).with_alpha(GHOST_ALPHA).into()
+}
@@ -0,0 +1,220 @@
+//! The last few finished runs of each level, so that it's easy to tell whether changes
+//! to a layout are actually helping. Shown as a table, toggled with I.
+
+use crate::{
+    color,
+    controls::Action,
+    playing_enter_system,
+    save::{InputMap, RunHistory, RunRecord},
+    sim::{SimulationState, SimulationSteps, SimulationTimeline},
+    update_score_system, AfterUpdate, Cost, DrawingInput, DrawingState, GameState, Handles, Score,
+    ScoreCalc, SelectedLevel, BOTTOM_BAR_HEIGHT,
+};
+use bevy::prelude::*;
+
+/// How many runs are kept for each level.
+const HISTORY_RUNS: usize = 8;
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_history_panel_system.after(playing_enter_system),
+        );
+        app.add_systems(
+            AfterUpdate,
+            record_run_system
+                .after(update_score_system)
+                .in_set(ScoreCalc),
+        );
+        app.add_systems(Update, history_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            update_history_panel_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct HistoryPanel;
+#[derive(Component)]
+struct HistoryTable;
+
+/// Adds `run` to the end of `runs`, dropping the oldest runs to keep at most `max`.
+pub fn push_run(runs: &mut Vec<RunRecord>, run: RunRecord, max: usize) {
+    runs.push(run);
+
+    if runs.len() > max {
+        runs.drain(..runs.len() - max);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_run_system(
+    sim_state: Res<SimulationState>,
+    sim_steps: Res<SimulationSteps>,
+    timeline: Res<SimulationTimeline>,
+    score: Res<Score>,
+    cost: Res<Cost>,
+    drawing_state: Res<DrawingState>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    mut history: ResMut<RunHistory>,
+) {
+    if !sim_state.is_changed() || *sim_state != SimulationState::Finished {
+        return;
+    }
+
+    // Like best scores, runs where the roads were changed part way through don't count.
+    if drawing_state.edited_while_running {
+        return;
+    }
+
+    let Some(val) = score.0 else {
+        return;
+    };
+
+    let run = RunRecord {
+        score: val,
+        cost: cost.0,
+        elapsed: sim_steps.get_elapsed_f32(),
+        exploded: timeline.flavors.values().map(|t| t.exploded).sum(),
+    };
+
+    let id = handles.level_id(selected_level.0);
+    push_run(history.0.entry(id).or_default(), run, HISTORY_RUNS);
+}
+
+fn history_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut q_panel: Query<&mut Node, With<HistoryPanel>>,
+) {
+    if !input_map.just_pressed(Action::History, &keyboard_input) {
+        return;
+    }
+
+    for mut node in q_panel.iter_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+fn spawn_history_panel_system(mut commands: Commands, handles: Res<Handles>) {
+    commands
+        .spawn((
+            Node {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(BOTTOM_BAR_HEIGHT + 10.),
+                right: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            BackgroundColor(color::DIALOG_BACKGROUND.with_alpha(0.8)),
+            HistoryPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("HISTORY (I)"),
+                TextFont {
+                    font: handles.fonts[0].clone(),
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(color::UI_WHITE),
+            ));
+
+            parent.spawn((
+                Node {
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::auto(4),
+                    column_gap: Val::Px(12.),
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                HistoryTable,
+            ));
+        });
+}
+
+fn update_history_panel_system(
+    mut commands: Commands,
+    history: Res<RunHistory>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    q_table: Query<Entity, With<HistoryTable>>,
+    q_added: Query<(), Added<HistoryTable>>,
+) {
+    if !history.is_changed() && q_added.is_empty() {
+        return;
+    }
+
+    let Ok(table) = q_table.get_single() else {
+        return;
+    };
+
+    let text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 16.0,
+        ..default()
+    };
+
+    let id = handles.level_id(selected_level.0);
+    let runs = history.0.get(&id).map(Vec::as_slice).unwrap_or_default();
+
+    commands.entity(table).despawn_descendants();
+    commands.entity(table).with_children(|parent| {
+        let mut cell = |text: String, color: Color| {
+            parent.spawn((Text::new(text), text_font.clone(), TextColor(color)));
+        };
+
+        for heading in ["SCORE", "COST", "TIME", "LOST"] {
+            cell(heading.to_string(), color::UI_WHITE.with_alpha(0.6));
+        }
+
+        if runs.is_empty() {
+            for _ in 0..4 {
+                cell("-".to_string(), color::UI_WHITE);
+            }
+            return;
+        }
+
+        // Newest first. Scores that improved on the run before are picked out.
+        for (i, run) in runs.iter().enumerate().rev() {
+            let improved = i > 0 && run.score > runs[i - 1].score;
+            let score_color = if improved {
+                color::FINISHED_ROAD[1]
+            } else {
+                color::UI_WHITE
+            };
+
+            cell(format!("Æ{}", run.score), score_color);
+            cell(format!("§{}", run.cost), color::UI_WHITE);
+            cell(format!("ŧ{:.1}", run.elapsed), color::UI_WHITE);
+            cell(format!("{}", run.exploded), color::UI_WHITE);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push() {
+        let run = |score| RunRecord { score, ..default() };
+
+        let mut runs = vec![];
+        for score in 0..5 {
+            push_run(&mut runs, run(score), 3);
+        }
+
+        assert_eq!(runs, vec![run(2), run(3), run(4)]);
+    }
+}
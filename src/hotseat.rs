@@ -0,0 +1,256 @@
+//! Two players sharing one mouse, taking turns placing a few segments at a time
+//! before releasing the pixies together.
+
+use crate::{
+    controls::{key_label, Action},
+    playing_enter_system,
+    save::InputMap,
+    sim::SimulationState,
+    theme::Theme,
+    DrawingInput, GameState, Handles, LineDrawingState,
+};
+use bevy::prelude::*;
+
+/// Number of road segments each player may place before the turn passes.
+pub const SEGMENTS_PER_TURN: u32 = 3;
+const PLAYERS: usize = 2;
+
+pub struct HotseatPlugin;
+impl Plugin for HotseatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HotseatMode>();
+        app.init_resource::<HotseatTurn>();
+
+        app.add_systems(
+            Update,
+            (hotseat_button_system, hotseat_button_text_system)
+                .chain()
+                .run_if(in_state(GameState::LevelSelect)),
+        );
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_turn_indicator_system.after(playing_enter_system),
+        );
+        app.add_systems(Update, pass_turn_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            update_turn_indicator_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Whether levels are played in hotseat mode. Chosen on the level select screen.
+#[derive(Resource, Default)]
+pub struct HotseatMode(pub bool);
+
+#[derive(Component)]
+pub struct HotseatButton;
+#[derive(Component)]
+struct TurnIndicator;
+
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct HotseatTurn {
+    enabled: bool,
+    /// Index of the player whose turn it is.
+    player: usize,
+    /// Segments placed so far this turn.
+    placed: u32,
+    /// Segments placed by each player over the whole level.
+    contributions: [u32; PLAYERS],
+}
+
+impl HotseatTurn {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..default()
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn remaining(&self) -> u32 {
+        SEGMENTS_PER_TURN.saturating_sub(self.placed)
+    }
+
+    /// Whether the current player may place `segments` more segments this turn.
+    pub fn allows(&self, segments: usize) -> bool {
+        !self.enabled || segments as u32 <= self.remaining()
+    }
+
+    /// Records segments placed by the current player. Returns true if that used up
+    /// their turn.
+    pub fn place(&mut self, segments: usize) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        self.placed += segments as u32;
+        self.contributions[self.player] += segments as u32;
+
+        if self.remaining() == 0 {
+            self.end_turn();
+            return true;
+        }
+
+        false
+    }
+
+    pub fn end_turn(&mut self) {
+        self.player = (self.player + 1) % PLAYERS;
+        self.placed = 0;
+    }
+
+    /// The turn indicator text, mentioning `pass_key` for passing the turn on early.
+    pub fn label(&self, pass_key: &str) -> String {
+        format!(
+            "P{} ▸ {} LEFT ({pass_key} TO PASS)",
+            self.player + 1,
+            self.remaining()
+        )
+    }
+
+    /// A summary of each player's contribution, for the score dialog.
+    pub fn contributions_label(&self) -> String {
+        self.contributions
+            .iter()
+            .enumerate()
+            .map(|(i, segments)| format!("P{} {segments}", i + 1))
+            .collect::<Vec<_>>()
+            .join("  ")
+            + " SEGMENTS"
+    }
+}
+
+fn player_color(theme: &Theme, player: usize) -> Color {
+    theme.pixie(player as u32).into()
+}
+
+fn hotseat_button_system(
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<HotseatButton>)>,
+    mut mode: ResMut<HotseatMode>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        mode.0 = !mode.0;
+    }
+}
+
+fn hotseat_button_text_system(
+    mode: Res<HotseatMode>,
+    q_button: Query<&Children, With<HotseatButton>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in q_button.iter() {
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            let label = if mode.0 { "HOTSEAT ON" } else { "HOTSEAT OFF" };
+            if text.0 != label {
+                text.0 = label.to_string();
+            }
+        }
+    }
+}
+
+fn pass_turn_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    sim_state: Res<SimulationState>,
+    mut turn: ResMut<HotseatTurn>,
+    mut line_state: ResMut<LineDrawingState>,
+) {
+    if !turn.enabled || *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    if !input_map.just_pressed(Action::PassTurn, &keyboard_input) {
+        return;
+    }
+
+    turn.end_turn();
+
+    line_state.drawing = false;
+    line_state.segments = vec![];
+}
+
+fn spawn_turn_indicator_system(
+    mut commands: Commands,
+    mode: Res<HotseatMode>,
+    handles: Res<Handles>,
+    theme: Res<Theme>,
+) {
+    if !mode.0 {
+        return;
+    }
+
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 25.0,
+            ..default()
+        },
+        TextColor(player_color(&theme, 0)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(10.),
+            ..default()
+        },
+        TurnIndicator,
+    ));
+}
+
+fn update_turn_indicator_system(
+    turn: Res<HotseatTurn>,
+    theme: Res<Theme>,
+    input_map: Res<InputMap>,
+    mut q_indicator: Query<(&mut Text, &mut TextColor), With<TurnIndicator>>,
+) {
+    for (mut text, mut color) in q_indicator.iter_mut() {
+        if !turn.is_changed() && !text.0.is_empty() {
+            continue;
+        }
+
+        text.0 = turn.label(&key_label(input_map.key(Action::PassTurn)));
+        color.0 = player_color(&theme, turn.player);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_alternate() {
+        let mut turn = HotseatTurn::new(true);
+
+        assert!(turn.allows(3));
+        assert!(!turn.allows(4));
+
+        assert!(!turn.place(2));
+        assert!(!turn.allows(2));
+        assert!(turn.place(1));
+
+        assert_eq!(turn.player, 1);
+        assert_eq!(turn.remaining(), SEGMENTS_PER_TURN);
+        assert_eq!(turn.contributions, [3, 0]);
+
+        turn.place(1);
+        turn.end_turn();
+
+        assert_eq!(turn.player, 0);
+        assert_eq!(turn.contributions, [3, 1]);
+    }
+
+    #[test]
+    fn disabled_is_unlimited() {
+        let mut turn = HotseatTurn::new(false);
+
+        assert!(turn.allows(100));
+        assert!(!turn.place(100));
+        assert_eq!(turn.contributions, [0, 0]);
+    }
+}
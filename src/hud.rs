@@ -0,0 +1,101 @@
+//! A compact HUD mode that tucks the bottom bar away while pixies are running, so
+//! that small screens can show the whole board. Toggled with H.
+
+use crate::{
+    controls::Action,
+    save::{InputMap, Settings},
+    sim::SimulationState,
+    DrawingInput, GameState, MouseState, BOTTOM_BAR_HEIGHT,
+};
+use bevy::prelude::*;
+
+/// How close to the bottom of the window the cursor must be to reveal the bar.
+const REVEAL_DISTANCE: f32 = BOTTOM_BAR_HEIGHT;
+/// How fast the bar slides, in pixels per second.
+const SLIDE_SPEED: f32 = 400.0;
+
+pub struct HudPlugin;
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hud>();
+
+        app.add_systems(Update, hud_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            slide_bottom_bar_system
+                .after(hud_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(OnEnter(GameState::Playing), reset_hud_system);
+    }
+}
+
+#[derive(Component)]
+pub struct BottomBar;
+
+#[derive(Resource, Default)]
+pub struct Hud {
+    /// Whether the bottom bar hides during the simulation. Kept between levels.
+    compact: bool,
+    /// How far the bottom bar has slid out of view.
+    offset: f32,
+}
+
+impl Hud {
+    /// The height of the part of the bottom bar that's on screen, which takes
+    /// clicks instead of the board.
+    pub fn bar_height(&self) -> f32 {
+        BOTTOM_BAR_HEIGHT - self.offset
+    }
+}
+
+fn reset_hud_system(mut hud: ResMut<Hud>) {
+    hud.offset = 0.0;
+}
+
+fn hud_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut hud: ResMut<Hud>,
+) {
+    if input_map.just_pressed(Action::Hud, &keyboard_input) {
+        hud.compact = !hud.compact;
+    }
+}
+
+fn slide_bottom_bar_system(
+    time: Res<Time>,
+    mut hud: ResMut<Hud>,
+    mouse: Res<MouseState>,
+    sim_state: Res<SimulationState>,
+    settings: Res<Settings>,
+    q_window: Query<&Window>,
+    mut q_bar: Query<&mut Node, With<BottomBar>>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    let approaching = mouse.window_position.y > window.resolution.height() - REVEAL_DISTANCE;
+    let hidden = hud.compact && *sim_state == SimulationState::Running && !approaching;
+
+    let target = if hidden { BOTTOM_BAR_HEIGHT } else { 0.0 };
+    if hud.offset == target {
+        return;
+    }
+
+    let step = if settings.reduced_motion {
+        BOTTOM_BAR_HEIGHT
+    } else {
+        SLIDE_SPEED * time.delta_secs()
+    };
+    hud.offset = if hud.offset < target {
+        (hud.offset + step).min(target)
+    } else {
+        (hud.offset - step).max(target)
+    };
+
+    for mut node in q_bar.iter_mut() {
+        node.margin.bottom = Val::Px(-hud.offset);
+    }
+}
@@ -1,7 +1,9 @@
 pub const GRID: f32 = 0.0;
 pub const OBSTACLE: f32 = 0.0;
 pub const TERMINUS: f32 = 1.0;
+pub const GHOST_ROAD: f32 = 9.0;
 pub const ROAD: f32 = 10.0;
+pub const VIA: f32 = 10.2;
 pub const PIXIE: f32 = 10.5;
 pub const ROAD_OVERLAY: f32 = 20.0;
 pub const CURSOR: f32 = 40.0;
@@ -0,0 +1,157 @@
+use crate::{
+    color, controls::Action, level::Level, playing_enter_system, save::InputMap, theme::Theme,
+    DrawingInput, GameState, Handles, SelectedLevel, GRID_SIZE,
+};
+use bevy::prelude::*;
+use itertools::Itertools;
+
+/// Levels with at least this many flavors show the legend when they are entered.
+const LEGEND_AUTO_SHOW_FLAVORS: usize = 4;
+
+pub struct LegendPlugin;
+impl Plugin for LegendPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_legend_system.after(playing_enter_system),
+        );
+        app.add_systems(Update, legend_key_system.in_set(DrawingInput));
+    }
+}
+
+#[derive(Component)]
+struct Legend;
+
+fn legend_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut q_legend: Query<&mut Node, With<Legend>>,
+) {
+    if !input_map.just_pressed(Action::Legend, &keyboard_input) {
+        return;
+    }
+
+    for mut node in q_legend.iter_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+fn spawn_legend_system(
+    mut commands: Commands,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
+    theme: Res<Theme>,
+) {
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    let flavors: Vec<_> = level
+        .terminuses
+        .iter()
+        .flat_map(|t| t.emits.iter().chain(t.collects.iter()))
+        .copied()
+        .unique()
+        .sorted()
+        .collect();
+
+    let text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 18.0,
+        ..default()
+    };
+
+    // Terminuses don't have names, so refer to them by their grid coordinates.
+    let describe = |points: Vec<Vec2>| {
+        if points.is_empty() {
+            return "?".to_string();
+        }
+
+        points
+            .iter()
+            .map(|p| {
+                let p = (*p / GRID_SIZE).round();
+                format!("{},{}", p.x, p.y)
+            })
+            .join(" ")
+    };
+
+    commands
+        .spawn((
+            Node {
+                display: if flavors.len() >= LEGEND_AUTO_SHOW_FLAVORS {
+                    Display::Flex
+                } else {
+                    Display::None
+                },
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            BackgroundColor(color::DIALOG_BACKGROUND.with_alpha(0.8)),
+            Legend,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("LEGEND (L)"),
+                text_font.clone(),
+                TextColor(color::UI_WHITE),
+            ));
+
+            for flavor in flavors.iter() {
+                let emitters = level
+                    .terminuses
+                    .iter()
+                    .filter(|t| t.emits.contains(flavor))
+                    .map(|t| t.point)
+                    .collect();
+                let collectors = level
+                    .terminuses
+                    .iter()
+                    .filter(|t| t.collects.contains(flavor))
+                    .map(|t| t.point)
+                    .collect();
+
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Node {
+                                width: Val::Px(12.),
+                                height: Val::Px(12.),
+                                ..default()
+                            },
+                            BackgroundColor(theme.pixie(flavor.color).into()),
+                        ));
+
+                        parent.spawn((
+                            Text::new(format!(
+                                "NET {}  OUT {} → IN {}",
+                                flavor.net + 1,
+                                describe(emitters),
+                                describe(collectors)
+                            )),
+                            text_font.clone(),
+                            TextColor(color::UI_WHITE),
+                        ));
+                    });
+            }
+        });
+}
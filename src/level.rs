@@ -1,5 +1,14 @@
-use crate::PixieFlavor;
-use bevy::{prelude::*, reflect::TypePath, utils::HashSet};
+use crate::{
+    collision::{
+        point_segment_collision, point_segment_distance, segment_collision, SegmentCollision,
+    },
+    color,
+    tutorial::TutorialStep,
+    PixieFlavor, RoadSegment, EMITTER_INTERVAL_SECS, GRID_SIZE, PIXIES_PER_TERMINUS,
+    SCORE_MULTIPLIER,
+};
+use bevy::{asset::AssetPath, prelude::*, reflect::TypePath, utils::HashSet};
+use itertools::Itertools;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Asset, TypePath)]
@@ -9,12 +18,476 @@ pub struct Level {
     pub layers: u32,
     pub terminuses: Vec<Terminus>,
     pub obstacles: Vec<Obstacle>,
+    /// Buildings that change the color of pixies that drive over them.
+    #[serde(default)]
+    pub converters: Vec<Converter>,
     pub star_thresholds: Vec<u32>,
+    /// What each via, where a road changes layer, adds to the cost of a solution. Free
+    /// unless a level says otherwise, so that older levels score as they always have.
+    #[serde(default)]
+    pub via_cost: f32,
+    /// The longest that a single straight road or arc can be, in grid units. Stops
+    /// long diagonal shortcuts across the board in levels that want to.
+    #[serde(default)]
+    pub max_segment_length: Option<f32>,
+    /// How much pixies delivered, cost and time each count towards the score.
+    #[serde(default)]
+    pub scoring: ScoreWeights,
+    /// How many pixies each terminus emits, shared between the flavors it emits.
+    #[serde(default = "default_pixies")]
+    pub pixies: u32,
+    /// Hints shown one after another while playing the level.
+    #[serde(default)]
+    pub tutorial: Vec<TutorialStep>,
 }
 
+fn default_pixies() -> u32 {
+    PIXIES_PER_TERMINUS
+}
+
+impl Level {
+    /// Problems that stop the level from being played at all. Levels with any of these
+    /// can't be picked from the level select screen.
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = vec![];
+
+        let max_layers = color::FINISHED_ROAD.len() as u32;
+        if !(1..=max_layers).contains(&self.layers) {
+            errors.push(format!(
+                "The level has {} layers, but it can only have 1 to {max_layers}",
+                self.layers
+            ));
+        }
+
+        if self.terminuses.is_empty() {
+            errors.push("The level has no terminuses".to_string());
+        }
+
+        for terminus in self.terminuses.iter() {
+            if terminus.capacity.is_some_and(|c| c <= 0.0) {
+                errors.push(format!(
+                    "The terminus at {} has no capacity, so pixies could never reach it",
+                    terminus.point
+                ));
+            }
+        }
+
+        if self.max_segment_length.is_some_and(|l| l < 1.0) {
+            errors.push(
+                "The maximum segment length is under one grid unit, so no road fits".to_string(),
+            );
+        }
+
+        if self.star_thresholds.len() > 3 {
+            errors.push(format!(
+                "The level has {} star thresholds, but there are only 3 stars",
+                self.star_thresholds.len()
+            ));
+        }
+
+        errors
+    }
+
+    /// Problems that would make the level look broken or impossible to solve, for
+    /// level authors.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+
+        if self.star_thresholds.windows(2).any(|t| t[0] > t[1]) {
+            warnings.push("The star thresholds are out of order".to_string());
+        }
+
+        let scoring = self.scoring;
+        if [scoring.pixies, scoring.cost, scoring.time]
+            .iter()
+            .any(|w| *w < 0.0)
+        {
+            warnings
+                .push("A scoring weight is negative, so it rewards the wrong thing".to_string());
+        }
+
+        for terminus in self.terminuses.iter() {
+            if (terminus.point / GRID_SIZE).fract() != Vec2::ZERO {
+                warnings.push(format!(
+                    "The terminus at {} is off the grid",
+                    terminus.point
+                ));
+            }
+        }
+
+        // Converters can turn pixies of one color into another on the way.
+        let emitted: HashSet<_> = self.terminuses.iter().flat_map(|t| &t.emits).collect();
+        let collected: HashSet<_> = self.terminuses.iter().flat_map(|t| &t.collects).collect();
+        for flavor in emitted.iter().sorted() {
+            if !collected.contains(flavor)
+                && !self.converters.iter().any(|c| c.from == flavor.color)
+            {
+                warnings.push(format!(
+                    "Nothing collects the pixies of color {} from net {}",
+                    flavor.color, flavor.net
+                ));
+            }
+        }
+        for flavor in collected.iter().sorted() {
+            if !emitted.contains(flavor) && !self.converters.iter().any(|c| c.to == flavor.color) {
+                warnings.push(format!(
+                    "Nothing emits the pixies of color {} for net {}",
+                    flavor.color, flavor.net
+                ));
+            }
+        }
+
+        let outlines: Vec<_> = self.obstacles.iter().map(Obstacle::outline).collect();
+        let edges = || {
+            outlines
+                .iter()
+                .flat_map(|o| o.iter().copied().zip(o.iter().copied().cycle().skip(1)))
+        };
+
+        for terminus in self.terminuses.iter() {
+            let point = terminus.point;
+
+            if self.obstacles.iter().any(|o| o.contains(point))
+                || edges().any(|(a, b)| point_segment_distance(point, a, b) < TERMINUS_CLEARANCE)
+            {
+                warnings.push(format!("An obstacle covers the terminus at {point}"));
+            }
+
+            let labels = terminus.emits.len() + terminus.collects.len();
+            let label_covered = (0..labels).any(|i| {
+                let center = point - Vec2::Y * TERMINUS_LABEL_SPACING * (i + 1) as f32;
+                let rect = Rect::from_center_half_size(center, TERMINUS_LABEL_HALF_SIZE);
+
+                self.obstacles.iter().any(|o| o.contains(center))
+                    || edges().any(|edge| segment_hits_rect(edge, rect))
+            });
+            if label_covered {
+                warnings.push(format!(
+                    "An obstacle covers a label of the terminus at {point}"
+                ));
+            }
+
+            if terminus.capacity.is_some() && terminus.collects.is_empty() {
+                warnings.push(format!(
+                    "The terminus at {point} has a capacity but doesn't collect anything"
+                ));
+            }
+
+            if self.converters.iter().any(|c| c.point == point) {
+                warnings.push(format!("A converter sits on the terminus at {point}"));
+            }
+
+            if terminus.emits.is_empty() {
+                continue;
+            }
+
+            let has_exit = (0..8).any(|i| {
+                let direction = Vec2::from_angle(i as f32 * std::f32::consts::FRAC_PI_4);
+                let exit = (point + direction.round() * GRID_SIZE, point);
+
+                edges().all(|(a, b)| {
+                    matches!(
+                        segment_collision(a, b, exit.0, exit.1),
+                        SegmentCollision::None
+                    )
+                })
+            });
+            if !has_exit {
+                warnings.push(format!("The emitter at {point} has no way out"));
+            }
+        }
+
+        for converter in self.converters.iter() {
+            if self.obstacles.iter().any(|o| o.contains(converter.point)) {
+                warnings.push(format!(
+                    "An obstacle covers the converter at {}",
+                    converter.point
+                ));
+            }
+        }
+
+        warnings
+    }
+
+    /// A hash of everything that decides whether a road layout fits the level, saved
+    /// with solutions so that they can be checked when the level changes. It only
+    /// changes when the level does, across runs and builds.
+    pub fn layout_hash(&self) -> u64 {
+        let mut hash = LayoutHash::default();
+
+        hash.u64(self.layers as u64);
+        hash.u64(self.obstacles.len() as u64);
+        for obstacle in self.obstacles.iter() {
+            obstacle.hash_layout(&mut hash);
+        }
+        match self.max_segment_length {
+            Some(length) => {
+                hash.u64(1);
+                hash.f32(length);
+            }
+            None => hash.u64(0),
+        }
+        hash.u64(self.terminuses.len() as u64);
+        for terminus in self.terminuses.iter() {
+            hash.vec2(terminus.point);
+        }
+        hash.u64(self.converters.len() as u64);
+        for converter in self.converters.iter() {
+            hash.vec2(converter.point);
+        }
+
+        hash.0
+    }
+
+    /// Whether a road could still be part of a layout on this level, for loading
+    /// layouts saved before the level changed.
+    pub fn fits(&self, segment: &RoadSegment) -> bool {
+        let (a, b) = segment.points;
+
+        (1..=self.layers).contains(&segment.layer)
+            && !self
+                .obstacles
+                .iter()
+                .any(|o| o.blocks_segment(a, b, segment.layer))
+            && !self.terminuses.iter().any(|t| {
+                matches!(
+                    point_segment_collision(t.point, a, b),
+                    SegmentCollision::Touching
+                )
+            })
+    }
+}
+
+fn segment_hits_rect(segment: (Vec2, Vec2), rect: Rect) -> bool {
+    if rect.contains(segment.0) || rect.contains(segment.1) {
+        return true;
+    }
+
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+    ];
+
+    corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .any(|(a, b)| {
+            !matches!(
+                segment_collision(*a, *b, segment.0, segment.1),
+                SegmentCollision::None
+            )
+        })
+}
+
+/// A name for the level at `path` that doesn't depend on its position in the level
+/// list, used to key saved progress. Built-in levels are just their number, and user
+/// levels look like `custom/name`.
+pub fn level_id(path: &AssetPath) -> String {
+    let path = path.path();
+    let id = path
+        .strip_prefix("levels")
+        .unwrap_or(path)
+        .iter()
+        .map(|c| c.to_string_lossy())
+        .join("/");
+
+    id.strip_suffix(".level.ron").unwrap_or(&id).to_string()
+}
+
+/// Distance below a terminus of its first label, and between its labels.
+pub const TERMINUS_LABEL_SPACING: f32 = 22.0;
+/// Rough half-extents of a terminus label, for checking that nothing covers it.
+const TERMINUS_LABEL_HALF_SIZE: Vec2 = Vec2::new(24.0, 11.0);
+/// How close an obstacle may come to a terminus.
+const TERMINUS_CLEARANCE: f32 = 6.0;
+
+/// Number of sides of the polygon that stands in for a circular obstacle's outline.
+const CIRCLE_SIDES: usize = 24;
+
 #[derive(Deserialize, Debug)]
 pub enum Obstacle {
     Rect(Vec2, Vec2),
+    Circle(Vec2, f32),
+    Polygon(Vec<Vec2>),
+    /// Another obstacle that only blocks roads on the given layers.
+    Layers(Vec<u32>, Box<Obstacle>),
+    /// A rectangle that sits at the first waypoint while roads are drawn, and then
+    /// drives around the waypoints in a loop once the pixies are released, at `speed`
+    /// units per second. Pixies that it runs into are destroyed.
+    MovingRect {
+        size: Vec2,
+        waypoints: Vec<Vec2>,
+        speed: f32,
+    },
+}
+
+/// FNV-1a, which unlike the standard library's hasher is the same everywhere. Numbers
+/// go in by their bits, so that the hash doesn't depend on how they'd be printed.
+struct LayoutHash(u64);
+impl Default for LayoutHash {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+impl LayoutHash {
+    fn u64(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn f32(&mut self, value: f32) {
+        // Adding zero turns -0.0 into 0.0, which are the same place.
+        self.u64((value + 0.0).to_bits() as u64);
+    }
+
+    fn vec2(&mut self, value: Vec2) {
+        self.f32(value.x);
+        self.f32(value.y);
+    }
+}
+
+impl Obstacle {
+    fn hash_layout(&self, hash: &mut LayoutHash) {
+        match self {
+            Self::Rect(a, b) => {
+                hash.u64(0);
+                hash.vec2(*a);
+                hash.vec2(*b);
+            }
+            Self::Circle(center, radius) => {
+                hash.u64(1);
+                hash.vec2(*center);
+                hash.f32(*radius);
+            }
+            Self::Polygon(points) => {
+                hash.u64(2);
+                hash.u64(points.len() as u64);
+                points.iter().for_each(|p| hash.vec2(*p));
+            }
+            Self::Layers(layers, inner) => {
+                hash.u64(3);
+                hash.u64(layers.len() as u64);
+                layers.iter().for_each(|l| hash.u64(*l as u64));
+                inner.hash_layout(hash);
+            }
+            Self::MovingRect {
+                size,
+                waypoints,
+                speed,
+            } => {
+                hash.u64(4);
+                hash.vec2(*size);
+                hash.u64(waypoints.len() as u64);
+                waypoints.iter().for_each(|p| hash.vec2(*p));
+                hash.f32(*speed);
+            }
+        }
+    }
+
+    /// The obstacle's shape, without any layer restrictions.
+    pub fn base(&self) -> &Obstacle {
+        match self {
+            Self::Layers(_, inner) => inner.base(),
+            _ => self,
+        }
+    }
+
+    /// Whether `point` is inside the obstacle's outline.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let outline = self.outline();
+
+        outline
+            .iter()
+            .zip(outline.iter().cycle().skip(1))
+            .filter(|(a, b)| {
+                (a.y > point.y) != (b.y > point.y)
+                    && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            })
+            .count()
+            % 2
+            == 1
+    }
+
+    /// Whether a road from `a` to `b` on `layer` would run into this obstacle.
+    pub fn blocks_segment(&self, a: Vec2, b: Vec2, layer: u32) -> bool {
+        if self.layers().is_some_and(|l| !l.blocks(layer)) {
+            return false;
+        }
+
+        let outline = self.outline();
+        let crosses = outline
+            .iter()
+            .zip(outline.iter().cycle().skip(1))
+            .any(|(c, d)| !matches!(segment_collision(*c, *d, a, b), SegmentCollision::None));
+
+        crosses || self.contains(a)
+    }
+
+    /// The layers that this obstacle blocks, or `None` if it blocks all of them.
+    pub fn layers(&self) -> Option<ObstacleLayers> {
+        match self {
+            Self::Layers(layers, _) => Some(ObstacleLayers::new(layers)),
+            _ => None,
+        }
+    }
+
+    /// The corners of the obstacle's outline. Circles are approximated by a polygon
+    /// that contains them.
+    pub fn outline(&self) -> Vec<Vec2> {
+        match self.base() {
+            Self::Rect(top_left, bottom_right) => vec![
+                *top_left,
+                Vec2::new(bottom_right.x, top_left.y),
+                *bottom_right,
+                Vec2::new(top_left.x, bottom_right.y),
+            ],
+            Self::Circle(center, radius) => {
+                let step = std::f32::consts::TAU / CIRCLE_SIDES as f32;
+                let radius = radius / (step / 2.0).cos();
+
+                (0..CIRCLE_SIDES)
+                    .map(|i| *center + Vec2::from_angle(step * i as f32) * radius)
+                    .collect()
+            }
+            Self::Polygon(points) => points.clone(),
+            Self::MovingRect {
+                size, waypoints, ..
+            } => {
+                let rect =
+                    Rect::from_center_size(waypoints.first().copied().unwrap_or_default(), *size);
+
+                vec![
+                    Vec2::new(rect.min.x, rect.max.y),
+                    rect.max,
+                    Vec2::new(rect.max.x, rect.min.y),
+                    rect.min,
+                ]
+            }
+            Self::Layers(..) => unreachable!(),
+        }
+    }
+}
+
+/// The layers blocked by an obstacle, as a bit mask.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObstacleLayers(u32);
+
+impl ObstacleLayers {
+    pub fn new(layers: &[u32]) -> Self {
+        Self(layers.iter().fold(0, |mask, layer| mask | (1 << layer)))
+    }
+
+    pub fn blocks(&self, layer: u32) -> bool {
+        self.0 & (1 << layer) != 0
+    }
+
+    /// The lowest layer that is blocked.
+    pub fn lowest(&self) -> Option<u32> {
+        (self.0 != 0).then(|| self.0.trailing_zeros())
+    }
 }
 
 #[derive(Default, Debug, Deserialize, Clone, Component)]
@@ -22,4 +495,234 @@ pub struct Terminus {
     pub point: Vec2,
     pub emits: HashSet<PixieFlavor>,
     pub collects: HashSet<PixieFlavor>,
+    /// How many pixies this terminus emits, instead of the level's `pixies`.
+    #[serde(default)]
+    pub pixies: Option<u32>,
+    /// Seconds between pixies, instead of the usual steady stream.
+    #[serde(default)]
+    pub interval: Option<f32>,
+    /// Seconds to wait after the pixies are released before emitting any.
+    #[serde(default)]
+    pub delay: f32,
+    /// How many pixies per second this terminus can collect. Pixies that arrive any
+    /// faster wait their turn.
+    #[serde(default)]
+    pub capacity: Option<f32>,
+}
+
+impl Terminus {
+    /// When this terminus emits its pixies, on a level with `level_pixies` per
+    /// terminus.
+    pub fn schedule(&self, level_pixies: u32) -> EmitterSchedule {
+        EmitterSchedule {
+            pixies: self.pixies.unwrap_or(level_pixies),
+            interval: self.interval.unwrap_or(EMITTER_INTERVAL_SECS),
+            delay: self.delay,
+        }
+    }
+}
+
+/// Turns pixies of the `from` color that drive over `point` into the `to` color,
+/// keeping their net.
+#[derive(Default, Debug, Deserialize, Clone, Component)]
+pub struct Converter {
+    pub point: Vec2,
+    pub from: u32,
+    pub to: u32,
+}
+
+/// Exponents for each part of the score, so that a level can make speed matter more
+/// than cost, or the other way around. With the default of 1.0 for each, the score is
+/// pixies delivered per second per unit of cost.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub pixies: f32,
+    pub cost: f32,
+    pub time: f32,
+}
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            pixies: 1.0,
+            cost: 1.0,
+            time: 1.0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    pub fn score(&self, pixies: u32, cost: u32, elapsed: f32) -> u32 {
+        if cost == 0 || elapsed <= 0.0 {
+            return 0;
+        }
+
+        let val = (pixies as f32).powf(self.pixies)
+            / (cost as f32).powf(self.cost)
+            / elapsed.powf(self.time);
+
+        (val * SCORE_MULTIPLIER).ceil() as u32
+    }
+}
+
+/// How many pixies a terminus emits, and how often. The pixies are shared between the
+/// flavors it emits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmitterSchedule {
+    pub pixies: u32,
+    pub interval: f32,
+    pub delay: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_ids() {
+        assert_eq!(level_id(&AssetPath::from("levels/3.level.ron")), "3");
+        assert_eq!(
+            level_id(&AssetPath::from("levels/custom/spiral.level.ron")),
+            "custom/spiral"
+        );
+    }
+
+    #[test]
+    fn layer_specific_obstacles() {
+        let obstacle = Obstacle::Layers(vec![1, 3], Box::new(Obstacle::Circle(Vec2::ZERO, 10.0)));
+        let layers = obstacle.layers().unwrap();
+
+        assert!(layers.blocks(1));
+        assert!(!layers.blocks(2));
+        assert!(layers.blocks(3));
+        assert_eq!(layers.lowest(), Some(1));
+        assert!(Obstacle::Rect(Vec2::ZERO, Vec2::ONE).layers().is_none());
+
+        // The outline of a circle shouldn't cut inside it.
+        let outline = obstacle.outline();
+        for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+            assert!(((*a + *b) / 2.0).length() >= 10.0 - 0.001);
+        }
+    }
+
+    fn emitter_level(obstacles: Vec<Obstacle>) -> Level {
+        Level {
+            terminuses: vec![
+                Terminus {
+                    point: Vec2::ZERO,
+                    emits: [PixieFlavor::default()].into_iter().collect(),
+                    collects: HashSet::default(),
+                    ..default()
+                },
+                Terminus {
+                    point: Vec2::new(480.0, 0.0),
+                    emits: HashSet::default(),
+                    collects: [PixieFlavor::default()].into_iter().collect(),
+                    ..default()
+                },
+            ],
+            obstacles,
+            ..crate::fixtures::level(1)
+        }
+    }
+
+    #[test]
+    fn level_warnings() {
+        assert!(emitter_level(vec![]).warnings().is_empty());
+
+        // Far away
+        let far = Obstacle::Rect(Vec2::new(200.0, 200.0), Vec2::new(300.0, 300.0));
+        assert!(emitter_level(vec![far]).warnings().is_empty());
+
+        let covering = Obstacle::Rect(Vec2::new(-40.0, -40.0), Vec2::new(40.0, 40.0));
+        let warnings = emitter_level(vec![covering]).warnings();
+        assert!(warnings[0].contains("covers the terminus"));
+        assert!(warnings[1].contains("covers a label"));
+        assert!(warnings[2].contains("no way out"));
+
+        // Just below the terminus, over its label
+        let label = Obstacle::Rect(Vec2::new(-10.0, -30.0), Vec2::new(10.0, -20.0));
+        let warnings = emitter_level(vec![label]).warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("covers a label"));
+    }
+
+    #[test]
+    fn level_errors() {
+        let mut level = emitter_level(vec![]);
+        assert!(level.errors().is_empty());
+
+        level.star_thresholds = vec![40, 20];
+        level.terminuses[1].point = Vec2::new(500.0, 0.0);
+        level.terminuses[1].collects = [PixieFlavor { color: 1, net: 0 }].into_iter().collect();
+        let warnings = level.warnings();
+        assert!(warnings[0].contains("out of order"));
+        assert!(warnings[1].contains("off the grid"));
+        assert!(warnings[2].contains("Nothing collects"));
+        assert!(warnings[3].contains("Nothing emits"));
+
+        level.terminuses[1].capacity = Some(0.0);
+        assert!(level.errors()[0].contains("no capacity"));
+
+        level.max_segment_length = Some(0.5);
+        assert!(level.errors()[1].contains("no road fits"));
+
+        level.layers = 4;
+        level.terminuses.clear();
+        assert_eq!(level.errors().len(), 3);
+    }
+
+    #[test]
+    fn score_weights() {
+        let weights = ScoreWeights::default();
+        assert_eq!(weights.score(64, 8, 4.0), 20000);
+        assert_eq!(weights.score(64, 0, 4.0), 0);
+
+        // Slower runs lose out more when time counts for more.
+        let speedy = ScoreWeights {
+            time: 2.0,
+            ..default()
+        };
+        assert_eq!(speedy.score(64, 8, 4.0), 5000);
+        assert_eq!(speedy.score(64, 8, 2.0), 20000);
+    }
+
+    #[test]
+    fn layout_changes() {
+        let level = emitter_level(vec![]);
+        let mut renamed = emitter_level(vec![]);
+        renamed.name = "Renamed".to_string();
+        assert_eq!(level.layout_hash(), renamed.layout_hash());
+
+        let block = Obstacle::Rect(Vec2::new(200.0, -10.0), Vec2::new(240.0, 10.0));
+        let blocked = emitter_level(vec![block]);
+        assert_ne!(level.layout_hash(), blocked.layout_hash());
+
+        // The smallest of moves counts, and the sign of zero doesn't.
+        let nudged = Obstacle::Rect(Vec2::new(200.0, -10.0), Vec2::new(240.0, 10.000001));
+        assert_ne!(
+            blocked.layout_hash(),
+            emitter_level(vec![nudged]).layout_hash()
+        );
+        let signed = Obstacle::Circle(Vec2::new(-0.0, 100.0), 10.0);
+        let unsigned = Obstacle::Circle(Vec2::new(0.0, 100.0), 10.0);
+        assert_eq!(
+            emitter_level(vec![signed]).layout_hash(),
+            emitter_level(vec![unsigned]).layout_hash()
+        );
+
+        let road = |a: Vec2, b: Vec2, layer| RoadSegment {
+            points: (a, b),
+            layer,
+            wide: false,
+            bias: crate::bias::RouteBias::default(),
+        };
+        let across = road(Vec2::ZERO, Vec2::new(480.0, 0.0), 1);
+        assert!(level.fits(&across));
+        assert!(!blocked.fits(&across));
+        assert!(!level.fits(&road(Vec2::ZERO, Vec2::new(48.0, 0.0), 2)));
+
+        // Through the middle of a terminus.
+        assert!(!level.fits(&road(Vec2::new(-48.0, 0.0), Vec2::new(48.0, 0.0), 1)));
+    }
 }
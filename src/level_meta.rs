@@ -0,0 +1,195 @@
+//! Per-level extras that are saved alongside best scores, such as how many times a
+//! level has been attempted.
+
+use crate::{
+    badge::LevelBadges,
+    hotseat::HotseatTurn,
+    save::{BestScores, LevelMetadata},
+    sim::SimulationState,
+    update_score_system, AfterUpdate, GameState, Handles, RoadSegment, Score, ScoreCalc,
+    SelectedLevel,
+};
+use bevy::{prelude::*, utils::SystemTime};
+
+pub struct LevelMetaPlugin;
+impl Plugin for LevelMetaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), played_system);
+        app.add_systems(
+            AfterUpdate,
+            (
+                attempt_system,
+                best_modifiers_system.after(update_score_system),
+            )
+                .in_set(ScoreCalc),
+        );
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Reflect)]
+pub struct LevelMeta {
+    /// Optional constraints that any solution has been completed under.
+    pub badges: LevelBadges,
+    /// Modifiers that were in effect when the best score was set.
+    pub modifiers: Vec<String>,
+    /// Number of times the simulation has been started.
+    pub attempts: u32,
+    /// When the level was last played, in seconds since the unix epoch.
+    pub last_played: u64,
+}
+
+impl LevelMeta {
+    /// Lines describing this level's history, for the level select screen.
+    pub fn tooltip(&self, now: u64) -> Vec<String> {
+        let mut lines = vec![format!("ATTEMPTS {}", self.attempts)];
+
+        if self.last_played > 0 {
+            lines.push(format!(
+                "PLAYED {}",
+                time_ago(now.saturating_sub(self.last_played))
+            ));
+        }
+        if !self.modifiers.is_empty() {
+            lines.push(format!("BEST WITH {}", self.modifiers.join(" ")));
+        }
+        if self.badges.any() {
+            lines.push(format!("BADGES {}", self.badges.label()));
+        }
+
+        lines
+    }
+}
+
+/// Seconds since the unix epoch, or zero if the clock is unavailable.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn time_ago(seconds: u64) -> String {
+    match seconds {
+        0..=59 => "JUST NOW".to_string(),
+        60..=3599 => format!("{}M AGO", seconds / 60),
+        3600..=86399 => format!("{}H AGO", seconds / 3600),
+        _ => format!("{}D AGO", seconds / 86400),
+    }
+}
+
+/// The modifiers in effect for a solution.
+pub fn modifiers<'a>(
+    turn: &HotseatTurn,
+    segments: impl IntoIterator<Item = &'a RoadSegment>,
+) -> Vec<String> {
+    let mut modifiers = vec![];
+
+    if turn.enabled() {
+        modifiers.push("HOTSEAT".to_string());
+    }
+    if segments.into_iter().any(|s| s.wide) {
+        modifiers.push("WIDE".to_string());
+    }
+
+    modifiers
+}
+
+fn played_system(
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    mut metadata: ResMut<LevelMetadata>,
+) {
+    metadata
+        .0
+        .entry(handles.level_id(selected_level.0))
+        .or_default()
+        .last_played = now();
+}
+
+fn attempt_system(
+    sim_state: Res<SimulationState>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    mut metadata: ResMut<LevelMetadata>,
+) {
+    if !sim_state.is_changed() || *sim_state != SimulationState::Running {
+        return;
+    }
+
+    metadata
+        .0
+        .entry(handles.level_id(selected_level.0))
+        .or_default()
+        .attempts += 1;
+}
+
+fn best_modifiers_system(
+    sim_state: Res<SimulationState>,
+    score: Res<Score>,
+    best_scores: Res<BestScores>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    turn: Res<HotseatTurn>,
+    mut metadata: ResMut<LevelMetadata>,
+    q_segments: Query<&RoadSegment>,
+) {
+    if !sim_state.is_changed() || *sim_state != SimulationState::Finished {
+        return;
+    }
+
+    let id = handles.level_id(selected_level.0);
+
+    if score.0.is_none() || score.0 != best_scores.0.get(&id).copied() {
+        return;
+    }
+
+    metadata.0.entry(id).or_default().modifiers = modifiers(&turn, q_segments.iter());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bias::RouteBias;
+
+    #[test]
+    fn tooltip() {
+        let meta = LevelMeta {
+            badges: LevelBadges {
+                flat: false,
+                lean: true,
+            },
+            modifiers: vec!["WIDE".to_string()],
+            attempts: 3,
+            last_played: 1000,
+        };
+
+        assert_eq!(
+            meta.tooltip(1000 + 7200),
+            vec![
+                "ATTEMPTS 3",
+                "PLAYED 2H AGO",
+                "BEST WITH WIDE",
+                "BADGES LEAN"
+            ]
+        );
+        assert_eq!(LevelMeta::default().tooltip(1000), vec!["ATTEMPTS 0"]);
+    }
+
+    #[test]
+    fn hotseat_is_a_modifier() {
+        let segment = RoadSegment {
+            points: (Vec2::ZERO, Vec2::X),
+            layer: 1,
+            wide: true,
+            bias: RouteBias::default(),
+        };
+
+        assert_eq!(
+            modifiers(&HotseatTurn::new(false), []),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            modifiers(&HotseatTurn::new(true), [&segment]),
+            vec!["HOTSEAT", "WIDE"]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Moving straight to the previous or next level without going back to the level
+//! select screen. Page Up and Page Down do the same as the buttons, unless rebound.
+
+use crate::{
+    color, controls::Action, level::Level, loading::NUM_LEVELS, playing_enter_system,
+    restart::Restart, save::InputMap, DrawingInput, GameState, Handles, LineDrawingState,
+    SelectedLevel, BOTTOM_BAR_HEIGHT,
+};
+use bevy::prelude::*;
+
+pub struct LevelNavPlugin;
+impl Plugin for LevelNavPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelNav>();
+        app.add_event::<NavigateLevel>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            level_nav_enter_system.after(playing_enter_system),
+        );
+        app.add_systems(Update, level_nav_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            (
+                level_nav_button_system,
+                navigate_level_system,
+                level_nav_prompt_system,
+            )
+                .chain()
+                .after(level_nav_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// A button that moves by the given number of levels.
+#[derive(Component)]
+pub struct LevelNavButton(pub i32);
+
+#[derive(Component)]
+struct LevelNavPrompt;
+
+#[derive(Event)]
+struct NavigateLevel(i32);
+
+#[derive(Resource, Default)]
+struct LevelNav {
+    /// A level that we're waiting for a second press to move to, because leaving
+    /// now would throw away the road being drawn.
+    confirming: Option<u32>,
+}
+
+/// The level that's `direction` levels away from the selected one, if it's available.
+fn neighbor_level(
+    selected: u32,
+    direction: i32,
+    handles: &Handles,
+    levels: &Assets<Level>,
+) -> Option<u32> {
+    // User levels aren't a sequence, so only the built-in levels can be stepped through.
+    if selected > NUM_LEVELS {
+        return None;
+    }
+
+    let target = selected.checked_add_signed(direction)?;
+    if !(1..=NUM_LEVELS).contains(&target) {
+        return None;
+    }
+
+    handles
+        .levels
+        .get(target as usize - 1)
+        .and_then(|h| levels.get(h))
+        .map(|_| target)
+}
+
+fn level_nav_enter_system(
+    mut nav: ResMut<LevelNav>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    q_button: Query<(&LevelNavButton, &Children)>,
+    mut q_text: Query<&mut TextColor>,
+) {
+    nav.confirming = None;
+
+    for (button, children) in q_button.iter() {
+        let available = neighbor_level(selected_level.0, button.0, &handles, &levels).is_some();
+
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text_color) = iter.fetch_next() {
+            text_color.0 = if available {
+                color::UI_BUTTON_TEXT
+            } else {
+                color::UI_GREY_RED
+            };
+        }
+    }
+}
+
+fn level_nav_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut events: EventWriter<NavigateLevel>,
+) {
+    if input_map.just_pressed(Action::PreviousLevel, &keyboard_input) {
+        events.send(NavigateLevel(-1));
+    } else if input_map.just_pressed(Action::NextLevel, &keyboard_input) {
+        events.send(NavigateLevel(1));
+    }
+}
+
+fn level_nav_button_system(
+    q_interaction: Query<(&Interaction, &LevelNavButton), Changed<Interaction>>,
+    mut events: EventWriter<NavigateLevel>,
+) {
+    for (_, button) in q_interaction
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        events.send(NavigateLevel(button.0));
+    }
+}
+
+fn navigate_level_system(
+    mut events: EventReader<NavigateLevel>,
+    mut nav: ResMut<LevelNav>,
+    selected_level: Res<SelectedLevel>,
+    mut restart: Restart,
+    line_state: Res<LineDrawingState>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+) {
+    let Some(NavigateLevel(direction)) = events.read().last() else {
+        return;
+    };
+
+    let Some(target) = neighbor_level(selected_level.0, *direction, &handles, &levels) else {
+        return;
+    };
+
+    // Finished roads are saved as they're placed, but a road that's still being drawn
+    // would be lost.
+    if line_state.drawing && nav.confirming != Some(target) {
+        nav.confirming = Some(target);
+        return;
+    }
+
+    restart.play_level(target);
+}
+
+fn level_nav_prompt_system(
+    mut commands: Commands,
+    mut nav: ResMut<LevelNav>,
+    line_state: Res<LineDrawingState>,
+    handles: Res<Handles>,
+    q_prompt: Query<Entity, With<LevelNavPrompt>>,
+) {
+    if !line_state.drawing && nav.confirming.is_some() {
+        nav.confirming = None;
+    }
+
+    if !nav.is_changed() {
+        return;
+    }
+
+    for entity in q_prompt.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(target) = nav.confirming else {
+        return;
+    };
+
+    commands.spawn((
+        Text::new(format!(
+            "UNFINISHED ROAD WILL BE LOST. PRESS AGAIN FOR L{target}"
+        )),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(color::UI_GREY_RED),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(BOTTOM_BAR_HEIGHT + 10.),
+            left: Val::Px(0.),
+            right: Val::Px(0.),
+            ..default()
+        },
+        LevelNavPrompt,
+    ));
+}
@@ -1,11 +1,26 @@
-use crate::{color, level::Level, loading::NUM_LEVELS, save::BestScores, GameState, Handles};
-use bevy::prelude::*;
+use crate::{
+    color,
+    friends::{friend_color, ExportScoresButton, Friends},
+    hotseat::HotseatButton,
+    level::Level,
+    level_meta,
+    loading::NUM_LEVELS,
+    save::{BestCosts, BestScores, BestTimes, LevelMetadata},
+    settings::SettingsPanelButton,
+    stats::StatsButton,
+    theme::{Theme, ThemeButton},
+    GameState, Handles,
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+use itertools::Itertools;
 
 pub struct LevelSelectPlugin;
 #[derive(Component)]
 pub struct LevelSelectScreen;
 #[derive(Component)]
 pub struct LevelSelectButton(u32);
+#[derive(Component)]
+struct LevelTooltip;
 
 impl Plugin for LevelSelectPlugin {
     fn build(&self, app: &mut App) {
@@ -17,6 +32,7 @@ impl Plugin for LevelSelectPlugin {
                 level_select_update,
                 crate::button_system,
                 level_select_button_system,
+                level_tooltip_system,
             )
                 .run_if(in_state(GameState::LevelSelect)),
         );
@@ -25,6 +41,18 @@ impl Plugin for LevelSelectPlugin {
     }
 }
 
+/// A level's records, like `Æ40 §12 ŧ4.5`, leaving out any that it doesn't have.
+pub fn records_label(score: Option<u32>, cost: Option<u32>, time: Option<f32>) -> String {
+    [
+        score.map(|score| format!("Æ{score}")),
+        cost.map(|cost| format!("§{cost}")),
+        time.map(|time| format!("ŧ{time:.1}")),
+    ]
+    .into_iter()
+    .flatten()
+    .join(" ")
+}
+
 fn level_select_button_system(
     query: Query<(&Interaction, &LevelSelectButton), Changed<Interaction>>,
     mut next_state: ResMut<NextState<GameState>>,
@@ -37,7 +65,7 @@ fn level_select_button_system(
             .levels
             .get(button.0 as usize - 1)
             .and_then(|h| levels.get(h))
-            .is_none()
+            .is_none_or(|level| !level.errors().is_empty())
         {
             continue;
         };
@@ -50,8 +78,13 @@ fn level_select_button_system(
 fn level_select_enter(
     mut commands: Commands,
     best_scores: Res<BestScores>,
+    best_costs: Res<BestCosts>,
+    best_times: Res<BestTimes>,
+    metadata: Res<LevelMetadata>,
     handles: Res<Handles>,
     levels: Res<Assets<Level>>,
+    theme: Res<Theme>,
+    friends: Friends,
 ) {
     let total_score: u32 = best_scores.0.iter().map(|(_, v)| v).sum();
 
@@ -85,7 +118,7 @@ fn level_select_enter(
                             font_size: 50.0,
                             ..default()
                         },
-                        TextColor(color::PIXIE[1].into()),
+                        TextColor(theme.pixie(1).into()),
                     ));
                     parent.spawn((
                         Node {
@@ -100,6 +133,121 @@ fn level_select_enter(
                         },
                         TextColor(color::FINISHED_ROAD[1]),
                     ));
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                align_self: AlignSelf::Center,
+                                margin: UiRect::top(Val::Px(10.)),
+                                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                                ..default()
+                            },
+                            BackgroundColor(color::UI_NORMAL_BUTTON),
+                            HotseatButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::default(),
+                                TextFont {
+                                    font: handles.fonts[0].clone(),
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(color::UI_BUTTON_TEXT),
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                align_self: AlignSelf::Center,
+                                margin: UiRect::top(Val::Px(10.)),
+                                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                                ..default()
+                            },
+                            BackgroundColor(color::UI_NORMAL_BUTTON),
+                            ThemeButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::default(),
+                                TextFont {
+                                    font: handles.fonts[0].clone(),
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(color::UI_BUTTON_TEXT),
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                align_self: AlignSelf::Center,
+                                margin: UiRect::top(Val::Px(10.)),
+                                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                                ..default()
+                            },
+                            BackgroundColor(color::UI_NORMAL_BUTTON),
+                            ExportScoresButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("EXPORT SCORES"),
+                                TextFont {
+                                    font: handles.fonts[0].clone(),
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(color::UI_BUTTON_TEXT),
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                align_self: AlignSelf::Center,
+                                margin: UiRect::top(Val::Px(10.)),
+                                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                                ..default()
+                            },
+                            BackgroundColor(color::UI_NORMAL_BUTTON),
+                            SettingsPanelButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("SETTINGS"),
+                                TextFont {
+                                    font: handles.fonts[0].clone(),
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(color::UI_BUTTON_TEXT),
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                align_self: AlignSelf::Center,
+                                margin: UiRect::top(Val::Px(10.)),
+                                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                                ..default()
+                            },
+                            BackgroundColor(color::UI_NORMAL_BUTTON),
+                            StatsButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("STATS"),
+                                TextFont {
+                                    font: handles.fonts[0].clone(),
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(color::UI_BUTTON_TEXT),
+                            ));
+                        });
                 });
 
             let cols = (NUM_LEVELS as f32 / 3.).ceil() as u16;
@@ -115,107 +263,293 @@ fn level_select_enter(
                 })
                 .with_children(|parent| {
                     for i in 1..=NUM_LEVELS {
-                        parent
-                            .spawn((
-                                Button,
-                                Node {
-                                    width: Val::Px(150.),
-                                    height: Val::Px(150.),
-                                    flex_direction: FlexDirection::Column,
-                                    justify_content: JustifyContent::Center,
-                                    align_items: AlignItems::Center,
-                                    ..default()
-                                },
-                                BackgroundColor(color::UI_NORMAL_BUTTON),
-                                LevelSelectButton(i),
-                            ))
-                            .with_children(|parent| {
-                                let level = handles
+                        spawn_level_button(
+                            parent,
+                            i,
+                            format!("{i}"),
+                            50.0,
+                            &best_scores,
+                            &best_costs,
+                            &best_times,
+                            &metadata,
+                            &handles,
+                            &levels,
+                            &theme,
+                            &friends,
+                        );
+                    }
+                });
+
+            let num_custom =
+                handles.levels.len() as u32 - NUM_LEVELS.min(handles.levels.len() as u32);
+            if num_custom == 0 {
+                return;
+            }
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("USER LEVELS"),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 25.0,
+                            ..default()
+                        },
+                        TextColor(color::UI_WHITE),
+                    ));
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            flex_wrap: FlexWrap::Wrap,
+                            justify_content: JustifyContent::Center,
+                            row_gap: Val::Px(10.),
+                            column_gap: Val::Px(10.),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for i in NUM_LEVELS + 1..=NUM_LEVELS + num_custom {
+                                let name = handles
                                     .levels
                                     .get(i as usize - 1)
-                                    .and_then(|h| levels.get(h));
-
-                                let level_color = match level {
-                                    Some(_) => color::UI_WHITE,
-                                    None => color::UI_GREY_RED,
-                                };
-
-                                let (score_text, star_text_one, star_text_two) =
-                                    if let (Some(score), Some(level)) =
-                                        (best_scores.0.get(&i), level)
-                                    {
-                                        let stars = level
-                                            .star_thresholds
-                                            .iter()
-                                            .filter(|t| **t <= *score)
-                                            .count();
-
-                                        (
-                                            format!("Æ{score}"),
-                                            "★".repeat(stars),
-                                            "★".repeat(3 - stars),
-                                        )
-                                    } else {
-                                        ("".to_string(), "".to_string(), "".to_string())
-                                    };
-
-                                parent
-                                    .spawn((
-                                        Text::default(),
-                                        // See Bevy#16521
-                                        TextFont {
-                                            font: handles.fonts[0].clone(),
-                                            ..default()
-                                        },
-                                    ))
-                                    .with_children(|parent| {
-                                        parent.spawn((
-                                            TextSpan::new(star_text_one),
-                                            TextFont {
-                                                font: handles.fonts[0].clone(),
-                                                font_size: 25.0,
-                                                ..default()
-                                            },
-                                            TextColor(color::UI_WHITE),
-                                        ));
-                                        parent.spawn((
-                                            TextSpan::new(star_text_two),
-                                            TextFont {
-                                                font: handles.fonts[0].clone(),
-                                                font_size: 25.0,
-                                                ..default()
-                                            },
-                                            TextColor(Srgba::gray(0.25).into()),
-                                        ));
-                                    });
-
-                                parent.spawn((
-                                    Text::new(format!("{i}")),
-                                    TextFont {
-                                        font: handles.fonts[0].clone(),
-                                        font_size: 50.0,
-                                        ..default()
-                                    },
-                                    TextColor(level_color),
-                                ));
-
-                                parent.spawn((
-                                    Text::new(score_text),
-                                    TextFont {
-                                        font: handles.fonts[0].clone(),
-                                        font_size: 25.0,
-                                        ..default()
-                                    },
-                                    TextColor(color::FINISHED_ROAD[1]),
-                                ));
-                            });
-                    }
+                                    .and_then(|h| levels.get(h))
+                                    .map(|l| l.name.to_uppercase())
+                                    .unwrap_or_default();
+
+                                spawn_level_button(
+                                    parent,
+                                    i,
+                                    name,
+                                    18.0,
+                                    &best_scores,
+                                    &best_costs,
+                                    &best_times,
+                                    &metadata,
+                                    &handles,
+                                    &levels,
+                                    &theme,
+                                    &friends,
+                                );
+                            }
+                        });
                 });
         });
+
+    commands.spawn((
+        Node {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        Text::default(),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(color::UI_WHITE),
+        BackgroundColor(color::DIALOG_BACKGROUND),
+        GlobalZIndex(1),
+        LevelSelectScreen,
+        LevelTooltip,
+    ));
+}
+
+fn spawn_level_button(
+    parent: &mut ChildBuilder,
+    i: u32,
+    title: String,
+    title_size: f32,
+    best_scores: &BestScores,
+    best_costs: &BestCosts,
+    best_times: &BestTimes,
+    metadata: &LevelMetadata,
+    handles: &Handles,
+    levels: &Assets<Level>,
+    theme: &Theme,
+    friends: &Friends,
+) {
+    let id = handles.level_id(i);
+
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(150.),
+                height: Val::Px(150.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(color::UI_NORMAL_BUTTON),
+            LevelSelectButton(i),
+        ))
+        .with_children(|parent| {
+            let level = handles
+                .levels
+                .get(i as usize - 1)
+                .and_then(|h| levels.get(h));
+
+            let level_color = match level {
+                Some(level) if level.errors().is_empty() => color::UI_WHITE,
+                _ => color::UI_GREY_RED,
+            };
+
+            let (score_text, star_text_one, star_text_two) =
+                if let (Some(score), Some(level)) = (best_scores.0.get(&id), level) {
+                    let stars = level
+                        .star_thresholds
+                        .iter()
+                        .filter(|t| **t <= *score)
+                        .count();
+
+                    (
+                        format!("Æ{score}"),
+                        "★".repeat(stars),
+                        "★".repeat(3 - stars),
+                    )
+                } else {
+                    ("".to_string(), "".to_string(), "".to_string())
+                };
+
+            parent
+                .spawn((
+                    Text::default(),
+                    // See Bevy#16521
+                    TextFont {
+                        font: handles.fonts[0].clone(),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextSpan::new(star_text_one),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 25.0,
+                            ..default()
+                        },
+                        TextColor(color::UI_WHITE),
+                    ));
+                    parent.spawn((
+                        TextSpan::new(star_text_two),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 25.0,
+                            ..default()
+                        },
+                        TextColor(Srgba::gray(0.25).into()),
+                    ));
+                });
+
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font: handles.fonts[0].clone(),
+                    font_size: title_size,
+                    ..default()
+                },
+                TextColor(level_color),
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+
+            parent.spawn((
+                Text::new(score_text),
+                TextFont {
+                    font: handles.fonts[0].clone(),
+                    font_size: 25.0,
+                    ..default()
+                },
+                TextColor(color::FINISHED_ROAD[1]),
+            ));
+
+            let records = records_label(
+                None,
+                best_costs.0.get(&id).copied(),
+                best_times.0.get(&id).copied(),
+            );
+            if !records.is_empty() {
+                parent.spawn((
+                    Text::new(records),
+                    TextFont {
+                        font: handles.fonts[0].clone(),
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(color::UI_WHITE),
+                ));
+            }
+
+            if let Some((name, friend_score)) = friends.best(&id) {
+                parent.spawn((
+                    Text::new(format!("{name} Æ{friend_score}")),
+                    TextFont {
+                        font: handles.fonts[0].clone(),
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(friend_color(friend_score, best_scores.0.get(&id).copied())),
+                ));
+            }
+
+            if let Some(badges) = metadata.0.get(&id).map(|m| m.badges).filter(|b| b.any()) {
+                parent.spawn((
+                    Text::new(badges.label()),
+                    TextFont {
+                        font: handles.fonts[0].clone(),
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(theme.pixie(5).into()),
+                ));
+            }
+        });
 }
 
 fn level_select_update() {}
 
+fn level_tooltip_system(
+    q_buttons: Query<(&Interaction, &LevelSelectButton)>,
+    mut q_tooltip: Query<(&mut Node, &mut Text), With<LevelTooltip>>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    metadata: Res<LevelMetadata>,
+    handles: Res<Handles>,
+) {
+    let Ok((mut node, mut text)) = q_tooltip.get_single_mut() else {
+        return;
+    };
+
+    let hovered = q_buttons
+        .iter()
+        .find(|(i, _)| **i != Interaction::None)
+        .and_then(|(_, button)| metadata.0.get(&handles.level_id(button.0)));
+
+    let (Some(meta), Some(cursor)) = (
+        hovered,
+        q_window.get_single().ok().and_then(|w| w.cursor_position()),
+    ) else {
+        node.display = Display::None;
+        return;
+    };
+
+    node.display = Display::Flex;
+    node.left = Val::Px(cursor.x + 16.);
+    node.top = Val::Px(cursor.y + 16.);
+
+    let tooltip = meta.tooltip(level_meta::now()).join("\n");
+    if text.0 != tooltip {
+        text.0 = tooltip;
+    }
+}
+
 fn level_select_exit(
     mut commands: Commands,
     query: Query<Entity, With<LevelSelectScreen>>,
@@ -228,3 +562,18 @@ fn level_select_exit(
     mouse.reset(MouseButton::Left);
     mouse.clear();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        assert_eq!(
+            records_label(Some(40), Some(12), Some(4.54)),
+            "Æ40 §12 ŧ4.5"
+        );
+        assert_eq!(records_label(None, Some(12), None), "§12");
+        assert_eq!(records_label(None, None, None), "");
+    }
+}
@@ -0,0 +1,140 @@
+//! Checks each level as it's loaded for problems that make it unplayable, such as an
+//! obstacle sitting on top of a terminus, and logs them for level authors.
+//!
+//! Levels with errors can't be played at all, and are listed in a panel on the level
+//! select screen. For the rest, debug builds list every warning while playing so that
+//! level authors can fix them. Players just get a heads up.
+
+use crate::{
+    level::{level_id, Level},
+    level_select::LevelSelectScreen,
+    playing_enter_system, GameState, Handles, SelectedLevel,
+};
+use bevy::prelude::*;
+
+pub struct LevelWarningsPlugin;
+impl Plugin for LevelWarningsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, log_level_problems_system);
+        app.add_systems(OnEnter(GameState::LevelSelect), level_errors_panel_system);
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            level_warnings_system.after(playing_enter_system),
+        );
+    }
+}
+
+fn log_level_problems_system(
+    mut events: EventReader<AssetEvent<Level>>,
+    levels: Res<Assets<Level>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        let Some(level) = levels.get(*id) else {
+            continue;
+        };
+
+        let name = asset_server
+            .get_path(*id)
+            .map_or_else(|| level.name.clone(), |path| level_id(&path));
+
+        for error in level.errors() {
+            error!("Level {name}: {error}");
+        }
+        for warning in level.warnings() {
+            warn!("Level {name}: {warning}");
+        }
+    }
+}
+
+fn level_errors_panel_system(
+    mut commands: Commands,
+    levels: Res<Assets<Level>>,
+    handles: Res<Handles>,
+) {
+    let lines: Vec<_> = handles
+        .levels
+        .iter()
+        .enumerate()
+        .filter_map(|(i, h)| Some((i, levels.get(h)?)))
+        .flat_map(|(i, level)| {
+            let id = handles.level_id(i as u32 + 1);
+            level
+                .errors()
+                .into_iter()
+                .map(move |error| format!("LEVEL {id}: {error}"))
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        Text::new(lines.join("\n")),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(bevy::color::palettes::css::RED.into()),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(0.),
+            right: Val::Px(0.),
+            ..default()
+        },
+        LevelSelectScreen,
+    ));
+}
+
+fn level_warnings_system(
+    mut commands: Commands,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+) {
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    let warnings = level.warnings();
+    if warnings.is_empty() {
+        return;
+    }
+
+    let text = if cfg!(debug_assertions) {
+        warnings.join("\n")
+    } else {
+        "THIS LEVEL MAY NOT BE SOLVABLE".to_string()
+    };
+
+    commands.spawn((
+        Text::new(text),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(bevy::color::palettes::css::RED.into()),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(0.),
+            right: Val::Px(0.),
+            ..default()
+        },
+    ));
+}
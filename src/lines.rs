@@ -1,10 +1,19 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_8};
+
 use bevy::prelude::*;
+use itertools::Itertools;
 
 use crate::{
-    collision::{point_segment_collision, SegmentCollision},
-    RoadSegment,
+    collision::{point_segment_collision, segment_collision, SegmentCollision},
+    RoadSegment, GRID_SIZE,
 };
 
+/// Number of straight chords used to draw a quarter circle arc, per grid unit of
+/// radius.
+const ARC_CHORDS_PER_GRID: f32 = 4.0;
+/// Upper limit on the number of chords in a single arc.
+const ARC_MAX_CHORDS: f32 = 16.0;
+
 #[derive(Clone, Copy, Debug)]
 pub enum Axis {
     X,
@@ -80,6 +89,166 @@ pub fn possible_lines(
     vec![vec![(from, b), (b, to)], vec![(from, a), (a, to)]]
 }
 
+/// Like [`possible_lines`], but the polylines may also travel at odd multiples of
+/// 22.5 degrees. Those never pass through two grid points, so the middle point of
+/// each polyline is usually off the grid.
+/// ```text
+///     i
+///    /
+/// o-o
+/// ```
+pub fn possible_fine_lines(
+    from: Vec2,
+    to: Vec2,
+    axis_preference: Option<Axis>,
+) -> Vec<Vec<(Vec2, Vec2)>> {
+    let diff = to - from;
+
+    if diff.x == 0.0 && diff.y == 0.0 {
+        return vec![];
+    }
+
+    let steps = diff.to_angle() / FRAC_PI_8;
+    if (steps - steps.round()).abs() < 1e-4 {
+        return vec![vec![(from, to)]];
+    }
+
+    // the two allowed directions on either side of the straight line, and how far
+    // to go in each of them to get there.
+    let dir_a = fine_direction(steps.floor());
+    let dir_b = fine_direction(steps.ceil());
+    let cross = dir_a.perp_dot(dir_b);
+
+    let a = from + dir_a * diff.perp_dot(dir_b) / cross;
+    let b = from + dir_b * dir_a.perp_dot(diff) / cross;
+
+    let leaves_in = |dir: Vec2, axis: Axis| match axis {
+        Axis::X => dir.y.abs() < 1e-4,
+        Axis::Y => dir.x.abs() < 1e-4,
+    };
+
+    if axis_preference.is_some_and(|axis| leaves_in(dir_b, axis)) {
+        return vec![vec![(from, b), (b, to)], vec![(from, a), (a, to)]];
+    }
+
+    vec![vec![(from, a), (a, to)], vec![(from, b), (b, to)]]
+}
+
+/// The unit vector `steps` multiples of 22.5 degrees around from the x axis, with
+/// no rounding error off the axes.
+fn fine_direction(steps: f32) -> Vec2 {
+    let dir = Vec2::from_angle(steps * FRAC_PI_8);
+    Vec2::select(dir.abs().cmplt(Vec2::splat(1e-6)), Vec2::ZERO, dir)
+}
+
+/// Given a start and endpoint that are diagonal from one another, return the two
+/// quarter circle arcs that connect them, each made from a number of short
+/// straight chords.
+/// ```text
+/// o - .          o
+///       .        .
+///        i        . _ i
+/// ```
+/// Returns nothing if the points aren't diagonal.
+///
+/// * `axis_preference` - If this is Some(Axis), we will offer up the arc that
+///   leaves `from` in the preferred axis as the first result.
+pub fn possible_arcs(
+    from: Vec2,
+    to: Vec2,
+    axis_preference: Option<Axis>,
+) -> Vec<Vec<(Vec2, Vec2)>> {
+    let diff = to - from;
+
+    if diff.x == 0.0 || diff.x.abs() != diff.y.abs() {
+        return vec![];
+    }
+
+    // an arc leaves its start at a right angle to the direction of its center
+    let x_first = arc(from, to, Vec2::new(from.x, to.y));
+    let y_first = arc(from, to, Vec2::new(to.x, from.y));
+
+    if matches!(axis_preference, Some(Axis::Y)) {
+        return vec![y_first, x_first];
+    }
+
+    vec![x_first, y_first]
+}
+
+/// A quarter circle around `center` from `from` to `to`, as a chain of chords.
+fn arc(from: Vec2, to: Vec2, center: Vec2) -> Vec<(Vec2, Vec2)> {
+    let start = from - center;
+    let sweep = FRAC_PI_2 * start.perp_dot(to - center).signum();
+
+    let chords = (start.length() / GRID_SIZE * ARC_CHORDS_PER_GRID)
+        .ceil()
+        .clamp(1.0, ARC_MAX_CHORDS) as usize;
+
+    // use the exact endpoints so that they still line up with the grid
+    (0..=chords)
+        .map(|i| match i {
+            0 => from,
+            i if i == chords => to,
+            i => center + Vec2::from_angle(sweep * i as f32 / chords as f32).rotate(start),
+        })
+        .tuple_windows()
+        .collect()
+}
+
+/// Joins consecutive segments of a path that continue in the same direction on
+/// the same layer. Each segment is expected to start where the previous one ends.
+pub fn merge_collinear(segments: Vec<RoadSegment>) -> Vec<RoadSegment> {
+    let mut merged: Vec<RoadSegment> = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        if let Some(last) = merged.last_mut() {
+            let a = (last.points.1 - last.points.0).normalize_or_zero();
+            let b = (segment.points.1 - segment.points.0).normalize_or_zero();
+
+            if last.layer == segment.layer
+                && last.wide == segment.wide
+                && last.bias == segment.bias
+                && last.points.1 == segment.points.0
+                && a.perp_dot(b).abs() < 1e-4
+                && a.dot(b) > 0.0
+            {
+                last.points.1 = segment.points.1;
+                continue;
+            }
+        }
+
+        merged.push(segment);
+    }
+
+    merged
+}
+
+/// Length of the longest piece of a line being drawn, in grid units. Arcs are made of
+/// many short segments, but count as one piece.
+pub fn longest_piece(segments: &[(Vec2, Vec2)], curved: bool) -> f32 {
+    let lengths = segments.iter().map(|(a, b)| a.distance(*b) / GRID_SIZE);
+
+    if curved {
+        lengths.sum()
+    } else {
+        lengths.fold(0.0, f32::max)
+    }
+}
+
+/// Whether any segment of `path` runs along any of `roads` on the same layer. Paths
+/// may join several roads into one segment, or run along them backwards.
+pub fn path_uses(path: &[RoadSegment], roads: &[RoadSegment]) -> bool {
+    path.iter().any(|a| {
+        roads.iter().any(|b| {
+            a.layer == b.layer
+                && matches!(
+                    segment_collision(a.points.0, a.points.1, b.points.0, b.points.1),
+                    SegmentCollision::Overlapping
+                )
+        })
+    })
+}
+
 pub fn distance_on_path(start: Vec2, point: Vec2, segments: &[(Vec2, Vec2)]) -> Option<f32> {
     let mut total_dist = 0.0;
     let mut starting_point = start;
@@ -99,6 +268,19 @@ pub fn distance_on_path(start: Vec2, point: Vec2, segments: &[(Vec2, Vec2)]) ->
     None
 }
 
+/// The direction of travel along `segments` at `point`, if it's on one of them.
+pub fn heading_on_path(point: Vec2, segments: &[(Vec2, Vec2)]) -> Option<Vec2> {
+    segments
+        .iter()
+        .find(|s| {
+            !matches!(
+                point_segment_collision(point, s.0, s.1),
+                SegmentCollision::None
+            )
+        })
+        .map(|s| (s.1 - s.0).normalize_or_zero())
+}
+
 /// * `start` The starting point, which should be on the first segment
 pub fn traveled_segments(
     start: Vec2,
@@ -163,3 +345,119 @@ pub fn travel(start: Vec2, distance: f32, segments: &[RoadSegment]) -> (Vec2, us
 
     (segments.last().unwrap().points.1, i)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::seg;
+
+    #[test]
+    fn heading() {
+        let path = [
+            (Vec2::new(0.0, 0.0), Vec2::new(48.0, 0.0)),
+            (Vec2::new(48.0, 0.0), Vec2::new(48.0, 48.0)),
+        ];
+
+        assert_eq!(heading_on_path(Vec2::new(24.0, 0.0), &path), Some(Vec2::X));
+        assert_eq!(heading_on_path(Vec2::new(48.0, 24.0), &path), Some(Vec2::Y));
+        assert_eq!(heading_on_path(Vec2::new(24.0, 24.0), &path), None);
+    }
+
+    #[test]
+    fn arcs() {
+        let from = Vec2::new(0.0, 0.0);
+        let to = Vec2::new(96.0, 96.0);
+
+        assert!(possible_arcs(from, Vec2::new(96.0, 48.0), None).is_empty());
+
+        let arcs = possible_arcs(from, to, Some(Axis::Y));
+        assert_eq!(arcs.len(), 2);
+
+        for arc in arcs.iter() {
+            assert_eq!(arc.first().unwrap().0, from);
+            assert_eq!(arc.last().unwrap().1, to);
+            assert!(arc.iter().tuple_windows().all(|(a, b)| a.1 == b.0));
+        }
+
+        // leaving along the y axis means curving around a center on the x axis
+        let center = Vec2::new(96.0, 0.0);
+        for (a, _) in arcs[0].iter() {
+            assert!((a.distance(center) - 96.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn fine_lines() {
+        let from = Vec2::new(0.0, 0.0);
+        let to = Vec2::new(96.0, 48.0);
+
+        assert_eq!(
+            possible_fine_lines(from, Vec2::new(96.0, 96.0), None),
+            vec![vec![(from, Vec2::new(96.0, 96.0))]]
+        );
+
+        let lines = possible_fine_lines(from, to, Some(Axis::X));
+        assert_eq!(lines.len(), 2);
+        // between 22.5 and 45 degrees, so neither leaves along the x axis
+        for line in lines.iter() {
+            assert_eq!(line.len(), 2);
+            assert_eq!(line[0].0, from);
+            assert_eq!(line[1].1, to);
+
+            for (a, b) in line.iter() {
+                let steps = (*b - *a).to_angle() / FRAC_PI_8;
+                assert!((steps - steps.round()).abs() < 1e-3);
+            }
+        }
+
+        let lines = possible_fine_lines(from, Vec2::new(96.0, 24.0), Some(Axis::X));
+        assert_eq!(lines[0][0].1.y, 0.0);
+    }
+
+    #[test]
+    fn merge_straight() {
+        let merged = merge_collinear(vec![
+            seg((0.0, 0.0), (1.0, 1.0), 1),
+            seg((1.0, 1.0), (2.0, 2.0), 1),
+            seg((2.0, 2.0), (5.0, 5.0), 1),
+        ]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].points, (Vec2::ZERO, Vec2::new(5.0, 5.0)));
+    }
+
+    #[test]
+    fn merge_keeps_corners_and_layer_changes() {
+        let merged = merge_collinear(vec![
+            seg((0.0, 0.0), (1.0, 0.0), 1),
+            seg((1.0, 0.0), (2.0, 0.0), 2),
+            seg((2.0, 0.0), (3.0, 0.0), 2),
+            seg((3.0, 0.0), (3.0, 1.0), 2),
+        ]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[1].points, (Vec2::new(1.0, 0.0), Vec2::new(3.0, 0.0)));
+    }
+
+    #[test]
+    fn longest() {
+        let corner = [
+            (Vec2::ZERO, Vec2::new(96.0, 0.0)),
+            (Vec2::new(96.0, 0.0), Vec2::new(96.0, 48.0)),
+        ];
+        assert_eq!(longest_piece(&corner, false), 2.0);
+        assert_eq!(longest_piece(&corner, true), 3.0);
+        assert_eq!(longest_piece(&[], false), 0.0);
+    }
+
+    #[test]
+    fn uses() {
+        let path = [
+            seg((0.0, 0.0), (96.0, 0.0), 1),
+            seg((96.0, 0.0), (96.0, 48.0), 1),
+        ];
+
+        assert!(path_uses(&path, &[seg((96.0, 48.0), (96.0, 0.0), 1)]));
+        assert!(path_uses(&path, &[seg((48.0, 0.0), (96.0, 0.0), 1)]));
+        assert!(!path_uses(&path, &[seg((48.0, 0.0), (96.0, 0.0), 2)]));
+        assert!(!path_uses(&path, &[seg((96.0, 0.0), (144.0, 0.0), 1)]));
+    }
+}
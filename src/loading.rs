@@ -1,10 +1,19 @@
-use crate::{save::SaveFile, GameState, Handles, MainCamera};
-use bevy::{asset::LoadState, prelude::*};
+use crate::{
+    level::Level, save::SaveFile, sfx::Sfx, theme::THEME_FILES, GameState, Handles, MainCamera,
+};
+use bevy::{
+    asset::{LoadState, LoadedFolder},
+    prelude::*,
+};
 use bevy_simple_prefs::PrefsStatus;
+use itertools::Itertools;
 
 pub struct LoadingPlugin;
 
+/// Number of built-in levels. User levels are numbered after these.
 pub const NUM_LEVELS: u32 = 12;
+/// Where user levels are loaded from, relative to the assets folder.
+const CUSTOM_LEVELS_FOLDER: &str = "levels/custom";
 
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
@@ -32,14 +41,27 @@ fn loading_setup(
             .push(asset_server.load(format!("levels/{i}.level.ron")));
     }
 
+    for name in THEME_FILES {
+        handles
+            .themes
+            .push(asset_server.load(format!("themes/{name}.theme.ron")));
+    }
+
+    for sfx in Sfx::ALL {
+        handles.sfx.push(asset_server.load(sfx.path()));
+    }
+
+    handles.custom_levels = Some(asset_server.load_folder(CUSTOM_LEVELS_FOLDER));
+
     handles
         .fonts
         .push(asset_server.load("fonts/ChakraPetch-Regular-PixieWrangler.ttf"));
 }
 
 fn loading_update(
-    handles: Res<Handles>,
+    mut handles: ResMut<Handles>,
     asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
     mut next_state: ResMut<NextState<GameState>>,
     prefs: Res<PrefsStatus<SaveFile>>,
 ) {
@@ -59,6 +81,56 @@ fn loading_update(
         return;
     }
 
+    // A theme that fails to load is left out rather than holding up the game.
+    if handles.themes.iter().any(|h| {
+        matches!(
+            asset_server.get_load_state(h),
+            Some(LoadState::NotLoaded | LoadState::Loading)
+        )
+    }) {
+        return;
+    }
+
+    if let Some(folder) = handles.custom_levels.clone() {
+        match asset_server.get_load_state(&folder) {
+            Some(LoadState::Loaded) => {
+                let Some(folder) = folders.get(&folder) else {
+                    return;
+                };
+
+                let custom: Vec<Handle<Level>> = folder
+                    .handles
+                    .iter()
+                    .filter_map(|h| h.clone().try_typed::<Level>().ok())
+                    .sorted_by_key(|h| h.path().map(|p| p.to_string()))
+                    .collect();
+
+                // Skip over any user levels that couldn't be parsed rather than
+                // waiting on them forever.
+                if custom.iter().any(|h| {
+                    matches!(
+                        asset_server.get_load_state(h),
+                        Some(LoadState::NotLoaded | LoadState::Loading)
+                    )
+                }) {
+                    return;
+                }
+
+                let custom = custom
+                    .into_iter()
+                    .filter(|h| matches!(asset_server.get_load_state(h), Some(LoadState::Loaded)));
+
+                handles.levels.extend(custom);
+                handles.custom_levels = None;
+            }
+            // The folder is optional, and can't be loaded at all on the web.
+            Some(LoadState::Failed(_)) => {
+                handles.custom_levels = None;
+            }
+            _ => return,
+        }
+    }
+
     if !prefs.loaded {
         return;
     }
@@ -0,0 +1,176 @@
+//! A breakdown of where pixies exploded during a run, shown in the score dialog. Each
+//! entry is a junction and the number of pixies lost closest to it. Hovering an entry
+//! marks those explosions on the board, and hovering the row marks all of them.
+
+use crate::{color, layer, sim::JunctionLosses, Handles, PlayAreaNode};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+/// How many of the worst junctions get an entry.
+const LOSS_ENTRIES: usize = 5;
+const JUNCTION_MARKER_RADIUS: f32 = 10.0;
+const EXPLOSION_MARKER_RADIUS: f32 = 3.0;
+
+pub struct LossesPlugin;
+impl Plugin for LossesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, loss_markers_system);
+    }
+}
+
+#[derive(Component)]
+struct LossesRow(Vec<JunctionLosses>);
+#[derive(Component)]
+struct LossEntry(usize);
+#[derive(Component)]
+struct LossMarker;
+
+/// Adds the losses row to the score dialog.
+pub fn spawn_losses(parent: &mut ChildBuilder, handles: &Handles, losses: Vec<JunctionLosses>) {
+    let total: usize = losses.iter().map(|l| l.explosions.len()).sum();
+
+    let text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 18.0,
+        ..default()
+    };
+
+    parent
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(8.),
+                ..default()
+            },
+            Interaction::default(),
+            LossesRow(losses.clone()),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("LOST {total}")),
+                text_font.clone(),
+                TextColor(color::UI_WHITE),
+            ));
+
+            for (i, loss) in losses.iter().take(LOSS_ENTRIES).enumerate() {
+                parent.spawn((
+                    Text::new(loss.explosions.len().to_string()),
+                    text_font.clone(),
+                    TextColor(color::UI_GREY_RED),
+                    Interaction::default(),
+                    LossEntry(i),
+                ));
+            }
+        });
+}
+
+/// Which of the `count` losses should be marked on the board, given what's hovered.
+fn hovered_losses(
+    row: Interaction,
+    entries: &[(Interaction, usize)],
+    count: usize,
+) -> Option<Vec<usize>> {
+    if let Some((_, i)) = entries.iter().find(|(i, _)| *i != Interaction::None) {
+        return Some(vec![*i]);
+    }
+
+    (row != Interaction::None).then(|| (0..count).collect())
+}
+
+fn loss_markers_system(
+    mut commands: Commands,
+    mut shown: Local<Option<Vec<usize>>>,
+    q_row: Query<(&Interaction, &LossesRow)>,
+    q_entries: Query<(&Interaction, &LossEntry)>,
+    q_markers: Query<Entity, With<LossMarker>>,
+    mut q_play_area: Query<&mut BackgroundColor, With<PlayAreaNode>>,
+) {
+    let row = q_row.get_single().ok();
+
+    let hovered = row.and_then(|(interaction, row)| {
+        let entries: Vec<_> = q_entries.iter().map(|(i, e)| (*i, e.0)).collect();
+        hovered_losses(*interaction, &entries, row.0.len())
+    });
+
+    if hovered == *shown {
+        return;
+    }
+
+    for entity in q_markers.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // The dialog darkens the board, which would hide the markers.
+    let overlay = if hovered.is_some() {
+        Color::NONE
+    } else {
+        color::OVERLAY
+    };
+    if row.is_some() {
+        for mut background in q_play_area.iter_mut() {
+            background.0 = overlay;
+        }
+    }
+
+    if let (Some((_, row)), Some(hovered)) = (row, hovered.as_ref()) {
+        for loss in hovered.iter().filter_map(|i| row.0.get(*i)) {
+            let radius = JUNCTION_MARKER_RADIUS + (loss.explosions.len() as f32).sqrt() * 2.0;
+
+            commands.spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Circle {
+                        radius,
+                        center: loss.junction,
+                    }),
+                    transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                    ..default()
+                },
+                Stroke::new(color::UI_GREY_RED, 2.0),
+                LossMarker,
+            ));
+
+            let explosions =
+                loss.explosions
+                    .iter()
+                    .fold(GeometryBuilder::new(), |builder, point| {
+                        builder.add(&shapes::Circle {
+                            radius: EXPLOSION_MARKER_RADIUS,
+                            center: *point,
+                        })
+                    });
+
+            commands.spawn((
+                ShapeBundle {
+                    path: explosions.build(),
+                    transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                    ..default()
+                },
+                Fill::color(color::UI_GREY_RED.with_alpha(0.6)),
+                LossMarker,
+            ));
+        }
+    }
+
+    *shown = hovered;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hovered() {
+        let none = [(Interaction::None, 0), (Interaction::None, 1)];
+        assert_eq!(hovered_losses(Interaction::None, &none, 3), None);
+        assert_eq!(
+            hovered_losses(Interaction::Hovered, &none, 3),
+            Some(vec![0, 1, 2])
+        );
+
+        let second = [(Interaction::None, 0), (Interaction::Hovered, 1)];
+        assert_eq!(
+            hovered_losses(Interaction::Hovered, &second, 3),
+            Some(vec![1])
+        );
+    }
+}
@@ -1,29 +1,103 @@
 // disable console on windows for release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::sync::Arc;
+
 #[cfg(feature = "debugdump")]
 use std::{fs::File, io::Write};
 
 use crate::{
-    collision::{point_segment_collision, segment_collision, SegmentCollision},
-    level::{Level, Obstacle, Terminus},
-    level_select::LevelSelectPlugin,
-    lines::{possible_lines, Axis},
-    loading::LoadingPlugin,
-    pixie::{Pixie, PixieEmitter, PixieFlavor, PixiePlugin},
+    backdrop::BackdropPlugin,
+    backup::BackupPlugin,
+    badge::{BadgeEligibility, BadgePlugin},
+    best_layout::BestLayoutPlugin,
+    bias::{BiasButton, BiasPlugin, RouteBias},
+    bridges::BridgesPlugin,
+    collision::{
+        bounding_boxes_overlap, point_segment_collision, point_segment_distance, segment_collision,
+        segment_rect_collision, SegmentCollision,
+    },
+    compat::SolutionCompatPlugin,
+    congestion::CongestionPlugin,
+    controls::{Action, ActionPressed, ControlsPlugin},
+    converter::{convert_along, find_path, reachable_colors, ConverterPlugin},
+    diagnostics::DiagnosticsOverlayPlugin,
+    drag::{DragButton, DragPlugin, DragState},
+    erase::ErasePlugin,
+    estimate::{EstimatePlugin, EstimateText},
+    export::{ExportImageButton, ExportImageText, ExportPlugin},
+    flavor_summary::FlavorSummaryPlugin,
+    focus::{AccessibleName, FocusPlugin},
+    friends::{friend_color, FriendScores, Friends, FriendsPlugin},
+    ghost::GhostPlugin,
+    history::HistoryPlugin,
+    hotseat::{HotseatMode, HotseatPlugin, HotseatTurn},
+    hud::{BottomBar, Hud, HudPlugin},
+    legend::LegendPlugin,
+    level::{
+        level_id, Converter, EmitterSchedule, Level, Obstacle, ObstacleLayers, Terminus,
+        TERMINUS_LABEL_SPACING,
+    },
+    level_meta::LevelMetaPlugin,
+    level_nav::{LevelNavButton, LevelNavPlugin},
+    level_select::{records_label, LevelSelectPlugin},
+    level_warnings::LevelWarningsPlugin,
+    lines::{
+        longest_piece, merge_collinear, path_uses, possible_arcs, possible_fine_lines,
+        possible_lines, Axis,
+    },
+    loading::{LoadingPlugin, NUM_LEVELS},
+    losses::{spawn_losses, LossesPlugin},
+    moving_obstacle::{MovingObstacle, MovingObstaclePlugin},
+    nets::NetsPlugin,
+    pacing::{DrawingPacing, PacingPlugin},
+    pause::PausePlugin,
+    pixie::{Burst, Pixie, PixieEmitter, PixieFlavor, PixiePlugin, PIXIE_VISION_DISTANCE},
+    planning::PlanningPlugin,
+    preview::PreviewPlugin,
+    prune::{PruneButton, PrunePlugin, PruneState},
     radio_button::{RadioButton, RadioButtonGroup, RadioButtonGroupRelation, RadioButtonPlugin},
-    save::{BestScores, SavePlugin, Solution, Solutions},
-    sim::{SimulationPlugin, SimulationSettings, SimulationState},
+    replay::{ReplayButton, ReplayPlayback, ReplayPlugin},
+    reroute::{RerouteButton, ReroutePlugin, RerouteState},
+    reset::{ResetLayout, ResetPlugin},
+    restart::{Restart, RestartPlugin},
+    resume::ResumePlugin,
+    sandbox::{Sandbox, SandboxPlugin},
+    save::{
+        BestCosts, BestScores, BestSolutions, BestTimes, InputMap, Records, SavePlugin, Settings,
+        Solution, Solutions, Statistics,
+    },
+    settings::{GraphicsQuality, SettingsPlugin},
+    sfx::{PlaySfx, Sfx, SfxPlugin},
+    share::SharePlugin,
+    sim::{
+        EmissionStrategy, SimulationPaused, SimulationPlugin, SimulationSeed, SimulationSettings,
+        SimulationState, SimulationTimeline, BURST_SIZE, EMISSION_JITTER,
+    },
+    slots::SlotsPlugin,
+    snapshot::{SimulationSnapshots, SnapshotPlugin},
+    stars::{score_breakdown, thresholds_label, StarThresholdText, StarsPlugin},
+    stats::StatsPlugin,
+    theme::{Theme, ThemePlugin},
+    tooltip::TooltipPlugin,
+    touch::{FocusCamera, TouchPlugin},
+    tutorial::TutorialPlugin,
+    vias::{via_points, ViasPlugin},
+    viewport::{Viewport, ViewportPlugin},
 };
 
 use bevy::{
     app::MainScheduleOrder,
-    asset::AssetMetaCheck,
-    ecs::schedule::ScheduleLabel,
+    asset::{AssetMetaCheck, LoadedFolder},
+    ecs::{schedule::ScheduleLabel, system::SystemParam},
+    input::{
+        mouse::{MouseScrollUnit, MouseWheel},
+        touch::{TouchInput, TouchPhase},
+    },
     prelude::*,
     sprite::Anchor,
-    utils::{Duration, HashMap, HashSet},
-    window::CursorMoved,
+    utils::{Duration, HashMap, HashSet, Instant},
+    window::{CursorLeft, CursorMoved, WindowFocused},
 };
 
 use bevy_common_assets::ron::RonAssetPlugin;
@@ -33,24 +107,89 @@ use itertools::Itertools;
 use petgraph::{
     algo::astar,
     dot::{Config, Dot},
-    stable_graph::{NodeIndex, StableUnGraph},
-    visit::{DfsPostOrder, Walker},
+    stable_graph::{EdgeReference, NodeIndex, StableUnGraph},
+    visit::{DfsPostOrder, EdgeRef, Walker},
 };
+use serde::Deserialize;
 
 use radio_button::RadioButtonSet;
 use sim::SimulationSteps;
 
+mod backdrop;
+mod backup;
+mod badge;
+#[cfg(feature = "benchmark")]
+mod bench;
+mod best_layout;
+mod bias;
+mod bridges;
+mod capacity;
 mod collision;
 mod color;
+mod compat;
+mod congestion;
+mod controls;
+mod converter;
+mod diagnostics;
+mod drag;
+mod erase;
+mod estimate;
+mod export;
+#[cfg(test)]
+mod fixtures;
+mod flavor_summary;
+mod focus;
+mod friends;
+mod ghost;
+mod history;
+mod hotseat;
+mod hud;
 mod layer;
+mod legend;
 mod level;
+mod level_meta;
+mod level_nav;
 mod level_select;
+mod level_warnings;
 mod lines;
 mod loading;
+mod losses;
+mod moving_obstacle;
+mod nets;
+mod pacing;
+mod pause;
 mod pixie;
+mod planning;
+mod preview;
+mod prune;
 mod radio_button;
+mod replay;
+mod reroute;
+mod reset;
+mod restart;
+mod resume;
+mod sandbox;
 mod save;
+#[cfg(feature = "screenshot_import")]
+mod screenshot;
+mod settings;
+mod sfx;
+mod share;
 mod sim;
+mod slots;
+mod snapshot;
+mod stars;
+mod stats;
+#[cfg(feature = "stresstest")]
+mod stress;
+mod theme;
+mod tooltip;
+mod touch;
+mod tutorial;
+#[cfg(feature = "validate")]
+mod validate;
+mod vias;
+mod viewport;
 
 fn main() {
     let mut app = App::new();
@@ -81,17 +220,103 @@ fn main() {
     #[cfg(feature = "debugdump")]
     let default = default.disable::<bevy::log::LogPlugin>();
 
+    // No window or graphics card, so that solutions can be checked anywhere.
+    #[cfg(feature = "validate")]
+    let default = default
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: bevy::window::ExitCondition::DontExit,
+            ..default()
+        })
+        .set(bevy::render::RenderPlugin {
+            render_creation: bevy::render::settings::WgpuSettings {
+                backends: None,
+                ..default()
+            }
+            .into(),
+            ..default()
+        })
+        .disable::<bevy::winit::WinitPlugin>()
+        .add(bevy::app::ScheduleRunnerPlugin::run_loop(Duration::ZERO));
+
     app.add_plugins(default)
         .add_plugins(RonAssetPlugin::<Level>::new(&["level.ron"]))
+        .add_plugins(RonAssetPlugin::<Theme>::new(&["theme.ron"]))
+        .add_plugins(RonAssetPlugin::<FriendScores>::new(&["scores.ron"]))
         .add_plugins(ShapePlugin)
         .add_plugins(RadioButtonPlugin)
         .add_plugins(PixiePlugin)
+        .add_plugins(PausePlugin)
+        .add_plugins(PacingPlugin)
+        .add_plugins(PrunePlugin)
+        .add_plugins(ReroutePlugin)
+        .add_plugins(DragPlugin)
+        .add_plugins(ErasePlugin)
+        .add_plugins(BiasPlugin)
+        .add_plugins(BadgePlugin)
+        .add_plugins(BridgesPlugin)
+        .add_plugins(LevelMetaPlugin)
+        .add_plugins(LevelNavPlugin)
+        .add_plugins(LevelWarningsPlugin)
+        .add_plugins(LegendPlugin)
+        .add_plugins(TooltipPlugin)
+        .add_plugins(EstimatePlugin)
+        .add_plugins(PlanningPlugin)
+        .add_plugins(HotseatPlugin)
+        .add_plugins(HistoryPlugin)
+        .add_plugins(HudPlugin)
+        .add_plugins(TouchPlugin)
+        .add_plugins(SlotsPlugin)
+        .add_plugins(SolutionCompatPlugin)
+        .add_plugins(SharePlugin)
         .add_plugins(SimulationPlugin)
+        .add_plugins(SnapshotPlugin)
+        .add_plugins(ReplayPlugin)
+        .add_plugins(ExportPlugin)
+        .add_plugins(LossesPlugin)
+        .add_plugins(GhostPlugin)
+        .add_plugins(PreviewPlugin)
         .add_plugins(LoadingPlugin)
         .add_plugins(LevelSelectPlugin)
+        .add_plugins(ThemePlugin)
+        .add_plugins(FriendsPlugin)
         .add_plugins(SavePlugin)
+        .add_plugins(SfxPlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(ControlsPlugin)
+        .add_plugins(SettingsPlugin)
+        .add_plugins(TutorialPlugin)
+        .add_plugins(CongestionPlugin)
+        .add_plugins(NetsPlugin)
+        .add_plugins(BackupPlugin)
+        .add_plugins(MovingObstaclePlugin)
+        .add_plugins(ViewportPlugin)
+        .add_plugins(BestLayoutPlugin)
+        .add_plugins(ConverterPlugin)
+        .add_plugins(ResumePlugin)
+        .add_plugins(FlavorSummaryPlugin)
+        .add_plugins(FocusPlugin)
+        .add_plugins(SandboxPlugin)
+        .add_plugins(StarsPlugin)
+        .add_plugins(BackdropPlugin)
+        .add_plugins(ViasPlugin)
+        .add_plugins(ResetPlugin)
+        .add_plugins(RestartPlugin)
+        .add_plugins(DiagnosticsOverlayPlugin)
         .add_plugins(EasingsPlugin::default());
 
+    #[cfg(feature = "stresstest")]
+    app.add_plugins(stress::StressTestPlugin);
+
+    #[cfg(feature = "benchmark")]
+    app.add_plugins(bench::BenchmarkPlugin);
+
+    #[cfg(feature = "validate")]
+    app.add_plugins(validate::ValidatePlugin);
+
+    #[cfg(feature = "screenshot_import")]
+    app.add_plugins(screenshot::ScreenshotImportPlugin);
+
     app.init_state::<GameState>();
 
     app.add_systems(OnEnter(GameState::Playing), playing_enter_system);
@@ -103,6 +328,7 @@ fn main() {
         (
             keyboard_system.before(mouse_movement_system),
             mouse_movement_system,
+            pointer_suspend_system.after(mouse_movement_system),
         )
             .before(RadioButtonSet)
             .in_set(DrawingInput),
@@ -129,6 +355,7 @@ fn main() {
         Update,
         (
             tool_button_system,
+            layer_swap_button_system.before(tool_button_system),
             tool_button_display_system,
             drawing_mode_change_system,
         )
@@ -156,7 +383,11 @@ fn main() {
 
     app.add_systems(
         Update,
-        dismiss_score_dialog_button_system
+        (
+            dismiss_score_dialog_button_system,
+            worst_junction_button_system,
+            scroll_score_details_system,
+        )
             .after(DrawingInteraction)
             .run_if(in_state(GameState::Playing)),
     );
@@ -167,9 +398,11 @@ fn main() {
         (
             button_system,
             pixie_button_system,
-            reset_button_system,
+            reset_system,
             speed_button_system,
+            speed_button_text_system.after(speed_button_system),
             back_button_system,
+            onward_button_system,
         )
             .run_if(in_state(GameState::Playing)),
     );
@@ -199,6 +432,7 @@ fn main() {
             pixie_button_text_system,
             update_pixie_count_text_system,
             update_elapsed_text_system,
+            update_timeline_system,
             update_score_text_system,
             show_score_dialog_system,
         )
@@ -214,6 +448,7 @@ fn main() {
     app.init_resource::<RoadGraph>();
     app.init_resource::<PixieCount>();
     app.init_resource::<Cost>();
+    app.init_resource::<PixieButtonLatch>();
 
     #[cfg(feature = "debugdump")]
     {
@@ -252,12 +487,30 @@ enum GameState {
     Loading,
     LevelSelect,
     Playing,
+    /// On the way back into a state, for a frame. See `restart`.
+    Restarting,
 }
 
 #[derive(Resource, Default)]
 struct Handles {
+    /// Built-in levels, followed by any user levels.
     levels: Vec<Handle<Level>>,
     fonts: Vec<Handle<Font>>,
+    themes: Vec<Handle<Theme>>,
+    /// Sound effects, in the order of `Sfx::ALL`.
+    sfx: Vec<Handle<AudioSource>>,
+    /// The folder of user levels, while it is loading.
+    custom_levels: Option<Handle<LoadedFolder>>,
+}
+impl Handles {
+    /// The stable identifier of the level with the given number.
+    fn level_id(&self, number: u32) -> String {
+        self.levels
+            .get(number as usize - 1)
+            .and_then(|h| h.path())
+            .map(level_id)
+            .unwrap_or_else(|| number.to_string())
+    }
 }
 #[derive(Component)]
 struct MainCamera;
@@ -277,12 +530,16 @@ struct CostText;
 struct ScoreText;
 #[derive(Component)]
 struct ElapsedText;
+#[derive(Component)]
+struct TimelineBar;
 
 #[derive(Component)]
 struct ToolButton;
 #[derive(Component)]
 struct LayerButton(u32);
 #[derive(Component)]
+struct LayerSwapButton;
+#[derive(Component)]
 struct NetRippingButton;
 #[derive(Component)]
 struct PixieButton;
@@ -294,23 +551,73 @@ struct SpeedButton;
 struct BackButton;
 #[derive(Component)]
 struct DismissScoreDialogButton;
+/// Dismisses the score dialog, and zooms in on the junction where the most pixies
+/// exploded.
+#[derive(Component)]
+struct WorstJunctionButton(Vec2);
+#[derive(Component)]
+struct OnwardButton;
 #[derive(Component)]
 struct PlayAreaNode;
 #[derive(Component)]
 struct ScoreDialog;
+/// The part of the score dialog between the score and the buttons, which scrolls when
+/// it doesn't fit.
+#[derive(Component)]
+struct ScoreDetails;
 
 #[derive(Resource, Default)]
 struct SelectedLevel(u32);
+/// The level being played, if it has loaded.
+#[derive(SystemParam)]
+struct CurrentLevel<'w> {
+    selected_level: Res<'w, SelectedLevel>,
+    handles: Res<'w, Handles>,
+    levels: Res<'w, Assets<Level>>,
+}
+
+impl CurrentLevel<'_> {
+    fn get(&self) -> Option<&Level> {
+        self.handles
+            .levels
+            .get(self.selected_level.0 as usize - 1)
+            .and_then(|h| self.levels.get(h))
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct PixieCount(u32);
 #[derive(Resource, Default)]
 struct Cost(u32);
 #[derive(Resource, Default)]
 struct Score(Option<u32>);
-#[derive(Debug, Clone, Component, Reflect)]
+
+/// How the run that just finished went.
+#[derive(SystemParam)]
+struct FinishedRun<'w> {
+    score: Res<'w, Score>,
+    pixie_count: Res<'w, PixieCount>,
+    cost: Res<'w, Cost>,
+    seed: Res<'w, SimulationSeed>,
+    timeline: Res<'w, SimulationTimeline>,
+    sim_steps: Res<'w, SimulationSteps>,
+}
+
+/// Seconds remaining until the pixie button will respond to another press.
+#[derive(Resource, Default)]
+struct PixieButtonLatch(f32);
+#[derive(Debug, Clone, Component, Reflect, Deserialize)]
 pub struct RoadSegment {
     points: (Vec2, Vec2),
     layer: u32,
+    /// Wide roads cost more, but let pixies overtake one another.
+    #[reflect(default)]
+    #[serde(default)]
+    wide: bool,
+    /// Nudges pixies onto or off this road when there's a choice.
+    #[reflect(default)]
+    #[serde(default)]
+    bias: RouteBias,
 }
 
 #[derive(Component, Debug)]
@@ -318,16 +625,22 @@ struct PointGraphNode(NodeIndex);
 #[derive(Component, Debug)]
 struct SegmentGraphNodes(NodeIndex, NodeIndex);
 
-#[derive(Default)]
-enum DrawingMode {
+#[derive(Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum DrawingMode {
     #[default]
     LineDrawing,
     NetRipping,
+    Rerouting,
+    Dragging,
+    Biasing,
 }
 
 #[derive(Resource, Default)]
 struct DrawingState {
     mode: DrawingMode,
+    /// Whether roads were placed while pixies were running. Those roads weren't part
+    /// of the run, so it doesn't count towards the best score.
+    edited_while_running: bool,
 }
 #[derive(Resource)]
 struct LineDrawingState {
@@ -335,12 +648,30 @@ struct LineDrawingState {
     start: Vec2,
     end: Vec2,
     valid: bool,
+    /// Why the line being drawn (or the point under the cursor) is not valid.
+    error: Option<PlacementError>,
     stop: bool,
     segments: Vec<(Vec2, Vec2)>,
     adds: Vec<AddSegment>,
     axis_preference: Option<Axis>,
     layer: u32,
     prev_layer: u32,
+    /// The layer that was drawn on before the current one, for swapping back and
+    /// forth while weaving roads across each other.
+    other_layer: u32,
+    /// Whether diagonal lines are drawn as quarter circle arcs.
+    arcs: bool,
+    prev_arcs: bool,
+    /// Whether the line being drawn is an arc.
+    curved: bool,
+    /// Whether new roads are wide.
+    wide: bool,
+    /// The pointer left the window while drawing. The line is kept, and the next
+    /// click resumes drawing it rather than placing it.
+    suspended: bool,
+    /// Where the last placed road ended, if that wasn't at a terminus. Drawing can be
+    /// picked up from there again with a key, rather than clicking on the exact point.
+    last_end: Option<Vec2>,
 }
 impl Default for LineDrawingState {
     fn default() -> Self {
@@ -349,12 +680,38 @@ impl Default for LineDrawingState {
             start: Vec2::new(0.0, 0.0),
             end: Vec2::new(0.0, 0.0),
             valid: false,
+            error: None,
             stop: false,
             segments: vec![],
             adds: vec![],
             axis_preference: None,
             layer: 1,
             prev_layer: 1,
+            other_layer: 2,
+            arcs: false,
+            prev_arcs: false,
+            curved: false,
+            wide: false,
+            suspended: false,
+            last_end: None,
+        }
+    }
+}
+impl LineDrawingState {
+    fn set_layer(&mut self, layer: u32) {
+        if layer != self.layer {
+            self.other_layer = self.layer;
+            self.layer = layer;
+        }
+    }
+
+    /// Number of road segments that the line being drawn counts as. Arcs are made of
+    /// many short segments, but count as one.
+    fn pieces(&self) -> usize {
+        if self.curved {
+            1
+        } else {
+            self.adds.len()
         }
     }
 }
@@ -363,12 +720,25 @@ struct NetRippingState {
     entities: Vec<Entity>,
     nodes: Vec<NodeIndex>,
     segments: Vec<(Vec2, Vec2)>,
+    /// What ripping the highlighted net would give back.
+    refund: f32,
+    /// Total length of the highlighted net, in grid units.
+    length: f32,
+    /// Where the net was picked, for placing the refund label.
+    point: Vec2,
+    /// Where the mouse button went down, while it's held.
+    box_start: Option<Vec2>,
+    /// The box dragged out from `box_start`, once it's big enough to count as one.
+    /// Everything in it is ripped, rather than a single net.
+    selection: Option<Rect>,
 }
 
 #[derive(Resource, Default)]
 struct PathfindingState {
     valid: bool,
-    paths: Vec<(PixieFlavor, Entity, Vec<RoadSegment>)>,
+    paths: Vec<(PixieFlavor, Entity, Arc<[RoadSegment]>)>,
+    /// Road segment entities traversed by at least one of `paths`.
+    used_segments: HashSet<Entity>,
     invalid_nodes: Vec<Entity>,
 }
 
@@ -407,10 +777,60 @@ enum SegmentConnection {
     Split(Entity),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlacementError {
+    ObstacleCollision,
+    SameLayerCross,
+    Overlapping,
+    MidSegmentTouch,
+    MidTerminusTouch,
+    SplitLimit,
+    TurnBudget,
+    PixiesNearby,
+    TooLong,
+}
+impl PlacementError {
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::ObstacleCollision => "Blocked by an obstacle",
+            Self::SameLayerCross => "Crosses a road on the same layer",
+            Self::Overlapping => "Overlaps an existing road",
+            Self::MidSegmentTouch => "Roads can only join at the ends of a line",
+            Self::MidTerminusTouch => "Passes through a terminus",
+            Self::SplitLimit => "Can't split roads on multiple layers at once",
+            Self::TurnBudget => "Not enough segments left this turn",
+            Self::PixiesNearby => "Too close to pixies on this layer",
+            Self::TooLong => "Longer than this level allows",
+        }
+    }
+}
+
 const GRID_SIZE: f32 = 48.0;
 const BOTTOM_BAR_HEIGHT: f32 = 70.0;
+/// Scales scores up to whole numbers that are easy to compare.
+const SCORE_MULTIPLIER: f32 = 10000.0;
 const LAYER_TWO_MULTIPLIER: f32 = 2.0;
 const LAYER_THREE_MULTIPLIER: f32 = 4.0;
+const WIDE_ROAD_MULTIPLIER: f32 = 1.5;
+const WIDE_ROAD_STROKE: f32 = 6.0;
+/// How far from a road segment, in screen pixels, the cursor can be while still
+/// selecting it for net ripping.
+const NET_RIPPING_HIT_RADIUS: f32 = 8.0;
+/// How far the mouse must be dragged while ripping to start a selection box.
+const NET_RIPPING_BOX_MIN_SIZE: f32 = 8.0;
+const PIXIE_BUTTON_LATCH_SECS: f32 = 0.4;
+/// Number of pixies released by each emitting terminus, split between its flavors.
+const PIXIES_PER_TERMINUS: u32 = 50;
+/// Time between pixies released by a single emitting terminus.
+const EMITTER_INTERVAL_SECS: f32 = 0.4;
+/// Number of columns in the score dialog's delivery chart.
+const THROUGHPUT_BUCKETS: usize = 40;
+const THROUGHPUT_CHART_HEIGHT: f32 = 60.0;
+/// The tallest the score dialog gets, so that its buttons stay on screen. Details that
+/// don't fit scroll.
+const SCORE_DIALOG_MAX_HEIGHT: f32 = 590.0;
+/// How far the score dialog's details scroll for each line the mouse wheel moves.
+const SCORE_DETAILS_SCROLL_LINE: f32 = 20.0;
 
 fn tool_button_display_system(
     mut q_text: Query<&mut TextColor>,
@@ -433,12 +853,15 @@ fn tool_button_system(
     mut line_state: ResMut<LineDrawingState>,
     q_interaction_layer: Query<(&Interaction, &LayerButton), Changed<Interaction>>,
     q_interaction_rip: Query<&Interaction, (Changed<Interaction>, With<NetRippingButton>)>,
+    q_interaction_reroute: Query<&Interaction, (Changed<Interaction>, With<RerouteButton>)>,
+    q_interaction_drag: Query<&Interaction, (Changed<Interaction>, With<DragButton>)>,
+    q_interaction_bias: Query<&Interaction, (Changed<Interaction>, With<BiasButton>)>,
 ) {
     for (_, layer_button) in q_interaction_layer
         .iter()
         .filter(|(i, _)| **i == Interaction::Pressed)
     {
-        line_state.layer = layer_button.0;
+        line_state.set_layer(layer_button.0);
         if !matches!(drawing_state.mode, DrawingMode::LineDrawing) {
             drawing_state.mode = DrawingMode::LineDrawing;
         }
@@ -452,6 +875,59 @@ fn tool_button_system(
             drawing_state.mode = DrawingMode::NetRipping;
         }
     }
+
+    for _ in q_interaction_reroute
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+    {
+        if !matches!(drawing_state.mode, DrawingMode::Rerouting) {
+            drawing_state.mode = DrawingMode::Rerouting;
+        }
+    }
+
+    for _ in q_interaction_drag
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+    {
+        if !matches!(drawing_state.mode, DrawingMode::Dragging) {
+            drawing_state.mode = DrawingMode::Dragging;
+        }
+    }
+
+    for _ in q_interaction_bias
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+    {
+        if !matches!(drawing_state.mode, DrawingMode::Biasing) {
+            drawing_state.mode = DrawingMode::Biasing;
+        }
+    }
+}
+
+fn layer_swap_button_system(
+    mut drawing_state: ResMut<DrawingState>,
+    mut line_state: ResMut<LineDrawingState>,
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<LayerSwapButton>)>,
+    q_layer_button: Query<(Entity, &LayerButton)>,
+    mut q_radio_button: Query<&mut RadioButton>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        let layer = line_state.other_layer;
+
+        let Some((ent, _)) = q_layer_button.iter().find(|(_, b)| b.0 == layer) else {
+            continue;
+        };
+
+        if !matches!(drawing_state.mode, DrawingMode::LineDrawing) {
+            drawing_state.mode = DrawingMode::LineDrawing;
+        }
+
+        line_state.set_layer(layer);
+
+        if let Ok(mut radio) = q_radio_button.get_mut(ent) {
+            radio.selected = true;
+        }
+    }
 }
 
 fn button_system(
@@ -473,26 +949,76 @@ fn pathfinding_system(
     graph: Res<RoadGraph>,
     mut pathfinding: ResMut<PathfindingState>,
     q_terminuses: Query<(Entity, &Terminus, &PointGraphNode)>,
-    q_road_chunks: Query<&RoadSegment>,
+    q_road_chunks: Query<(&RoadSegment, &SegmentGraphNodes)>,
+    q_converters: Query<&Converter>,
 ) {
     if !graph.is_changed() {
         return;
     }
 
+    let converters: Vec<_> = q_converters.iter().cloned().collect();
+
     let mut ok = true;
     let mut paths = vec![];
+    let mut used_segments = HashSet::default();
     let mut not_ok = vec![];
 
-    for (a_entity, a, a_node) in q_terminuses.iter() {
-        for (_, b, b_node) in q_terminuses.iter() {
-            for flavor in a.emits.intersection(&b.collects) {
-                let path = astar(
-                    &graph.graph,
-                    a_node.0,
-                    |finish| finish == b_node.0,
-                    |e| *e.weight(),
-                    |_| 0.0,
-                );
+    // Visit terminuses and flavors in an order that doesn't depend on entity
+    // allocation or hashing, so that paths (and therefore emitter staggering and
+    // scores) are the same from run to run.
+    let terminuses: Vec<_> = q_terminuses
+        .iter()
+        .sorted_by(|(_, a, _), (_, b, _)| {
+            a.point
+                .x
+                .total_cmp(&b.point.x)
+                .then(a.point.y.total_cmp(&b.point.y))
+        })
+        .collect();
+
+    for (a_entity, a, a_node) in terminuses.iter().copied() {
+        for (_, b, b_node) in terminuses.iter().copied() {
+            // Pixies may be collected as any color that converters could turn them into,
+            // and there must be a way for each of them to get there.
+            let deliveries = a.emits.iter().flat_map(|flavor| {
+                let reachable = reachable_colors(&converters, flavor.color);
+                b.collects
+                    .iter()
+                    .filter(move |c| c.net == flavor.net && reachable.contains(&c.color))
+                    .map(move |c| (*flavor, c.color))
+            });
+
+            for (flavor, goal_color) in deliveries.sorted() {
+                let cost = |e: EdgeReference<f32>| {
+                    let bias = graph
+                        .graph
+                        .node_weight(e.source())
+                        .and_then(|ent| q_road_chunks.get(*ent).ok())
+                        .map_or(1.0, |(seg, _)| seg.bias.multiplier());
+                    *e.weight() * bias
+                };
+
+                let path = if converters.is_empty() {
+                    astar(
+                        &graph.graph,
+                        a_node.0,
+                        |finish| finish == b_node.0,
+                        cost,
+                        |_| 0.0,
+                    )
+                } else {
+                    find_path(
+                        &graph.graph,
+                        a_node.0,
+                        b_node.0,
+                        flavor.color,
+                        goal_color,
+                        cost,
+                        |from, to, color| {
+                            convert_on_segment(&graph, &q_road_chunks, &converters, from, to, color)
+                        },
+                    )
+                };
 
                 if let Some(path) = path {
                     let mut prev_end = graph
@@ -508,15 +1034,17 @@ fn pathfinding_system(
                         .iter()
                         .filter_map(|node| graph.graph.node_weight(*node))
                         .dedup()
-                        .filter_map(|ent| q_road_chunks.get(*ent).ok());
+                        .filter_map(|ent| q_road_chunks.get(*ent).ok().map(|(seg, _)| (*ent, seg)));
 
                     let mut world_path = vec![];
 
-                    for seg in segments {
+                    for (ent, seg) in segments {
+                        used_segments.insert(ent);
+
                         let flipped_seg = if seg.points.0 != prev_end {
                             RoadSegment {
                                 points: (seg.points.1, seg.points.0),
-                                layer: seg.layer,
+                                ..seg.clone()
                             }
                         } else {
                             seg.clone()
@@ -532,7 +1060,9 @@ fn pathfinding_system(
                         continue;
                     }
 
-                    paths.push((*flavor, a_entity, world_path));
+                    // Long chains of short segments would otherwise cost pixies time
+                    // in corner checks for corners that aren't really there.
+                    paths.push((flavor, a_entity, merge_collinear(world_path).into()));
                 } else {
                     ok = false;
                     not_ok.push(a_entity);
@@ -548,9 +1078,40 @@ fn pathfinding_system(
     }
 
     pathfinding.paths = paths;
+    pathfinding.used_segments = used_segments;
     pathfinding.valid = true;
 }
 
+/// The color of a pixie of `color` after following the graph edge from `from` to `to`.
+/// Only the edges that drive along a road can take it over a converter.
+fn convert_on_segment(
+    graph: &RoadGraph,
+    q_road_chunks: &Query<(&RoadSegment, &SegmentGraphNodes)>,
+    converters: &[Converter],
+    from: NodeIndex,
+    to: NodeIndex,
+    color: u32,
+) -> u32 {
+    let (Some(a), Some(b)) = (graph.graph.node_weight(from), graph.graph.node_weight(to)) else {
+        return color;
+    };
+    if a != b {
+        return color;
+    }
+
+    let Ok((segment, nodes)) = q_road_chunks.get(*a) else {
+        return color;
+    };
+
+    let (start, end) = if nodes.0 == from {
+        segment.points
+    } else {
+        (segment.points.1, segment.points.0)
+    };
+
+    convert_along(converters, start, end, color)
+}
+
 fn pixie_button_text_system(
     pathfinding: Res<PathfindingState>,
     sim_state: Res<SimulationState>,
@@ -584,11 +1145,18 @@ fn show_score_dialog_system(
     handles: Res<Handles>,
     selected_level: Res<SelectedLevel>,
     levels: Res<Assets<Level>>,
-    score: Res<Score>,
+    run: FinishedRun,
+    turn: Res<HotseatTurn>,
+    theme: Res<Theme>,
+    settings: Res<Settings>,
+    records: Records,
+    friends: Friends,
+    badge_eligibility: BadgeEligibility,
     mut q_node: Query<(Entity, &mut BackgroundColor), With<PlayAreaNode>>,
     q_dialog: Query<Entity, With<ScoreDialog>>,
+    q_segments: Query<&RoadSegment>,
 ) {
-    if !sim_state.is_changed() && !score.is_changed() {
+    if !sim_state.is_changed() && !run.score.is_changed() {
         return;
     }
 
@@ -608,7 +1176,7 @@ fn show_score_dialog_system(
         return;
     };
 
-    let Some(score) = score.0 else { return };
+    let Some(score) = run.score.0 else { return };
 
     let num_stars = level
         .star_thresholds
@@ -616,9 +1184,57 @@ fn show_score_dialog_system(
         .filter(|t| **t <= score)
         .count();
 
+    let badges = badge_eligibility.earned(level, q_segments.iter());
+
+    // User levels aren't a sequence, so there's no going onward from them.
+    let has_next_level = selected_level.0 < NUM_LEVELS
+        && handles
+            .levels
+            .get(selected_level.0 as usize)
+            .and_then(|h| levels.get(h))
+            .is_some();
+
+    // When there's nowhere to go onward to, point the player at the next star instead.
+    let next_star_hint = if has_next_level {
+        None
+    } else {
+        level
+            .star_thresholds
+            .get(num_stars)
+            .map(|t| format!("Æ{t} FOR ANOTHER ★"))
+    };
+
+    let id = handles.level_id(selected_level.0);
+    let friend_best = friends.best(&id);
+
+    let (best_score, best_cost, best_time) = records.get(&id);
+    let records = format!("BEST {}", records_label(best_score, best_cost, best_time));
+
+    let throughput = run
+        .timeline
+        .throughput(run.sim_steps.get_elapsed_f32(), THROUGHPUT_BUCKETS);
+
+    let junctions: Vec<_> = q_segments
+        .iter()
+        .flat_map(|s| [s.points.0, s.points.1])
+        .unique_by(|p| (p.x as i32, p.y as i32))
+        .collect();
+    let losses = run.timeline.losses(&junctions);
+    let worst_junction = run.timeline.worst_junction(&junctions);
+
+    // The seed, the star thresholds, the score breakdown, the records, and the
+    // throughput chart which is about two lines tall
+    let extra_lines = 6
+        + usize::from(worst_junction.is_some())
+        + usize::from(!losses.is_empty())
+        + usize::from(badges.any())
+        + usize::from(next_star_hint.is_some())
+        + usize::from(turn.enabled())
+        + usize::from(friend_best.is_some());
+
     let dialog_node = Node {
         width: Val::Px(320.0),
-        height: Val::Px(300.0),
+        height: Val::Px((350.0 + 40.0 * extra_lines as f32).min(SCORE_DIALOG_MAX_HEIGHT)),
         margin: UiRect {
             top: Val::Px(-1000.0),
             ..default()
@@ -634,14 +1250,11 @@ fn show_score_dialog_system(
 
     let dialog_entity = commands
         .spawn((
-            dialog_node.clone(),
-            dialog_node.ease_to(
-                dialog_node_to,
-                EaseFunction::QuadraticInOut,
-                EasingType::Once {
-                    duration: Duration::from_secs_f32(0.7),
-                },
-            ),
+            if settings.reduced_motion {
+                dialog_node_to.clone()
+            } else {
+                dialog_node.clone()
+            },
             BackgroundColor(color::DIALOG_BACKGROUND),
             ScoreDialog,
         ))
@@ -678,11 +1291,153 @@ fn show_score_dialog_system(
                 TextColor(color::FINISHED_ROAD[1]),
             ));
 
-            // bottom buttons
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(100.),
+                        flex_grow: 1.,
+                        min_height: Val::Px(0.),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::SpaceBetween,
+                        align_items: AlignItems::Center,
+                        overflow: Overflow::scroll_y(),
+                        ..default()
+                    },
+                    ScoreDetails,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(score_breakdown(
+                            run.pixie_count.0,
+                            run.cost.0,
+                            run.sim_steps.get_elapsed_f32(),
+                            &level.scoring,
+                        )),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(color::UI_WHITE),
+                    ));
+
+                    parent.spawn((
+                        Text::new(thresholds_label(&level.star_thresholds, 0)),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(color::UI_WHITE),
+                    ));
+
+                    parent.spawn((
+                        Text::new(records),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(color::UI_WHITE),
+                    ));
+
+                    spawn_throughput_chart(parent, &throughput);
+
+                    if !losses.is_empty() {
+                        spawn_losses(parent, &handles, losses);
+                    }
+
+                    if badges.any() {
+                        parent.spawn((
+                            Text::new(badges.label()),
+                            TextFont {
+                                font: handles.fonts[0].clone(),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                            TextColor(theme.pixie(5).into()),
+                        ));
+                    }
+
+                    if turn.enabled() {
+                        parent.spawn((
+                            Text::new(turn.contributions_label()),
+                            TextFont {
+                                font: handles.fonts[0].clone(),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                            TextColor(color::UI_WHITE),
+                        ));
+                    }
+
+                    if let Some((name, friend_score)) = friend_best {
+                        parent.spawn((
+                            Text::new(format!("{name} Æ{friend_score}")),
+                            TextFont {
+                                font: handles.fonts[0].clone(),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                            TextColor(friend_color(friend_score, Some(score))),
+                        ));
+                    }
+
+                    if let Some(hint) = next_star_hint.as_ref() {
+                        parent.spawn((
+                            Text::new(hint),
+                            TextFont {
+                                font: handles.fonts[0].clone(),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                            TextColor(color::UI_WHITE),
+                        ));
+                    }
+
+                    parent.spawn((
+                        Text::new(format!("SEED {:X}", run.seed.0)),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(Srgba::gray(0.5).into()),
+                    ));
+                });
+
+            if let Some((point, count)) = worst_junction {
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Percent(100.),
+                            height: Val::Px(40.),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(color::UI_NORMAL_BUTTON),
+                        DismissScoreDialogButton,
+                        WorstJunctionButton(point),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(format!("SHOW WORST JUNCTION ({count})")),
+                            TextFont {
+                                font: handles.fonts[0].clone(),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                            TextColor(color::UI_GREY_RED),
+                        ));
+                    });
+            }
+
             parent
                 .spawn(Node {
                     width: Val::Percent(100.),
-                    height: Val::Px(70.),
+                    height: Val::Px(40.),
                     flex_direction: FlexDirection::Row,
                     align_items: AlignItems::Stretch,
                     column_gap: Val::Px(10.),
@@ -699,19 +1454,56 @@ fn show_score_dialog_system(
                                 ..default()
                             },
                             BackgroundColor(color::UI_NORMAL_BUTTON),
-                            DismissScoreDialogButton,
+                            ReplayButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("REPLAY"),
+                                TextFont {
+                                    font: handles.fonts[0].clone(),
+                                    font_size: 25.0,
+                                    ..default()
+                                },
+                                TextColor(color::UI_BUTTON_TEXT),
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                flex_grow: 1.,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BackgroundColor(color::UI_NORMAL_BUTTON),
+                            ExportImageButton,
                         ))
                         .with_children(|parent| {
                             parent.spawn((
-                                Text::new("DISMISS"),
+                                Text::new("SAVE IMAGE"),
                                 TextFont {
                                     font: handles.fonts[0].clone(),
                                     font_size: 25.0,
                                     ..default()
                                 },
                                 TextColor(color::UI_BUTTON_TEXT),
+                                ExportImageText,
                             ));
                         });
+                });
+
+            // bottom buttons
+            parent
+                .spawn(Node {
+                    width: Val::Percent(100.),
+                    height: Val::Px(70.),
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Stretch,
+                    column_gap: Val::Px(10.),
+                    ..default()
+                })
+                .with_children(|parent| {
                     parent
                         .spawn((
                             Button,
@@ -722,11 +1514,15 @@ fn show_score_dialog_system(
                                 ..default()
                             },
                             BackgroundColor(color::UI_NORMAL_BUTTON),
-                            BackButton,
+                            DismissScoreDialogButton,
                         ))
                         .with_children(|parent| {
                             parent.spawn((
-                                Text::new("ONWARD →"),
+                                Text::new(if next_star_hint.is_some() {
+                                    "RETRY"
+                                } else {
+                                    "DISMISS"
+                                }),
                                 TextFont {
                                     font: handles.fonts[0].clone(),
                                     font_size: 25.0,
@@ -735,9 +1531,50 @@ fn show_score_dialog_system(
                                 TextColor(color::UI_BUTTON_TEXT),
                             ));
                         });
+                    let mut onward = parent.spawn((
+                        Button,
+                        Node {
+                            flex_grow: 1.,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(color::UI_NORMAL_BUTTON),
+                    ));
+                    if has_next_level {
+                        onward.insert(OnwardButton);
+                    } else {
+                        onward.insert(BackButton);
+                    }
+                    onward.with_children(|parent| {
+                        parent.spawn((
+                            Text::new(if has_next_level {
+                                "ONWARD →"
+                            } else {
+                                "LEVELS"
+                            }),
+                            TextFont {
+                                font: handles.fonts[0].clone(),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                            TextColor(color::UI_BUTTON_TEXT),
+                        ));
+                    });
                 });
         })
         .id();
+
+    if !settings.reduced_motion {
+        commands.entity(dialog_entity).insert(dialog_node.ease_to(
+            dialog_node_to,
+            EaseFunction::QuadraticInOut,
+            EasingType::Once {
+                duration: Duration::from_secs_f32(0.7),
+            },
+        ));
+    }
+
     if let Ok((entity, mut color)) = q_node.get_single_mut() {
         commands.entity(entity).add_children(&[dialog_entity]);
         *color = color::OVERLAY.into();
@@ -746,37 +1583,114 @@ fn show_score_dialog_system(
 
 fn back_button_system(
     q_interaction: Query<&Interaction, (Changed<Interaction>, With<Button>, With<BackButton>)>,
+    mut actions: EventReader<ActionPressed>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+    let key = actions.read().any(|a| a.0 == Action::Back);
+
+    if key || q_interaction.iter().any(|i| *i == Interaction::Pressed) {
         next_state.set(GameState::LevelSelect);
     }
 }
 
-fn dismiss_score_dialog_button_system(
-    mut commands: Commands,
-    mut sim_state: ResMut<SimulationState>,
-    mut pixie_count: ResMut<PixieCount>,
-    q_interaction: Query<
-        &Interaction,
-        (
-            Changed<Interaction>,
-            With<Button>,
-            With<DismissScoreDialogButton>,
-        ),
-    >,
-    q_dialog: Query<Entity, With<ScoreDialog>>,
-    q_emitters: Query<Entity, With<PixieEmitter>>,
-    mut q_node: Query<&mut BackgroundColor, With<PlayAreaNode>>,
-    mut score: ResMut<Score>,
+fn worst_junction_button_system(
+    q_interaction: Query<(&Interaction, &WorstJunctionButton), Changed<Interaction>>,
+    mut events: EventWriter<FocusCamera>,
+) {
+    for (_, button) in q_interaction
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        events.send(FocusCamera(button.0));
+    }
+}
+
+fn onward_button_system(
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<Button>, With<OnwardButton>)>,
+    selected_level: Res<SelectedLevel>,
+    mut restart: Restart,
 ) {
     for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
-        if let Ok(entity) = q_dialog.get_single() {
-            commands.entity(entity).despawn_recursive();
-            *sim_state = SimulationState::default();
-            *pixie_count = PixieCount::default();
-            *score = Score::default();
-        }
+        restart.play_level(selected_level.0 + 1);
+    }
+}
+
+/// A small chart of pixie deliveries over the course of a run.
+fn spawn_throughput_chart(parent: &mut ChildBuilder, counts: &[u32]) {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+
+    parent
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Px(THROUGHPUT_CHART_HEIGHT),
+                // Keeps its height when the score details scroll.
+                flex_shrink: 0.,
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::FlexEnd,
+                border: UiRect::bottom(Val::Px(1.)),
+                ..default()
+            },
+            BorderColor(Srgba::gray(0.5).into()),
+        ))
+        .with_children(|parent| {
+            for count in counts {
+                // A line along the top of a faint area
+                parent.spawn((
+                    Node {
+                        flex_grow: 1.,
+                        height: Val::Percent(*count as f32 / max as f32 * 100.),
+                        border: UiRect::top(Val::Px(2.)),
+                        ..default()
+                    },
+                    BackgroundColor(color::FINISHED_ROAD[1].with_alpha(0.2)),
+                    BorderColor(color::FINISHED_ROAD[1]),
+                ));
+            }
+        });
+}
+
+fn scroll_score_details_system(
+    mut wheel_events: EventReader<MouseWheel>,
+    mut q_details: Query<&mut ScrollPosition, With<ScoreDetails>>,
+) {
+    for event in wheel_events.read() {
+        let dy = match event.unit {
+            MouseScrollUnit::Line => event.y * SCORE_DETAILS_SCROLL_LINE,
+            MouseScrollUnit::Pixel => event.y,
+        };
+
+        // Layout keeps the offset from running past the end of the details.
+        for mut scroll in q_details.iter_mut() {
+            scroll.offset_y = (scroll.offset_y - dy).max(0.0);
+        }
+    }
+}
+
+fn dismiss_score_dialog_button_system(
+    mut commands: Commands,
+    mut sim_state: ResMut<SimulationState>,
+    mut pixie_count: ResMut<PixieCount>,
+    q_interaction: Query<
+        &Interaction,
+        (
+            Changed<Interaction>,
+            With<Button>,
+            With<DismissScoreDialogButton>,
+        ),
+    >,
+    q_dialog: Query<Entity, With<ScoreDialog>>,
+    q_emitters: Query<Entity, With<PixieEmitter>>,
+    mut q_node: Query<&mut BackgroundColor, With<PlayAreaNode>>,
+    mut score: ResMut<Score>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        if let Ok(entity) = q_dialog.get_single() {
+            commands.entity(entity).despawn_recursive();
+            *sim_state = SimulationState::default();
+            *pixie_count = PixieCount::default();
+            *score = Score::default();
+        }
 
         for entity in q_emitters.iter() {
             commands.entity(entity).despawn();
@@ -790,92 +1704,174 @@ fn dismiss_score_dialog_button_system(
 
 fn pixie_button_system(
     mut commands: Commands,
+    time: Res<Time>,
+    mut latch: ResMut<PixieButtonLatch>,
     mut pixie_count: ResMut<PixieCount>,
     mut sim_state: ResMut<SimulationState>,
     mut line_state: ResMut<LineDrawingState>,
+    mut drawing_state: ResMut<DrawingState>,
     pathfinding: Res<PathfindingState>,
+    sandbox: Res<Sandbox>,
+    simulation_settings: Res<SimulationSettings>,
+    current_level: CurrentLevel,
     q_interaction: Query<&Interaction, (Changed<Interaction>, With<Button>, With<PixieButton>)>,
-    q_emitters: Query<Entity, With<PixieEmitter>>,
-    q_pixies: Query<Entity, With<Pixie>>,
+    mut actions: EventReader<ActionPressed>,
+    q_running: Query<Entity, Or<(With<PixieEmitter>, With<Pixie>)>>,
+    q_terminuses: Query<&Terminus>,
     mut q_indicator: Query<(&mut Visibility, &Parent), With<TerminusIssueIndicator>>,
 ) {
+    let pressed = actions.read().any(|a| a.0 == Action::Release)
+        || q_interaction.iter().any(|i| *i == Interaction::Pressed);
+
+    latch.0 = (latch.0 - time.delta_secs()).max(0.0);
+
     // do nothing while score dialog is shown
     if *sim_state == SimulationState::Finished {
         return;
     }
 
-    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
-        line_state.drawing = false;
-        line_state.segments = vec![];
+    if !pressed {
+        return;
+    }
 
-        if *sim_state == SimulationState::Running {
-            // If the sim is ongoing, the button is a cancel button.
-            for entity in q_emitters.iter().chain(q_pixies.iter()) {
-                commands.entity(entity).despawn();
-            }
+    // A quick second click would otherwise cancel the run that the first
+    // click just started.
+    if latch.0 > 0.0 {
+        return;
+    }
+    latch.0 = PIXIE_BUTTON_LATCH_SECS;
 
-            *sim_state = SimulationState::NotStarted;
-        } else {
-            if !pathfinding.valid {
-                for (mut visibility, parent) in q_indicator.iter_mut() {
-                    *visibility = if pathfinding.invalid_nodes.contains(&parent.get()) {
-                        Visibility::Visible
-                    } else {
-                        Visibility::Hidden
-                    }
-                }
+    line_state.drawing = false;
+    line_state.segments = vec![];
 
-                return;
-            }
+    if *sim_state == SimulationState::Running {
+        // If the sim is ongoing, the button is a cancel button.
+        for entity in q_running.iter() {
+            commands.entity(entity).despawn();
+        }
 
-            for (mut visible, _) in q_indicator.iter_mut() {
-                *visible = Visibility::Hidden;
+        *sim_state = SimulationState::NotStarted;
+    } else {
+        if !pathfinding.valid {
+            for (mut visibility, parent) in q_indicator.iter_mut() {
+                *visibility = if pathfinding.invalid_nodes.contains(&parent.get()) {
+                    Visibility::Visible
+                } else {
+                    Visibility::Hidden
+                }
             }
 
-            let duration = 0.4;
-            let total_pixies = 50;
+            return;
+        }
 
-            let mut counts = HashMap::default();
-            for (_, start_entity, _) in pathfinding.paths.iter() {
-                *counts.entry(start_entity).or_insert(0) += 1;
-            }
+        for (mut visible, _) in q_indicator.iter_mut() {
+            *visible = Visibility::Hidden;
+        }
+
+        let Some(level) = current_level.get() else {
+            return;
+        };
+
+        spawn_emitters(
+            &mut commands,
+            &pathfinding,
+            simulation_settings.emission,
+            sandbox.enabled,
+            |entity| {
+                q_terminuses.get(entity).map_or_else(
+                    |_| Terminus::default().schedule(level.pixies),
+                    |t| t.schedule(level.pixies),
+                )
+            },
+        );
 
-            let mut is = HashMap::default();
+        drawing_state.edited_while_running = false;
+        *sim_state = SimulationState::Running;
+    }
 
-            for (flavor, start_entity, world_path) in pathfinding.paths.iter() {
-                let i = is.entry(start_entity).or_insert(0);
+    pixie_count.0 = 0;
+}
 
-                // unwrap: we just inserted these above
-                let count = counts.get(start_entity).unwrap();
-                let pixies = total_pixies / *count;
+/// Spawns an emitter for each path found by `pathfinding_system`, releasing pixies
+/// according to the schedule of the terminus that the path starts at. Emitters that
+/// share a terminus take turns according to `strategy`. `endless` emitters never run
+/// out.
+fn spawn_emitters(
+    commands: &mut Commands,
+    pathfinding: &PathfindingState,
+    strategy: EmissionStrategy,
+    endless: bool,
+    schedule: impl Fn(Entity) -> EmitterSchedule,
+) {
+    let mut counts = HashMap::default();
+    for (_, start_entity, _) in pathfinding.paths.iter() {
+        *counts.entry(start_entity).or_insert(0) += 1;
+    }
 
-                // if we have multiple pixies coming out of the same starting
-                // point, stagger their emitters evenly. this prevents some
-                // awkward bunching up at the start of the path.
+    let mut is = HashMap::default();
 
-                let mut timer = Timer::from_seconds(duration * *count as f32, TimerMode::Repeating);
-                timer.set_elapsed(Duration::from_secs_f32((*i + 1) as f32 * duration));
+    for (flavor, start_entity, world_path) in pathfinding.paths.iter() {
+        let i = is.entry(start_entity).or_insert(0);
 
-                commands.spawn(PixieEmitter {
-                    flavor: *flavor,
-                    path: world_path.clone(),
-                    remaining: pixies,
-                    timer,
-                });
+        // unwrap: we just inserted these above
+        let count = counts.get(start_entity).unwrap();
+        let schedule = schedule(*start_entity);
+        let pixies = schedule.pixies / *count;
+        let duration = schedule.interval;
 
-                *i += 1;
+        // if we have multiple pixies coming out of the same starting
+        // point, stagger their emitters evenly. this prevents some
+        // awkward bunching up at the start of the path.
+
+        let (timer, burst) = match strategy {
+            EmissionStrategy::RoundRobin | EmissionStrategy::Jitter => {
+                let mut timer = Timer::from_seconds(duration * *count as f32, TimerMode::Repeating);
+                let first = strategy.release_time(0, *i, *count, duration);
+                timer.set_elapsed(timer.duration() - Duration::from_secs_f32(first));
+                (timer, None)
+            }
+            // The timer goes off for every slot, and the emitter counts its way around
+            // to its own turn.
+            EmissionStrategy::Burst => {
+                let mut timer = Timer::from_seconds(duration, TimerMode::Repeating);
+                timer.set_elapsed(timer.duration());
+
+                let cycle = BURST_SIZE * *count;
+                let turn = *count - *i - 1;
+                let burst = Burst {
+                    size: BURST_SIZE,
+                    cycle,
+                    slot: (cycle - turn * BURST_SIZE) % cycle,
+                };
+                (timer, Some(burst))
             }
+        };
 
-            *sim_state = SimulationState::Running;
-        }
+        let jitter = if strategy == EmissionStrategy::Jitter {
+            duration * EMISSION_JITTER
+        } else {
+            0.0
+        };
 
-        pixie_count.0 = 0;
+        commands.spawn(PixieEmitter {
+            flavor: *flavor,
+            path: world_path.clone(),
+            remaining: pixies,
+            timer,
+            delay: schedule.delay,
+            endless,
+            jitter,
+            late: 0.0,
+            burst,
+        });
+
+        *i += 1;
     }
 }
 
-fn reset_button_system(
+fn reset_system(
     mut commands: Commands,
-    q_interaction: Query<&Interaction, (Changed<Interaction>, With<Button>, With<ResetButton>)>,
+    mut resets: EventReader<ResetLayout>,
     mut graph: ResMut<RoadGraph>,
     mut pixie_count: ResMut<PixieCount>,
     mut sim_state: ResMut<SimulationState>,
@@ -886,59 +1882,64 @@ fn reset_button_system(
     q_terminuses: Query<Entity, With<Terminus>>,
     mut q_indicator: Query<&mut Visibility, With<TerminusIssueIndicator>>,
 ) {
-    // do nothing while score dialog is shown
-    if *sim_state == SimulationState::Finished {
+    if resets.read().count() == 0 {
         return;
     }
 
-    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
-        for chunk in q_road_chunks
-            .iter()
-            .chain(q_pixies.iter())
-            .chain(q_emitters.iter())
-        {
-            commands.entity(chunk).despawn_recursive();
-        }
+    for chunk in q_road_chunks
+        .iter()
+        .chain(q_pixies.iter())
+        .chain(q_emitters.iter())
+    {
+        commands.entity(chunk).despawn_recursive();
+    }
 
-        for mut visibility in q_indicator.iter_mut() {
-            *visibility = Visibility::Hidden;
-        }
+    for mut visibility in q_indicator.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
 
-        graph.graph.clear();
+    graph.graph.clear();
 
-        // we just nuked the graph, but left the start/end points
-        // so we need to overwrite their old nodes with new ones.
-        for entity in q_terminuses.iter() {
-            let node = graph.graph.add_node(entity);
-            commands.entity(entity).insert(PointGraphNode(node));
-        }
+    // we just nuked the graph, but left the start/end points
+    // so we need to overwrite their old nodes with new ones.
+    for entity in q_terminuses.iter() {
+        let node = graph.graph.add_node(entity);
+        commands.entity(entity).insert(PointGraphNode(node));
+    }
 
-        line_state.drawing = false;
-        line_state.segments = vec![];
+    line_state.drawing = false;
+    line_state.segments = vec![];
 
-        *sim_state = SimulationState::default();
+    *sim_state = SimulationState::default();
 
-        pixie_count.0 = 0;
-    }
+    pixie_count.0 = 0;
 }
 
 fn speed_button_system(
-    q_interaction: Query<
-        (&Interaction, &Children),
-        (Changed<Interaction>, With<Button>, With<SpeedButton>),
-    >,
-    mut q_text: Query<&mut Text>,
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<Button>, With<SpeedButton>)>,
+    mut actions: EventReader<ActionPressed>,
     mut simulation_settings: ResMut<SimulationSettings>,
 ) {
-    for (_, children) in q_interaction
-        .iter()
-        .filter(|(i, _)| **i == Interaction::Pressed)
-    {
+    let key = actions.read().any(|a| a.0 == Action::Speed);
+
+    if key || q_interaction.iter().any(|i| *i == Interaction::Pressed) {
         simulation_settings.speed = simulation_settings.speed.next();
+    }
+}
+
+fn speed_button_text_system(
+    simulation_settings: Res<SimulationSettings>,
+    q_button: Query<&Children, With<SpeedButton>>,
+    mut q_text: Query<&mut Text>,
+) {
+    if !simulation_settings.is_changed() {
+        return;
+    }
 
+    for children in q_button.iter() {
         let mut iter = q_text.iter_many_mut(children);
         while let Some(mut text) = iter.fetch_next() {
-            text.0 = simulation_settings.speed.label();
+            text.0 = simulation_settings.current_speed().label();
         }
     }
 }
@@ -951,6 +1952,9 @@ fn draw_mouse_system(
     mut commands: Commands,
     line_drawing: Res<LineDrawingState>,
     mouse: Res<MouseState>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
     q_cursor: Query<Entity, With<Cursor>>,
     q_drawing: Query<Entity, With<DrawingLine>>,
 ) {
@@ -980,6 +1984,67 @@ fn draw_mouse_system(
             Stroke::new(color, 2.0),
             Cursor,
         ));
+
+        if let Some(error) = line_drawing.error {
+            commands.spawn((
+                Text2d::new(error.reason()),
+                TextFont {
+                    font: handles.fonts[0].clone(),
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(bevy::color::palettes::css::RED.into()),
+                Anchor::TopLeft,
+                Transform::from_translation((snapped + Vec2::new(12., -12.)).extend(layer::CURSOR)),
+                Cursor,
+            ));
+        } else if line_drawing.suspended {
+            commands.spawn((
+                Text2d::new("Click to resume drawing"),
+                TextFont {
+                    font: handles.fonts[0].clone(),
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(color::UI_WHITE),
+                Anchor::TopLeft,
+                Transform::from_translation((snapped + Vec2::new(12., -12.)).extend(layer::CURSOR)),
+                Cursor,
+            ));
+        }
+
+        // A ruler showing how long the line is in grid units, and how long the level
+        // allows.
+        if line_drawing.drawing && !line_drawing.segments.is_empty() {
+            let length = longest_piece(&line_drawing.segments, line_drawing.curved);
+            let max_length = handles
+                .levels
+                .get(selected_level.0 as usize - 1)
+                .and_then(|h| levels.get(h))
+                .and_then(|l| l.max_segment_length);
+
+            let (text, color) = match max_length {
+                Some(max) if length > max + 0.01 => (
+                    format!("{length:.1}/{max}"),
+                    bevy::color::palettes::css::RED.into(),
+                ),
+                Some(max) => (format!("{length:.1}/{max}"), color::UI_WHITE),
+                None => (format!("{length:.1}"), color::UI_WHITE),
+            };
+
+            commands.spawn((
+                Text2d::new(text),
+                TextFont {
+                    font: handles.fonts[0].clone(),
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(color),
+                Anchor::BottomLeft,
+                Transform::from_translation((snapped + Vec2::new(12., 12.)).extend(layer::CURSOR)),
+                Cursor,
+            ));
+        }
     }
 
     if !line_drawing.is_changed() {
@@ -1004,7 +2069,14 @@ fn draw_mouse_system(
                     transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
                     ..default()
                 },
-                Stroke::new(color, 2.0),
+                Stroke::new(
+                    color,
+                    if line_drawing.wide {
+                        WIDE_ROAD_STROKE
+                    } else {
+                        2.0
+                    },
+                ),
                 DrawingLine,
             ));
         }
@@ -1014,6 +2086,7 @@ fn draw_mouse_system(
 fn draw_net_ripping_system(
     mut commands: Commands,
     ripping_state: Res<NetRippingState>,
+    handles: Res<Handles>,
     q_ripping: Query<Entity, With<RippingLine>>,
 ) {
     if !ripping_state.is_changed() {
@@ -1035,6 +2108,45 @@ fn draw_net_ripping_system(
             RippingLine,
         ));
     }
+
+    if let Some(selection) = ripping_state.selection {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Rectangle {
+                    extents: selection.size(),
+                    origin: shapes::RectangleOrigin::CustomCenter(selection.center()),
+                    ..default()
+                }),
+                transform: Transform::from_xyz(0.0, 0.0, layer::CURSOR),
+                ..default()
+            },
+            Stroke::new(bevy::color::palettes::css::RED.with_alpha(0.5), 1.0),
+            RippingLine,
+        ));
+    }
+
+    if ripping_state.segments.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        Text2d::new(format!(
+            "REFUND §{} ({} LONG)",
+            ripping_state.refund.ceil(),
+            ripping_state.length.round()
+        )),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(color::UI_WHITE),
+        Anchor::TopLeft,
+        Transform::from_translation(
+            (ripping_state.point + Vec2::new(12., -12.)).extend(layer::CURSOR),
+        ),
+        RippingLine,
+    ));
 }
 
 fn drawing_mode_change_system(
@@ -1051,16 +2163,28 @@ fn drawing_mode_change_system(
             ripping_state.entities = vec![];
             ripping_state.nodes = vec![];
             ripping_state.segments = vec![];
+            ripping_state.box_start = None;
+            ripping_state.selection = None;
         }
         DrawingMode::NetRipping => {
             line_state.drawing = false;
             line_state.segments = vec![];
         }
+        DrawingMode::Rerouting | DrawingMode::Dragging | DrawingMode::Biasing => {
+            ripping_state.entities = vec![];
+            ripping_state.nodes = vec![];
+            ripping_state.segments = vec![];
+            ripping_state.box_start = None;
+            ripping_state.selection = None;
+            line_state.drawing = false;
+            line_state.segments = vec![];
+        }
     }
 }
 
 fn keyboard_system(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
     mut line_state: ResMut<LineDrawingState>,
     mut drawing_state: ResMut<DrawingState>,
     levels: Res<Assets<Level>>,
@@ -1069,23 +2193,29 @@ fn keyboard_system(
     mut q_radio_button: Query<&mut RadioButton>,
     q_layer_button: Query<(Entity, &LayerButton)>,
     q_net_ripping_button: Query<Entity, With<NetRippingButton>>,
+    q_drag_button: Query<Entity, With<DragButton>>,
+    q_bias_button: Query<Entity, With<BiasButton>>,
+    sim_state: Res<SimulationState>,
+    q_segments: Query<&RoadSegment>,
+    mut sfx: EventWriter<PlaySfx>,
 ) {
     if !keyboard_input.is_changed() {
         return;
     }
 
-    if keyboard_input.pressed(KeyCode::Digit1)
-        || keyboard_input.pressed(KeyCode::Digit2)
-        || keyboard_input.pressed(KeyCode::Digit3)
-    {
-        let layer = if keyboard_input.pressed(KeyCode::Digit1) {
-            1
-        } else if keyboard_input.pressed(KeyCode::Digit2) {
-            2
-        } else {
-            3
-        };
+    let layer = if input_map.pressed(Action::Layer1, &keyboard_input) {
+        Some(1)
+    } else if input_map.pressed(Action::Layer2, &keyboard_input) {
+        Some(2)
+    } else if input_map.pressed(Action::Layer3, &keyboard_input) {
+        Some(3)
+    } else if input_map.just_pressed(Action::SwapLayer, &keyboard_input) {
+        Some(line_state.other_layer)
+    } else {
+        None
+    };
 
+    if let Some(layer) = layer {
         let level = levels
             .get(&handles.levels[selected_level.0 as usize - 1])
             .unwrap();
@@ -1095,7 +2225,7 @@ fn keyboard_system(
                 drawing_state.mode = DrawingMode::LineDrawing;
             }
 
-            line_state.layer = layer;
+            line_state.set_layer(layer);
 
             for (ent, _) in q_layer_button
                 .iter()
@@ -1106,14 +2236,40 @@ fn keyboard_system(
                 }
             }
         }
-    } else if keyboard_input.pressed(KeyCode::Escape) {
-        if matches!(drawing_state.mode, DrawingMode::NetRipping) {
+    } else if input_map.pressed(Action::Cancel, &keyboard_input) {
+        if !matches!(drawing_state.mode, DrawingMode::LineDrawing) {
             drawing_state.mode = DrawingMode::LineDrawing;
         } else {
             line_state.drawing = false;
             line_state.segments = vec![];
         }
-    } else if keyboard_input.pressed(KeyCode::KeyR) {
+    } else if input_map.just_pressed(Action::Continue, &keyboard_input) {
+        if line_state.drawing
+            || !matches!(drawing_state.mode, DrawingMode::LineDrawing)
+            || !sim_state.allows_drawing()
+        {
+            return;
+        }
+
+        // The road may have been ripped up or moved since.
+        let Some(point) = line_state.last_end.filter(|p| {
+            q_segments
+                .iter()
+                .any(|s| s.points.0 == *p || s.points.1 == *p)
+        }) else {
+            sfx.send(PlaySfx(Sfx::Invalid));
+            return;
+        };
+
+        line_state.drawing = true;
+        line_state.suspended = false;
+        line_state.start = point;
+        line_state.end = point;
+    } else if input_map.just_pressed(Action::Arcs, &keyboard_input) {
+        line_state.arcs = !line_state.arcs;
+    } else if input_map.just_pressed(Action::Wide, &keyboard_input) {
+        line_state.wide = !line_state.wide;
+    } else if input_map.pressed(Action::Rip, &keyboard_input) {
         if !matches!(drawing_state.mode, DrawingMode::NetRipping) {
             drawing_state.mode = DrawingMode::NetRipping;
         }
@@ -1123,26 +2279,100 @@ fn keyboard_system(
                 radio.selected = true;
             }
         }
+    } else if input_map.pressed(Action::Drag, &keyboard_input) {
+        if !matches!(drawing_state.mode, DrawingMode::Dragging) {
+            drawing_state.mode = DrawingMode::Dragging;
+        }
+
+        if let Ok(ent) = q_drag_button.get_single() {
+            if let Ok(mut radio) = q_radio_button.get_mut(ent) {
+                radio.selected = true;
+            }
+        }
+    } else if input_map.pressed(Action::Bias, &keyboard_input) {
+        if !matches!(drawing_state.mode, DrawingMode::Biasing) {
+            drawing_state.mode = DrawingMode::Biasing;
+        }
+
+        if let Ok(ent) = q_bias_button.get_single() {
+            if let Ok(mut radio) = q_radio_button.get_mut(ent) {
+                radio.selected = true;
+            }
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn net_ripping_mouse_click_system(
     mut commands: Commands,
     mouse_input: ResMut<ButtonInput<MouseButton>>,
+    mouse: Res<MouseState>,
     mut ripping_state: ResMut<NetRippingState>,
     sim_state: Res<SimulationState>,
-    drawing_state: Res<DrawingState>,
+    mut drawing_state: ResMut<DrawingState>,
     mut graph: ResMut<RoadGraph>,
+    mut snapshots: ResMut<SimulationSnapshots>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut statistics: ResMut<Statistics>,
+    q_road_segments: Query<&RoadSegment>,
+    mut q_pixies: Query<&mut Pixie>,
+    mut q_emitters: Query<&mut PixieEmitter>,
 ) {
     if !matches!(drawing_state.mode, DrawingMode::NetRipping) {
         return;
     }
 
-    if *sim_state != SimulationState::NotStarted {
+    if !sim_state.allows_drawing() {
         return;
     }
 
     if mouse_input.just_pressed(MouseButton::Left) {
+        ripping_state.box_start = Some(mouse.position);
+        return;
+    }
+
+    // Ripping happens when the button comes back up, so that a drag can select a box
+    // full of roads instead.
+    if mouse_input.just_released(MouseButton::Left) && ripping_state.box_start.is_some() {
+        ripping_state.box_start = None;
+        ripping_state.selection = None;
+
+        if !ripping_state.entities.is_empty() {
+            sfx.send(PlaySfx(Sfx::Rip));
+            statistics.nets_ripped += 1;
+        }
+
+        if *sim_state == SimulationState::Running && !ripping_state.entities.is_empty() {
+            drawing_state.edited_while_running = true;
+
+            let ripped: Vec<_> = ripping_state
+                .entities
+                .iter()
+                .filter_map(|e| q_road_segments.get(*e).ok())
+                .cloned()
+                .collect();
+
+            // Pixies with a ripped road still ahead of them have nowhere to go, and
+            // neither do the ones that haven't been released yet.
+            for mut pixie in q_pixies.iter_mut() {
+                if path_uses(
+                    pixie.path.get(pixie.path_index..).unwrap_or_default(),
+                    &ripped,
+                ) {
+                    pixie.exploding = true;
+                }
+            }
+            for mut emitter in q_emitters.iter_mut() {
+                if path_uses(&emitter.path, &ripped) {
+                    emitter.remaining = 0;
+                    emitter.endless = false;
+                }
+            }
+
+            // Rewinding would bring back pixies on roads that aren't there anymore.
+            snapshots.clear();
+        }
+
         for entity in ripping_state.entities.iter() {
             commands.entity(*entity).despawn_recursive();
         }
@@ -1161,20 +2391,25 @@ fn drawing_mouse_click_system(
     mut commands: Commands,
     mouse_input: ResMut<ButtonInput<MouseButton>>,
     mouse: Res<MouseState>,
-    drawing_state: ResMut<DrawingState>,
+    hud: Res<Hud>,
+    mut drawing_state: ResMut<DrawingState>,
     mut line_state: ResMut<LineDrawingState>,
     sim_state: Res<SimulationState>,
+    mut turn: ResMut<HotseatTurn>,
     mut graph: ResMut<RoadGraph>,
     q_point_nodes: Query<&PointGraphNode>,
     q_segment_nodes: Query<&SegmentGraphNodes>,
     q_road_segments: Query<&RoadSegment>,
+    q_pixies: Query<(&Pixie, &Transform)>,
     q_window: Query<&Window>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut statistics: ResMut<Statistics>,
 ) {
     let Ok(window) = q_window.get_single() else {
         return;
     };
 
-    if mouse.window_position.y > window.resolution.height() - BOTTOM_BAR_HEIGHT {
+    if mouse.window_position.y > window.resolution.height() - hud.bar_height() {
         return;
     }
 
@@ -1182,7 +2417,7 @@ fn drawing_mouse_click_system(
         return;
     }
 
-    if *sim_state != SimulationState::NotStarted {
+    if !sim_state.allows_drawing() {
         return;
     }
 
@@ -1190,11 +2425,22 @@ fn drawing_mouse_click_system(
         return;
     }
 
+    // Pick a suspended line back up, rather than placing it wherever the pointer
+    // happened to come back.
+    if line_state.suspended {
+        line_state.suspended = false;
+        if line_state.drawing {
+            return;
+        }
+    }
+
     if !line_state.drawing {
         if line_state.valid {
             line_state.drawing = true;
             line_state.start = mouse.snapped;
             line_state.end = line_state.start;
+        } else {
+            sfx.send(PlaySfx(Sfx::Invalid));
         }
         return;
     }
@@ -1205,6 +2451,7 @@ fn drawing_mouse_click_system(
     }
 
     if !line_state.valid {
+        sfx.send(PlaySfx(Sfx::Invalid));
         return;
     }
 
@@ -1212,24 +2459,38 @@ fn drawing_mouse_click_system(
         return;
     }
 
+    // Pixies may have driven up to the road since it was last checked.
+    if *sim_state == SimulationState::Running
+        && pixies_near(&line_state.segments, line_state.layer, &q_pixies)
+    {
+        line_state.valid = false;
+        line_state.error = Some(PlacementError::PixiesNearby);
+        sfx.send(PlaySfx(Sfx::Invalid));
+        return;
+    }
+
+    if *sim_state == SimulationState::Running {
+        drawing_state.edited_while_running = true;
+    }
+
     let mut previous_end: Option<NodeIndex> = None;
 
     for add in line_state.adds.iter() {
         // SegmentConnection::TryExtend is only valid if extending the
-        // target segment would not break any existing connections.
+        // target segment would not break any existing connections, or change
+        // its width.
 
-        let valid_extension_a = add.connections.0.len() == 1
-            && add
-                .connections
-                .0
-                .iter()
-                .all(|c| matches!(c, SegmentConnection::TryExtend(_)));
-        let valid_extension_b = add.connections.1.len() == 1
-            && add
-                .connections
-                .1
-                .iter()
-                .all(|c| matches!(c, SegmentConnection::TryExtend(_)));
+        let can_extend = |c: &SegmentConnection| match c {
+            SegmentConnection::TryExtend(entity) => q_road_segments
+                .get(*entity)
+                .is_ok_and(|s| s.wide == line_state.wide && s.bias == RouteBias::default()),
+            _ => false,
+        };
+
+        let valid_extension_a =
+            add.connections.0.len() == 1 && add.connections.0.iter().all(can_extend);
+        let valid_extension_b =
+            add.connections.1.len() == 1 && add.connections.1.iter().all(can_extend);
 
         let mut points = add.points;
 
@@ -1262,6 +2523,8 @@ fn drawing_mouse_click_system(
             RoadSegment {
                 points,
                 layer: line_state.layer,
+                wide: line_state.wide,
+                bias: RouteBias::default(),
             },
         );
 
@@ -1359,7 +2622,7 @@ fn drawing_mouse_click_system(
                             &mut graph,
                             RoadSegment {
                                 points: (segment.points.0, *point),
-                                layer: segment.layer,
+                                ..segment.clone()
                             },
                         );
 
@@ -1375,7 +2638,7 @@ fn drawing_mouse_click_system(
                             &mut graph,
                             RoadSegment {
                                 points: (*point, segment.points.1),
-                                layer: segment.layer,
+                                ..segment.clone()
                             },
                         );
 
@@ -1399,11 +2662,26 @@ fn drawing_mouse_click_system(
         previous_end = Some(end_node);
     }
 
+    sfx.send(PlaySfx(Sfx::Place));
+
+    statistics.road_length += line_state
+        .adds
+        .iter()
+        .map(|add| (add.points.0 - add.points.1).length() / GRID_SIZE)
+        .sum::<f32>();
+
+    line_state.last_end = (!line_state.stop).then_some(line_state.end);
+
     if line_state.stop {
         line_state.drawing = false;
         line_state.stop = false;
     }
 
+    // The next player starts their own line.
+    if turn.place(line_state.pieces()) {
+        line_state.drawing = false;
+    }
+
     line_state.start = line_state.end;
     line_state.adds = vec![];
     line_state.segments = vec![];
@@ -1436,84 +2714,157 @@ fn mouse_movement_system(
     }
 }
 
+/// Suspends the line being drawn when the pointer leaves the window or is cancelled,
+/// which happens easily when the game is embedded in a web page.
+fn pointer_suspend_system(
+    mut cursor_left_events: EventReader<CursorLeft>,
+    mut focused_events: EventReader<WindowFocused>,
+    mut touch_events: EventReader<TouchInput>,
+    mut line_state: ResMut<LineDrawingState>,
+) {
+    let left = cursor_left_events.read().count() > 0;
+    let unfocused = focused_events.read().any(|e| !e.focused);
+    let cancelled = touch_events.read().any(|e| e.phase == TouchPhase::Canceled);
+
+    if (left || unfocused || cancelled) && line_state.drawing && !line_state.suspended {
+        line_state.suspended = true;
+    }
+}
+
 fn net_ripping_mouse_movement_system(
     drawing_state: Res<DrawingState>,
     mouse: Res<MouseState>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
     mut ripping_state: ResMut<NetRippingState>,
     sim_state: Res<SimulationState>,
     graph: Res<RoadGraph>,
     q_colliders: Query<(&Parent, &Collider, &ColliderLayer)>,
     q_road_segments: Query<&RoadSegment>,
     q_segment_nodes: Query<&SegmentGraphNodes>,
+    q_all_segments: Query<(Entity, &RoadSegment, &SegmentGraphNodes)>,
+    q_projection: Query<&OrthographicProjection, With<MainCamera>>,
+    viewport: Res<Viewport>,
 ) {
     if !matches!(drawing_state.mode, DrawingMode::NetRipping) {
         return;
     }
 
-    if *sim_state != SimulationState::NotStarted {
+    if !sim_state.allows_drawing() {
         return;
     }
 
-    if !mouse.is_changed() && !drawing_state.is_changed() {
+    let segment_key = input_map.key(Action::RipSegment);
+    let segment_key_changed =
+        keyboard_input.just_pressed(segment_key) || keyboard_input.just_released(segment_key);
+
+    if !mouse.is_changed() && !drawing_state.is_changed() && !segment_key_changed {
         return;
     }
 
-    // TODO we don't need to do this work if mouse.snapped is not changed.
-    // maybe we need a separate resource / change detection for MouseSnappingState
-    // or something.
-
     ripping_state.entities = vec![];
     ripping_state.nodes = vec![];
     ripping_state.segments = vec![];
+    ripping_state.refund = 0.0;
+    ripping_state.length = 0.0;
 
-    let mut collisions: Vec<_> = q_colliders
-        .iter()
-        .filter_map(|(parent, collider, layer)| match collider {
-            Collider::Segment(segment) => {
-                match point_segment_collision(mouse.snapped, segment.0, segment.1) {
-                    SegmentCollision::None => None,
-                    _ => {
-                        if layer.0 == 0 {
-                            None
-                        } else {
-                            Some((parent.get(), layer.0))
-                        }
-                    }
+    // The hit radius and box size are in screen pixels, whatever the zoom and window size.
+    let scale = q_projection.get_single().map_or(1.0, |p| p.scale) / viewport.pixels_per_unit;
+
+    if let Some(start) = ripping_state.box_start {
+        if ripping_state.selection.is_some()
+            || start.distance(mouse.position) >= NET_RIPPING_BOX_MIN_SIZE * scale
+        {
+            let selection = Rect::from_corners(start, mouse.position);
+            ripping_state.selection = Some(selection);
+
+            for (entity, seg, nodes) in q_all_segments.iter() {
+                if !segment_rect_collision(seg.points.0, seg.points.1, selection) {
+                    continue;
                 }
+
+                ripping_state.entities.push(entity);
+                ripping_state.nodes.extend([nodes.0, nodes.1]);
+                ripping_state.segments.push(seg.points);
+                ripping_state.refund += segment_cost(seg);
+                ripping_state.length += seg.points.0.distance(seg.points.1) / GRID_SIZE;
             }
-            _ => None,
-        })
-        .collect();
 
-    if collisions.is_empty() {
-        return;
+            ripping_state.point = mouse.position;
+            return;
+        }
     }
 
-    // if there are multiple collisions, choose one on the top-most layer
+    let Some(entity) =
+        segment_near_point(mouse.position, NET_RIPPING_HIT_RADIUS * scale, &q_colliders)
+    else {
+        return;
+    };
 
-    collisions.sort_by(|a, b| a.1.cmp(&b.1));
+    // Holding the segment key rips just the hovered segment instead of its whole net.
+    if keyboard_input.pressed(segment_key) {
+        if let (Ok(seg), Ok(nodes)) = (q_road_segments.get(entity), q_segment_nodes.get(entity)) {
+            ripping_state.entities.push(entity);
+            ripping_state.nodes.extend([nodes.0, nodes.1]);
+            ripping_state.segments.push(seg.points);
+            ripping_state.refund += segment_cost(seg);
+            ripping_state.length += seg.points.0.distance(seg.points.1) / GRID_SIZE;
+            ripping_state.point = mouse.position;
+        }
+        return;
+    }
 
-    if let Some((entity, _layer)) = collisions.first() {
-        if let Ok(node) = q_segment_nodes.get(*entity) {
-            let dfs = DfsPostOrder::new(&graph.graph, node.0);
-            for index in dfs.iter(&graph.graph) {
-                if let Some(net_entity) = graph.graph.node_weight(index) {
-                    if let Ok(seg) = q_road_segments.get(*net_entity) {
-                        ripping_state.entities.push(*net_entity);
-                        ripping_state.nodes.push(index);
-                        ripping_state.segments.push(seg.points);
-                    }
+    if let Ok(node) = q_segment_nodes.get(entity) {
+        let dfs = DfsPostOrder::new(&graph.graph, node.0);
+        for index in dfs.iter(&graph.graph) {
+            if let Some(net_entity) = graph.graph.node_weight(index) {
+                if let Ok(seg) = q_road_segments.get(*net_entity) {
+                    ripping_state.entities.push(*net_entity);
+                    ripping_state.nodes.push(index);
+                    ripping_state.segments.push(seg.points);
+                    ripping_state.refund += segment_cost(seg);
+                    ripping_state.length += seg.points.0.distance(seg.points.1) / GRID_SIZE;
                 }
             }
         }
+
+        ripping_state.point = mouse.position;
     }
 }
 
+/// Returns the road segment entity within `radius` of `point`. If there are several,
+/// the nearest one on the top-most layer is chosen.
+fn segment_near_point(
+    point: Vec2,
+    radius: f32,
+    q_colliders: &Query<(&Parent, &Collider, &ColliderLayer)>,
+) -> Option<Entity> {
+    q_colliders
+        .iter()
+        .filter_map(|(parent, collider, layer)| match collider {
+            Collider::Segment(segment) if layer.0 != 0 => {
+                let dist = point_segment_distance(point, segment.0, segment.1);
+                (dist <= radius).then_some((parent.get(), layer.0, dist))
+            }
+            _ => None,
+        })
+        .min_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)))
+        .map(|(entity, _, _)| entity)
+}
+
+/// Whether the obstacle `parent`, which owns a collider, blocks roads on `layer`.
+fn obstacle_blocks(q_obstacle_layers: &Query<&ObstacleLayers>, parent: Entity, layer: u32) -> bool {
+    q_obstacle_layers
+        .get(parent)
+        .map_or(true, |layers| layers.blocks(layer))
+}
+
 fn not_drawing_mouse_movement_system(
     mut line_state: ResMut<LineDrawingState>,
     drawing_state: Res<DrawingState>,
     mouse: Res<MouseState>,
     q_colliders: Query<(&Parent, &Collider, &ColliderLayer)>,
+    q_obstacle_layers: Query<&ObstacleLayers>,
 ) {
     if !matches!(drawing_state.mode, DrawingMode::LineDrawing) {
         return;
@@ -1529,43 +2880,63 @@ fn not_drawing_mouse_movement_system(
 
     let bad = q_colliders
         .iter()
-        .any(|(_parent, collider, layer)| match collider {
+        .any(|(parent, collider, layer)| match collider {
             Collider::Segment(segment) => {
                 match point_segment_collision(mouse.snapped, segment.0, segment.1) {
                     SegmentCollision::None => false,
-                    _ => layer.0 == 0,
+                    _ => {
+                        layer.0 == 0
+                            && obstacle_blocks(&q_obstacle_layers, parent.get(), line_state.layer)
+                    }
                 }
             }
             _ => false,
         });
 
-    if bad && line_state.valid {
+    if bad && line_state.error != Some(PlacementError::ObstacleCollision) {
         line_state.valid = false;
+        line_state.error = Some(PlacementError::ObstacleCollision);
     } else if !bad && !line_state.valid {
         line_state.valid = true;
+        line_state.error = None;
     }
 }
 
 fn drawing_mouse_movement_system(
     mut line_state: ResMut<LineDrawingState>,
     sim_state: Res<SimulationState>,
+    turn: Res<HotseatTurn>,
     mouse: Res<MouseState>,
+    settings: Res<Settings>,
+    mut pacing: ResMut<DrawingPacing>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
     q_colliders: Query<(&Parent, &Collider, &ColliderLayer)>,
+    q_obstacle_layers: Query<&ObstacleLayers>,
+    q_pixies: Query<(&Pixie, &Transform)>,
 ) {
     if !line_state.drawing {
         return;
     }
 
-    if *sim_state != SimulationState::NotStarted {
+    if !sim_state.allows_drawing() {
         return;
     }
 
-    if mouse.snapped == line_state.end && line_state.layer == line_state.prev_layer {
+    if mouse.snapped == line_state.end
+        && line_state.layer == line_state.prev_layer
+        && line_state.arcs == line_state.prev_arcs
+        && !settings.is_changed()
+    {
         return;
     }
 
+    let started = Instant::now();
+
     line_state.end = mouse.snapped;
     line_state.prev_layer = line_state.layer;
+    line_state.prev_arcs = line_state.arcs;
 
     // line drawing can be coerced to follow one axis or another by moving the mouse to a
     // position that is a straight line from the starting point in that axis.
@@ -1580,18 +2951,47 @@ fn drawing_mouse_movement_system(
         line_state.segments = vec![];
         line_state.adds = vec![];
         line_state.valid = true;
+        line_state.error = None;
     }
 
-    let possible = possible_lines(line_state.start, mouse.snapped, line_state.axis_preference);
+    let arcs = if line_state.arcs {
+        possible_arcs(line_state.start, mouse.snapped, line_state.axis_preference)
+    } else {
+        vec![]
+    };
+
+    line_state.curved = !arcs.is_empty();
+
+    let possible = if line_state.curved {
+        arcs
+    } else if settings.fine_angles {
+        possible_fine_lines(line_state.start, mouse.snapped, line_state.axis_preference)
+    } else {
+        possible_lines(line_state.start, mouse.snapped, line_state.axis_preference)
+    };
 
     // groan
     let mut filtered_adds = vec![];
     let mut filtered_segments = vec![];
     let mut filtered_stops = vec![];
+    let mut errors = vec![];
+
+    let max_length = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+        .and_then(|l| l.max_segment_length);
 
     for possibility in possible.iter() {
+        // A little slack for diagonals, whose lengths never come out exactly.
+        if max_length.is_some_and(|max| longest_piece(possibility, line_state.curved) > max + 0.01)
+        {
+            errors.push(PlacementError::TooLong);
+            continue;
+        }
+
         let mut adds = vec![];
-        let mut ok = true;
+        let mut error = None;
         let mut stop = false;
 
         for (segment_i, (a, b)) in possibility.iter().enumerate() {
@@ -1600,24 +3000,49 @@ fn drawing_mouse_movement_system(
             let mut split_layers: (HashSet<u32>, HashSet<u32>) =
                 (HashSet::default(), HashSet::default());
 
-            if segment_i == 1 {
+            if segment_i > 0 {
                 connections.0.push(SegmentConnection::Previous);
             }
 
             for (parent, collider, layer) in q_colliders.iter() {
+                if layer.0 == 0
+                    && !obstacle_blocks(&q_obstacle_layers, parent.get(), line_state.layer)
+                {
+                    continue;
+                }
+
+                if pacing.broadphase {
+                    let bounds = match collider {
+                        Collider::Segment(s) => *s,
+                        Collider::Point(p) => (*p, *p),
+                    };
+
+                    if !bounding_boxes_overlap(bounds, (*a, *b)) {
+                        continue;
+                    }
+                }
+
                 match collider {
                     Collider::Segment(s) => {
                         let collision = segment_collision(s.0, s.1, *a, *b);
 
                         match collision {
                             SegmentCollision::Intersecting => {
-                                if layer.0 == line_state.layer || layer.0 == 0 {
-                                    ok = false;
+                                if layer.0 == 0 {
+                                    error = Some(PlacementError::ObstacleCollision);
+                                    break;
+                                }
+                                if layer.0 == line_state.layer {
+                                    error = Some(PlacementError::SameLayerCross);
                                     break;
                                 }
                             }
                             SegmentCollision::Overlapping => {
-                                ok = false;
+                                error = Some(if layer.0 == 0 {
+                                    PlacementError::ObstacleCollision
+                                } else {
+                                    PlacementError::Overlapping
+                                });
                                 break;
                             }
                             SegmentCollision::Touching => {
@@ -1628,7 +3053,7 @@ fn drawing_mouse_movement_system(
                                 // point(s) and we could just check that.
 
                                 if layer.0 == 0 {
-                                    ok = false;
+                                    error = Some(PlacementError::ObstacleCollision);
                                     break;
                                 }
 
@@ -1642,7 +3067,7 @@ fn drawing_mouse_movement_system(
                                 );
 
                                 if !start_touching && !end_touching {
-                                    ok = false;
+                                    error = Some(PlacementError::MidSegmentTouch);
                                     break;
                                 }
 
@@ -1656,7 +3081,7 @@ fn drawing_mouse_movement_system(
                                     && !split_layers.0.contains(&layer.0)
                                     && !split_layers.0.is_empty()
                                 {
-                                    ok = false;
+                                    error = Some(PlacementError::SplitLimit);
                                     break;
                                 }
 
@@ -1664,7 +3089,7 @@ fn drawing_mouse_movement_system(
                                     && !split_layers.1.contains(&layer.0)
                                     && !split_layers.1.is_empty()
                                 {
-                                    ok = false;
+                                    error = Some(PlacementError::SplitLimit);
                                     break;
                                 }
 
@@ -1685,7 +3110,7 @@ fn drawing_mouse_movement_system(
                                 // point(s) and we could just check that.
 
                                 if layer.0 == 0 {
-                                    ok = false;
+                                    error = Some(PlacementError::ObstacleCollision);
                                     break;
                                 }
 
@@ -1699,7 +3124,7 @@ fn drawing_mouse_movement_system(
                                 );
 
                                 if !start_touching && !end_touching {
-                                    ok = false;
+                                    error = Some(PlacementError::MidSegmentTouch);
                                     break;
                                 }
 
@@ -1739,7 +3164,7 @@ fn drawing_mouse_movement_system(
                             // terminus
 
                             if *p != line_state.start && *p != line_state.end {
-                                ok = false;
+                                error = Some(PlacementError::MidTerminusTouch);
                                 break;
                             }
 
@@ -1756,14 +3181,14 @@ fn drawing_mouse_movement_system(
                         }
                         SegmentCollision::None => {}
                         _ => {
-                            ok = false;
+                            error = Some(PlacementError::MidTerminusTouch);
                             break;
                         }
                     },
                 }
             }
 
-            if !ok {
+            if error.is_some() {
                 break;
             }
 
@@ -1773,7 +3198,9 @@ fn drawing_mouse_movement_system(
             });
         }
 
-        if ok {
+        if let Some(error) = error {
+            errors.push(error);
+        } else {
             filtered_adds.push(adds);
             filtered_segments.push(possibility.clone());
             filtered_stops.push(stop);
@@ -1785,15 +3212,49 @@ fn drawing_mouse_movement_system(
         line_state.adds = filtered_adds.first().cloned().unwrap();
         line_state.stop = filtered_stops.first().cloned().unwrap();
         line_state.valid = true;
+        line_state.error = None;
+
+        if !turn.allows(line_state.pieces()) {
+            line_state.valid = false;
+            line_state.error = Some(PlacementError::TurnBudget);
+        } else if *sim_state == SimulationState::Running
+            && pixies_near(&line_state.segments, line_state.layer, &q_pixies)
+        {
+            line_state.valid = false;
+            line_state.error = Some(PlacementError::PixiesNearby);
+        }
     } else if let Some(segments) = possible.first() {
         line_state.segments.clone_from(segments);
         line_state.adds = vec![];
         line_state.valid = false;
+        line_state.error = errors.first().copied();
     } else {
         line_state.segments = vec![];
         line_state.adds = vec![];
         line_state.valid = false;
+        line_state.error = None;
     }
+
+    pacing.record(started.elapsed(), q_colliders.iter().len());
+}
+
+/// Whether any pixie driving on `layer` is close enough to `segments` to be in the way
+/// of building them.
+fn pixies_near(
+    segments: &[(Vec2, Vec2)],
+    layer: u32,
+    q_pixies: &Query<(&Pixie, &Transform)>,
+) -> bool {
+    q_pixies.iter().any(|(pixie, transform)| {
+        if pixie.path.get(pixie.path_index).map(|s| s.layer) != Some(layer) {
+            return false;
+        }
+
+        let position = pixie.road_position(transform);
+        segments
+            .iter()
+            .any(|(a, b)| point_segment_distance(position, *a, *b) < PIXIE_VISION_DISTANCE)
+    })
 }
 
 fn update_pixie_count_text_system(
@@ -1822,7 +3283,7 @@ fn spawn_road_segment(
                 transform: Transform::from_xyz(0.0, 0.0, layer::ROAD - segment.layer as f32),
                 ..default()
             },
-            Stroke::new(color, 2.0),
+            Stroke::new(color, if segment.wide { WIDE_ROAD_STROKE } else { 2.0 }),
             segment.clone(),
         ))
         .with_children(|parent| {
@@ -1849,55 +3310,63 @@ fn spawn_road_segment(
 }
 
 fn spawn_obstacle(commands: &mut Commands, obstacle: &Obstacle) {
-    match obstacle {
-        Obstacle::Rect(top_left, bottom_right) => {
-            let diff = *bottom_right - *top_left;
-            let origin = (*top_left + *bottom_right) / 2.0;
+    let outline = obstacle.outline();
+    let layers = obstacle.layers();
+
+    let path = match obstacle.base() {
+        Obstacle::Circle(center, radius) => GeometryBuilder::build_as(&shapes::Circle {
+            radius: *radius,
+            center: *center,
+        }),
+        _ => GeometryBuilder::build_as(&shapes::Polygon {
+            points: outline.clone(),
+            closed: true,
+        }),
+    };
 
-            commands
-                .spawn((
-                    ShapeBundle {
-                        path: GeometryBuilder::build_as(&shapes::Rectangle {
-                            extents: Vec2::new(diff.x.abs(), diff.y.abs()),
-                            ..default()
-                        }),
-                        transform: Transform::from_translation(origin.extend(layer::OBSTACLE)),
-                        ..default()
-                    },
-                    Fill::color(color::OBSTACLE),
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        Collider::Segment((
-                            Vec2::new(top_left.x, top_left.y),
-                            Vec2::new(bottom_right.x, top_left.y),
-                        )),
-                        ColliderLayer(0),
-                    ));
-                    parent.spawn((
-                        Collider::Segment((
-                            Vec2::new(bottom_right.x, top_left.y),
-                            Vec2::new(bottom_right.x, bottom_right.y),
-                        )),
-                        ColliderLayer(0),
-                    ));
-                    parent.spawn((
-                        Collider::Segment((
-                            Vec2::new(bottom_right.x, bottom_right.y),
-                            Vec2::new(top_left.x, bottom_right.y),
-                        )),
-                        ColliderLayer(0),
-                    ));
-                    parent.spawn((
-                        Collider::Segment((
-                            Vec2::new(top_left.x, bottom_right.y),
-                            Vec2::new(top_left.x, top_left.y),
-                        )),
-                        ColliderLayer(0),
-                    ));
-                });
+    let mut entity = commands.spawn((
+        ShapeBundle {
+            path,
+            transform: Transform::from_xyz(0.0, 0.0, layer::OBSTACLE),
+            ..default()
+        },
+        Fill::color(color::OBSTACLE),
+    ));
+
+    if let Obstacle::MovingRect {
+        size,
+        waypoints,
+        speed,
+    } = obstacle.base()
+    {
+        // Drawn over the roads that it drives across.
+        entity.insert((
+            MovingObstacle {
+                size: *size,
+                waypoints: waypoints.clone(),
+                speed: *speed,
+            },
+            Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+        ));
+    }
+
+    // Obstacles that only block some layers are outlined in the color of the lowest.
+    if let Some(layers) = layers {
+        entity.insert(layers);
+
+        if let Some(color) = layers
+            .lowest()
+            .and_then(|l| color::FINISHED_ROAD.get((l as usize).wrapping_sub(1)))
+        {
+            entity.insert(Stroke::new(*color, 2.0));
         }
     }
+
+    entity.with_children(|parent| {
+        for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+            parent.spawn((Collider::Segment((*a, *b)), ColliderLayer(0)));
+        }
+    });
 }
 
 fn spawn_name(
@@ -1906,9 +3375,17 @@ fn spawn_name(
     handles: &Res<Handles>,
     name: &String,
     name_position: &Vec2,
+    pixies: u32,
 ) {
+    // Most levels release the usual number of pixies. Point it out when they don't.
+    let label = if pixies == PIXIES_PER_TERMINUS {
+        format!("L{}: {}", number, name)
+    } else {
+        format!("L{}: {}\n₽{} PER TERMINUS", number, name, pixies)
+    };
+
     commands.spawn((
-        Text2d::new(format!("L{}: {}", number, name)),
+        Text2d::new(label),
         TextFont {
             font: handles.fonts[0].clone(),
             font_size: 25.0,
@@ -1924,10 +3401,11 @@ fn spawn_terminus(
     commands: &mut Commands,
     graph: &mut ResMut<RoadGraph>,
     handles: &Res<Handles>,
+    theme: &Theme,
     terminus: &Terminus,
 ) -> (Entity, NodeIndex) {
-    let label_offset = 22.0;
-    let label_spacing = 22.0;
+    let label_offset = TERMINUS_LABEL_SPACING;
+    let label_spacing = TERMINUS_LABEL_SPACING;
 
     let ent = commands
         .spawn((
@@ -1965,7 +3443,7 @@ fn spawn_terminus(
                         font_size: 25.0,
                         ..default()
                     },
-                    TextColor(color::PIXIE[flavor.color as usize].into()),
+                    TextColor(theme.pixie(flavor.color).into()),
                     TextLayout::new_with_justify(JustifyText::Center),
                     Transform::from_translation(label_pos.extend(layer::TERMINUS)),
                 ));
@@ -1977,11 +3455,14 @@ fn spawn_terminus(
                 let label_pos =
                     Vec2::new(0.0, -1.0 * label_offset + -1.0 * i as f32 * label_spacing);
 
-                let label = if flavor.net > 0 {
+                let mut label = if flavor.net > 0 {
                     format!("IN.{}", flavor.net + 1)
                 } else {
                     "IN".to_string()
                 };
+                if let Some(capacity) = terminus.capacity {
+                    label.push_str(&format!(" {capacity}/S"));
+                }
 
                 parent.spawn((
                     Text2d::new(label),
@@ -1990,7 +3471,7 @@ fn spawn_terminus(
                         font_size: 25.0,
                         ..default()
                     },
-                    TextColor(color::PIXIE[flavor.color as usize].into()),
+                    TextColor(theme.pixie(flavor.color).into()),
                     TextLayout::new_with_justify(JustifyText::Center),
                     Transform::from_translation(label_pos.extend(layer::TERMINUS)),
                 ));
@@ -2024,42 +3505,59 @@ fn spawn_terminus(
     (ent, node)
 }
 
+/// How much more a road costs on the given layer than a plain road on the bottom one.
+fn layer_multiplier(layer: u32) -> f32 {
+    match layer {
+        1 => LAYER_TWO_MULTIPLIER,
+        2 => LAYER_THREE_MULTIPLIER,
+        _ => 1.0,
+    }
+}
+
+/// What a road adds to the cost of a solution.
+fn segment_cost(segment: &RoadSegment) -> f32 {
+    let width_multiplier = if segment.wide {
+        WIDE_ROAD_MULTIPLIER
+    } else {
+        1.0
+    };
+
+    segment.points.0.distance(segment.points.1) * layer_multiplier(segment.layer) * width_multiplier
+        / GRID_SIZE
+}
+
+#[allow(clippy::too_many_arguments)]
 fn update_cost_system(
     graph: Res<RoadGraph>,
     line_draw: Res<LineDrawingState>,
+    ripping_state: Res<NetRippingState>,
     mut r_cost: ResMut<Cost>,
-    q_segments: Query<(&RoadSegment, &Children)>,
-    q_colliders: Query<&ColliderLayer>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    q_segments: Query<&RoadSegment>,
+    q_terminuses: Query<&Terminus>,
     mut q_cost: Query<Entity, With<CostText>>,
     mut writer: TextUiWriter,
 ) {
-    if !graph.is_changed() && !line_draw.is_changed() {
+    if !graph.is_changed() && !line_draw.is_changed() && !ripping_state.is_changed() {
         return;
     }
 
-    let mut cost = 0.0;
-
-    for (segment, children) in q_segments.iter() {
-        let Some(child) = children.first() else {
-            continue;
-        };
-
-        let Ok(layer) = q_colliders.get(*child) else {
-            continue;
-        };
-
-        let multiplier = if layer.0 == 1 {
-            LAYER_TWO_MULTIPLIER
-        } else if layer.0 == 2 {
-            LAYER_THREE_MULTIPLIER
-        } else {
-            1.0
-        };
+    let via_cost = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+        .map_or(0.0, |level| level.via_cost);
 
-        cost += (segment.points.0 - segment.points.1).length() * multiplier;
-    }
+    let terminuses: Vec<_> = q_terminuses.iter().map(|t| t.point).collect();
+    let ends: Vec<_> = q_segments
+        .iter()
+        .flat_map(|s| [(s.points.0, s.layer), (s.points.1, s.layer)])
+        .collect();
+    let vias = via_points(ends.iter().copied(), &terminuses).len();
 
-    cost /= GRID_SIZE;
+    let cost: f32 = q_segments.iter().map(segment_cost).sum::<f32>() + vias as f32 * via_cost;
     let cost_round = cost.ceil();
 
     r_cost.0 = cost as u32;
@@ -2067,39 +3565,60 @@ fn update_cost_system(
     let mut potential_cost = 0.0;
     if line_draw.valid {
         for segment in line_draw.segments.iter() {
-            let multiplier = if line_draw.layer == 1 {
-                LAYER_TWO_MULTIPLIER
-            } else if line_draw.layer == 2 {
-                LAYER_THREE_MULTIPLIER
+            let multiplier = layer_multiplier(line_draw.layer);
+            let width_multiplier = if line_draw.wide {
+                WIDE_ROAD_MULTIPLIER
             } else {
                 1.0
             };
-            potential_cost += (segment.0 - segment.1).length() * multiplier;
+            potential_cost += (segment.0 - segment.1).length() * multiplier * width_multiplier;
         }
     }
 
     potential_cost /= GRID_SIZE;
+
+    // Vias that the line being drawn would add, where it meets roads on other layers.
+    if line_draw.valid && via_cost > 0.0 {
+        let pending = line_draw
+            .segments
+            .iter()
+            .flat_map(|s| [(s.0, line_draw.layer), (s.1, line_draw.layer)]);
+        let new_vias = via_points(ends.iter().copied().chain(pending), &terminuses).len();
+        potential_cost += new_vias.saturating_sub(vias) as f32 * via_cost;
+    }
+
     let potential_cost_round = (cost + potential_cost).ceil() - cost_round;
 
     for entity in q_cost.iter_mut() {
         *writer.text(entity, 1) = format!("§{cost_round}");
-        if potential_cost_round > 0.0 {
+        if !ripping_state.entities.is_empty() {
+            *writer.text(entity, 2) = format!("-{}", ripping_state.refund.ceil());
+            *writer.color(entity, 2) = color::UI_GREY_RED.into();
+        } else if potential_cost_round > 0.0 {
             *writer.text(entity, 2) = format!("+{potential_cost_round}");
+            *writer.color(entity, 2) = color::FINISHED_ROAD[line_draw.layer as usize - 1].into();
         } else {
             *writer.text(entity, 2) = "".to_string();
         }
-        *writer.color(entity, 2) = color::FINISHED_ROAD[line_draw.layer as usize - 1].into();
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_score_system(
     pixie_count: Res<PixieCount>,
     sim_state: Res<SimulationState>,
     sim_steps: Res<SimulationSteps>,
     mut score: ResMut<Score>,
     mut best_scores: ResMut<BestScores>,
+    mut best_costs: ResMut<BestCosts>,
+    mut best_times: ResMut<BestTimes>,
+    mut best_solutions: ResMut<BestSolutions>,
     selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
     cost: Res<Cost>,
+    drawing_state: Res<DrawingState>,
+    q_segments: Query<&RoadSegment>,
 ) {
     if !sim_state.is_changed() {
         return;
@@ -2111,21 +3630,52 @@ fn update_score_system(
 
     let elapsed = sim_steps.get_elapsed_f32();
 
-    let val = ((pixie_count.0 as f32 / cost.0 as f32 / elapsed) * 10000.0).ceil() as u32;
+    // By default, pixies delivered per second per unit of cost. Being a rate, it's
+    // comparable between levels that release a short burst of pixies and ones that
+    // release a steady stream.
+    let level = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h));
+    let weights = level.map(|level| level.scoring).unwrap_or_default();
+    let val = weights.score(pixie_count.0, cost.0, elapsed);
 
     score.0 = Some(val);
 
-    if let Some(best) = best_scores.0.get_mut(&selected_level.0) {
-        if *best < val {
-            *best = val;
-        }
-    } else {
-        best_scores.0.insert(selected_level.0, val);
+    if drawing_state.edited_while_running {
+        return;
+    }
+
+    let id = handles.level_id(selected_level.0);
+
+    // The cheapest and fastest runs are kept separately, as they may not be the
+    // best scoring one.
+    if best_costs.0.get(&id).map_or(true, |best| cost.0 < *best) {
+        best_costs.0.insert(id.clone(), cost.0);
+    }
+    if best_times.0.get(&id).map_or(true, |best| elapsed < *best) {
+        best_times.0.insert(id.clone(), elapsed);
     }
+
+    if best_scores.0.get(&id).is_some_and(|best| *best >= val) {
+        return;
+    }
+
+    best_scores.0.insert(id.clone(), val);
+
+    let segments = q_segments.iter().cloned().collect();
+    best_solutions.0.insert(
+        id,
+        Solution {
+            segments,
+            level_hash: level.map_or(0, Level::layout_hash),
+        },
+    );
 }
 
 fn update_score_text_system(
     selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
     best_scores: Res<BestScores>,
     mut q_score_text: Query<&mut Text, With<ScoreText>>,
 ) {
@@ -2134,7 +3684,7 @@ fn update_score_text_system(
     }
 
     if let Some(mut text) = q_score_text.iter_mut().next() {
-        if let Some(best) = best_scores.0.get(&selected_level.0) {
+        if let Some(best) = best_scores.0.get(&handles.level_id(selected_level.0)) {
             text.0 = format!("Æ{best}");
         } else {
             text.0 = "Æ?".to_string();
@@ -2155,6 +3705,50 @@ fn update_elapsed_text_system(
     }
 }
 
+fn update_timeline_system(
+    mut commands: Commands,
+    timeline: Res<SimulationTimeline>,
+    sim_steps: Res<SimulationSteps>,
+    theme: Res<Theme>,
+    q_bar: Query<Entity, With<TimelineBar>>,
+) {
+    if !timeline.is_changed() && !sim_steps.is_changed() {
+        return;
+    }
+
+    let Ok(bar) = q_bar.get_single() else {
+        return;
+    };
+
+    commands.entity(bar).despawn_descendants();
+
+    let elapsed = sim_steps.get_elapsed_f32();
+    if elapsed <= 0.0 {
+        return;
+    }
+
+    let marks = timeline
+        .emitters_finished
+        .iter()
+        .map(|(flavor_color, t)| (*t, Color::from(theme.pixie(*flavor_color))))
+        .chain(timeline.last_delivery.map(|t| (t, color::UI_WHITE)));
+
+    commands.entity(bar).with_children(|parent| {
+        for (t, mark_color) in marks {
+            parent.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(t / elapsed * 100.),
+                    width: Val::Px(2.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                BackgroundColor(mark_color),
+            ));
+        }
+    });
+}
+
 fn playing_exit_system(
     mut commands: Commands,
     query: Query<Entity, (Without<MainCamera>, Without<Window>)>,
@@ -2168,6 +3762,8 @@ fn save_solution_system(
     query: Query<&RoadSegment>,
     graph: Res<RoadGraph>,
     level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
     mut solutions: ResMut<Solutions>,
 ) {
     if !graph.is_changed() {
@@ -2178,8 +3774,20 @@ fn save_solution_system(
     // the graph is modified after a particular level
     // is loaded.
 
+    let level_hash = handles
+        .levels
+        .get(level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+        .map_or(0, Level::layout_hash);
+
     let segments = query.iter().cloned().collect();
-    solutions.0.insert(level.0, Solution { segments });
+    solutions.0.insert(
+        handles.level_id(level.0),
+        Solution {
+            segments,
+            level_hash,
+        },
+    );
 }
 
 fn playing_enter_system(
@@ -2191,7 +3799,23 @@ fn playing_enter_system(
     handles: Res<Handles>,
     solutions: Res<Solutions>,
     simulation_settings: Res<SimulationSettings>,
+    hotseat_mode: Res<HotseatMode>,
+    theme: Res<Theme>,
+    quality: Res<GraphicsQuality>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
+    // Levels that didn't load, or can't be played, are listed on the level select
+    // screen instead.
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+        .filter(|level| level.errors().is_empty())
+    else {
+        next_state.set(GameState::LevelSelect);
+        return;
+    };
+
     // Reset
     commands.insert_resource(Score::default());
     commands.insert_resource(PixieCount::default());
@@ -2200,13 +3824,45 @@ fn playing_enter_system(
     commands.insert_resource(LineDrawingState::default());
     commands.insert_resource(NetRippingState::default());
     commands.insert_resource(SimulationState::default());
+    commands.insert_resource(SimulationPaused::default());
+    commands.insert_resource(SimulationTimeline::default());
     commands.insert_resource(PathfindingState::default());
+    commands.insert_resource(PruneState::default());
+    commands.insert_resource(RerouteState::default());
+    commands.insert_resource(DragState::default());
+    commands.insert_resource(ReplayPlayback::default());
+    commands.insert_resource(DrawingPacing::default());
+    commands.insert_resource(HotseatTurn::new(hotseat_mode.0));
     graph.graph.clear();
 
     // Build arena
 
-    for x in ((-25 * (GRID_SIZE as i32))..=25 * (GRID_SIZE as i32)).step_by(GRID_SIZE as usize) {
-        for y in (-15 * (GRID_SIZE as i32)..=15 * (GRID_SIZE as i32)).step_by(GRID_SIZE as usize) {
+    let grid_points = ((-25 * (GRID_SIZE as i32))..=25 * (GRID_SIZE as i32))
+        .step_by(GRID_SIZE as usize)
+        .cartesian_product(
+            (-15 * (GRID_SIZE as i32)..=15 * (GRID_SIZE as i32)).step_by(GRID_SIZE as usize),
+        );
+
+    if *quality == GraphicsQuality::Low {
+        // one mesh for the whole grid instead of an entity per point
+        let grid = grid_points.fold(GeometryBuilder::new(), |builder, (x, y)| {
+            builder.add(&shapes::Circle {
+                radius: 2.5,
+                center: Vec2::new(x as f32, y as f32),
+            })
+        });
+
+        commands.spawn((
+            ShapeBundle {
+                path: grid.build(),
+                transform: Transform::from_xyz(0.0, 0.0, layer::GRID),
+                ..default()
+            },
+            Fill::color(color::GRID),
+            GridPoint,
+        ));
+    } else {
+        for (x, y) in grid_points {
             commands.spawn((
                 ShapeBundle {
                     path: GeometryBuilder::build_as(&shapes::Circle {
@@ -2226,12 +3882,8 @@ fn playing_enter_system(
 
     let mut connections: Vec<(Vec2, NodeIndex)> = vec![];
 
-    let level = levels
-        .get(&handles.levels[selected_level.0 as usize - 1])
-        .unwrap();
-
     for t in level.terminuses.iter() {
-        let (_, node) = spawn_terminus(&mut commands, &mut graph, &handles, t);
+        let (_, node) = spawn_terminus(&mut commands, &mut graph, &handles, &theme, t);
         connections.push((t.point, node));
     }
 
@@ -2245,6 +3897,7 @@ fn playing_enter_system(
         &handles,
         &level.name,
         &level.name_position,
+        level.pixies,
     );
 
     println!(
@@ -2254,7 +3907,7 @@ fn playing_enter_system(
 
     // Spawn previous solution to level
 
-    if let Some(solution) = solutions.0.get(&selected_level.0) {
+    if let Some(solution) = solutions.0.get(&handles.level_id(selected_level.0)) {
         for seg in solution.segments.iter() {
             let (_, node_a, node_b) = spawn_road_segment(&mut commands, &mut graph, seg.clone());
 
@@ -2298,6 +3951,7 @@ fn playing_enter_system(
                         ..default()
                     },
                     BackgroundColor(color::BOTTOM_BAR_BACKGROUND),
+                    BottomBar,
                 ))
                 .with_children(|parent| {
                     // Container for left-aligned buttons
@@ -2317,16 +3971,11 @@ fn playing_enter_system(
                                         width: Val::Px(50.),
                                         justify_content: JustifyContent::Center,
                                         align_items: AlignItems::Center,
-                                        // extra padding to separate the back button from
-                                        // the tools
-                                        margin: UiRect {
-                                            right: Val::Px(10.0),
-                                            ..default()
-                                        },
                                         ..default()
                                     },
                                     BackgroundColor(color::UI_NORMAL_BUTTON),
                                     BackButton,
+                                    AccessibleName::new("BACK TO LEVEL SELECT"),
                                 ))
                                 .with_children(|parent| {
                                     parent.spawn((
@@ -2340,6 +3989,46 @@ fn playing_enter_system(
                                     ));
                                 });
 
+                            // Previous and next level buttons
+                            for (direction, label, name) in
+                                [(-1, "<", "PREVIOUS LEVEL"), (1, ">", "NEXT LEVEL")]
+                            {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        Node {
+                                            width: Val::Px(30.),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            // extra padding to separate the level buttons
+                                            // from the tools
+                                            margin: UiRect {
+                                                right: Val::Px(if direction > 0 {
+                                                    10.0
+                                                } else {
+                                                    0.0
+                                                }),
+                                                ..default()
+                                            },
+                                            ..default()
+                                        },
+                                        BackgroundColor(color::UI_NORMAL_BUTTON),
+                                        LevelNavButton(direction),
+                                        AccessibleName::new(name),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(label),
+                                            TextFont {
+                                                font: handles.fonts[0].clone(),
+                                                font_size: 25.0,
+                                                ..default()
+                                            },
+                                            TextColor(color::UI_BUTTON_TEXT),
+                                        ));
+                                    });
+                            }
+
                             // Tool Buttons
                             let mut tool_button_ids = vec![];
 
@@ -2355,6 +4044,7 @@ fn playing_enter_system(
                                         },
                                         BackgroundColor(color::UI_NORMAL_BUTTON),
                                         LayerButton(layer),
+                                        AccessibleName::new(format!("LAYER {layer}")),
                                         ToolButton,
                                         RadioButton {
                                             selected: layer == 1,
@@ -2376,6 +4066,33 @@ fn playing_enter_system(
                                 tool_button_ids.push(id);
                             }
 
+                            if level.layers > 1 {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        Node {
+                                            width: Val::Px(50.),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        BackgroundColor(color::UI_NORMAL_BUTTON),
+                                        LayerSwapButton,
+                                        AccessibleName::new("SWAP LAYER"),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new("SW"),
+                                            TextFont {
+                                                font: handles.fonts[0].clone(),
+                                                font_size: 25.0,
+                                                ..default()
+                                            },
+                                            TextColor(color::UI_BUTTON_TEXT),
+                                        ));
+                                    });
+                            }
+
                             let net_ripping_id = parent
                                 .spawn((
                                     Button,
@@ -2387,6 +4104,7 @@ fn playing_enter_system(
                                     },
                                     BackgroundColor(color::UI_NORMAL_BUTTON),
                                     NetRippingButton,
+                                    AccessibleName::new("RIP NETS"),
                                     ToolButton,
                                     RadioButton { selected: false },
                                 ))
@@ -2405,6 +4123,96 @@ fn playing_enter_system(
 
                             tool_button_ids.push(net_ripping_id);
 
+                            let reroute_id = parent
+                                .spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(50.),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(color::UI_NORMAL_BUTTON),
+                                    RerouteButton,
+                                    AccessibleName::new("REROUTE"),
+                                    ToolButton,
+                                    RadioButton { selected: false },
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        Text::new("RE"),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 25.0,
+                                            ..default()
+                                        },
+                                        TextColor(color::UI_BUTTON_TEXT),
+                                    ));
+                                })
+                                .id();
+
+                            tool_button_ids.push(reroute_id);
+
+                            let drag_id = parent
+                                .spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(50.),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(color::UI_NORMAL_BUTTON),
+                                    DragButton,
+                                    AccessibleName::new("MOVE ROADS"),
+                                    ToolButton,
+                                    RadioButton { selected: false },
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        Text::new("M"),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 25.0,
+                                            ..default()
+                                        },
+                                        TextColor(color::UI_BUTTON_TEXT),
+                                    ));
+                                })
+                                .id();
+
+                            tool_button_ids.push(drag_id);
+
+                            let bias_id = parent
+                                .spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(50.),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(color::UI_NORMAL_BUTTON),
+                                    BiasButton,
+                                    AccessibleName::new("ROUTE BIAS"),
+                                    ToolButton,
+                                    RadioButton { selected: false },
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        Text::new("B"),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 25.0,
+                                            ..default()
+                                        },
+                                        TextColor(color::UI_BUTTON_TEXT),
+                                    ));
+                                })
+                                .id();
+
+                            tool_button_ids.push(bias_id);
+
                             let tool_group_id = more_commands
                                 .spawn(RadioButtonGroup {
                                     entities: tool_button_ids.clone(),
@@ -2460,7 +4268,7 @@ fn playing_enter_system(
                                             font_size: 25.0,
                                             ..default()
                                         },
-                                        TextColor(color::PIXIE[0].into()),
+                                        TextColor(theme.pixie(0).into()),
                                     ));
                                 });
 
@@ -2471,7 +4279,7 @@ fn playing_enter_system(
                                     font_size: 25.0,
                                     ..default()
                                 },
-                                TextColor(color::PIXIE[1].into()),
+                                TextColor(theme.pixie(1).into()),
                                 Node {
                                     width: Val::Percent(25.),
                                     ..default()
@@ -2479,35 +4287,76 @@ fn playing_enter_system(
                                 PixieCountText,
                             ));
 
-                            parent.spawn((
-                                Text::new("ŧ0.0"),
-                                TextFont {
-                                    font: handles.fonts[0].clone(),
-                                    font_size: 25.0,
-                                    ..default()
-                                },
-                                TextColor(color::PIXIE[2].into()),
-                                Node {
+                            parent
+                                .spawn(Node {
                                     width: Val::Percent(25.),
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(4.),
                                     ..default()
-                                },
-                                ElapsedText,
-                            ));
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        Text::new("ŧ0.0"),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 25.0,
+                                            ..default()
+                                        },
+                                        TextColor(theme.pixie(2).into()),
+                                        ElapsedText,
+                                    ));
 
-                            parent.spawn((
-                                Text::new("Æ?"),
-                                TextFont {
-                                    font: handles.fonts[0].clone(),
-                                    font_size: 25.0,
-                                    ..default()
-                                },
-                                TextColor(color::FINISHED_ROAD[1]),
-                                Node {
+                                    parent.spawn((
+                                        Node {
+                                            width: Val::Px(80.),
+                                            height: Val::Px(6.),
+                                            ..default()
+                                        },
+                                        TimelineBar,
+                                    ));
+
+                                    parent.spawn((
+                                        Text::default(),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 14.0,
+                                            ..default()
+                                        },
+                                        TextColor(color::UI_WHITE),
+                                        EstimateText,
+                                    ));
+                                });
+
+                            parent
+                                .spawn(Node {
                                     width: Val::Percent(25.),
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(4.),
                                     ..default()
-                                },
-                                ScoreText,
-                            ));
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        Text::new("Æ?"),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 25.0,
+                                            ..default()
+                                        },
+                                        TextColor(color::FINISHED_ROAD[1]),
+                                        ScoreText,
+                                    ));
+
+                                    parent.spawn((
+                                        Text::default(),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 14.0,
+                                            ..default()
+                                        },
+                                        TextColor(color::UI_WHITE),
+                                        StarThresholdText,
+                                    ));
+                                });
                         });
 
                     // Container for right-aligned bar items
@@ -2521,6 +4370,29 @@ fn playing_enter_system(
                             ..default()
                         })
                         .with_children(|parent| {
+                            parent
+                                .spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(110.),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(color::UI_NORMAL_BUTTON),
+                                    PruneButton,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        Text::new("PRUNE"),
+                                        TextFont {
+                                            font: handles.fonts[0].clone(),
+                                            font_size: 25.0,
+                                            ..default()
+                                        },
+                                        TextColor(color::UI_BUTTON_TEXT),
+                                    ));
+                                });
                             parent
                                 .spawn((
                                     Button,
@@ -0,0 +1,189 @@
+//! Obstacles that drive around a loop of waypoints while the simulation runs. They sit
+//! still at their first waypoint while roads are drawn, so roads may cross their track,
+//! but pixies on those roads need to get through between passes.
+
+use crate::{
+    color, layer,
+    pixie::{Pixie, PIXIE_RADIUS},
+    sim::{SimulationState, SimulationSteps},
+    Collider, GameState,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct MovingObstaclePlugin;
+impl Plugin for MovingObstaclePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_tracks_system, reset_obstacles_system).run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct MovingObstacle {
+    pub size: Vec2,
+    pub waypoints: Vec<Vec2>,
+    pub speed: f32,
+}
+
+impl MovingObstacle {
+    fn start(&self) -> Vec2 {
+        self.waypoints.first().copied().unwrap_or_default()
+    }
+
+    /// Where the middle of the obstacle is after driving for `time` seconds.
+    pub fn position(&self, time: f32) -> Vec2 {
+        let legs: Vec<_> = self
+            .waypoints
+            .iter()
+            .copied()
+            .zip(self.waypoints.iter().copied().cycle().skip(1))
+            .collect();
+
+        let length: f32 = legs.iter().map(|(a, b)| a.distance(*b)).sum();
+        if length <= 0.0 {
+            return self.start();
+        }
+
+        let mut remaining = (time * self.speed).rem_euclid(length);
+        for (a, b) in legs {
+            let leg = a.distance(b);
+            if remaining <= leg {
+                return a + (b - a).normalize_or_zero() * remaining;
+            }
+            remaining -= leg;
+        }
+
+        self.start()
+    }
+
+    fn edges(&self, center: Vec2) -> [(Vec2, Vec2); 4] {
+        let rect = Rect::from_center_size(center, self.size);
+        let corners = [
+            Vec2::new(rect.min.x, rect.max.y),
+            rect.max,
+            Vec2::new(rect.max.x, rect.min.y),
+            rect.min,
+        ];
+
+        [0, 1, 2, 3].map(|i| (corners[i], corners[(i + 1) % 4]))
+    }
+}
+
+#[derive(Component)]
+struct MovingObstacleTrack;
+
+/// Moves the obstacle's shape and colliders so that its middle is at `center`.
+fn place(
+    obstacle: &MovingObstacle,
+    center: Vec2,
+    transform: &mut Transform,
+    children: &Children,
+    q_colliders: &mut Query<&mut Collider>,
+) {
+    // The shape was built around the first waypoint.
+    let offset = center - obstacle.start();
+    transform.translation.x = offset.x;
+    transform.translation.y = offset.y;
+
+    let mut iter = q_colliders.iter_many_mut(children);
+    let mut edges = obstacle.edges(center).into_iter();
+    while let (Some(mut collider), Some(edge)) = (iter.fetch_next(), edges.next()) {
+        *collider = Collider::Segment(edge);
+    }
+}
+
+pub fn move_obstacles_system(
+    steps: Res<SimulationSteps>,
+    mut q_obstacles: Query<(&MovingObstacle, &mut Transform, &Children)>,
+    mut q_colliders: Query<&mut Collider>,
+    mut q_pixies: Query<(&mut Pixie, &Transform), Without<MovingObstacle>>,
+) {
+    let time = steps.get_elapsed_f32();
+
+    for (obstacle, mut transform, children) in q_obstacles.iter_mut() {
+        let center = obstacle.position(time);
+        place(obstacle, center, &mut transform, children, &mut q_colliders);
+
+        let rect = Rect::from_center_size(center, obstacle.size + Vec2::splat(PIXIE_RADIUS * 2.0));
+
+        for (mut pixie, pixie_transform) in q_pixies.iter_mut() {
+            if !pixie.exploding && rect.contains(pixie.road_position(pixie_transform)) {
+                pixie.exploding = true;
+            }
+        }
+    }
+}
+
+/// Puts obstacles back at the start when the simulation is reset.
+fn reset_obstacles_system(
+    sim_state: Res<SimulationState>,
+    mut q_obstacles: Query<(&MovingObstacle, &mut Transform, &Children)>,
+    mut q_colliders: Query<&mut Collider>,
+) {
+    if !sim_state.is_changed() || *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    for (obstacle, mut transform, children) in q_obstacles.iter_mut() {
+        place(
+            obstacle,
+            obstacle.start(),
+            &mut transform,
+            children,
+            &mut q_colliders,
+        );
+    }
+}
+
+fn spawn_tracks_system(
+    mut commands: Commands,
+    q_obstacles: Query<&MovingObstacle, Added<MovingObstacle>>,
+) {
+    for obstacle in q_obstacles.iter() {
+        if obstacle.waypoints.len() < 2 {
+            continue;
+        }
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Polygon {
+                    points: obstacle.waypoints.clone(),
+                    closed: true,
+                }),
+                transform: Transform::from_xyz(0.0, 0.0, layer::GRID),
+                ..default()
+            },
+            Stroke::new(color::NAME, 2.0),
+            MovingObstacleTrack,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position() {
+        let obstacle = MovingObstacle {
+            size: Vec2::splat(10.0),
+            waypoints: vec![Vec2::ZERO, Vec2::new(100.0, 0.0)],
+            speed: 50.0,
+        };
+
+        assert_eq!(obstacle.position(0.0), Vec2::ZERO);
+        assert_eq!(obstacle.position(1.0), Vec2::new(50.0, 0.0));
+        // Back again on the way around the loop.
+        assert_eq!(obstacle.position(3.0), Vec2::new(50.0, 0.0));
+        assert_eq!(obstacle.position(4.0), Vec2::ZERO);
+
+        let stuck = MovingObstacle {
+            waypoints: vec![Vec2::ONE],
+            ..obstacle
+        };
+        assert_eq!(stuck.position(2.0), Vec2::ONE);
+    }
+}
@@ -0,0 +1,245 @@
+//! An overlay for levels with more than one net. Each group of connected roads is
+//! drawn in its own color and labeled with the nets whose terminuses it joins, so it's
+//! easy to see whether IN.2 is actually connected to OUT.2. Toggled with N.
+
+use crate::{
+    color, controls::Action, layer, level::Terminus, save::InputMap, DrawingInput, GameState,
+    Handles, RoadGraph, RoadSegment,
+};
+use bevy::{prelude::*, utils::HashSet};
+use bevy_prototype_lyon::prelude::*;
+use itertools::Itertools;
+use petgraph::{stable_graph::StableUnGraph, visit::Bfs};
+
+const NET_OVERLAY_WIDTH: f32 = 6.0;
+
+pub struct NetsPlugin;
+impl Plugin for NetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetOverlay>();
+
+        app.add_systems(Update, net_overlay_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            draw_net_overlay_system
+                .after(net_overlay_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct NetOverlay {
+    /// Whether the overlay is shown. Kept between levels.
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct NetOverlayItem;
+
+/// The entities in each connected part of the road graph, in a stable order.
+pub fn connected_groups(graph: &StableUnGraph<Entity, f32>) -> Vec<Vec<Entity>> {
+    let mut visited = HashSet::default();
+    let mut groups = vec![];
+
+    for start in graph.node_indices() {
+        if visited.contains(&start) {
+            continue;
+        }
+
+        let mut group = vec![];
+        let mut bfs = Bfs::new(graph, start);
+        while let Some(node) = bfs.next(graph) {
+            visited.insert(node);
+            group.push(graph[node]);
+        }
+
+        groups.push(group.into_iter().sorted().dedup().collect::<Vec<_>>());
+    }
+
+    groups.sort();
+    groups
+}
+
+/// Every net with a terminus among `terminuses`, and whether both its emitter and its
+/// collector are there.
+pub fn group_nets<'a>(terminuses: impl IntoIterator<Item = &'a Terminus>) -> Vec<(u32, bool)> {
+    let mut emits = HashSet::default();
+    let mut collects = HashSet::default();
+
+    for terminus in terminuses {
+        emits.extend(terminus.emits.iter().map(|f| f.net));
+        collects.extend(terminus.collects.iter().map(|f| f.net));
+    }
+
+    emits
+        .union(&collects)
+        .copied()
+        .sorted()
+        .map(|net| (net, emits.contains(&net) && collects.contains(&net)))
+        .collect()
+}
+
+fn net_overlay_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut overlay: ResMut<NetOverlay>,
+) {
+    if input_map.just_pressed(Action::Nets, &keyboard_input) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+fn draw_net_overlay_system(
+    mut commands: Commands,
+    overlay: Res<NetOverlay>,
+    graph: Res<RoadGraph>,
+    handles: Res<Handles>,
+    q_segments: Query<&RoadSegment>,
+    q_terminuses: Query<&Terminus>,
+    q_items: Query<Entity, With<NetOverlayItem>>,
+) {
+    if !overlay.is_changed() && !graph.is_changed() {
+        return;
+    }
+
+    for entity in q_items.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // Levels with a single net don't have anything to tell apart.
+    let multiple_nets = q_terminuses
+        .iter()
+        .flat_map(|t| t.emits.iter().chain(t.collects.iter()))
+        .any(|f| f.net > 0);
+
+    if !overlay.enabled || !multiple_nets {
+        return;
+    }
+
+    for group in connected_groups(&graph.graph) {
+        let segments: Vec<_> = q_segments.iter_many(&group).collect();
+        if segments.is_empty() {
+            continue;
+        }
+
+        let nets = group_nets(q_terminuses.iter_many(&group));
+
+        // Connected nets keep the same color however the roads change, and roads that
+        // don't connect anything are faded out.
+        let color = nets
+            .iter()
+            .find(|(_, connected)| *connected)
+            .map_or(color::NET_UNCONNECTED, |(net, _)| {
+                color::NETS[*net as usize % color::NETS.len()]
+            });
+
+        let path = segments.iter().fold(GeometryBuilder::new(), |builder, s| {
+            builder.add(&shapes::Line(s.points.0, s.points.1))
+        });
+
+        commands.spawn((
+            ShapeBundle {
+                path: path.build(),
+                transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                ..default()
+            },
+            Stroke::new(color, NET_OVERLAY_WIDTH),
+            NetOverlayItem,
+        ));
+
+        if nets.is_empty() {
+            continue;
+        }
+
+        // An unconnected net is marked, so a group joining OUT.2 to nothing says so.
+        let label = nets
+            .iter()
+            .map(|(net, connected)| format!("NET {}{}", net + 1, if *connected { "" } else { "?" }))
+            .join(" ");
+
+        let Some(longest) = segments.iter().max_by(|a, b| {
+            a.points
+                .0
+                .distance(a.points.1)
+                .total_cmp(&b.points.0.distance(b.points.1))
+        }) else {
+            continue;
+        };
+        let midpoint = (longest.points.0 + longest.points.1) / 2.0;
+
+        commands.spawn((
+            Text2d::new(label),
+            TextFont {
+                font: handles.fonts[0].clone(),
+                font_size: 18.0,
+                ..default()
+            },
+            TextColor(color.with_alpha(1.0)),
+            TextLayout::new_with_justify(JustifyText::Center),
+            Transform::from_translation(
+                (midpoint + Vec2::new(0.0, 14.0)).extend(layer::ROAD_OVERLAY),
+            ),
+            NetOverlayItem,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixie::PixieFlavor;
+
+    #[test]
+    fn groups() {
+        let mut graph = StableUnGraph::default();
+        let a = graph.add_node(Entity::from_raw(1));
+        let b = graph.add_node(Entity::from_raw(2));
+        let c = graph.add_node(Entity::from_raw(2));
+        graph.add_edge(a, b, 1.0);
+        graph.add_edge(b, c, 0.0);
+        graph.add_node(Entity::from_raw(3));
+        graph.add_node(Entity::from_raw(0));
+
+        assert_eq!(
+            connected_groups(&graph),
+            vec![
+                vec![Entity::from_raw(0)],
+                vec![Entity::from_raw(1), Entity::from_raw(2)],
+                vec![Entity::from_raw(3)],
+            ]
+        );
+    }
+
+    #[test]
+    fn nets() {
+        let terminus = |emits: &[u32], collects: &[u32]| Terminus {
+            emits: emits
+                .iter()
+                .map(|net| PixieFlavor {
+                    color: 0,
+                    net: *net,
+                })
+                .collect(),
+            collects: collects
+                .iter()
+                .map(|net| PixieFlavor {
+                    color: 0,
+                    net: *net,
+                })
+                .collect(),
+            ..default()
+        };
+
+        let out1 = terminus(&[0], &[]);
+        let in1 = terminus(&[], &[0]);
+        let out2 = terminus(&[1], &[]);
+
+        assert_eq!(group_nets([&out1, &in1]), vec![(0, true)]);
+        assert_eq!(
+            group_nets([&out1, &in1, &out2]),
+            vec![(0, true), (1, false)]
+        );
+        assert!(group_nets([]).is_empty());
+    }
+}
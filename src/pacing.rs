@@ -0,0 +1,92 @@
+//! Keeps an eye on how long road drawing takes, switching to cheaper collision
+//! checks on boards where it gets slow.
+//!
+//! With the `drawing_telemetry` feature, slow frames and a summary for each level are
+//! logged so that we can learn which boards need the attention. They're logged as
+//! warnings, because release builds leave out anything quieter.
+
+use crate::{GameState, Handles, SelectedLevel};
+use bevy::{prelude::*, utils::Duration};
+
+/// Drawing taking longer than this in a single frame turns on the broadphase.
+pub const DRAWING_BUDGET: Duration = Duration::from_millis(4);
+
+pub struct PacingPlugin;
+impl Plugin for PacingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DrawingPacing>();
+        app.add_systems(OnExit(GameState::Playing), telemetry_summary_system);
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct DrawingPacing {
+    /// Whether colliders are filtered by bounding box before the exact checks.
+    pub broadphase: bool,
+    samples: u32,
+    slow: u32,
+    worst: Duration,
+    colliders: usize,
+}
+
+impl DrawingPacing {
+    /// Records the time spent validating a line against `colliders` colliders.
+    pub fn record(&mut self, elapsed: Duration, colliders: usize) {
+        self.samples += 1;
+        self.worst = self.worst.max(elapsed);
+        self.colliders = self.colliders.max(colliders);
+
+        if elapsed <= DRAWING_BUDGET {
+            return;
+        }
+
+        self.slow += 1;
+
+        if cfg!(feature = "drawing_telemetry") {
+            warn!(
+                "Drawing took {:.2}ms with {colliders} colliders (broadphase: {})",
+                elapsed.as_secs_f32() * 1000.,
+                self.broadphase
+            );
+        }
+
+        self.broadphase = true;
+    }
+}
+
+fn telemetry_summary_system(
+    pacing: Res<DrawingPacing>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+) {
+    if !cfg!(feature = "drawing_telemetry") || pacing.samples == 0 {
+        return;
+    }
+
+    warn!(
+        "Level {}: {} drawing samples, {} over budget, worst {:.2}ms, up to {} colliders",
+        handles.level_id(selected_level.0),
+        pacing.samples,
+        pacing.slow,
+        pacing.worst.as_secs_f32() * 1000.,
+        pacing.colliders
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_drawing_enables_broadphase() {
+        let mut pacing = DrawingPacing::default();
+
+        pacing.record(DRAWING_BUDGET, 10);
+        assert!(!pacing.broadphase);
+
+        pacing.record(DRAWING_BUDGET * 2, 5000);
+        assert!(pacing.broadphase);
+        assert_eq!(pacing.slow, 1);
+        assert_eq!(pacing.colliders, 5000);
+    }
+}
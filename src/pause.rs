@@ -0,0 +1,458 @@
+use crate::{
+    color,
+    controls::{key_label, not_rebinding, Action},
+    restart::Restart,
+    save::InputMap,
+    settings::spawn_settings,
+    sim::{SimulationPaused, SimulationSettings, SimulationState},
+    snapshot::RewindSimulation,
+    BackButton, DrawingInput, DrawingInteraction, DrawingMode, DrawingMouseMovement, DrawingState,
+    GameState, Handles, LineDrawingState, SpeedButton,
+};
+use bevy::{prelude::*, ui::FocusPolicy};
+
+/// How far back the rewind button moves a running simulation.
+const REWIND_SECONDS: f32 = 5.0;
+
+pub struct PausePlugin;
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PausedRipping>();
+
+        app.add_systems(OnEnter(GameState::Playing), reset_paused_ripping_system);
+        app.add_systems(
+            Update,
+            (
+                pause_key_system.before(DrawingInput).run_if(not_rebinding),
+                pause_button_system,
+                emission_button_system,
+                paused_ripping_system.after(DrawingInput),
+                pause_menu_system,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+
+        // Don't let clicks and keypresses meant for the menu reach the board.
+        app.configure_sets(
+            Update,
+            (DrawingInput, DrawingMouseMovement, DrawingInteraction).run_if(board_available),
+        );
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+#[derive(Component)]
+struct PauseSettings;
+#[derive(Component)]
+struct ResumeButton;
+#[derive(Component)]
+struct RetryButton;
+#[derive(Component)]
+struct RewindButton;
+#[derive(Component)]
+struct SettingsButton;
+#[derive(Component)]
+struct EmissionButton;
+#[derive(Component)]
+struct RipButton;
+
+/// While paused, the menu can be put aside to rip roads up with the pixies held where
+/// they are. Holds the tool to go back to when the menu comes back.
+#[derive(Resource, Default)]
+struct PausedRipping(Option<DrawingMode>);
+
+/// Whether the board takes input: when not paused, or when ripping while paused.
+fn board_available(paused: Res<SimulationPaused>, ripping: Res<PausedRipping>) -> bool {
+    !paused.0 || ripping.0.is_some()
+}
+
+fn reset_paused_ripping_system(mut ripping: ResMut<PausedRipping>) {
+    ripping.0 = None;
+}
+
+pub fn pause_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    drawing_state: Res<DrawingState>,
+    line_state: Res<LineDrawingState>,
+    ripping: Res<PausedRipping>,
+    mut paused: ResMut<SimulationPaused>,
+) {
+    if !input_map.just_pressed(Action::Cancel, &keyboard_input) {
+        return;
+    }
+
+    // Escape is also used to cancel drawing and to exit the net ripping tool,
+    // so only pause when it would otherwise do nothing. When ripping while paused,
+    // exiting the tool brings the menu back.
+
+    if ripping.0.is_some() {
+        return;
+    }
+
+    if paused.0 {
+        paused.0 = false;
+    } else if matches!(drawing_state.mode, DrawingMode::LineDrawing) && !line_state.drawing {
+        paused.0 = true;
+    }
+}
+
+fn pause_button_system(
+    mut paused: ResMut<SimulationPaused>,
+    mut ripping: ResMut<PausedRipping>,
+    mut drawing_state: ResMut<DrawingState>,
+    mut mouse_input: ResMut<ButtonInput<MouseButton>>,
+    mut restart: Restart,
+    q_resume: Query<&Interaction, (Changed<Interaction>, With<ResumeButton>)>,
+    q_retry: Query<&Interaction, (Changed<Interaction>, With<RetryButton>)>,
+    q_rewind: Query<&Interaction, (Changed<Interaction>, With<RewindButton>)>,
+    q_rip: Query<&Interaction, (Changed<Interaction>, With<RipButton>)>,
+    q_settings: Query<&Interaction, (Changed<Interaction>, With<SettingsButton>)>,
+    mut q_settings_node: Query<&mut Node, With<PauseSettings>>,
+    mut rewind_events: EventWriter<RewindSimulation>,
+) {
+    for _ in q_resume.iter().filter(|i| **i == Interaction::Pressed) {
+        paused.0 = false;
+    }
+
+    for _ in q_retry.iter().filter(|i| **i == Interaction::Pressed) {
+        // Re-entering the level rebuilds it from the saved solution.
+        paused.0 = false;
+        restart.set(GameState::Playing);
+    }
+
+    for _ in q_rewind.iter().filter(|i| **i == Interaction::Pressed) {
+        rewind_events.send(RewindSimulation {
+            seconds: REWIND_SECONDS,
+        });
+    }
+
+    for _ in q_rip.iter().filter(|i| **i == Interaction::Pressed) {
+        ripping.0 = Some(drawing_state.mode);
+        drawing_state.mode = DrawingMode::NetRipping;
+
+        // The click on the button would otherwise start a box on the board.
+        mouse_input.clear_just_pressed(MouseButton::Left);
+    }
+
+    for _ in q_settings.iter().filter(|i| **i == Interaction::Pressed) {
+        for mut node in q_settings_node.iter_mut() {
+            node.display = match node.display {
+                Display::None => Display::Flex,
+                _ => Display::None,
+            };
+        }
+    }
+}
+
+/// Brings the menu back when the player is done ripping, which is when they switch to
+/// any other tool. Escape does that, as it always does for the net ripping tool.
+fn paused_ripping_system(
+    mut ripping: ResMut<PausedRipping>,
+    mut drawing_state: ResMut<DrawingState>,
+) {
+    let Some(previous) = ripping.0 else {
+        return;
+    };
+
+    if drawing_state.mode != DrawingMode::NetRipping {
+        drawing_state.mode = previous;
+        ripping.0 = None;
+    }
+}
+
+/// Cycles how emitters sharing a terminus take turns. It applies to the next run.
+fn emission_button_system(
+    mut simulation_settings: ResMut<SimulationSettings>,
+    q_interaction: Query<(&Interaction, &Children), (Changed<Interaction>, With<EmissionButton>)>,
+    mut q_text: Query<&mut Text>,
+) {
+    for (_, children) in q_interaction
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        simulation_settings.emission = simulation_settings.emission.next();
+
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0 = simulation_settings.emission.label().to_string();
+        }
+    }
+}
+
+fn pause_menu_system(
+    mut commands: Commands,
+    paused: Res<SimulationPaused>,
+    ripping: Res<PausedRipping>,
+    handles: Res<Handles>,
+    simulation_settings: Res<SimulationSettings>,
+    sim_state: Res<SimulationState>,
+    input_map: Res<InputMap>,
+    q_menu: Query<Entity, With<PauseMenu>>,
+) {
+    if !paused.is_changed() && !ripping.is_changed() {
+        return;
+    }
+
+    for entity in q_menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !paused.0 {
+        return;
+    }
+
+    if ripping.0.is_some() {
+        let hint = format!(
+            "PAUSED. {} TO RETURN TO THE MENU",
+            key_label(input_map.key(Action::Cancel))
+        );
+
+        // Out of the way of the board, and letting clicks through to it.
+        commands.spawn((
+            Text::new(hint),
+            TextFont {
+                font: handles.fonts[0].clone(),
+                font_size: 25.0,
+                ..default()
+            },
+            TextColor(color::UI_WHITE),
+            TextLayout::new_with_justify(JustifyText::Center),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                left: Val::Px(0.),
+                right: Val::Px(0.),
+                ..default()
+            },
+            FocusPolicy::Pass,
+            GlobalZIndex(1),
+            PauseMenu,
+        ));
+        return;
+    }
+
+    let button_text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 25.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(color::OVERLAY),
+            FocusPolicy::Block,
+            GlobalZIndex(1),
+            PauseMenu,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::all(Val::Px(20.0)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    BackgroundColor(color::DIALOG_BACKGROUND),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("PAUSED"),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 50.0,
+                            ..default()
+                        },
+                        TextColor(color::UI_WHITE),
+                    ));
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Start,
+                            column_gap: Val::Px(20.),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent
+                                .spawn(Node {
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(10.),
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    spawn_menu_button(
+                                        parent,
+                                        "RESUME",
+                                        ResumeButton,
+                                        &button_text_font,
+                                    );
+                                    if *sim_state == SimulationState::Running {
+                                        spawn_menu_button(
+                                            parent,
+                                            &format!("REWIND {REWIND_SECONDS}S"),
+                                            RewindButton,
+                                            &button_text_font,
+                                        );
+                                    }
+                                    if sim_state.allows_drawing() {
+                                        spawn_menu_button(
+                                            parent,
+                                            "RIP NETS",
+                                            RipButton,
+                                            &button_text_font,
+                                        );
+                                    }
+                                    spawn_menu_button(
+                                        parent,
+                                        "RETRY",
+                                        RetryButton,
+                                        &button_text_font,
+                                    );
+                                    spawn_menu_button(
+                                        parent,
+                                        "SETTINGS",
+                                        SettingsButton,
+                                        &button_text_font,
+                                    );
+                                    spawn_menu_button(
+                                        parent,
+                                        "QUIT",
+                                        BackButton,
+                                        &button_text_font,
+                                    );
+                                });
+
+                            // Beside the buttons rather than below them, so that the
+                            // menu still fits on short windows.
+                            parent
+                                .spawn((
+                                    Node {
+                                        display: Display::None,
+                                        flex_direction: FlexDirection::Column,
+                                        row_gap: Val::Px(10.),
+                                        ..default()
+                                    },
+                                    PauseSettings,
+                                ))
+                                .with_children(|parent| {
+                                    parent
+                                        .spawn(Node {
+                                            flex_direction: FlexDirection::Row,
+                                            align_items: AlignItems::Center,
+                                            column_gap: Val::Px(10.),
+                                            ..default()
+                                        })
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                Text::new("SPEED"),
+                                                button_text_font.clone(),
+                                                TextColor(color::UI_WHITE),
+                                            ));
+                                            parent
+                                                .spawn((
+                                                    Button,
+                                                    Node {
+                                                        width: Val::Px(50.),
+                                                        height: Val::Px(50.),
+                                                        justify_content: JustifyContent::Center,
+                                                        align_items: AlignItems::Center,
+                                                        ..default()
+                                                    },
+                                                    BackgroundColor(color::UI_NORMAL_BUTTON),
+                                                    SpeedButton,
+                                                ))
+                                                .with_children(|parent| {
+                                                    parent.spawn((
+                                                        Text::new(
+                                                            simulation_settings.speed.label(),
+                                                        ),
+                                                        button_text_font.clone(),
+                                                        TextColor(color::UI_BUTTON_TEXT),
+                                                    ));
+                                                });
+                                        });
+
+                                    parent
+                                        .spawn(Node {
+                                            flex_direction: FlexDirection::Row,
+                                            align_items: AlignItems::Center,
+                                            column_gap: Val::Px(10.),
+                                            ..default()
+                                        })
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                Text::new("RELEASE"),
+                                                button_text_font.clone(),
+                                                TextColor(color::UI_WHITE),
+                                            ));
+                                            parent
+                                                .spawn((
+                                                    Button,
+                                                    Node {
+                                                        width: Val::Px(120.),
+                                                        height: Val::Px(50.),
+                                                        justify_content: JustifyContent::Center,
+                                                        align_items: AlignItems::Center,
+                                                        ..default()
+                                                    },
+                                                    BackgroundColor(color::UI_NORMAL_BUTTON),
+                                                    EmissionButton,
+                                                ))
+                                                .with_children(|parent| {
+                                                    parent.spawn((
+                                                        Text::new(
+                                                            simulation_settings.emission.label(),
+                                                        ),
+                                                        button_text_font.clone(),
+                                                        TextColor(color::UI_BUTTON_TEXT),
+                                                    ));
+                                                });
+                                        });
+
+                                    spawn_settings(parent, &input_map, &button_text_font);
+                                });
+                        });
+                });
+        });
+}
+
+pub fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    marker: impl Component,
+    font: &TextFont,
+) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(250.),
+                height: Val::Px(50.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(color::UI_NORMAL_BUTTON),
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                font.clone(),
+                TextColor(color::UI_BUTTON_TEXT),
+            ));
+        });
+}
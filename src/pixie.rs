@@ -1,17 +1,24 @@
-use rstar::{RTree, RTreeObject, AABB};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
-    color, layer,
+    capacity::TerminusCapacity,
+    congestion::Congestion,
+    converter::convert_along,
+    layer,
+    level::Converter,
     lines::corner_angle,
-    lines::{distance_on_path, travel, traveled_segments},
-    sim::SIMULATION_TIMESTEP,
-    GameState, PixieCount, RoadSegment, GRID_SIZE,
+    lines::{distance_on_path, heading_on_path, travel, traveled_segments},
+    settings::GraphicsQuality,
+    sfx::{PlaySfx, Sfx},
+    sim::{SimulationSeed, SimulationSteps, SimulationTimeline, SIMULATION_TIMESTEP},
+    stats::StatisticsTally,
+    theme::Theme,
+    GameState, PixieCount, RoadGraph, RoadSegment, GRID_SIZE,
 };
 
 use bevy::{
     prelude::*,
-    utils::{HashMap, HashSet},
+    utils::{HashMap, HashSet, Instant},
 };
 
 use bevy_prototype_lyon::prelude::*;
@@ -24,21 +31,72 @@ pub const PIXIE_BRAKING_DISTANCE: f32 = PIXIE_RADIUS * 3.0;
 pub const PIXIE_EXPLOSION_DISTANCE: f32 = PIXIE_RADIUS * 0.5;
 pub const PIXIE_MIN_SPEED: f32 = 10.0;
 pub const PIXIE_MAX_SPEED: f32 = 60.0;
+/// A pixie's maximum speed when traveling through a 22.5 degree angle.
+pub const PIXIE_MAX_SPEED_22: f32 = 5.0;
 /// A pixie's maximum speed when traveling through a 45 degree angle.
 pub const PIXIE_MAX_SPEED_45: f32 = 10.0;
+/// A pixie's maximum speed when traveling through a 67.5 degree angle.
+pub const PIXIE_MAX_SPEED_67: f32 = 20.0;
 /// A pixie's maximum speed when traveling through a 90 degree angle.
 pub const PIXIE_MAX_SPEED_90: f32 = 30.0;
+/// A pixie's maximum speed when traveling through a 112.5 degree angle.
+pub const PIXIE_MAX_SPEED_112: f32 = 45.0;
 pub const PIXIE_MAX_SPEED_ATTRACTED: f32 = 120.0;
+pub const PIXIE_ACCELERATION: f32 = 50.0;
+pub const PIXIE_DECELERATION: f32 = 50.0;
 pub const CORNER_DEBUFF_ACTIVATION_DISTANCE: f32 = GRID_SIZE;
 pub const CORNER_DEBUFF_DISTANCE: f32 = 24.0;
+/// How long a delivered pixie takes to fade out, in seconds.
+pub const DELIVERED_FADE_DURATION: f32 = 0.25;
+pub const DELIVERED_SPARKS: usize = 6;
+pub const DELIVERED_SPARK_LIFE: f32 = 0.4;
+pub const DELIVERED_SPARK_SPEED: f32 = 60.0;
+/// How far to the side of a wide road an overtaking pixie drives.
+pub const OVERTAKING_LANE_OFFSET: f32 = PIXIE_RADIUS * 1.5;
+/// How far a pixie stays in the overtaking lane once it pulls out.
+pub const OVERTAKING_DISTANCE: f32 = PIXIE_RADIUS * 8.0;
+/// How quickly a pixie moves between lanes, in lanes per second.
+pub const LANE_CHANGE_SPEED: f32 = 4.0;
+/// How far to the right of the middle of the road pixies drive, so that pixies going
+/// the other way keep to their own side.
+pub const ONCOMING_LANE_OFFSET: f32 = PIXIE_RADIUS * 0.5;
+/// A pixie ahead is oncoming, and passes by rather than being followed, when its
+/// heading is more than about 120 degrees from the way the road ahead goes.
+pub const ONCOMING_HEADING_DOT: f32 = -0.5;
+/// How close a pixie pulls up behind one that is queueing for a full terminus.
+pub const PIXIE_QUEUE_DISTANCE: f32 = PIXIE_RADIUS * 2.5;
 
 pub struct PixiePlugin;
 impl Plugin for PixiePlugin {
     fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_pixie_meshes_system);
         app.add_systems(
             Update,
-            move_fragments_system.run_if(in_state(GameState::Playing)),
+            pixie_materials_system.run_if(resource_changed::<Theme>),
         );
+        app.add_systems(
+            Update,
+            (
+                move_fragments_system,
+                fade_delivered_pixies_system,
+                move_sparks_system,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Every pixie shares one mesh, and pixies of the same color share a material, so
+/// that they can be drawn in a handful of batches rather than one at a time.
+#[derive(Resource)]
+pub struct PixieMeshes {
+    mesh: Handle<Mesh>,
+    /// Materials indexed by flavor color. Kept in sync with the theme.
+    materials: Vec<Handle<ColorMaterial>>,
+}
+impl PixieMeshes {
+    fn material(&self, color: u32) -> Handle<ColorMaterial> {
+        self.materials[color as usize % self.materials.len()].clone()
     }
 }
 
@@ -56,10 +114,31 @@ impl Default for PixieFragment {
     }
 }
 
+/// A pixie that has reached its destination and is fading away. It no longer
+/// participates in the simulation.
+#[derive(Component)]
+pub struct DeliveredPixie {
+    life_remaining: f32,
+}
+impl Default for DeliveredPixie {
+    fn default() -> Self {
+        Self {
+            life_remaining: DELIVERED_FADE_DURATION,
+        }
+    }
+}
+
 #[derive(Component)]
+pub struct PixieSpark {
+    direction: Vec2,
+    life_remaining: f32,
+}
+
+#[derive(Component, Clone)]
 pub struct Pixie {
     pub flavor: PixieFlavor,
-    pub path: Vec<RoadSegment>,
+    /// Shared with the emitter that spawned this pixie and its siblings.
+    pub path: Arc<[RoadSegment]>,
     pub path_index: usize,
     pub next_corner_angle: Option<f32>,
     pub current_speed: f32,
@@ -70,23 +149,53 @@ pub struct Pixie {
     pub driving_state: DrivingState,
     pub corner_debuff_distance_remaining: f32,
     pub corner_debuff_acceleration: f32,
+    /// Position across a wide road, from 0.0 in the middle to 1.0 in the overtaking
+    /// lane.
+    pub lane: f32,
+    /// The displacement from the middle of the road currently applied to this
+    /// pixie's transform.
+    pub lane_offset: Vec2,
+    pub overtaking_distance_remaining: f32,
+    /// Waiting for a full terminus to take it, or behind a pixie that is.
+    pub queued: bool,
+}
+impl Pixie {
+    /// The pixie's position along the middle of the road, ignoring its lane.
+    pub fn road_position(&self, transform: &Transform) -> Vec2 {
+        transform.translation.truncate() - self.lane_offset
+    }
+
+    fn on_wide_road(&self) -> bool {
+        self.path.get(self.path_index).is_some_and(|s| s.wide)
+    }
+
+    /// The direction the pixie is driving in along its current road.
+    fn heading(&self) -> Vec2 {
+        self.path.get(self.path_index).map_or(Vec2::ZERO, |s| {
+            (s.points.1 - s.points.0).normalize_or_zero()
+        })
+    }
 }
 impl Default for Pixie {
     fn default() -> Self {
         Self {
             flavor: PixieFlavor::default(),
-            path: vec![],
+            path: Arc::from([]),
             path_index: 0,
             next_corner_angle: None,
             current_speed: PIXIE_MAX_SPEED,
-            acceleration: 50.0,
-            deceleration: 50.0,
+            acceleration: PIXIE_ACCELERATION,
+            deceleration: PIXIE_DECELERATION,
             exploding: false,
 
             lead_pixie: None,
             driving_state: DrivingState::Cruising,
             corner_debuff_distance_remaining: 0.0,
             corner_debuff_acceleration: 0.0,
+            lane: 0.0,
+            lane_offset: Vec2::ZERO,
+            overtaking_distance_remaining: 0.0,
+            queued: false,
         }
     }
 }
@@ -96,6 +205,7 @@ pub struct LeadPixie {
     distance: f32,
     speed: f32,
     attractor: bool,
+    queued: bool,
 }
 
 #[derive(Clone)]
@@ -104,16 +214,37 @@ pub enum DrivingState {
     Cruising,
     Braking,
 }
-#[derive(Component)]
+#[derive(Component, Clone)]
 
 pub struct PixieEmitter {
     pub flavor: PixieFlavor,
-    pub path: Vec<RoadSegment>,
+    pub path: Arc<[RoadSegment]>,
     pub remaining: u32,
     pub timer: Timer,
+    /// Seconds left before the emitter starts.
+    pub delay: f32,
+    /// Keeps releasing pixies without ever running out, for sandbox runs.
+    pub endless: bool,
+    /// The most each pixie may be released late, in seconds.
+    pub jitter: f32,
+    /// How late the next pixie is due to be released.
+    pub late: f32,
+    pub burst: Option<Burst>,
+}
+
+/// An emitter that takes turns with the others at its terminus, a few pixies at a time.
+/// Its timer goes off once per pixie slot, and it only releases in its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Burst {
+    /// Pixies released back to back in each turn.
+    pub size: u32,
+    /// Slots in a full round of turns.
+    pub cycle: u32,
+    /// Where the emitter is in the round. Its turn is the first `size` slots.
+    pub slot: u32,
 }
 
-#[derive(Copy, Clone, Default, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Default, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PixieFlavor {
     pub color: u32,
     pub net: u32,
@@ -142,8 +273,94 @@ pub fn move_fragments_system(
     }
 }
 
-pub fn explode_pixies_system(mut commands: Commands, query: Query<(Entity, &Pixie, &Transform)>) {
-    let mut rng = rand::thread_rng();
+pub fn fade_delivered_pixies_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut query: Query<(
+        Entity,
+        &mut DeliveredPixie,
+        &mut Transform,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
+) {
+    for (entity, mut delivered, mut transform, material) in query.iter_mut() {
+        delivered.life_remaining -= time.delta_secs();
+        if delivered.life_remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let remaining = delivered.life_remaining / DELIVERED_FADE_DURATION;
+
+        transform.scale = Vec3::splat(remaining);
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.color.set_alpha(remaining);
+        }
+    }
+}
+
+pub fn move_sparks_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut PixieSpark, &mut Transform, &mut Fill)>,
+) {
+    let delta = time.delta_secs();
+
+    for (entity, mut spark, mut transform, mut fill) in query.iter_mut() {
+        spark.life_remaining -= delta;
+        if spark.life_remaining <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (spark.direction * DELIVERED_SPARK_SPEED * delta).extend(0.0);
+
+        fill.color
+            .set_alpha(spark.life_remaining / DELIVERED_SPARK_LIFE);
+    }
+}
+
+fn spawn_delivery_sparks(
+    commands: &mut Commands,
+    theme: &Theme,
+    translation: Vec3,
+    flavor: PixieFlavor,
+) {
+    let shape = shapes::Circle {
+        radius: PIXIE_RADIUS / 4.0,
+        ..default()
+    };
+
+    for i in 0..DELIVERED_SPARKS {
+        let theta = i as f32 / DELIVERED_SPARKS as f32 * std::f32::consts::TAU;
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shape),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Fill::color(theme.pixie(flavor.color)),
+            PixieSpark {
+                direction: Vec2::new(theta.cos(), theta.sin()),
+                life_remaining: DELIVERED_SPARK_LIFE,
+            },
+        ));
+    }
+}
+
+pub fn explode_pixies_system(
+    mut commands: Commands,
+    seed: Res<SimulationSeed>,
+    steps: Res<SimulationSteps>,
+    theme: Res<Theme>,
+    quality: Res<GraphicsQuality>,
+    mut timeline: ResMut<SimulationTimeline>,
+    mut sfx: EventWriter<PlaySfx>,
+    query: Query<(Entity, &Pixie, &Transform)>,
+) {
+    let mut rng = seed.rng(steps.step());
 
     let shape = shapes::RegularPolygon {
         sides: 3,
@@ -153,6 +370,17 @@ pub fn explode_pixies_system(mut commands: Commands, query: Query<(Entity, &Pixi
 
     for (entity, pixie, transform) in query.iter().filter(|(_, p, _)| p.exploding) {
         commands.entity(entity).despawn();
+        sfx.send(PlaySfx(Sfx::Explode));
+        timeline.explosions.push(transform.translation.truncate());
+        timeline
+            .flavors
+            .entry(pixie.flavor.color)
+            .or_default()
+            .exploded += 1;
+
+        if *quality == GraphicsQuality::Low {
+            continue;
+        }
 
         // ideally we would have just stored a list of annihilating pairs so we can fling
         // pixie fragments in opposite directions, and then we wouldn't have to iter
@@ -170,7 +398,7 @@ pub fn explode_pixies_system(mut commands: Commands, query: Query<(Entity, &Pixi
                     transform: *transform,
                     ..default()
                 },
-                Fill::color(color::PIXIE[(pixie.flavor.color) as usize]),
+                Fill::color(theme.pixie(pixie.flavor.color)),
                 PixieFragment {
                     direction: Vec2::new(theta.cos(), theta.sin()),
                     ..default()
@@ -180,41 +408,112 @@ pub fn explode_pixies_system(mut commands: Commands, query: Query<(Entity, &Pixi
     }
 }
 
-struct PixiePoint {
-    entity: Entity,
-    pos: Vec2,
+/// A spatial index of pixies, bucketed into square cells as wide as a pixie can see,
+/// so that the pixies near any one pixie are in the 3x3 block of cells around it.
+///
+/// Pixies only move a little each tick, so the grid is kept between ticks and a
+/// pixie is only moved to another cell when it crosses into one.
+#[derive(Resource, Default)]
+pub struct PixieGrid {
+    cells: HashMap<IVec2, Vec<Entity>>,
+    pixies: HashMap<Entity, IVec2>,
+    /// Time spent keeping the grid up to date since the diagnostics overlay last took
+    /// it.
+    pub update_time: Duration,
 }
 
-impl RTreeObject for PixiePoint {
-    type Envelope = AABB<[f32; 2]>;
+impl PixieGrid {
+    const CELL_SIZE: f32 = PIXIE_VISION_DISTANCE;
+
+    fn cell(pos: Vec2) -> IVec2 {
+        (pos / Self::CELL_SIZE).floor().as_ivec2()
+    }
+
+    /// Puts `entity` in the cell for `pos`, if it isn't there already.
+    fn update(&mut self, entity: Entity, pos: Vec2) {
+        let cell = Self::cell(pos);
+
+        match self.pixies.insert(entity, cell) {
+            Some(old) if old == cell => return,
+            Some(old) => self.remove_from_cell(entity, old),
+            None => {}
+        }
+
+        self.cells.entry(cell).or_default().push(entity);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        if let Some(cell) = self.pixies.remove(&entity) {
+            self.remove_from_cell(entity, cell);
+        }
+    }
 
-    fn envelope(&self) -> Self::Envelope {
-        AABB::from_point([self.pos.x, self.pos.y])
+    fn remove_from_cell(&mut self, entity: Entity, cell: IVec2) {
+        let Some(entities) = self.cells.get_mut(&cell) else {
+            return;
+        };
+
+        if let Some(index) = entities.iter().position(|e| *e == entity) {
+            entities.swap_remove(index);
+        }
+        if entities.is_empty() {
+            self.cells.remove(&cell);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cells.clear();
+        self.pixies.clear();
+    }
+
+    /// The pixies in every cell touched by the square reaching `distance` from `pos`.
+    /// Some may be further away than that.
+    fn near(&self, pos: Vec2, distance: f32) -> impl Iterator<Item = Entity> + '_ {
+        let min = Self::cell(pos - distance);
+        let max = Self::cell(pos + distance);
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
     }
 }
 
 pub fn collide_pixies_system(
     query: Query<(Entity, &Transform), With<Pixie>>,
     mut pixie_query: Query<&mut Pixie>,
+    mut grid: ResMut<PixieGrid>,
+    mut removed: RemovedComponents<Pixie>,
 ) {
-    // rather than attempt to correctly maintain our spatial index when
-    // pixies move and spawn and despawn, we're just going to create a
-    // new index on every frame.
-    //
-    // this turns out to be a huge win vs. no spatial index at all.
-
-    let tree = RTree::<PixiePoint>::bulk_load(
-        query
-            .iter()
-            .map(|(ent, transform)| PixiePoint {
-                entity: ent,
-                pos: transform.translation.truncate(),
-            })
-            .collect::<Vec<_>>(),
-    );
+    let start = Instant::now();
+
+    for entity in removed.read() {
+        grid.remove(entity);
+    }
+
+    // Everything is despawned when a run is reset, so start over rather than rely on
+    // having seen every removal.
+    if query.is_empty() {
+        grid.clear();
+    }
+
+    for (entity, transform) in query.iter() {
+        // collisions are checked along the middle of the road, ignoring any lane
+        // offset.
+        let pos = pixie_query
+            .get(entity)
+            .map_or(transform.translation.truncate(), |p| {
+                p.road_position(transform)
+            });
+        grid.update(entity, pos);
+    }
+
+    grid.update_time += start.elapsed();
 
     let mut collisions = vec![];
     let mut explosions = vec![];
+    let mut overtakes = vec![];
 
     // prevent any pixie that is attracting another from itself being
     // attracted
@@ -234,31 +533,26 @@ pub fn collide_pixies_system(
         // and grab the pixies between this pixie and that point
 
         let layer = p1.path[p1.path_index].layer;
+        let pos1 = p1.road_position(t1);
 
-        let travel_segs = traveled_segments(
-            t1.translation.truncate(),
-            PIXIE_VISION_DISTANCE,
-            &p1.path[p1.path_index..],
-        );
+        let travel_segs = traveled_segments(pos1, PIXIE_VISION_DISTANCE, &p1.path[p1.path_index..]);
 
         let mut potential_cols = vec![];
 
-        let collision_rect = AABB::from_corners(
-            [
-                t1.translation.x - PIXIE_VISION_DISTANCE,
-                t1.translation.y - PIXIE_VISION_DISTANCE,
-            ],
-            [
-                t1.translation.x + PIXIE_VISION_DISTANCE,
-                t1.translation.y + PIXIE_VISION_DISTANCE,
-            ],
-        );
+        for e2 in grid.near(pos1, PIXIE_VISION_DISTANCE) {
+            if e2 == e1 {
+                continue;
+            }
 
-        for obj in tree.locate_in_envelope(&collision_rect) {
-            let (e2, t2) = query.get(obj.entity).unwrap();
+            // entries for pixies despawned outside of the simulation are cleared
+            // up when their removal is read.
+            let Ok((_, t2)) = query.get(e2) else {
+                continue;
+            };
             let p2 = pixie_query.get(e2).unwrap();
+            let pos2 = p2.road_position(t2);
 
-            if e2 == e1 {
+            if (pos2 - pos1).abs().max_element() > PIXIE_VISION_DISTANCE {
                 continue;
             }
 
@@ -270,25 +564,35 @@ pub fn collide_pixies_system(
                 continue;
             }
 
-            let dist = distance_on_path(
-                t1.translation.truncate(),
-                t2.translation.truncate(),
-                &travel_segs,
-            );
+            // pixies of the same flavor in different lanes pass each other by
+            if p2.flavor.color == p1.flavor.color && (p2.lane - p1.lane).abs() >= 0.5 {
+                continue;
+            }
+
+            // pixies of the same flavor coming the other way along the road are on
+            // their own side of it
+            if p2.flavor.color == p1.flavor.color
+                && heading_on_path(pos2, &travel_segs)
+                    .is_some_and(|heading| heading.dot(p2.heading()) < ONCOMING_HEADING_DOT)
+            {
+                continue;
+            }
+
+            let dist = distance_on_path(pos1, pos2, &travel_segs);
 
             if let Some(dist) = dist {
-                potential_cols.push((e2, p2.flavor, p2.current_speed, dist));
+                potential_cols.push((e2, p2.flavor, p2.current_speed, p2.queued, dist));
             }
         }
 
         // we probably only need to care about the "lead pixie"
-        potential_cols.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap());
+        potential_cols.sort_by(|a, b| a.4.partial_cmp(&b.4).unwrap());
 
         // TODO it would probably be proper to collect these, sort them by distance,
         // and then iterate them again so that pixies with the closest lead-pixie
         // get preferential treatment when deciding who can be attracted to whom.
 
-        if let Some((e2, flavor, current_speed, dist)) = potential_cols.first() {
+        if let Some((e2, flavor, current_speed, queued, dist)) = potential_cols.first() {
             if flavor.color != p1.flavor.color && *dist <= PIXIE_EXPLOSION_DISTANCE {
                 explosions.push(e1);
                 explosions.push(*e2);
@@ -310,6 +614,19 @@ pub fn collide_pixies_system(
                 _ => {}
             }
 
+            // rather than slowing down for a pixie of the same flavor on a wide road,
+            // pull out and go around it.
+            if flavor.color == p1.flavor.color
+                && *dist < PIXIE_BRAKING_DISTANCE
+                && *current_speed < PIXIE_MAX_SPEED
+                && !*queued
+                && p1.on_wide_road()
+                && p1.overtaking_distance_remaining <= 0.0
+            {
+                overtakes.push(e1);
+                continue;
+            }
+
             collisions.push((
                 e1,
                 *e2,
@@ -317,6 +634,7 @@ pub fn collide_pixies_system(
                     speed: *current_speed,
                     distance: *dist,
                     attractor: flavor.color != p1.flavor.color,
+                    queued: *queued,
                 },
             ));
 
@@ -339,19 +657,65 @@ pub fn collide_pixies_system(
             pixie.lead_pixie = Some(lead_pixie.clone());
         }
     }
+
+    for entity in overtakes.iter() {
+        if let Ok(mut pixie) = pixie_query.get_mut(*entity) {
+            pixie.overtaking_distance_remaining = OVERTAKING_DISTANCE;
+        }
+    }
 }
 
 pub fn move_pixies_system(
     mut commands: Commands,
     mut score: ResMut<PixieCount>,
-    mut query: Query<(Entity, &mut Pixie, &mut Transform)>,
+    mut timeline: ResMut<SimulationTimeline>,
+    sim_steps: Res<SimulationSteps>,
+    theme: Res<Theme>,
+    quality: Res<GraphicsQuality>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut tally: ResMut<StatisticsTally>,
+    mut congestion: ResMut<Congestion>,
+    mut capacity: ResMut<TerminusCapacity>,
+    pixie_meshes: Res<PixieMeshes>,
+    graph: Res<RoadGraph>,
+    q_converters: Query<&Converter>,
+    q_roads: Query<&RoadSegment>,
+    mut query: Query<(
+        Entity,
+        &mut Pixie,
+        &mut Transform,
+        &mut MeshMaterial2d<ColorMaterial>,
+    )>,
 ) {
     let delta = SIMULATION_TIMESTEP;
+    let converters: Vec<_> = q_converters.iter().cloned().collect();
 
-    for (entity, mut pixie, mut transform) in query.iter_mut() {
+    if graph.is_changed() {
+        congestion.roads_changed();
+    }
+
+    for (entity, mut pixie, mut transform, mut material) in query.iter_mut() {
         if pixie.path_index > pixie.path.len() - 1 {
-            commands.entity(entity).despawn_recursive();
+            // Fading out changes the color, so a delivered pixie needs its own material.
+            let material = materials.add(Color::from(theme.pixie(pixie.flavor.color)));
+
+            commands
+                .entity(entity)
+                .remove::<Pixie>()
+                .insert((DeliveredPixie::default(), MeshMaterial2d(material)));
+            spawn_delivery_sparks(&mut commands, &theme, transform.translation, pixie.flavor);
             score.0 += 1;
+            tally.pixies_delivered += 1;
+            timeline.last_delivery = Some(sim_steps.get_elapsed_f32());
+            timeline.deliveries.push(sim_steps.get_elapsed_f32());
+            if let Some(last) = pixie.path.last() {
+                timeline.terminus(last.points.1).collected += 1;
+            }
+            timeline
+                .flavors
+                .entry(pixie.flavor.color)
+                .or_default()
+                .delivered += 1;
             continue;
         }
 
@@ -372,32 +736,36 @@ pub fn move_pixies_system(
         } else {
             current_layer
         };
-        let dist = transform.translation.truncate().distance(next_waypoint);
-        let last_dist = transform.translation.truncate().distance(prev_waypoint);
+        let position = pixie.road_position(&transform);
+        let dist = position.distance(next_waypoint);
+        let last_dist = position.distance(prev_waypoint);
 
         // determine speed limit and acceleration based on environmental factors
 
         let mut speed_limit = PIXIE_MAX_SPEED;
+        pixie.queued = false;
 
         if let Some(lead_pixie) = &pixie.lead_pixie {
             if !lead_pixie.attractor && lead_pixie.distance < PIXIE_BRAKING_DISTANCE {
                 speed_limit = lead_pixie.speed - 10.0;
                 speed_limit = speed_limit.max(PIXIE_MIN_SPEED);
             }
+
+            // join the back of the queue rather than creeping into it
+            if lead_pixie.queued && lead_pixie.distance < PIXIE_QUEUE_DISTANCE {
+                speed_limit = 0.0;
+                pixie.queued = true;
+            }
         }
         if dist < CORNER_DEBUFF_ACTIVATION_DISTANCE {
             // pixies must slow down as they approach sharp corners
 
-            if let Some(angle) = pixie.next_corner_angle {
-                if angle <= 45.0 {
-                    speed_limit = speed_limit.min(PIXIE_MAX_SPEED_45);
-                    pixie.corner_debuff_distance_remaining = CORNER_DEBUFF_DISTANCE;
-                    pixie.corner_debuff_acceleration = pixie.acceleration / 8.0;
-                } else if angle <= 90.0 {
-                    speed_limit = speed_limit.min(PIXIE_MAX_SPEED_90);
-                    pixie.corner_debuff_distance_remaining = CORNER_DEBUFF_DISTANCE;
-                    pixie.corner_debuff_acceleration = pixie.acceleration / 6.0;
-                }
+            if let Some((max_speed, slowdown)) =
+                pixie.next_corner_angle.and_then(corner_speed_limit)
+            {
+                speed_limit = speed_limit.min(max_speed);
+                pixie.corner_debuff_distance_remaining = CORNER_DEBUFF_DISTANCE;
+                pixie.corner_debuff_acceleration = pixie.acceleration / slowdown;
             }
         }
         if let Some(lead_pixie) = &pixie.lead_pixie {
@@ -415,6 +783,7 @@ pub fn move_pixies_system(
             pixie.acceleration
         };
 
+        let was_braking = matches!(pixie.driving_state, DrivingState::Braking);
         pixie.driving_state = DrivingState::Cruising;
 
         // move towards speed limit
@@ -433,18 +802,47 @@ pub fn move_pixies_system(
             pixie.driving_state = DrivingState::Accelerating;
         }
 
+        let braked = !was_braking && matches!(pixie.driving_state, DrivingState::Braking);
+        congestion.record(
+            &pixie.path[pixie.path_index],
+            transform.translation.truncate(),
+            braked,
+            q_roads.iter(),
+        );
+
         // move the pixie
 
         let step = pixie.current_speed * delta;
 
-        let (to, segments_traveled) = travel(
-            transform.translation.truncate(),
-            step,
-            &pixie.path[pixie.path_index..],
-        );
+        // a full terminus keeps pixies waiting at its door until it can take another
+        let arriving = pixie.path_index == pixie.path.len() - 1 && step >= dist;
+        if arriving && !capacity.accept(next_waypoint) {
+            pixie.current_speed = 0.0;
+            pixie.driving_state = DrivingState::Braking;
+            pixie.queued = true;
+            continue;
+        }
 
-        transform.translation.x = to.x;
-        transform.translation.y = to.y;
+        let (to, segments_traveled) = travel(position, step, &pixie.path[pixie.path_index..]);
+
+        // Drive over any converters on the way, including at corners turned this step.
+        if !converters.is_empty() {
+            let mut color = pixie.flavor.color;
+            let mut from = position;
+            for segment in pixie.path[pixie.path_index..]
+                .iter()
+                .take(segments_traveled)
+            {
+                color = convert_along(&converters, from, segment.points.1, color);
+                from = segment.points.1;
+            }
+            color = convert_along(&converters, from, to, color);
+
+            if color != pixie.flavor.color {
+                pixie.flavor.color = color;
+                *material = MeshMaterial2d(pixie_meshes.material(color));
+            }
+        }
 
         if segments_traveled == 0 {
             // pixies traveling uphill should stay above the next road as they approach it.
@@ -460,6 +858,33 @@ pub fn move_pixies_system(
             pixie.path_index += segments_traveled;
         }
 
+        // change lanes, getting back into the middle before the road narrows
+
+        pixie.overtaking_distance_remaining = (pixie.overtaking_distance_remaining - step).max(0.0);
+
+        let narrowing = pixie.path.get(pixie.path_index + 1).is_none_or(|s| !s.wide)
+            && pixie
+                .path
+                .get(pixie.path_index)
+                .is_some_and(|s| to.distance(s.points.1) < OVERTAKING_DISTANCE);
+
+        let target_lane =
+            if pixie.on_wide_road() && pixie.overtaking_distance_remaining > 0.0 && !narrowing {
+                1.0
+            } else {
+                0.0
+            };
+
+        let lane_step = LANE_CHANGE_SPEED * delta;
+        pixie.lane += (target_lane - pixie.lane).clamp(-lane_step, lane_step);
+
+        // the overtaking lane is to the left, and everyone keeps a little to the right
+        pixie.lane_offset =
+            pixie.heading().perp() * (pixie.lane * OVERTAKING_LANE_OFFSET - ONCOMING_LANE_OFFSET);
+
+        transform.translation.x = to.x + pixie.lane_offset.x;
+        transform.translation.y = to.y + pixie.lane_offset.y;
+
         if pixie.next_corner_angle.is_none() || step > dist {
             if let (Some(current_waypoint), Some(next_waypoint)) = (
                 pixie.path.get(pixie.path_index),
@@ -481,16 +906,51 @@ pub fn move_pixies_system(
         pixie.corner_debuff_distance_remaining =
             (pixie.corner_debuff_distance_remaining - step).max(0.0);
 
-        transform.rotate(Quat::from_rotation_z(pixie.current_speed * -0.08 * delta));
+        if *quality == GraphicsQuality::High {
+            transform.rotate(Quat::from_rotation_z(pixie.current_speed * -0.08 * delta));
+        }
     }
 }
 
-pub fn emit_pixies_system(mut q_emitters: Query<&mut PixieEmitter>, mut commands: Commands) {
+/// The maximum speed through a corner of `angle` degrees, and how many times slower
+/// a pixie accelerates coming out of it. Corners no sharper than 135 degrees don't
+/// slow pixies down.
+pub fn corner_speed_limit(angle: f32) -> Option<(f32, f32)> {
+    // a little slack for roads that don't start and end on the grid
+    const SLACK: f32 = 0.1;
+
+    [
+        (22.5, PIXIE_MAX_SPEED_22, 10.0),
+        (45.0, PIXIE_MAX_SPEED_45, 8.0),
+        (67.5, PIXIE_MAX_SPEED_67, 7.0),
+        (90.0, PIXIE_MAX_SPEED_90, 6.0),
+        (112.5, PIXIE_MAX_SPEED_112, 4.0),
+    ]
+    .into_iter()
+    .find(|(corner, _, _)| angle <= corner + SLACK)
+    .map(|(_, max_speed, slowdown)| (max_speed, slowdown))
+}
+
+pub fn emit_pixies_system(
+    mut q_emitters: Query<&mut PixieEmitter>,
+    mut commands: Commands,
+    mut timeline: ResMut<SimulationTimeline>,
+    seed: Res<SimulationSeed>,
+    sim_steps: Res<SimulationSteps>,
+    pixie_meshes: Res<PixieMeshes>,
+) {
+    let mut rng = seed.rng(sim_steps.step());
+
     for mut emitter in q_emitters.iter_mut() {
         if emitter.remaining == 0 {
             continue;
         }
 
+        if emitter.delay > 0.0 {
+            emitter.delay -= SIMULATION_TIMESTEP;
+            continue;
+        }
+
         emitter
             .timer
             .tick(Duration::from_secs_f32(SIMULATION_TIMESTEP));
@@ -499,34 +959,169 @@ pub fn emit_pixies_system(mut q_emitters: Query<&mut PixieEmitter>, mut commands
             continue;
         }
 
-        let shape = shapes::RegularPolygon {
-            sides: 6,
-            feature: shapes::RegularPolygonFeature::Radius(PIXIE_RADIUS),
-            ..shapes::RegularPolygon::default()
-        };
+        if let Some(burst) = emitter.burst.as_mut() {
+            let turn = burst.slot < burst.size;
+            burst.slot = (burst.slot + 1) % burst.cycle;
+            if !turn {
+                continue;
+            }
+        }
+
+        // The wait until the next pixie makes up for how late this one was, so that
+        // pixies stay close to their own turn rather than drifting into the others'.
+        if emitter.jitter > 0.0 {
+            let late = rng.gen_range(0.0..emitter.jitter);
+            let shift = emitter.late - late;
+            if shift >= 0.0 {
+                let elapsed = emitter.timer.elapsed_secs() + shift;
+                emitter.timer.set_elapsed(Duration::from_secs_f32(elapsed));
+            } else {
+                emitter.delay = -shift;
+            }
+            emitter.late = late;
+        }
 
         let first_segment = emitter.path.first().unwrap();
 
-        commands.spawn((
-            ShapeBundle {
-                path: GeometryBuilder::build_as(&shape),
-                transform: Transform::from_translation(
-                    first_segment
-                        .points
-                        .0
-                        .extend(layer::PIXIE - first_segment.layer as f32),
-                ),
-                ..default()
-            },
-            Fill::color(color::PIXIE[(emitter.flavor.color) as usize]),
+        spawn_pixie(
+            &mut commands,
+            &pixie_meshes,
             Pixie {
                 flavor: emitter.flavor,
                 path: emitter.path.clone(),
                 path_index: 0,
                 ..default()
             },
-        ));
+            Transform::from_translation(
+                first_segment
+                    .points
+                    .0
+                    .extend(layer::PIXIE - first_segment.layer as f32),
+            ),
+        );
+
+        timeline.terminus(first_segment.points.0).emitted += 1;
+
+        if !emitter.endless {
+            emitter.remaining -= 1;
+        }
+        timeline
+            .flavors
+            .entry(emitter.flavor.color)
+            .or_default()
+            .emitted += 1;
+
+        if emitter.remaining == 0 {
+            timeline
+                .emitters_finished
+                .push((emitter.flavor.color, sim_steps.get_elapsed_f32()));
+        }
+    }
+}
+
+pub fn spawn_pixie(
+    commands: &mut Commands,
+    pixie_meshes: &PixieMeshes,
+    pixie: Pixie,
+    transform: Transform,
+) {
+    commands.spawn((
+        Mesh2d(pixie_meshes.mesh.clone()),
+        MeshMaterial2d(pixie_meshes.material(pixie.flavor.color)),
+        transform,
+        pixie,
+    ));
+}
+
+fn setup_pixie_meshes_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    theme: Res<Theme>,
+) {
+    // Bevy's hexagons point up. Turn it so that it sits flat, like the other shapes.
+    let mesh = Mesh::from(RegularPolygon::new(PIXIE_RADIUS, 6))
+        .rotated_by(Quat::from_rotation_z(std::f32::consts::FRAC_PI_6));
+
+    commands.insert_resource(PixieMeshes {
+        mesh: meshes.add(mesh),
+        materials: (0..6)
+            .map(|color| materials.add(Color::from(theme.pixie(color))))
+            .collect(),
+    });
+}
+
+fn pixie_materials_system(
+    theme: Res<Theme>,
+    pixie_meshes: Option<Res<PixieMeshes>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(pixie_meshes) = pixie_meshes else {
+        return;
+    };
+
+    for (color, handle) in pixie_meshes.materials.iter().enumerate() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = theme.pixie(color as u32).into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn grid() {
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+
+        let mut grid = PixieGrid::default();
+        grid.update(a, Vec2::new(1.0, 1.0));
+        grid.update(b, Vec2::new(100.0, 100.0));
+
+        let near = |grid: &PixieGrid| {
+            grid.near(Vec2::ZERO, PIXIE_VISION_DISTANCE)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(near(&grid), vec![a]);
+
+        grid.update(b, Vec2::new(-10.0, 5.0));
+        assert_eq!(near(&grid).len(), 2);
+
+        grid.update(a, Vec2::new(200.0, 0.0));
+        assert_eq!(near(&grid), vec![b]);
+
+        grid.remove(b);
+        assert!(near(&grid).is_empty());
+        assert_eq!(grid.cells.len(), 1);
+    }
+
+    #[test]
+    fn head_on_explosion() {
+        let mut world = World::new();
+        world.init_resource::<PixieGrid>();
+
+        let mut spawn = |color, a, b, x| {
+            let pixie = Pixie {
+                flavor: PixieFlavor { color, net: 0 },
+                path: vec![crate::fixtures::seg(a, b, 1)].into(),
+                ..default()
+            };
+            world.spawn((pixie, Transform::from_xyz(x, 0.0, 0.0))).id()
+        };
+        let east = spawn(0, (0.0, 0.0), (100.0, 0.0), 50.0);
+        let west = spawn(
+            1,
+            (100.0, 0.0),
+            (0.0, 0.0),
+            50.0 + PIXIE_EXPLOSION_DISTANCE / 2.0,
+        );
+
+        world.run_system_once(collide_pixies_system).unwrap();
 
-        emitter.remaining -= 1;
+        assert!(world.get::<Pixie>(east).unwrap().exploding);
+        assert!(world.get::<Pixie>(west).unwrap().exploding);
     }
 }
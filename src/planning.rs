@@ -0,0 +1,313 @@
+//! A planning aid. Hovering over a terminus shows how much it would cost to reach
+//! each terminus that collects its pixies, both as the crow flies and going around
+//! obstacles.
+//!
+//! Routing around obstacles means searching the whole grid, so costs are cached
+//! until the level changes.
+
+use std::collections::HashMap;
+
+use crate::{
+    layer, layer_multiplier,
+    level::{Level, Terminus},
+    playing_enter_system,
+    share::ARENA_HALF_SIZE,
+    sim::SimulationState,
+    GameState, Handles, MouseState, SelectedLevel, GRID_SIZE,
+};
+use bevy::{prelude::*, sprite::Anchor};
+use petgraph::{
+    algo::astar,
+    graph::{NodeIndex, UnGraph},
+    visit::EdgeRef,
+};
+
+/// Directions to step in from each grid point. The opposite directions are covered
+/// by the neighbors stepping back.
+const STEPS: [IVec2; 4] = [
+    IVec2::new(1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(1, 1),
+    IVec2::new(-1, 1),
+];
+
+pub struct PlanningPlugin;
+impl Plugin for PlanningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoutePlanner>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            reset_planner_system.after(playing_enter_system),
+        );
+        app.add_systems(
+            Update,
+            hover_terminus_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct PlanningText;
+
+/// Every road that could be drawn on a level, one grid step at a time.
+pub struct RouteGraph {
+    graph: UnGraph<(IVec2, u32), f32>,
+    nodes: HashMap<(IVec2, u32), NodeIndex>,
+    terminuses: Vec<IVec2>,
+    /// The cost of the cheapest layer, per grid step.
+    cheapest: f32,
+}
+
+impl RouteGraph {
+    pub fn new(level: &Level) -> Self {
+        let mut graph = UnGraph::default();
+        let mut nodes = HashMap::new();
+
+        let (w, h) = (ARENA_HALF_SIZE.0 as i32, ARENA_HALF_SIZE.1 as i32);
+
+        for layer in 1..=level.layers {
+            for x in -w..=w {
+                for y in -h..=h {
+                    let point = IVec2::new(x, y);
+                    nodes.insert((point, layer), graph.add_node((point, layer)));
+                }
+            }
+        }
+
+        for layer in 1..=level.layers {
+            let multiplier = layer_multiplier(layer);
+
+            for x in -w..=w {
+                for y in -h..=h {
+                    let a = IVec2::new(x, y);
+
+                    // Switching layers doesn't cost anything.
+                    if layer > 1 {
+                        graph.add_edge(nodes[&(a, layer - 1)], nodes[&(a, layer)], 0.0);
+                    }
+
+                    for step in STEPS {
+                        let b = a + step;
+                        let Some(node_b) = nodes.get(&(b, layer)) else {
+                            continue;
+                        };
+
+                        let (a_world, b_world) = (a.as_vec2() * GRID_SIZE, b.as_vec2() * GRID_SIZE);
+                        if level
+                            .obstacles
+                            .iter()
+                            .any(|o| o.blocks_segment(a_world, b_world, layer))
+                        {
+                            continue;
+                        }
+
+                        let cost = step.as_vec2().length() * multiplier;
+                        graph.add_edge(nodes[&(a, layer)], *node_b, cost);
+                    }
+                }
+            }
+        }
+
+        let terminuses = level
+            .terminuses
+            .iter()
+            .map(|t| grid_point(t.point))
+            .collect();
+
+        let cheapest = (1..=level.layers)
+            .map(layer_multiplier)
+            .fold(f32::INFINITY, f32::min);
+
+        Self {
+            graph,
+            nodes,
+            terminuses,
+            cheapest,
+        }
+    }
+
+    /// The cost of the cheapest route between two terminuses, or `None` if every
+    /// route is blocked.
+    pub fn min_cost(&self, from: IVec2, to: IVec2) -> Option<f32> {
+        let start = *self.nodes.get(&(from, 1))?;
+
+        // Roads can't pass through other terminuses on their way.
+        let forbidden = |node: NodeIndex| {
+            let point = self.graph[node].0;
+            point != from && point != to && self.terminuses.contains(&point)
+        };
+
+        let (cost, _) = astar(
+            &self.graph,
+            start,
+            |n| self.graph[n].0 == to,
+            |e| {
+                if forbidden(e.source()) || forbidden(e.target()) {
+                    f32::INFINITY
+                } else {
+                    *e.weight()
+                }
+            },
+            |n| octile_distance(self.graph[n].0, to) * self.cheapest,
+        )?;
+
+        cost.is_finite().then_some(cost)
+    }
+
+    /// The cost of a straight road between two terminuses, ignoring obstacles and
+    /// the road angles that are allowed.
+    pub fn straight_cost(&self, from: IVec2, to: IVec2) -> f32 {
+        from.as_vec2().distance(to.as_vec2()) * self.cheapest
+    }
+}
+
+#[derive(Resource, Default)]
+struct RoutePlanner {
+    graph: Option<RouteGraph>,
+    costs: HashMap<(IVec2, IVec2), Option<f32>>,
+    hovered: Option<IVec2>,
+}
+
+fn grid_point(point: Vec2) -> IVec2 {
+    (point / GRID_SIZE).round().as_ivec2()
+}
+
+/// The length of the shortest route between two grid points using only horizontal,
+/// vertical and diagonal steps.
+fn octile_distance(a: IVec2, b: IVec2) -> f32 {
+    let d = (a - b).abs();
+    let (long, short) = (d.max_element() as f32, d.min_element() as f32);
+    (long - short) + short * std::f32::consts::SQRT_2
+}
+
+fn reset_planner_system(mut planner: ResMut<RoutePlanner>) {
+    *planner = RoutePlanner::default();
+}
+
+fn hover_terminus_system(
+    mut commands: Commands,
+    mut planner: ResMut<RoutePlanner>,
+    mouse: Res<MouseState>,
+    sim_state: Res<SimulationState>,
+    levels: Res<Assets<Level>>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    q_terminus: Query<&Terminus>,
+    q_text: Query<Entity, With<PlanningText>>,
+) {
+    let hovered = q_terminus
+        .iter()
+        .find(|t| t.point == mouse.snapped && !t.emits.is_empty())
+        .filter(|_| *sim_state == SimulationState::NotStarted);
+
+    let hovered_point = hovered.map(|t| grid_point(t.point));
+    if planner.hovered == hovered_point {
+        return;
+    }
+    planner.hovered = hovered_point;
+
+    for entity in q_text.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(hovered) = hovered else {
+        return;
+    };
+
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    let planner = &mut *planner;
+    let graph = planner.graph.get_or_insert_with(|| RouteGraph::new(level));
+
+    let from = grid_point(hovered.point);
+    let mut lines = vec![];
+
+    for collector in q_terminus
+        .iter()
+        .filter(|t| t.collects.iter().any(|f| hovered.emits.contains(f)))
+    {
+        let to = grid_point(collector.point);
+
+        let route = *planner
+            .costs
+            .entry((from, to))
+            .or_insert_with(|| graph.min_cost(from, to));
+
+        let route = match route {
+            Some(cost) => format!("§{}", cost.ceil()),
+            None => "NO ROUTE".to_string(),
+        };
+
+        lines.push(format!(
+            "TO {},{} STRAIGHT §{} ROUTE {}",
+            to.x,
+            to.y,
+            graph.straight_cost(from, to).ceil(),
+            route
+        ));
+    }
+
+    if lines.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        Text2d::new(lines.join("\n")),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(crate::color::UI_WHITE),
+        Anchor::BottomLeft,
+        Transform::from_translation(
+            (hovered.point + Vec2::new(12., 12.)).extend(layer::ROAD_OVERLAY),
+        ),
+        PlanningText,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Obstacle;
+
+    fn level(obstacles: Vec<Obstacle>) -> Level {
+        let terminus = |x: f32| Terminus {
+            point: Vec2::new(x * GRID_SIZE, 0.0),
+            emits: Default::default(),
+            collects: Default::default(),
+            ..default()
+        };
+
+        Level {
+            terminuses: vec![terminus(-2.0), terminus(2.0)],
+            obstacles,
+            ..crate::fixtures::level(1)
+        }
+    }
+
+    #[test]
+    fn route_costs() {
+        let from = IVec2::new(-2, 0);
+        let to = IVec2::new(2, 0);
+        let multiplier = layer_multiplier(1);
+
+        let open = RouteGraph::new(&level(vec![]));
+        assert_eq!(open.min_cost(from, to), Some(4.0 * multiplier));
+        assert_eq!(open.straight_cost(from, to), 4.0 * multiplier);
+
+        // A wall across the direct route, with a gap just above and below it.
+        let wall = Obstacle::Rect(Vec2::new(-12.0, 60.0), Vec2::new(12.0, -60.0));
+        let blocked = RouteGraph::new(&level(vec![wall]));
+        let cost = blocked.min_cost(from, to).unwrap();
+        assert!((cost - 4.0 * std::f32::consts::SQRT_2 * multiplier).abs() < 0.001);
+    }
+}
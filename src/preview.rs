@@ -0,0 +1,159 @@
+//! A preview of where pixies will go, shown while editing the board. A few hollow
+//! pixies loop along each path that the pixies would take if they were released now.
+//! Toggled with P.
+
+use crate::{
+    controls::Action,
+    layer,
+    lines::travel,
+    pixie::{PIXIE_MAX_SPEED, PIXIE_RADIUS},
+    playing_enter_system,
+    save::InputMap,
+    sim::SimulationState,
+    theme::Theme,
+    DrawingInput, GameState, PathfindingState,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+/// Number of preview pixies spread along each path.
+const PREVIEW_PIXIES: usize = 3;
+/// Preview pixies move faster than real ones, so that long paths don't take ages.
+const PREVIEW_SPEED: f32 = PIXIE_MAX_SPEED * 2.0;
+
+pub struct PreviewPlugin;
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PathPreview>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            reset_preview_system.after(playing_enter_system),
+        );
+        app.add_systems(Update, preview_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            preview_system
+                .after(preview_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct PathPreview {
+    /// Whether the preview is shown. Toggled with P, and kept between levels.
+    enabled: bool,
+    time: f32,
+    /// Reusable pixie entities.
+    pool: Vec<Entity>,
+}
+
+#[derive(Component)]
+struct PreviewPixie;
+
+fn reset_preview_system(mut preview: ResMut<PathPreview>) {
+    // Entities from the previous level were despawned on the way out.
+    preview.pool.clear();
+    preview.time = 0.0;
+}
+
+fn preview_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut preview: ResMut<PathPreview>,
+) {
+    if input_map.just_pressed(Action::Preview, &keyboard_input) {
+        preview.enabled = !preview.enabled;
+    }
+}
+
+fn preview_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut preview: ResMut<PathPreview>,
+    pathfinding: Res<PathfindingState>,
+    sim_state: Res<SimulationState>,
+    theme: Res<Theme>,
+    mut q_pixies: Query<(&mut Transform, &mut Stroke, &mut Visibility), With<PreviewPixie>>,
+) {
+    let showing = preview.enabled && pathfinding.valid && *sim_state == SimulationState::NotStarted;
+
+    let mut pixies = vec![];
+
+    if showing {
+        preview.time += time.delta_secs();
+
+        for (flavor, _, path) in pathfinding.paths.iter() {
+            let Some(first) = path.first() else {
+                continue;
+            };
+
+            let length: f32 = path.iter().map(|s| s.points.0.distance(s.points.1)).sum();
+            if length <= 0.0 {
+                continue;
+            }
+
+            for i in 0..PREVIEW_PIXIES {
+                let offset = i as f32 / PREVIEW_PIXIES as f32 * length;
+                let distance = (preview.time * PREVIEW_SPEED + offset) % length;
+
+                let (position, index) = travel(first.points.0, distance, path);
+                let segment_layer = path[index.min(path.len() - 1)].layer;
+
+                pixies.push((
+                    position.extend(layer::PIXIE - segment_layer as f32),
+                    flavor.color,
+                ));
+            }
+        }
+    }
+
+    let shape = shapes::RegularPolygon {
+        sides: 6,
+        feature: shapes::RegularPolygonFeature::Radius(PIXIE_RADIUS),
+        ..shapes::RegularPolygon::default()
+    };
+
+    while preview.pool.len() < pixies.len() {
+        let (translation, color) = pixies[preview.pool.len()];
+
+        let entity = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shape),
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                Stroke::new(theme.pixie(color), 1.5),
+                PreviewPixie,
+            ))
+            .id();
+
+        preview.pool.push(entity);
+    }
+
+    for (i, entity) in preview.pool.iter().enumerate() {
+        let Ok((mut transform, mut stroke, mut visibility)) = q_pixies.get_mut(*entity) else {
+            continue;
+        };
+
+        let Some((translation, color)) = pixies.get(i) else {
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+            }
+            continue;
+        };
+
+        transform.translation = *translation;
+
+        let color = theme.pixie(*color).into();
+        if stroke.color != color {
+            stroke.color = color;
+        }
+
+        if *visibility != Visibility::Inherited {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
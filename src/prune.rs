@@ -0,0 +1,217 @@
+use crate::{
+    color, layer, level::Terminus, sim::SimulationState, spawn_road_segment, AfterUpdate,
+    GameState, PathfindingState, PointGraphNode, RoadGraph, RoadSegment, ScoreUi,
+    SegmentGraphNodes,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use petgraph::stable_graph::NodeIndex;
+
+pub struct PrunePlugin;
+impl Plugin for PrunePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PruneState>();
+
+        app.add_systems(
+            Update,
+            (
+                prune_button_system,
+                prune_preview_system,
+                draw_prune_preview_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+
+        app.add_systems(
+            AfterUpdate,
+            (prune_invalidate_system, prune_button_text_system)
+                .chain()
+                .in_set(ScoreUi),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct PruneButton;
+#[derive(Component)]
+struct PruneLine;
+
+#[derive(Resource, Default)]
+pub struct PruneState {
+    /// Segments that would be removed by pruning, shown while the button is hovered.
+    preview: Vec<(Vec2, Vec2)>,
+    /// Segments removed by the last prune, which can be restored until the road
+    /// graph is modified by something else.
+    undo: Vec<RoadSegment>,
+    ignore_graph_change: bool,
+}
+
+fn prune_button_system(
+    mut commands: Commands,
+    mut prune: ResMut<PruneState>,
+    mut graph: ResMut<RoadGraph>,
+    pathfinding: Res<PathfindingState>,
+    sim_state: Res<SimulationState>,
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<Button>, With<PruneButton>)>,
+    q_segments: Query<(Entity, &RoadSegment, &SegmentGraphNodes)>,
+    q_terminuses: Query<(&Terminus, &PointGraphNode)>,
+) {
+    if *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        if !prune.undo.is_empty() {
+            // Reconnect the restored segments to anything sharing an endpoint, the
+            // same way a saved solution is restored.
+
+            let mut connections: Vec<(Vec2, NodeIndex)> = q_terminuses
+                .iter()
+                .map(|(terminus, node)| (terminus.point, node.0))
+                .chain(q_segments.iter().flat_map(|(_, segment, nodes)| {
+                    [(segment.points.0, nodes.0), (segment.points.1, nodes.1)]
+                }))
+                .collect();
+
+            for seg in std::mem::take(&mut prune.undo) {
+                let (_, node_a, node_b) =
+                    spawn_road_segment(&mut commands, &mut graph, seg.clone());
+
+                for (point, node) in connections.iter() {
+                    if *point == seg.points.0 {
+                        graph.graph.add_edge(*node, node_a, 0.0);
+                    }
+
+                    if *point == seg.points.1 {
+                        graph.graph.add_edge(*node, node_b, 0.0);
+                    }
+                }
+
+                connections.push((seg.points.0, node_a));
+                connections.push((seg.points.1, node_b));
+            }
+
+            prune.ignore_graph_change = true;
+            continue;
+        }
+
+        if !pathfinding.valid {
+            continue;
+        }
+
+        for (entity, segment, nodes) in q_segments
+            .iter()
+            .filter(|(entity, _, _)| !pathfinding.used_segments.contains(entity))
+        {
+            prune.undo.push(segment.clone());
+
+            graph.graph.remove_node(nodes.0);
+            graph.graph.remove_node(nodes.1);
+            commands.entity(entity).despawn_recursive();
+        }
+
+        if !prune.undo.is_empty() {
+            prune.ignore_graph_change = true;
+        }
+    }
+}
+
+fn prune_preview_system(
+    mut prune: ResMut<PruneState>,
+    pathfinding: Res<PathfindingState>,
+    sim_state: Res<SimulationState>,
+    q_interaction: Query<&Interaction, With<PruneButton>>,
+    q_segments: Query<(Entity, &RoadSegment)>,
+) {
+    let hovered = q_interaction.iter().any(|i| *i != Interaction::None);
+
+    let preview = if hovered
+        && prune.undo.is_empty()
+        && pathfinding.valid
+        && *sim_state == SimulationState::NotStarted
+    {
+        q_segments
+            .iter()
+            .filter(|(entity, _)| !pathfinding.used_segments.contains(entity))
+            .map(|(_, segment)| segment.points)
+            .collect()
+    } else {
+        vec![]
+    };
+
+    if prune.preview != preview {
+        prune.preview = preview;
+    }
+}
+
+fn draw_prune_preview_system(
+    mut commands: Commands,
+    prune: Res<PruneState>,
+    q_lines: Query<Entity, With<PruneLine>>,
+) {
+    if !prune.is_changed() {
+        return;
+    }
+
+    for entity in q_lines.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    for (a, b) in prune.preview.iter() {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Line(*a, *b)),
+                transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                ..default()
+            },
+            Stroke::new(bevy::color::palettes::css::RED, 2.0),
+            PruneLine,
+        ));
+    }
+}
+
+fn prune_invalidate_system(graph: Res<RoadGraph>, mut prune: ResMut<PruneState>) {
+    if !graph.is_changed() {
+        return;
+    }
+
+    if prune.ignore_graph_change {
+        prune.ignore_graph_change = false;
+        return;
+    }
+
+    // The road graph was modified by some other means, so restoring the pruned
+    // segments may no longer produce a valid layout.
+    if !prune.undo.is_empty() {
+        prune.undo.clear();
+    }
+}
+
+fn prune_button_text_system(
+    prune: Res<PruneState>,
+    pathfinding: Res<PathfindingState>,
+    q_button: Query<&Children, With<PruneButton>>,
+    mut q_text: Query<(&mut Text, &mut TextColor)>,
+) {
+    if !prune.is_changed() && !pathfinding.is_changed() {
+        return;
+    }
+
+    for children in q_button.iter() {
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some((mut text, mut color)) = iter.fetch_next() {
+            if prune.undo.is_empty() {
+                text.0 = "PRUNE".to_string();
+                color.0 = if pathfinding.valid {
+                    color::UI_BUTTON_TEXT
+                } else {
+                    color::UI_GREY_RED
+                };
+            } else {
+                text.0 = "UNDO".to_string();
+                color.0 = color::UI_BUTTON_TEXT;
+            }
+        }
+    }
+}
@@ -0,0 +1,453 @@
+//! Records where every pixie was during a run, so that the run can be watched again
+//! from the score dialog.
+
+use crate::{
+    color,
+    pixie::{Pixie, PIXIE_RADIUS},
+    sim::{
+        update_sim_state_system, SimulationSchedule, SimulationState, SimulationSteps,
+        SIMULATION_TIMESTEP,
+    },
+    theme::Theme,
+    GameState, Handles, PlayAreaNode, ScoreDialog, SelectedLevel, BOTTOM_BAR_HEIGHT,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+/// Number of simulation ticks between recorded frames.
+const REPLAY_INTERVAL: u32 = 2;
+/// Number of cells in the scrubbing bar.
+const SCRUB_CELLS: usize = 40;
+const SKIP_SECONDS: f32 = 5.0;
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecording>();
+        app.init_resource::<ReplayPlayback>();
+
+        app.add_systems(
+            SimulationSchedule,
+            record_system.after(update_sim_state_system),
+        );
+        app.add_systems(
+            Update,
+            (
+                replay_button_system,
+                replay_controls_system,
+                replay_playback_system,
+                update_replay_controls_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ReplayRecording {
+    /// The level that was being played.
+    pub level: u32,
+    frames: Vec<ReplayFrame>,
+}
+
+pub struct ReplayFrame {
+    step: u32,
+    /// The position and color of every pixie.
+    pub pixies: Vec<(Vec3, u32)>,
+}
+
+impl ReplayRecording {
+    pub fn duration(&self) -> f32 {
+        self.frames
+            .last()
+            .map_or(0.0, |f| f.step as f32 * SIMULATION_TIMESTEP)
+    }
+
+    /// The last frame recorded at or before `time`.
+    pub fn frame_at(&self, time: f32) -> Option<&ReplayFrame> {
+        let index = self
+            .frames
+            .partition_point(|f| f.step as f32 * SIMULATION_TIMESTEP <= time);
+
+        self.frames.get(index.saturating_sub(1))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ReplayPlayback {
+    active: bool,
+    playing: bool,
+    time: f32,
+    /// Reusable pixie entities for displaying frames.
+    pool: Vec<Entity>,
+}
+
+#[derive(Component)]
+pub struct ReplayButton;
+#[derive(Component)]
+struct ReplayControls;
+#[derive(Component)]
+struct ReplayPixie;
+#[derive(Component)]
+struct ReplayTimeText;
+#[derive(Component)]
+struct ReplayScrubCell(usize);
+#[derive(Component)]
+enum ReplayControlButton {
+    PlayPause,
+    Back,
+    Forward,
+    Close,
+}
+
+fn record_system(
+    sim_state: Res<SimulationState>,
+    steps: Res<SimulationSteps>,
+    selected_level: Res<SelectedLevel>,
+    mut recording: ResMut<ReplayRecording>,
+    q_pixies: Query<(&Pixie, &Transform)>,
+) {
+    if *sim_state == SimulationState::NotStarted {
+        return;
+    }
+
+    let step = steps.step();
+
+    // A new run has started, or this one was rewound.
+    if recording.frames.last().is_some_and(|f| f.step >= step) {
+        recording.frames.retain(|f| f.step < step);
+    }
+
+    recording.level = selected_level.0;
+
+    if step % REPLAY_INTERVAL != 0 && *sim_state == SimulationState::Running {
+        return;
+    }
+
+    recording.frames.push(ReplayFrame {
+        step,
+        pixies: q_pixies
+            .iter()
+            .map(|(pixie, transform)| (transform.translation, pixie.flavor.color))
+            .collect(),
+    });
+}
+
+fn replay_button_system(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    recording: Res<ReplayRecording>,
+    handles: Res<Handles>,
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<ReplayButton>)>,
+    mut q_dialog: Query<&mut Node, With<ScoreDialog>>,
+    mut q_play_area: Query<&mut BackgroundColor, With<PlayAreaNode>>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        if playback.active || recording.frames.is_empty() {
+            continue;
+        }
+
+        playback.active = true;
+        playback.playing = true;
+        playback.time = 0.0;
+
+        for mut node in q_dialog.iter_mut() {
+            node.display = Display::None;
+        }
+        for mut color in q_play_area.iter_mut() {
+            *color = Color::NONE.into();
+        }
+
+        spawn_replay_controls(&mut commands, &handles);
+    }
+}
+
+fn replay_controls_system(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    recording: Res<ReplayRecording>,
+    sim_state: Res<SimulationState>,
+    q_buttons: Query<(&Interaction, &ReplayControlButton), Changed<Interaction>>,
+    q_cells: Query<(&Interaction, &ReplayScrubCell), Changed<Interaction>>,
+    q_controls: Query<Entity, With<ReplayControls>>,
+    mut q_dialog: Query<&mut Node, With<ScoreDialog>>,
+    mut q_play_area: Query<&mut BackgroundColor, With<PlayAreaNode>>,
+) {
+    if !playback.active {
+        return;
+    }
+
+    let duration = recording.duration();
+    let mut close = *sim_state != SimulationState::Finished;
+
+    for (_, button) in q_buttons
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        match button {
+            ReplayControlButton::PlayPause => {
+                // Start over when playing from the end.
+                if !playback.playing && playback.time >= duration {
+                    playback.time = 0.0;
+                }
+                playback.playing = !playback.playing;
+            }
+            ReplayControlButton::Back => {
+                playback.time = (playback.time - SKIP_SECONDS).max(0.0);
+            }
+            ReplayControlButton::Forward => {
+                playback.time = (playback.time + SKIP_SECONDS).min(duration);
+            }
+            ReplayControlButton::Close => close = true,
+        }
+    }
+
+    for (_, cell) in q_cells.iter().filter(|(i, _)| **i == Interaction::Pressed) {
+        playback.time = cell.0 as f32 / SCRUB_CELLS as f32 * duration;
+    }
+
+    if !close {
+        return;
+    }
+
+    for entity in q_controls.iter().chain(playback.pool.iter().copied()) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    *playback = ReplayPlayback::default();
+
+    if *sim_state != SimulationState::Finished {
+        return;
+    }
+
+    for mut node in q_dialog.iter_mut() {
+        node.display = Display::Flex;
+    }
+    for mut color in q_play_area.iter_mut() {
+        *color = color::OVERLAY.into();
+    }
+}
+
+fn replay_playback_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut playback: ResMut<ReplayPlayback>,
+    recording: Res<ReplayRecording>,
+    theme: Res<Theme>,
+    mut q_pixies: Query<(&mut Transform, &mut Fill, &mut Visibility), With<ReplayPixie>>,
+) {
+    if !playback.active {
+        return;
+    }
+
+    if playback.playing {
+        let duration = recording.duration();
+
+        playback.time += time.delta_secs();
+        if playback.time >= duration {
+            playback.time = duration;
+            playback.playing = false;
+        }
+    }
+
+    let Some(frame) = recording.frame_at(playback.time) else {
+        return;
+    };
+
+    let shape = shapes::RegularPolygon {
+        sides: 6,
+        feature: shapes::RegularPolygonFeature::Radius(PIXIE_RADIUS),
+        ..shapes::RegularPolygon::default()
+    };
+
+    while playback.pool.len() < frame.pixies.len() {
+        let (translation, color) = frame.pixies[playback.pool.len()];
+
+        let entity = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shape),
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                Fill::color(theme.pixie(color)),
+                ReplayPixie,
+            ))
+            .id();
+
+        playback.pool.push(entity);
+    }
+
+    for (i, entity) in playback.pool.iter().enumerate() {
+        let Ok((mut transform, mut fill, mut visibility)) = q_pixies.get_mut(*entity) else {
+            continue;
+        };
+
+        if let Some((translation, color)) = frame.pixies.get(i) {
+            transform.translation = *translation;
+            fill.color = theme.pixie(*color).into();
+            *visibility = Visibility::Visible;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+fn update_replay_controls_system(
+    playback: Res<ReplayPlayback>,
+    recording: Res<ReplayRecording>,
+    mut q_text: Query<&mut Text, With<ReplayTimeText>>,
+    mut q_cells: Query<(&ReplayScrubCell, &mut BackgroundColor)>,
+    q_buttons: Query<(&ReplayControlButton, &Children)>,
+    mut q_button_text: Query<&mut Text, Without<ReplayTimeText>>,
+) {
+    if !playback.active || !playback.is_changed() {
+        return;
+    }
+
+    let duration = recording.duration();
+
+    for mut text in q_text.iter_mut() {
+        text.0 = format!("ŧ{:.1} / ŧ{:.1}", playback.time, duration);
+    }
+
+    let progress = if duration > 0.0 {
+        playback.time / duration
+    } else {
+        0.0
+    };
+
+    for (cell, mut color) in q_cells.iter_mut() {
+        *color = if (cell.0 as f32 + 0.5) / SCRUB_CELLS as f32 <= progress {
+            color::FINISHED_ROAD[1].into()
+        } else {
+            color::UI_NORMAL_BUTTON.into()
+        };
+    }
+
+    for (button, children) in q_buttons.iter() {
+        if !matches!(button, ReplayControlButton::PlayPause) {
+            continue;
+        }
+
+        let mut iter = q_button_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0 = if playback.playing { "PAUSE" } else { "PLAY" }.to_string();
+        }
+    }
+}
+
+fn spawn_replay_controls(commands: &mut Commands, handles: &Handles) {
+    let text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 18.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(BOTTOM_BAR_HEIGHT + 10.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ReplayControls,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::all(Val::Px(10.)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(6.),
+                        ..default()
+                    },
+                    BackgroundColor(color::DIALOG_BACKGROUND.with_alpha(0.8)),
+                ))
+                .with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(1.),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for i in 0..SCRUB_CELLS {
+                                parent.spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(10.),
+                                        height: Val::Px(16.),
+                                        ..default()
+                                    },
+                                    BackgroundColor(color::UI_NORMAL_BUTTON),
+                                    ReplayScrubCell(i),
+                                ));
+                            }
+                        });
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (label, button) in [
+                                (format!("-{SKIP_SECONDS}S"), ReplayControlButton::Back),
+                                ("PAUSE".to_string(), ReplayControlButton::PlayPause),
+                                (format!("+{SKIP_SECONDS}S"), ReplayControlButton::Forward),
+                            ] {
+                                spawn_control_button(parent, label, button, &text_font);
+                            }
+
+                            parent.spawn((
+                                Text::default(),
+                                text_font.clone(),
+                                TextColor(color::UI_WHITE),
+                                ReplayTimeText,
+                            ));
+
+                            spawn_control_button(
+                                parent,
+                                "CLOSE".to_string(),
+                                ReplayControlButton::Close,
+                                &text_font,
+                            );
+                        });
+                });
+        });
+}
+
+fn spawn_control_button(
+    parent: &mut ChildBuilder,
+    label: String,
+    button: ReplayControlButton,
+    font: &TextFont,
+) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(70.),
+                height: Val::Px(30.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(color::UI_NORMAL_BUTTON),
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                font.clone(),
+                TextColor(color::UI_BUTTON_TEXT),
+            ));
+        });
+}
@@ -0,0 +1,332 @@
+use crate::{
+    color, drawing_mouse_click_system, hud::Hud, layer, radio_button::RadioButton,
+    segment_near_point, sim::SimulationState, Collider, ColliderLayer, DrawingInteraction,
+    DrawingMode, DrawingMouseMovement, DrawingState, LayerButton, LineDrawingState, MainCamera,
+    MouseState, RoadGraph, RoadSegment, SegmentGraphNodes, NET_RIPPING_HIT_RADIUS,
+};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use itertools::Itertools;
+use petgraph::stable_graph::NodeIndex;
+
+pub struct ReroutePlugin;
+impl Plugin for ReroutePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RerouteState>();
+
+        app.add_systems(
+            Update,
+            reroute_mouse_movement_system.in_set(DrawingMouseMovement),
+        );
+        app.add_systems(
+            Update,
+            (
+                reroute_mouse_click_system.after(drawing_mouse_click_system),
+                reroute_ghost_system,
+                draw_reroute_system,
+            )
+                .chain()
+                .in_set(DrawingInteraction),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct RerouteButton;
+#[derive(Component)]
+struct RerouteLine;
+
+#[derive(Resource, Default)]
+pub struct RerouteState {
+    /// The route under the cursor, which will be ripped when clicked.
+    route: Option<Route>,
+    /// The segments of the route that was ripped, shown while its replacement is drawn.
+    ghost: Vec<(Vec2, Vec2)>,
+    /// The far end of the ripped route, where the replacement should connect.
+    target: Option<Vec2>,
+}
+
+/// A chain of road segments between two junctions, terminuses, or dead ends.
+#[derive(Clone, Debug, PartialEq)]
+struct Route {
+    entities: Vec<Entity>,
+    nodes: Vec<NodeIndex>,
+    segments: Vec<(Vec2, Vec2)>,
+    ends: (Vec2, Vec2),
+}
+
+fn reroute_mouse_movement_system(
+    drawing_state: Res<DrawingState>,
+    mouse: Res<MouseState>,
+    mut reroute: ResMut<RerouteState>,
+    sim_state: Res<SimulationState>,
+    graph: Res<RoadGraph>,
+    q_colliders: Query<(&Parent, &Collider, &ColliderLayer)>,
+    q_segments: Query<(&RoadSegment, &SegmentGraphNodes)>,
+    q_projection: Query<&OrthographicProjection, With<MainCamera>>,
+) {
+    if !matches!(drawing_state.mode, DrawingMode::Rerouting)
+        || *sim_state != SimulationState::NotStarted
+    {
+        if reroute.route.is_some() {
+            reroute.route = None;
+        }
+        return;
+    }
+
+    if !mouse.is_changed() && !drawing_state.is_changed() && !graph.is_changed() {
+        return;
+    }
+
+    let scale = q_projection.get_single().map_or(1.0, |p| p.scale);
+
+    let route = segment_near_point(mouse.position, NET_RIPPING_HIT_RADIUS * scale, &q_colliders)
+        .and_then(|entity| find_route(entity, &graph, &q_segments));
+
+    if reroute.route != route {
+        reroute.route = route;
+    }
+}
+
+fn find_route(
+    start: Entity,
+    graph: &RoadGraph,
+    q_segments: &Query<(&RoadSegment, &SegmentGraphNodes)>,
+) -> Option<Route> {
+    let (segment, nodes) = q_segments.get(start).ok()?;
+
+    let mut route = Route {
+        entities: vec![start],
+        nodes: vec![nodes.0, nodes.1],
+        segments: vec![segment.points],
+        ends: segment.points,
+    };
+
+    route.ends.0 = extend_route(
+        start,
+        nodes.0,
+        segment.points.0,
+        graph,
+        q_segments,
+        &mut route,
+    );
+    route.ends.1 = extend_route(
+        start,
+        nodes.1,
+        segment.points.1,
+        graph,
+        q_segments,
+        &mut route,
+    );
+
+    Some(route)
+}
+
+/// Walks away from `entity` through its `node` at `point`, adding segments to `route`
+/// until reaching a junction, a terminus, or a dead end. Returns the point where the
+/// walk stopped.
+fn extend_route(
+    mut entity: Entity,
+    mut node: NodeIndex,
+    mut point: Vec2,
+    graph: &RoadGraph,
+    q_segments: &Query<(&RoadSegment, &SegmentGraphNodes)>,
+    route: &mut Route,
+) -> Vec2 {
+    loop {
+        let Some(next) = only_neighbor(graph, node, entity) else {
+            return point;
+        };
+
+        let Some(next_entity) = graph.graph.node_weight(next).copied() else {
+            return point;
+        };
+
+        if route.entities.contains(&next_entity) {
+            return point;
+        }
+
+        // Terminuses aren't segments, so the route ends there.
+        let Ok((segment, nodes)) = q_segments.get(next_entity) else {
+            return point;
+        };
+
+        if only_neighbor(graph, next, next_entity) != Some(node) {
+            return point;
+        }
+
+        let (out_node, out_point) = if next == nodes.0 {
+            (nodes.1, segment.points.1)
+        } else {
+            (nodes.0, segment.points.0)
+        };
+
+        route.entities.push(next_entity);
+        route.nodes.extend([nodes.0, nodes.1]);
+        route.segments.push(segment.points);
+
+        entity = next_entity;
+        node = out_node;
+        point = out_point;
+    }
+}
+
+/// Returns the single node connected to `node` that belongs to something other
+/// than `owner`, if there is exactly one.
+fn only_neighbor(graph: &RoadGraph, node: NodeIndex, owner: Entity) -> Option<NodeIndex> {
+    graph
+        .graph
+        .neighbors(node)
+        .filter(|n| graph.graph.node_weight(*n) != Some(&owner))
+        .unique()
+        .exactly_one()
+        .ok()
+}
+
+fn reroute_mouse_click_system(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mouse: Res<MouseState>,
+    hud: Res<Hud>,
+    mut drawing_state: ResMut<DrawingState>,
+    mut line_state: ResMut<LineDrawingState>,
+    mut reroute: ResMut<RerouteState>,
+    sim_state: Res<SimulationState>,
+    mut graph: ResMut<RoadGraph>,
+    q_window: Query<&Window>,
+    q_layer_button: Query<(Entity, &LayerButton)>,
+    mut q_radio_button: Query<&mut RadioButton>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    if mouse.window_position.y > window.resolution.height() - hud.bar_height() {
+        return;
+    }
+
+    if !matches!(drawing_state.mode, DrawingMode::Rerouting) {
+        return;
+    }
+
+    if *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(route) = reroute.route.take() else {
+        return;
+    };
+
+    for entity in route.entities.iter() {
+        commands.entity(*entity).despawn_recursive();
+    }
+    for node in route.nodes.iter() {
+        graph.graph.remove_node(*node);
+    }
+
+    // Start drawing the replacement from whichever end of the old route is closer
+    // to the cursor.
+
+    let (start, target) =
+        if mouse.position.distance(route.ends.0) <= mouse.position.distance(route.ends.1) {
+            (route.ends.0, route.ends.1)
+        } else {
+            (route.ends.1, route.ends.0)
+        };
+
+    reroute.ghost = route.segments;
+    reroute.target = Some(target);
+
+    drawing_state.mode = DrawingMode::LineDrawing;
+    line_state.drawing = true;
+    line_state.start = start;
+    line_state.end = start;
+    line_state.segments = vec![];
+    line_state.adds = vec![];
+
+    for (entity, _) in q_layer_button
+        .iter()
+        .filter(|(_, layer_button)| layer_button.0 == line_state.layer)
+    {
+        if let Ok(mut radio) = q_radio_button.get_mut(entity) {
+            radio.selected = true;
+        }
+    }
+}
+
+/// Forgets the ghost of the ripped route once its replacement is finished or abandoned.
+fn reroute_ghost_system(
+    drawing_state: Res<DrawingState>,
+    line_state: Res<LineDrawingState>,
+    mut reroute: ResMut<RerouteState>,
+) {
+    if reroute.ghost.is_empty() {
+        return;
+    }
+
+    if matches!(drawing_state.mode, DrawingMode::LineDrawing) && line_state.drawing {
+        return;
+    }
+
+    reroute.ghost.clear();
+    reroute.target = None;
+}
+
+fn draw_reroute_system(
+    mut commands: Commands,
+    reroute: Res<RerouteState>,
+    q_lines: Query<Entity, With<RerouteLine>>,
+) {
+    if !reroute.is_changed() {
+        return;
+    }
+
+    for entity in q_lines.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if let Some(route) = reroute.route.as_ref() {
+        for (a, b) in route.segments.iter() {
+            commands.spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Line(*a, *b)),
+                    transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                    ..default()
+                },
+                Stroke::new(bevy::color::palettes::css::ORANGE, 2.0),
+                RerouteLine,
+            ));
+        }
+    }
+
+    for (a, b) in reroute.ghost.iter() {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Line(*a, *b)),
+                transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+                ..default()
+            },
+            Stroke::new(color::UI_WHITE.with_alpha(0.2), 2.0),
+            RerouteLine,
+        ));
+    }
+
+    if let Some(target) = reroute.target {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Circle {
+                    radius: 8.0,
+                    ..default()
+                }),
+                transform: Transform::from_translation(target.extend(layer::ROAD_OVERLAY)),
+                ..default()
+            },
+            Stroke::new(color::UI_WHITE, 2.0),
+            RerouteLine,
+        ));
+    }
+}
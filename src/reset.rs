@@ -0,0 +1,206 @@
+//! Asking before the reset button throws away a layout. Layouts with only a few roads
+//! are reset straight away, but anything bigger needs confirming, either with the
+//! button in the dialog or by pressing the reset key again, and cancel backs out.
+
+use crate::{
+    color,
+    controls::{Action, ActionPressed},
+    pause::{pause_key_system, spawn_menu_button},
+    save::InputMap,
+    sim::SimulationState,
+    DrawingInput, DrawingInteraction, DrawingMouseMovement, GameState, Handles, ResetButton,
+    RoadSegment,
+};
+use bevy::{prelude::*, ui::FocusPolicy};
+
+/// Layouts with more roads than this need confirming before they're reset.
+const RESET_CONFIRM_SEGMENTS: usize = 5;
+
+pub struct ResetPlugin;
+impl Plugin for ResetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResetConfirm>();
+        app.add_event::<ResetLayout>();
+
+        app.add_systems(OnEnter(GameState::Playing), close_reset_confirm_system);
+        app.add_systems(
+            Update,
+            reset_confirm_key_system
+                .before(pause_key_system)
+                .before(DrawingInput)
+                .run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(
+            Update,
+            (
+                reset_request_system,
+                reset_confirm_button_system,
+                reset_confirm_dialog_system,
+            )
+                .chain()
+                .after(DrawingInput)
+                .run_if(in_state(GameState::Playing)),
+        );
+
+        // Keep the board still while the dialog is up.
+        app.configure_sets(
+            Update,
+            (DrawingInput, DrawingMouseMovement, DrawingInteraction).run_if(not_confirming),
+        );
+    }
+}
+
+/// Clears every road and pixie from the board.
+#[derive(Event)]
+pub struct ResetLayout;
+
+#[derive(Resource, Default)]
+struct ResetConfirm {
+    /// How many roads will be lost, while the dialog is up.
+    segments: Option<usize>,
+}
+
+#[derive(Component)]
+struct ResetConfirmDialog;
+#[derive(Component)]
+struct ConfirmResetButton;
+#[derive(Component)]
+struct CancelResetButton;
+
+fn not_confirming(confirm: Res<ResetConfirm>) -> bool {
+    confirm.segments.is_none()
+}
+
+fn close_reset_confirm_system(mut confirm: ResMut<ResetConfirm>) {
+    confirm.segments = None;
+}
+
+/// The reset key confirms, and cancel backs out. Either way the key is used up here,
+/// so that it doesn't also pause the game or ask again.
+fn reset_confirm_key_system(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut confirm: ResMut<ResetConfirm>,
+    mut resets: EventWriter<ResetLayout>,
+) {
+    if confirm.segments.is_none() {
+        return;
+    }
+
+    if input_map.just_pressed(Action::Reset, &keyboard_input) {
+        confirm.segments = None;
+        resets.send(ResetLayout);
+        keyboard_input.clear_just_pressed(input_map.key(Action::Reset));
+    } else if input_map.just_pressed(Action::Cancel, &keyboard_input) {
+        confirm.segments = None;
+        keyboard_input.clear_just_pressed(input_map.key(Action::Cancel));
+    }
+}
+
+fn reset_request_system(
+    mut actions: EventReader<ActionPressed>,
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<Button>, With<ResetButton>)>,
+    sim_state: Res<SimulationState>,
+    mut confirm: ResMut<ResetConfirm>,
+    mut resets: EventWriter<ResetLayout>,
+    q_segments: Query<(), With<RoadSegment>>,
+) {
+    let pressed = actions.read().any(|a| a.0 == Action::Reset)
+        || q_interaction.iter().any(|i| *i == Interaction::Pressed);
+
+    // do nothing while score dialog is shown
+    if !pressed || *sim_state == SimulationState::Finished || confirm.segments.is_some() {
+        return;
+    }
+
+    let segments = q_segments.iter().count();
+    if segments > RESET_CONFIRM_SEGMENTS {
+        confirm.segments = Some(segments);
+    } else {
+        resets.send(ResetLayout);
+    }
+}
+
+fn reset_confirm_button_system(
+    mut confirm: ResMut<ResetConfirm>,
+    mut resets: EventWriter<ResetLayout>,
+    q_confirm: Query<&Interaction, (Changed<Interaction>, With<ConfirmResetButton>)>,
+    q_cancel: Query<&Interaction, (Changed<Interaction>, With<CancelResetButton>)>,
+) {
+    if q_confirm.iter().any(|i| *i == Interaction::Pressed) {
+        confirm.segments = None;
+        resets.send(ResetLayout);
+    }
+
+    if q_cancel.iter().any(|i| *i == Interaction::Pressed) {
+        confirm.segments = None;
+    }
+}
+
+fn reset_confirm_dialog_system(
+    mut commands: Commands,
+    confirm: Res<ResetConfirm>,
+    handles: Res<Handles>,
+    q_dialog: Query<Entity, With<ResetConfirmDialog>>,
+) {
+    if !confirm.is_changed() {
+        return;
+    }
+
+    for entity in q_dialog.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(segments) = confirm.segments else {
+        return;
+    };
+
+    let button_text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 25.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(color::OVERLAY),
+            FocusPolicy::Block,
+            GlobalZIndex(1),
+            ResetConfirmDialog,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::all(Val::Px(20.0)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    BackgroundColor(color::DIALOG_BACKGROUND),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(format!("RESET ALL {segments} ROADS?")),
+                        TextFont {
+                            font: handles.fonts[0].clone(),
+                            font_size: 25.0,
+                            ..default()
+                        },
+                        TextColor(color::UI_WHITE),
+                    ));
+
+                    spawn_menu_button(parent, "RESET", ConfirmResetButton, &button_text_font);
+                    spawn_menu_button(parent, "KEEP ROADS", CancelResetButton, &button_text_font);
+                });
+        });
+}
@@ -0,0 +1,55 @@
+//! Leaving a screen and entering it again, to build it from scratch. Setting the state
+//! the game is already in doesn't run any `OnExit` or `OnEnter` systems, so the game
+//! passes through `GameState::Restarting` for a frame on the way back.
+
+use crate::{GameState, SelectedLevel};
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+pub struct RestartPlugin;
+impl Plugin for RestartPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RestartTarget>();
+        app.add_systems(OnEnter(GameState::Restarting), restarting_system);
+    }
+}
+
+/// Where to go after `GameState::Restarting`.
+#[derive(Resource, Default)]
+struct RestartTarget {
+    state: Option<GameState>,
+    level: Option<u32>,
+}
+
+#[derive(SystemParam)]
+pub struct Restart<'w> {
+    next_state: ResMut<'w, NextState<GameState>>,
+    target: ResMut<'w, RestartTarget>,
+}
+
+impl Restart<'_> {
+    /// Leaves the current state and enters `state`, even if it's the same one.
+    pub fn set(&mut self, state: GameState) {
+        self.target.state = Some(state);
+        self.next_state.set(GameState::Restarting);
+    }
+
+    /// Leaves the current state and plays `level`. `SelectedLevel` only changes once
+    /// the old level has been left, so that nothing on the way out is saved under the
+    /// new one.
+    pub fn play_level(&mut self, level: u32) {
+        self.target.level = Some(level);
+        self.set(GameState::Playing);
+    }
+}
+
+fn restarting_system(
+    mut target: ResMut<RestartTarget>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if let Some(level) = target.level.take() {
+        selected_level.0 = level;
+    }
+
+    next_state.set(target.state.take().unwrap_or(GameState::LevelSelect));
+}
@@ -0,0 +1,257 @@
+//! Picking up where the player left off. Roads are saved as they're drawn, but the
+//! level being played and the tool in hand are only noted every few seconds. If the
+//! game was closed in the middle of a level, whether on purpose, by a crash or by
+//! reloading the page, the level select screen offers to go straight back to it.
+
+use crate::{
+    bias::BiasButton, color, drag::DragButton, level::Level, level_select::LevelSelectScreen,
+    playing_enter_system, radio_button::RadioButton, reroute::RerouteButton, save::Session,
+    DrawingMode, DrawingState, GameState, Handles, LayerButton, LineDrawingState, NetRippingButton,
+    SelectedLevel,
+};
+use bevy::prelude::*;
+
+/// How often the session is noted, in seconds.
+const AUTOSAVE_SECS: f32 = 5.0;
+
+pub struct ResumePlugin;
+impl Plugin for ResumePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Resume>();
+
+        app.add_systems(OnEnter(GameState::LevelSelect), spawn_resume_prompt_system);
+        app.add_systems(
+            Update,
+            resume_button_system.run_if(in_state(GameState::LevelSelect)),
+        );
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            restore_session_system.after(playing_enter_system),
+        );
+        app.add_systems(
+            Update,
+            autosave_session_system.run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(OnExit(GameState::Playing), end_session_system);
+    }
+}
+
+#[derive(Resource, Default)]
+struct Resume {
+    /// Whether the prompt has had its chance. It's only offered once, at startup.
+    offered: bool,
+    /// Whether the level being entered should be set up the way the session left it.
+    restoring: bool,
+}
+
+#[derive(Component)]
+struct ResumePrompt;
+
+#[derive(Component, Clone, Copy)]
+enum ResumeButton {
+    Resume(u32),
+    Dismiss,
+}
+
+fn autosave_session_system(
+    time: Res<Time>,
+    mut timer: Local<Timer>,
+    mut session: ResMut<Session>,
+    line_state: Res<LineDrawingState>,
+    drawing_state: Res<DrawingState>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+) {
+    if timer.duration().is_zero() {
+        *timer = Timer::from_seconds(AUTOSAVE_SECS, TimerMode::Repeating);
+    }
+
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let current = Session {
+        level: handles.level_id(selected_level.0),
+        layer: line_state.layer,
+        arcs: line_state.arcs,
+        wide: line_state.wide,
+        tool: drawing_state.mode,
+    };
+
+    // Only when something has changed, because changing it writes the save file.
+    if *session != current {
+        *session = current;
+    }
+}
+
+fn end_session_system(mut session: ResMut<Session>) {
+    if !session.level.is_empty() {
+        session.level.clear();
+    }
+}
+
+fn spawn_resume_prompt_system(
+    mut commands: Commands,
+    mut resume: ResMut<Resume>,
+    session: Res<Session>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+) {
+    if resume.offered {
+        return;
+    }
+    resume.offered = true;
+
+    if session.level.is_empty() {
+        return;
+    }
+
+    let Some((number, level)) = (1..=handles.levels.len() as u32)
+        .filter(|n| handles.level_id(*n) == session.level)
+        .find_map(|n| levels.get(&handles.levels[n as usize - 1]).map(|l| (n, l)))
+    else {
+        return;
+    };
+
+    let font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 18.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.),
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                right: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            BackgroundColor(color::DIALOG_BACKGROUND),
+            GlobalZIndex(1),
+            LevelSelectScreen,
+            ResumePrompt,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("PICK UP WHERE YOU LEFT OFF IN {}?", level.name)),
+                font.clone(),
+                TextColor(color::UI_WHITE),
+            ));
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in [
+                        (ResumeButton::Resume(number), "RESUME"),
+                        (ResumeButton::Dismiss, "NO THANKS"),
+                    ] {
+                        parent
+                            .spawn((
+                                Button,
+                                Node {
+                                    padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                                    ..default()
+                                },
+                                BackgroundColor(color::UI_NORMAL_BUTTON),
+                                button,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(label),
+                                    font.clone(),
+                                    TextColor(color::UI_BUTTON_TEXT),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+fn resume_button_system(
+    mut commands: Commands,
+    mut resume: ResMut<Resume>,
+    mut session: ResMut<Session>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+    q_interaction: Query<(&Interaction, &ResumeButton), Changed<Interaction>>,
+    q_prompt: Query<Entity, With<ResumePrompt>>,
+) {
+    for (_, button) in q_interaction
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        match button {
+            ResumeButton::Resume(number) => {
+                selected_level.0 = *number;
+                resume.restoring = true;
+                next_state.set(GameState::Playing);
+            }
+            ResumeButton::Dismiss => {
+                session.level.clear();
+                for entity in q_prompt.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+fn restore_session_system(
+    mut resume: ResMut<Resume>,
+    session: Res<Session>,
+    mut line_state: ResMut<LineDrawingState>,
+    mut drawing_state: ResMut<DrawingState>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    mut q_buttons: Query<(
+        &mut RadioButton,
+        Option<&LayerButton>,
+        Has<NetRippingButton>,
+        Has<RerouteButton>,
+        Has<DragButton>,
+        Has<BiasButton>,
+    )>,
+) {
+    if !std::mem::take(&mut resume.restoring) {
+        return;
+    }
+
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    if (1..=level.layers).contains(&session.layer) {
+        line_state.set_layer(session.layer);
+    }
+    line_state.arcs = session.arcs;
+    line_state.wide = session.wide;
+    drawing_state.mode = session.tool;
+
+    for (mut radio, layer, rip, reroute, drag, bias) in q_buttons.iter_mut() {
+        let selected = match drawing_state.mode {
+            DrawingMode::LineDrawing => layer.is_some_and(|l| l.0 == line_state.layer),
+            DrawingMode::NetRipping => rip,
+            DrawingMode::Rerouting => reroute,
+            DrawingMode::Dragging => drag,
+            DrawingMode::Biasing => bias,
+        };
+
+        if selected {
+            radio.selected = true;
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Sandbox runs, for trying out junctions and watching traffic without keeping score.
+//! Emitters never run out of pixies, so a run goes on until it's stopped with the
+//! release button. Toggled with K before releasing, and off again on every new level.
+
+use crate::{
+    color, controls::Action, playing_enter_system, save::InputMap, sim::SimulationState,
+    DrawingInput, GameState, Handles, BOTTOM_BAR_HEIGHT,
+};
+use bevy::prelude::*;
+
+pub struct SandboxPlugin;
+impl Plugin for SandboxPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sandbox>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            (reset_sandbox_system, spawn_sandbox_label_system).after(playing_enter_system),
+        );
+        app.add_systems(Update, sandbox_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            sandbox_label_system
+                .after(sandbox_key_system)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Sandbox {
+    /// Whether the next run releases pixies forever and skips scoring.
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+struct SandboxLabel;
+
+fn reset_sandbox_system(mut sandbox: ResMut<Sandbox>) {
+    sandbox.enabled = false;
+}
+
+fn sandbox_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    sim_state: Res<SimulationState>,
+    mut sandbox: ResMut<Sandbox>,
+) {
+    if *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    if input_map.just_pressed(Action::Sandbox, &keyboard_input) {
+        sandbox.enabled = !sandbox.enabled;
+    }
+}
+
+fn spawn_sandbox_label_system(mut commands: Commands, handles: Res<Handles>) {
+    commands
+        .spawn((
+            Node {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(BOTTOM_BAR_HEIGHT + 10.),
+                right: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            BackgroundColor(color::DIALOG_BACKGROUND.with_alpha(0.8)),
+            SandboxLabel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("SANDBOX: ENDLESS PIXIES, NO SCORE"),
+                TextFont {
+                    font: handles.fonts[0].clone(),
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(color::UI_WHITE),
+            ));
+        });
+}
+
+fn sandbox_label_system(sandbox: Res<Sandbox>, mut q_label: Query<&mut Node, With<SandboxLabel>>) {
+    if !sandbox.is_changed() {
+        return;
+    }
+
+    for mut node in q_label.iter_mut() {
+        node.display = if sandbox.enabled {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
@@ -1,25 +1,432 @@
-use crate::RoadSegment;
+use crate::{controls::Action, level_meta::LevelMeta, DrawingMode, RoadSegment};
 
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
 use bevy_simple_prefs::{Prefs, PrefsPlugin};
+use serde::{de, Deserialize, Deserializer};
+use std::fmt;
 
+/// Parts missing from older saves start out empty.
 #[derive(Prefs, Reflect, Default)]
+#[reflect(Default)]
 pub struct SaveFile {
-    scores: BestScores,
-    solutions: Solutions,
+    pub scores: BestScores,
+    pub costs: BestCosts,
+    pub times: BestTimes,
+    pub history: RunHistory,
+    pub solutions: Solutions,
+    pub best_solutions: BestSolutions,
+    pub levels: LevelMetadata,
+    pub slots: SolutionSlots,
+    pub theme: ThemeChoice,
+    pub sfx_volume: SfxVolume,
+    pub statistics: Statistics,
+    pub input_map: InputMap,
+    pub settings: Settings,
+    pub session: Session,
 }
+
+impl SaveFile {
+    /// Combines progress from another save into this one, keeping the best of both.
+    /// Preferences like volume and keys stay as they are here.
+    pub fn merge(&mut self, other: SaveFile) {
+        for (id, score) in other.scores.0 {
+            let new = !self.scores.0.contains_key(&id);
+            let best = self.scores.0.entry(id.clone()).or_insert(score);
+
+            // The roads that got the best score go with it.
+            if new || score > *best {
+                if let Some(solution) = other.best_solutions.0.get(&id) {
+                    self.best_solutions.0.insert(id.clone(), solution.clone());
+                }
+            }
+
+            if score > *best {
+                *best = score;
+
+                // The best score's modifiers go with it.
+                if let Some(meta) = other.levels.0.get(&id) {
+                    self.levels.0.entry(id).or_default().modifiers = meta.modifiers.clone();
+                }
+            }
+        }
+
+        for (id, cost) in other.costs.0 {
+            let best = self.costs.0.entry(id).or_insert(cost);
+            *best = (*best).min(cost);
+        }
+
+        for (id, time) in other.times.0 {
+            let best = self.times.0.entry(id).or_insert(time);
+            *best = best.min(time);
+        }
+
+        for (id, runs) in other.history.0 {
+            self.history.0.entry(id).or_insert(runs);
+        }
+
+        for (id, solution) in other.solutions.0 {
+            self.solutions.0.entry(id).or_insert(solution);
+        }
+
+        for (id, other_meta) in other.levels.0 {
+            let Some(meta) = self.levels.0.get_mut(&id) else {
+                self.levels.0.insert(id, other_meta);
+                continue;
+            };
+
+            meta.badges.merge(other_meta.badges);
+            meta.attempts = meta.attempts.max(other_meta.attempts);
+            meta.last_played = meta.last_played.max(other_meta.last_played);
+        }
+
+        for (id, other_slots) in other.slots.0 {
+            let slots = self.slots.0.entry(id).or_default();
+            for slot in other_slots {
+                if !slots.iter().any(|s| s.name == slot.name) {
+                    slots.push(slot);
+                }
+            }
+        }
+
+        // Totals from each device overlap by however much was played before they
+        // diverged, so adding them up would count that twice.
+        let stats = &mut self.statistics;
+        stats.pixies_delivered = stats
+            .pixies_delivered
+            .max(other.statistics.pixies_delivered);
+        stats.road_length = stats.road_length.max(other.statistics.road_length);
+        stats.nets_ripped = stats.nets_ripped.max(other.statistics.nets_ripped);
+        for (id, time) in other.statistics.play_time {
+            let total = stats.play_time.entry(id).or_default();
+            *total = total.max(time);
+        }
+    }
+}
+
+/// Every part of the save file, as resources.
+#[derive(SystemParam)]
+pub struct SaveResources<'w> {
+    scores: ResMut<'w, BestScores>,
+    costs: ResMut<'w, BestCosts>,
+    times: ResMut<'w, BestTimes>,
+    history: ResMut<'w, RunHistory>,
+    solutions: ResMut<'w, Solutions>,
+    best_solutions: ResMut<'w, BestSolutions>,
+    levels: ResMut<'w, LevelMetadata>,
+    slots: ResMut<'w, SolutionSlots>,
+    theme: ResMut<'w, ThemeChoice>,
+    sfx_volume: ResMut<'w, SfxVolume>,
+    statistics: ResMut<'w, Statistics>,
+    input_map: ResMut<'w, InputMap>,
+    settings: ResMut<'w, Settings>,
+    session: ResMut<'w, Session>,
+}
+
+impl SaveResources<'_> {
+    pub fn save_file(&self) -> SaveFile {
+        SaveFile {
+            scores: self.scores.clone(),
+            costs: self.costs.clone(),
+            times: self.times.clone(),
+            history: self.history.clone(),
+            solutions: self.solutions.clone(),
+            best_solutions: self.best_solutions.clone(),
+            levels: self.levels.clone(),
+            slots: self.slots.clone(),
+            theme: self.theme.clone(),
+            sfx_volume: *self.sfx_volume,
+            statistics: self.statistics.clone(),
+            input_map: self.input_map.clone(),
+            settings: self.settings.clone(),
+            session: self.session.clone(),
+        }
+    }
+
+    /// Replaces every resource, which also gets them saved.
+    pub fn set(&mut self, save_file: SaveFile) {
+        *self.scores = save_file.scores;
+        *self.costs = save_file.costs;
+        *self.times = save_file.times;
+        *self.history = save_file.history;
+        *self.solutions = save_file.solutions;
+        *self.best_solutions = save_file.best_solutions;
+        *self.levels = save_file.levels;
+        *self.slots = save_file.slots;
+        *self.theme = save_file.theme;
+        *self.sfx_volume = save_file.sfx_volume;
+        *self.statistics = save_file.statistics;
+        *self.input_map = save_file.input_map;
+        *self.settings = save_file.settings;
+        *self.session = save_file.session;
+    }
+}
+/// Keyed by `Handles::level_id`. Saves from before there were user levels are keyed
+/// by level number, and are read in as the ids of those built-in levels.
 #[derive(Resource, Clone, Debug, Default, Reflect)]
-pub struct BestScores(pub HashMap<u32, u32>);
+#[reflect(Deserialize)]
+pub struct BestScores(pub HashMap<String, u32>);
+/// The lowest cost of any finished run, keyed like `BestScores`. It may not be from
+/// the run that got the best score.
 #[derive(Resource, Clone, Debug, Default, Reflect)]
-pub struct Solutions(pub HashMap<u32, Solution>);
-#[derive(Clone, Debug, Default, Reflect)]
+pub struct BestCosts(pub HashMap<String, u32>);
+/// The shortest time of any finished run in seconds, keyed like `BestScores`.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct BestTimes(pub HashMap<String, f32>);
+/// The best score, cost and time of each level.
+#[derive(SystemParam)]
+pub struct Records<'w> {
+    scores: Res<'w, BestScores>,
+    costs: Res<'w, BestCosts>,
+    times: Res<'w, BestTimes>,
+}
+
+impl Records<'_> {
+    /// The best score, cost and time of the level with the given id.
+    pub fn get(&self, id: &str) -> (Option<u32>, Option<u32>, Option<f32>) {
+        (
+            self.scores.0.get(id).copied(),
+            self.costs.0.get(id).copied(),
+            self.times.0.get(id).copied(),
+        )
+    }
+}
+/// The most recent finished runs of each level, oldest first, keyed like `BestScores`.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct RunHistory(pub HashMap<String, Vec<RunRecord>>);
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub struct RunRecord {
+    pub score: u32,
+    pub cost: u32,
+    /// Seconds of simulation time the run took.
+    pub elapsed: f32,
+    pub exploded: u32,
+}
+/// The roads the player is working on, keyed like `BestScores`.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Deserialize)]
+pub struct Solutions(pub HashMap<String, Solution>);
+/// The roads that got each score in `BestScores`, keyed the same way.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct BestSolutions(pub HashMap<String, Solution>);
+/// Extras like badges and attempt counts, keyed like `BestScores`.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct LevelMetadata(pub HashMap<String, LevelMeta>);
+/// Solutions that the player has saved under a name, in addition to the one they
+/// are working on in `Solutions`.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct SolutionSlots(pub HashMap<String, Vec<NamedSolution>>);
+/// The name of the chosen theme, or empty for the built-in one.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct ThemeChoice(pub String);
+#[derive(Resource, Clone, Copy, Debug, Reflect)]
+pub struct SfxVolume(pub f32);
+impl Default for SfxVolume {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+/// Running totals across every session. Attempts are kept in `LevelMetadata`.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct Statistics {
+    pub pixies_delivered: u64,
+    /// Total length of roads placed, in grid units.
+    pub road_length: f32,
+    pub nets_ripped: u32,
+    /// Seconds spent playing each level, keyed like `BestScores`.
+    pub play_time: HashMap<String, f32>,
+}
+/// Display options. Sound volume and keys are kept separately, because they were
+/// saved before there was a settings panel.
+#[derive(Resource, Clone, Debug, Reflect)]
+pub struct Settings {
+    pub ui_scale: f32,
+    /// Snaps things into place instead of sliding them around.
+    pub reduced_motion: bool,
+    pub antialiasing: bool,
+    /// Allows roads at 22.5 degree angles, rather than only multiples of 45.
+    #[reflect(default)]
+    pub fine_angles: bool,
+    /// Turns off effects that are slow in the browser.
+    #[reflect(default)]
+    pub low_graphics: bool,
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            reduced_motion: false,
+            antialiasing: true,
+            fine_angles: false,
+            low_graphics: false,
+        }
+    }
+}
+/// Keys that the player has bound to something other than the default.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+pub struct InputMap(pub HashMap<Action, KeyCode>);
+/// Where the player was when the game was last closed, so that they can pick up from
+/// there. Kept up to date every few seconds while playing.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+pub struct Session {
+    /// The level being played, or empty when not playing one.
+    pub level: String,
+    pub layer: u32,
+    pub arcs: bool,
+    pub wide: bool,
+    pub tool: DrawingMode,
+}
+#[derive(Clone, Debug, Default, Reflect, Deserialize)]
 pub struct Solution {
     pub segments: Vec<RoadSegment>,
+    /// The `layout_hash` of the level when these roads were laid out on it, or zero if
+    /// that isn't known.
+    #[reflect(default)]
+    #[serde(default)]
+    pub level_hash: u64,
+}
+#[derive(Clone, Debug, Default, Reflect)]
+pub struct NamedSolution {
+    pub name: String,
+    pub solution: Solution,
+}
+
+/// A level id as a map key, which older saves wrote as a level number.
+#[derive(PartialEq, Eq, Hash)]
+struct SavedLevelId(String);
+impl<'de> Deserialize<'de> for SavedLevelId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdVisitor;
+        impl de::Visitor<'_> for IdVisitor {
+            type Value = SavedLevelId;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a level id or level number")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(SavedLevelId(v.to_string()))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(SavedLevelId(v.to_string()))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(SavedLevelId(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(IdVisitor)
+    }
+}
+
+/// The contents of a map keyed by level, as either kind of save has it.
+#[derive(Deserialize)]
+struct SavedLevelMap<V>(std::collections::HashMap<SavedLevelId, V>);
+impl<V> SavedLevelMap<V> {
+    fn into_ids(self) -> HashMap<String, V> {
+        self.0.into_iter().map(|(id, v)| (id.0, v)).collect()
+    }
+}
+
+impl<'de> Deserialize<'de> for BestScores {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SavedLevelMap::deserialize(deserializer).map(|m| Self(m.into_ids()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Solutions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SavedLevelMap::deserialize(deserializer).map(|m| Self(m.into_ids()))
+    }
 }
 
 pub struct SavePlugin;
 impl Plugin for SavePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(PrefsPlugin::<SaveFile>::default());
+        app.register_type::<SaveFile>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bias::RouteBias;
+
+    #[test]
+    fn merge_keeps_best() {
+        let mut mine = SaveFile::default();
+        mine.scores.0.insert("1".to_string(), 500);
+        mine.scores.0.insert("2".to_string(), 300);
+        mine.sfx_volume = SfxVolume(0.2);
+
+        let mut theirs = SaveFile::default();
+        theirs.scores.0.insert("1".to_string(), 400);
+        theirs.scores.0.insert("2".to_string(), 700);
+        theirs.scores.0.insert("3".to_string(), 100);
+        theirs
+            .solutions
+            .0
+            .insert("3".to_string(), Solution::default());
+        theirs.sfx_volume = SfxVolume(0.9);
+
+        mine.costs.0.insert("1".to_string(), 20);
+        theirs.costs.0.insert("1".to_string(), 15);
+        mine.times.0.insert("1".to_string(), 4.0);
+        theirs.times.0.insert("1".to_string(), 6.5);
+        theirs.times.0.insert("2".to_string(), 3.0);
+
+        let road = |layer| Solution {
+            segments: vec![RoadSegment {
+                points: (Vec2::ZERO, Vec2::X),
+                layer,
+                wide: false,
+                bias: RouteBias::default(),
+            }],
+            ..default()
+        };
+        mine.best_solutions.0.insert("1".to_string(), road(1));
+        theirs.best_solutions.0.insert("1".to_string(), road(2));
+        theirs.best_solutions.0.insert("2".to_string(), road(2));
+
+        mine.merge(theirs);
+
+        assert_eq!(mine.scores.0["1"], 500);
+        assert_eq!(mine.scores.0["2"], 700);
+        assert_eq!(mine.scores.0["3"], 100);
+        assert!(mine.solutions.0.contains_key("3"));
+        assert_eq!(mine.best_solutions.0["1"].segments[0].layer, 1);
+        assert_eq!(mine.best_solutions.0["2"].segments[0].layer, 2);
+        assert_eq!(mine.costs.0["1"], 15);
+        assert_eq!(mine.times.0["1"], 4.0);
+        assert_eq!(mine.times.0["2"], 3.0);
+        assert_eq!(mine.sfx_volume.0, 0.2);
+    }
+
+    #[test]
+    fn loads_level_number_keys() {
+        let mut registry = bevy::reflect::TypeRegistry::default();
+        registry.register::<SaveFile>();
+
+        // From before there were user levels, when only these two were saved.
+        let old = r#"(
+            scores: ({3: 500}),
+            solutions: ({3: (segments: [(points: ((0.0, 0.0), (48.0, 0.0)), layer: 1)])}),
+        )"#;
+        let save_file = crate::backup::from_ron(old, &registry).unwrap();
+
+        assert_eq!(save_file.scores.0["3"], 500);
+        let segment = &save_file.solutions.0["3"].segments[0];
+        assert_eq!(segment.points, (Vec2::ZERO, Vec2::new(48.0, 0.0)));
+        assert_eq!(segment.layer, 1);
+        assert_eq!(save_file.solutions.0["3"].level_hash, 0);
+        assert_eq!(save_file.sfx_volume.0, SfxVolume::default().0);
+
+        // And what gets saved now still loads.
+        let new = crate::backup::to_ron(&save_file, &registry).unwrap();
+        let again = crate::backup::from_ron(&new, &registry).unwrap();
+        assert_eq!(again.scores.0["3"], 500);
+        assert_eq!(again.solutions.0["3"].segments.len(), 1);
     }
 }
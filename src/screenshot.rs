@@ -0,0 +1,282 @@
+//! Experimental: rebuilding a solution from a screenshot of the board, for layouts
+//! that were never exported. Dropping a PNG onto the window imports it into the level
+//! being played.
+//!
+//! The screenshot must be cropped to the outermost grid points. Each step between
+//! neighboring grid points is sampled in a few places, and counts as road if they're
+//! all the color of a finished road. Wide roads can't be told apart reliably, so they
+//! come back as regular ones.
+
+use crate::{
+    bias::RouteBias,
+    color,
+    level::Level,
+    restart::Restart,
+    save::{Solution, Solutions},
+    share::{validate, ShareStatus, ARENA_HALF_SIZE},
+    sim::SimulationState,
+    GameState, Handles, RoadSegment, SelectedLevel, GRID_SIZE,
+};
+use bevy::prelude::*;
+use itertools::Itertools;
+
+/// Directions to step in from each grid point.
+const STEPS: [IVec2; 4] = [
+    IVec2::new(1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(1, 1),
+    IVec2::new(-1, 1),
+];
+/// Where each step is sampled, as a fraction of its length. These stay clear of the
+/// ends, where terminuses and bridges are drawn, and of the middle, where diagonals
+/// cross.
+const SAMPLES: [f32; 4] = [0.35, 0.42, 0.58, 0.65];
+/// How far each color channel may be from a road's color.
+const TOLERANCE: f32 = 0.1;
+
+pub struct ScreenshotImportPlugin;
+impl Plugin for ScreenshotImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            import_screenshot_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// RGBA pixels, one byte per channel.
+pub struct Screenshot<'a> {
+    pub size: UVec2,
+    pub pixels: &'a [u8],
+}
+
+impl Screenshot<'_> {
+    fn pixel(&self, x: i64, y: i64) -> Option<[f32; 3]> {
+        if x < 0 || y < 0 || x >= self.size.x as i64 || y >= self.size.y as i64 {
+            return None;
+        }
+
+        let i = (y as usize * self.size.x as usize + x as usize) * 4;
+        let rgb = self.pixels.get(i..i + 3)?;
+        Some([rgb[0], rgb[1], rgb[2]].map(|c| c as f32 / 255.0))
+    }
+
+    /// Whether any pixel right around `position` is close to `color`. Thin roads are
+    /// antialiased, so the nearest pixel alone might be a blend with the background.
+    fn matches(&self, position: Vec2, color: [f32; 3]) -> bool {
+        let (x, y) = (position.x.round() as i64, position.y.round() as i64);
+
+        (-1..=1).cartesian_product(-1..=1).any(|(dx, dy)| {
+            self.pixel(x + dx, y + dy).is_some_and(|pixel| {
+                pixel
+                    .iter()
+                    .zip(color)
+                    .all(|(a, b)| (a - b).abs() <= TOLERANCE)
+            })
+        })
+    }
+}
+
+/// The position in a screenshot of the given size of a point in grid coordinates.
+fn pixel_position(size: UVec2, point: Vec2) -> Vec2 {
+    let half = Vec2::new(ARENA_HALF_SIZE.0 as f32, ARENA_HALF_SIZE.1 as f32);
+
+    // Image rows go down, but the grid goes up.
+    Vec2::new(
+        (point.x + half.x) / (half.x * 2.0) * (size.x - 1) as f32,
+        (half.y - point.y) / (half.y * 2.0) * (size.y - 1) as f32,
+    )
+}
+
+/// Finds the roads in `screenshot`. Roads are as long as possible, but are split at
+/// `stops` so that they don't run through terminuses.
+pub fn recognize(screenshot: &Screenshot, layers: u32, stops: &[IVec2]) -> Solution {
+    let (w, h) = (ARENA_HALF_SIZE.0 as i32, ARENA_HALF_SIZE.1 as i32);
+    let in_bounds = |p: IVec2| p.x.abs() <= w && p.y.abs() <= h;
+
+    let mut segments = vec![];
+
+    for layer in 1..=layers {
+        let Some(color) = color::FINISHED_ROAD.get(layer as usize - 1) else {
+            continue;
+        };
+        let color = color.to_srgba();
+        let color = [color.red, color.green, color.blue];
+
+        for step in STEPS {
+            let is_road = |a: IVec2| {
+                let b = a + step;
+                in_bounds(a)
+                    && in_bounds(b)
+                    && SAMPLES.iter().all(|t| {
+                        let point = a.as_vec2().lerp(b.as_vec2(), *t);
+                        screenshot.matches(pixel_position(screenshot.size, point), color)
+                    })
+            };
+
+            for x in -w..=w {
+                for y in -h..=h {
+                    let start = IVec2::new(x, y);
+
+                    // Only start at the beginning of a road, and follow it to the end.
+                    if !is_road(start) || (is_road(start - step) && !stops.contains(&start)) {
+                        continue;
+                    }
+
+                    let mut end = start + step;
+                    while !stops.contains(&end) && is_road(end) {
+                        end += step;
+                    }
+
+                    segments.push(RoadSegment {
+                        points: (start.as_vec2() * GRID_SIZE, end.as_vec2() * GRID_SIZE),
+                        layer,
+                        wide: false,
+                        bias: RouteBias::default(),
+                    });
+                }
+            }
+        }
+    }
+
+    Solution {
+        segments,
+        ..default()
+    }
+}
+
+fn import_screenshot_system(
+    mut events: EventReader<FileDragAndDrop>,
+    mut status: ResMut<ShareStatus>,
+    mut solutions: ResMut<Solutions>,
+    mut restart: Restart,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    sim_state: Res<SimulationState>,
+) {
+    for event in events.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = event else {
+            continue;
+        };
+
+        if *sim_state != SimulationState::NotStarted {
+            continue;
+        }
+
+        let Some(level) = handles
+            .levels
+            .get(selected_level.0 as usize - 1)
+            .and_then(|h| levels.get(h))
+        else {
+            continue;
+        };
+
+        let image = match image::open(path_buf) {
+            Ok(image) => image.into_rgba8(),
+            Err(e) => {
+                warn!("Failed to read screenshot {}: {}", path_buf.display(), e);
+                status.0 = "NOT AN IMAGE".to_string();
+                continue;
+            }
+        };
+
+        let screenshot = Screenshot {
+            size: UVec2::new(image.width(), image.height()),
+            pixels: image.as_raw(),
+        };
+
+        let stops: Vec<IVec2> = level
+            .terminuses
+            .iter()
+            .map(|t| (t.point / GRID_SIZE).round().as_ivec2())
+            .collect();
+
+        let solution = recognize(&screenshot, level.layers, &stops);
+
+        if solution.segments.is_empty() {
+            status.0 = "NO ROADS FOUND".to_string();
+            continue;
+        }
+
+        if let Err(e) = validate(level, &solution) {
+            status.0 = e.reason().to_string();
+            continue;
+        }
+
+        info!(
+            "Recognized {} roads in {}",
+            solution.segments.len(),
+            path_buf.display()
+        );
+
+        // Replace the working solution and start the level over, which will build it.
+        solutions
+            .0
+            .insert(handles.level_id(selected_level.0), solution);
+        restart.set(GameState::Playing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognize_roads() {
+        // Ten pixels per grid step.
+        let size = UVec2::new(501, 301);
+
+        let background = color::BACKGROUND.to_srgba().to_u8_array();
+        let mut pixels: Vec<u8> = std::iter::repeat(background)
+            .take((size.x * size.y) as usize)
+            .flatten()
+            .collect();
+
+        let mut draw = |a: IVec2, b: IVec2, layer: usize| {
+            let color = color::FINISHED_ROAD[layer - 1].to_srgba().to_u8_array();
+            for i in 0..=100 {
+                let point = a.as_vec2().lerp(b.as_vec2(), i as f32 / 100.0);
+                let position = pixel_position(size, point).round().as_uvec2();
+                let i = ((position.y * size.x + position.x) * 4) as usize;
+                pixels[i..i + 4].copy_from_slice(&color);
+            }
+        };
+
+        draw(IVec2::new(-2, 0), IVec2::new(2, 0), 1);
+        draw(IVec2::new(0, -3), IVec2::new(3, 0), 2);
+
+        let screenshot = Screenshot {
+            size,
+            pixels: &pixels,
+        };
+
+        let road = |a: (f32, f32), b: (f32, f32), layer: u32| {
+            (Vec2::from(a) * GRID_SIZE, Vec2::from(b) * GRID_SIZE, layer)
+        };
+        let roads = |solution: Solution| {
+            solution
+                .segments
+                .iter()
+                .map(|s| (s.points.0, s.points.1, s.layer))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            roads(recognize(&screenshot, 2, &[])),
+            vec![
+                road((-2.0, 0.0), (2.0, 0.0), 1),
+                road((0.0, -3.0), (3.0, 0.0), 2),
+            ]
+        );
+
+        // A terminus in the middle of the road splits it.
+        assert_eq!(
+            roads(recognize(&screenshot, 1, &[IVec2::ZERO])),
+            vec![
+                road((-2.0, 0.0), (0.0, 0.0), 1),
+                road((0.0, 0.0), (2.0, 0.0), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Settings that apply everywhere: sound, display, and keys. The same panel is shown
+//! from the level select screen and from the pause menu.
+
+use crate::{
+    backup::spawn_backup_buttons,
+    color,
+    controls::spawn_bindings,
+    level_select::LevelSelectScreen,
+    save::{InputMap, Settings},
+    sfx::SfxVolumeButton,
+    GameState, Handles, MainCamera,
+};
+use bevy::prelude::*;
+
+/// UI scales that the scale button steps through.
+const UI_SCALE_STEPS: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GraphicsQuality>();
+
+        app.add_systems(
+            Update,
+            (
+                apply_settings_system,
+                setting_button_system,
+                setting_text_system,
+            )
+                .chain(),
+        );
+        app.add_systems(OnEnter(GameState::LevelSelect), spawn_settings_panel_system);
+        app.add_systems(
+            Update,
+            settings_panel_button_system.run_if(in_state(GameState::LevelSelect)),
+        );
+    }
+}
+
+/// How much effort goes into things that are only for show. Low skips pixie spinning
+/// and explosion fragments, and draws the grid as a single shape. The grid is only
+/// built when a level starts, so it changes from the next one.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsQuality {
+    #[default]
+    High,
+    Low,
+}
+
+/// Opens the settings panel on the level select screen.
+#[derive(Component)]
+pub struct SettingsPanelButton;
+#[derive(Component)]
+struct SettingsPanel;
+
+#[derive(Component, Clone, Copy)]
+enum SettingButton {
+    UiScale,
+    ReducedMotion,
+    Antialiasing,
+    FineAngles,
+    Graphics,
+}
+
+impl SettingButton {
+    fn label(&self, settings: &Settings) -> String {
+        let on_off = |on: bool| if on { "ON" } else { "OFF" };
+
+        match self {
+            Self::UiScale => format!("UI SCALE {}%", (settings.ui_scale * 100.).round()),
+            Self::ReducedMotion => format!("REDUCED MOTION {}", on_off(settings.reduced_motion)),
+            Self::Antialiasing => format!("ANTIALIASING {}", on_off(settings.antialiasing)),
+            Self::FineAngles => format!("FINE ANGLES {}", on_off(settings.fine_angles)),
+            Self::Graphics => format!(
+                "GRAPHICS {}",
+                if settings.low_graphics { "LOW" } else { "HIGH" }
+            ),
+        }
+    }
+}
+
+/// Adds every setting to `parent`, with the options in one column and the key
+/// bindings next to them.
+pub fn spawn_settings(parent: &mut ChildBuilder, input_map: &InputMap, font: &TextFont) {
+    let font = TextFont {
+        font_size: 18.0,
+        ..font.clone()
+    };
+
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Start,
+            column_gap: Val::Px(20.),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(10.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_setting_button(parent, SfxVolumeButton, &font);
+                    for button in [
+                        SettingButton::UiScale,
+                        SettingButton::ReducedMotion,
+                        SettingButton::Antialiasing,
+                        SettingButton::FineAngles,
+                        SettingButton::Graphics,
+                    ] {
+                        spawn_setting_button(parent, button, &font);
+                    }
+                });
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(10.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_bindings(parent, input_map, &font);
+                });
+        });
+}
+
+fn spawn_setting_button(parent: &mut ChildBuilder, marker: impl Component, font: &TextFont) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                ..default()
+            },
+            BackgroundColor(color::UI_NORMAL_BUTTON),
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::default(),
+                font.clone(),
+                TextColor(color::UI_BUTTON_TEXT),
+            ));
+        });
+}
+
+fn apply_settings_system(
+    settings: Res<Settings>,
+    mut ui_scale: ResMut<UiScale>,
+    mut quality: ResMut<GraphicsQuality>,
+    mut q_camera: Query<&mut Msaa, With<MainCamera>>,
+    q_added: Query<(), Added<MainCamera>>,
+) {
+    if !settings.is_changed() && q_added.is_empty() {
+        return;
+    }
+
+    if ui_scale.0 != settings.ui_scale {
+        ui_scale.0 = settings.ui_scale;
+    }
+
+    let graphics = if settings.low_graphics {
+        GraphicsQuality::Low
+    } else {
+        GraphicsQuality::High
+    };
+    if *quality != graphics {
+        *quality = graphics;
+    }
+
+    let msaa = if settings.antialiasing {
+        Msaa::Sample4
+    } else {
+        Msaa::Off
+    };
+    for mut camera_msaa in q_camera.iter_mut() {
+        if *camera_msaa != msaa {
+            *camera_msaa = msaa;
+        }
+    }
+}
+
+fn setting_button_system(
+    mut settings: ResMut<Settings>,
+    q_interaction: Query<(&Interaction, &SettingButton), Changed<Interaction>>,
+) {
+    for (_, button) in q_interaction
+        .iter()
+        .filter(|(i, _)| **i == Interaction::Pressed)
+    {
+        match button {
+            SettingButton::UiScale => {
+                let current = UI_SCALE_STEPS
+                    .iter()
+                    .position(|s| *s >= settings.ui_scale)
+                    .unwrap_or_default();
+
+                settings.ui_scale = UI_SCALE_STEPS[(current + 1) % UI_SCALE_STEPS.len()];
+            }
+            SettingButton::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            SettingButton::Antialiasing => settings.antialiasing = !settings.antialiasing,
+            SettingButton::FineAngles => settings.fine_angles = !settings.fine_angles,
+            SettingButton::Graphics => settings.low_graphics = !settings.low_graphics,
+        }
+    }
+}
+
+fn setting_text_system(
+    settings: Res<Settings>,
+    q_button: Query<(&SettingButton, &Children)>,
+    mut q_text: Query<&mut Text>,
+) {
+    for (button, children) in q_button.iter() {
+        let label = button.label(&settings);
+
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            if text.0 != label {
+                text.0.clone_from(&label);
+            }
+        }
+    }
+}
+
+fn spawn_settings_panel_system(
+    mut commands: Commands,
+    input_map: Res<InputMap>,
+    handles: Res<Handles>,
+) {
+    let font = TextFont {
+        font: handles.fonts[0].clone(),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(20.),
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            BackgroundColor(color::DIALOG_BACKGROUND),
+            GlobalZIndex(1),
+            LevelSelectScreen,
+            SettingsPanel,
+        ))
+        .with_children(|parent| {
+            spawn_settings(parent, &input_map, &font);
+            spawn_backup_buttons(
+                parent,
+                &TextFont {
+                    font_size: 18.0,
+                    ..font.clone()
+                },
+            );
+        });
+}
+
+fn settings_panel_button_system(
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<SettingsPanelButton>)>,
+    mut q_panel: Query<&mut Node, With<SettingsPanel>>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        for mut node in q_panel.iter_mut() {
+            node.display = match node.display {
+                Display::None => Display::Flex,
+                _ => Display::None,
+            };
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Sound effects for drawing and ripping up roads, and for pixie events.
+
+use crate::{color, save::SfxVolume, sim::SimulationState, Handles};
+use bevy::{audio::Volume, prelude::*};
+
+/// Volume levels that the volume button steps through.
+const VOLUME_STEPS: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+pub struct SfxPlugin;
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>();
+
+        app.add_systems(Update, (level_complete_sfx_system, play_sfx_system).chain());
+        app.add_systems(
+            Update,
+            (sfx_volume_button_system, sfx_volume_button_text_system).chain(),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sfx {
+    Place,
+    Invalid,
+    Rip,
+    Explode,
+    Complete,
+}
+
+impl Sfx {
+    /// Every sound, in the order that they're stored in `Handles`.
+    pub const ALL: [Sfx; 5] = [
+        Sfx::Place,
+        Sfx::Invalid,
+        Sfx::Rip,
+        Sfx::Explode,
+        Sfx::Complete,
+    ];
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            Sfx::Place => "sfx/place.wav",
+            Sfx::Invalid => "sfx/invalid.wav",
+            Sfx::Rip => "sfx/rip.wav",
+            Sfx::Explode => "sfx/explode.wav",
+            Sfx::Complete => "sfx/complete.wav",
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct PlaySfx(pub Sfx);
+
+#[derive(Component)]
+pub struct SfxVolumeButton;
+
+fn level_complete_sfx_system(sim_state: Res<SimulationState>, mut events: EventWriter<PlaySfx>) {
+    if sim_state.is_changed() && *sim_state == SimulationState::Finished {
+        events.send(PlaySfx(Sfx::Complete));
+    }
+}
+
+fn play_sfx_system(
+    mut commands: Commands,
+    mut events: EventReader<PlaySfx>,
+    volume: Res<SfxVolume>,
+    handles: Res<Handles>,
+) {
+    // Many pixies can explode at once, but one bang per frame is plenty.
+    let mut played = vec![];
+
+    for PlaySfx(sfx) in events.read() {
+        if volume.0 <= 0.0 || played.contains(sfx) {
+            continue;
+        }
+        played.push(*sfx);
+
+        let Some(handle) = handles.sfx.get(*sfx as usize) else {
+            continue;
+        };
+
+        commands.spawn((
+            AudioPlayer(handle.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(volume.0)),
+        ));
+    }
+}
+
+fn sfx_volume_button_system(
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<SfxVolumeButton>)>,
+    mut volume: ResMut<SfxVolume>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        let current = VOLUME_STEPS
+            .iter()
+            .position(|v| *v >= volume.0)
+            .unwrap_or_default();
+
+        volume.0 = VOLUME_STEPS[(current + 1) % VOLUME_STEPS.len()];
+    }
+}
+
+fn sfx_volume_button_text_system(
+    volume: Res<SfxVolume>,
+    q_button: Query<&Children, With<SfxVolumeButton>>,
+    mut q_text: Query<(&mut Text, &mut TextColor)>,
+) {
+    for children in q_button.iter() {
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some((mut text, mut text_color)) = iter.fetch_next() {
+            let label = format!("SFX {}%", (volume.0 * 100.).round());
+            if text.0 != label {
+                text.0 = label;
+                text_color.0 = if volume.0 > 0.0 {
+                    color::UI_BUTTON_TEXT
+                } else {
+                    color::UI_GREY_RED
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! Exporting solutions as short strings that can be pasted somewhere else, and
+//! importing them again.
+//!
+//! A shared solution is a version byte, the level id, and nine bytes for each road
+//! segment: both ends as pairs of 16 bit coordinates in fractions of a grid unit, and
+//! the layer, with the high bit of the layer byte set for wide roads and the next two
+//! for a route bias. Arcs are made of short roads between points off the grid, and
+//! lines at fine angles bend at one, which the fractions keep close enough to line up. That's base64 encoded so it survives
+//! chat apps. Codes from before arcs, with whole grid units in a byte each, still
+//! decode.
+
+use crate::{
+    bias::RouteBias,
+    collision::{point_segment_collision, SegmentCollision},
+    level::Level,
+    restart::Restart,
+    save::{Solution, Solutions},
+    sim::SimulationState,
+    GameState, Handles, RoadSegment, SelectedLevel, GRID_SIZE,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bevy::prelude::*;
+
+const SHARE_VERSION: u8 = 2;
+/// Codes with whole grid units, which can't hold arcs.
+const GRID_SHARE_VERSION: u8 = 1;
+/// How many parts of a grid unit coordinates are encoded in.
+const SUBDIVISIONS: f32 = 1024.0;
+/// The extent of the grid on either side of the origin, in grid points.
+pub const ARENA_HALF_SIZE: (i8, i8) = (25, 15);
+const WIDE_BIT: u8 = 0x80;
+const AVOID_BIT: u8 = 0x40;
+const PREFER_BIT: u8 = 0x20;
+const LAYER_MASK: u8 = 0x1F;
+
+pub struct SharePlugin;
+impl Plugin for SharePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShareStatus>();
+
+        app.add_systems(OnEnter(GameState::Playing), reset_share_status_system);
+        app.add_systems(
+            Update,
+            (share_button_system, share_status_system)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct ExportButton;
+#[derive(Component)]
+pub struct ImportButton;
+#[derive(Component)]
+pub struct ShareStatusText;
+
+/// The outcome of the most recent export or import, shown to the player.
+#[derive(Resource, Default)]
+pub struct ShareStatus(pub String);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShareError {
+    Clipboard,
+    Malformed,
+    WrongLevel,
+    OutOfBounds,
+    BadAngle,
+    OffGrid,
+    BadLayer,
+    ObstacleCollision,
+    MidTerminusTouch,
+}
+impl ShareError {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::Clipboard => "CLIPBOARD UNAVAILABLE",
+            Self::Malformed => "NOT A SOLUTION",
+            Self::WrongLevel => "FOR A DIFFERENT LEVEL",
+            Self::OutOfBounds => "ROAD OFF THE BOARD",
+            Self::BadAngle => "ROAD NOT STRAIGHT",
+            Self::OffGrid => "ROAD ENDS OFF THE GRID",
+            Self::BadLayer => "ROAD ON MISSING LAYER",
+            Self::ObstacleCollision => "ROAD BLOCKED BY OBSTACLE",
+            Self::MidTerminusTouch => "ROAD PASSES THROUGH TERMINUS",
+        }
+    }
+}
+
+/// Encodes `solution` for the level with the given id.
+pub fn encode(level_id: &str, solution: &Solution) -> String {
+    let mut bytes = vec![SHARE_VERSION, level_id.len() as u8];
+    bytes.extend(level_id.bytes());
+
+    for segment in solution.segments.iter() {
+        for point in [segment.points.0, segment.points.1] {
+            for coord in [point.x, point.y] {
+                let coord = (coord / GRID_SIZE * SUBDIVISIONS).round() as i16;
+                bytes.extend(coord.to_le_bytes());
+            }
+        }
+
+        let wide = if segment.wide { WIDE_BIT } else { 0 };
+        let bias = match segment.bias {
+            RouteBias::Normal => 0,
+            RouteBias::Avoid => AVOID_BIT,
+            RouteBias::Prefer => PREFER_BIT,
+        };
+        bytes.push(segment.layer as u8 | wide | bias);
+    }
+
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Decodes a string made by `encode`, returning the level id and solution.
+pub fn decode(encoded: &str) -> Result<(String, Solution), ShareError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(encoded.trim())
+        .map_err(|_| ShareError::Malformed)?;
+
+    let [version @ (SHARE_VERSION | GRID_SHARE_VERSION), id_len, rest @ ..] = bytes.as_slice()
+    else {
+        return Err(ShareError::Malformed);
+    };
+
+    if rest.len() < *id_len as usize {
+        return Err(ShareError::Malformed);
+    }
+    let (id, rest) = rest.split_at(*id_len as usize);
+    let id = String::from_utf8(id.to_vec()).map_err(|_| ShareError::Malformed)?;
+
+    // Each end's coordinates, then the layer byte.
+    let coord_len = if *version == GRID_SHARE_VERSION { 1 } else { 2 };
+    let chunk_len = coord_len * 4 + 1;
+
+    if rest.len() % chunk_len != 0 {
+        return Err(ShareError::Malformed);
+    }
+
+    let coord = |bytes: &[u8]| match bytes {
+        [grid] => *grid as i8 as f32 * GRID_SIZE,
+        [lo, hi] => i16::from_le_bytes([*lo, *hi]) as f32 / SUBDIVISIONS * GRID_SIZE,
+        _ => unreachable!(),
+    };
+
+    let segments = rest
+        .chunks_exact(chunk_len)
+        .map(|chunk| {
+            let (coords, flags) = chunk.split_at(chunk_len - 1);
+            let flags = flags[0];
+            let mut coords = coords.chunks_exact(coord_len).map(coord);
+            let mut point = || Vec2::new(coords.next().unwrap(), coords.next().unwrap());
+
+            RoadSegment {
+                points: (point(), point()),
+                layer: (flags & LAYER_MASK) as u32,
+                wide: flags & WIDE_BIT != 0,
+                bias: if flags & AVOID_BIT != 0 {
+                    RouteBias::Avoid
+                } else if flags & PREFER_BIT != 0 {
+                    RouteBias::Prefer
+                } else {
+                    RouteBias::Normal
+                },
+            }
+        })
+        .collect();
+
+    Ok((
+        id,
+        Solution {
+            segments,
+            ..default()
+        },
+    ))
+}
+
+fn on_grid(point: Vec2) -> bool {
+    (point / GRID_SIZE).round() * GRID_SIZE == point
+}
+
+/// Checks that every road in `solution` could have been drawn on `level`.
+///
+/// Roads between two grid points must be straight or diagonal. Points off the grid are
+/// where the short roads that make up an arc meet, or where a line at a fine angle
+/// bends, so any angle goes there, but roads can't end at one.
+pub fn validate(level: &Level, solution: &Solution) -> Result<(), ShareError> {
+    let max = Vec2::new(ARENA_HALF_SIZE.0 as f32, ARENA_HALF_SIZE.1 as f32) * GRID_SIZE;
+
+    let ends = solution
+        .segments
+        .iter()
+        .flat_map(|s| [s.points.0, s.points.1])
+        .collect::<Vec<_>>();
+
+    for segment in solution.segments.iter() {
+        let (a, b) = segment.points;
+
+        if [a, b].iter().any(|p| p.abs().cmpgt(max).any()) {
+            return Err(ShareError::OutOfBounds);
+        }
+
+        let diff = b - a;
+        let straight = diff.x == 0.0 || diff.y == 0.0 || diff.x.abs() == diff.y.abs();
+        if diff == Vec2::ZERO || (on_grid(a) && on_grid(b) && !straight) {
+            return Err(ShareError::BadAngle);
+        }
+
+        for point in [a, b].into_iter().filter(|p| !on_grid(*p)) {
+            if ends.iter().filter(|e| **e == point).count() < 2 {
+                return Err(ShareError::OffGrid);
+            }
+        }
+
+        if segment.layer == 0 || segment.layer > level.layers {
+            return Err(ShareError::BadLayer);
+        }
+
+        if level
+            .obstacles
+            .iter()
+            .any(|o| o.blocks_segment(a, b, segment.layer))
+        {
+            return Err(ShareError::ObstacleCollision);
+        }
+
+        for terminus in level.terminuses.iter() {
+            if matches!(
+                point_segment_collision(terminus.point, a, b),
+                SegmentCollision::Touching
+            ) {
+                return Err(ShareError::MidTerminusTouch);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn reset_share_status_system(mut status: ResMut<ShareStatus>) {
+    status.0.clear();
+}
+
+fn share_button_system(
+    mut status: ResMut<ShareStatus>,
+    mut solutions: ResMut<Solutions>,
+    mut restart: Restart,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    sim_state: Res<SimulationState>,
+    q_export: Query<&Interaction, (Changed<Interaction>, With<ExportButton>)>,
+    q_import: Query<&Interaction, (Changed<Interaction>, With<ImportButton>)>,
+    q_segments: Query<&RoadSegment>,
+) {
+    let id = handles.level_id(selected_level.0);
+
+    for _ in q_export.iter().filter(|i| **i == Interaction::Pressed) {
+        let encoded = encode(
+            &id,
+            &Solution {
+                segments: q_segments.iter().cloned().collect(),
+                ..default()
+            },
+        );
+
+        info!("Exported solution: {}", encoded);
+
+        status.0 = match set_clipboard(encoded) {
+            Ok(()) => "COPIED TO CLIPBOARD".to_string(),
+            Err(e) => e.reason().to_string(),
+        };
+    }
+
+    if *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    for _ in q_import.iter().filter(|i| **i == Interaction::Pressed) {
+        let Some(level) = handles
+            .levels
+            .get(selected_level.0 as usize - 1)
+            .and_then(|h| levels.get(h))
+        else {
+            continue;
+        };
+
+        let imported = get_clipboard().and_then(|text| {
+            let (imported_id, solution) = decode(&text)?;
+            if imported_id != id {
+                return Err(ShareError::WrongLevel);
+            }
+            validate(level, &solution)?;
+            Ok(solution)
+        });
+
+        match imported {
+            Ok(solution) => {
+                // Replace the working solution and start the level over, which will
+                // build it.
+                solutions.0.insert(id.clone(), solution);
+                restart.set(GameState::Playing);
+            }
+            Err(e) => status.0 = e.reason().to_string(),
+        }
+    }
+}
+
+fn share_status_system(
+    status: Res<ShareStatus>,
+    mut q_text: Query<&mut Text, With<ShareStatusText>>,
+    q_added: Query<(), Added<ShareStatusText>>,
+) {
+    if !status.is_changed() && q_added.is_empty() {
+        return;
+    }
+
+    for mut text in q_text.iter_mut() {
+        text.0.clone_from(&status.0);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn set_clipboard(text: String) -> Result<(), ShareError> {
+    arboard::Clipboard::new()
+        .and_then(|mut c| c.set_text(text))
+        .map_err(|_| ShareError::Clipboard)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn get_clipboard() -> Result<String, ShareError> {
+    arboard::Clipboard::new()
+        .and_then(|mut c| c.get_text())
+        .map_err(|_| ShareError::Clipboard)
+}
+
+// TODO the browser clipboard API is async, so sharing is desktop-only for now.
+#[cfg(target_arch = "wasm32")]
+fn set_clipboard(_text: String) -> Result<(), ShareError> {
+    Err(ShareError::Clipboard)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn get_clipboard() -> Result<String, ShareError> {
+    Err(ShareError::Clipboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::seg,
+        level::{Obstacle, Terminus},
+        lines::{possible_arcs, possible_fine_lines},
+    };
+    use itertools::Itertools;
+
+    fn solution() -> Solution {
+        Solution {
+            segments: vec![
+                seg((-96.0, 0.0), (0.0, 0.0), 1),
+                RoadSegment {
+                    points: (Vec2::new(0.0, 0.0), Vec2::new(48.0, -48.0)),
+                    layer: 2,
+                    wide: true,
+                    bias: RouteBias::Prefer,
+                },
+            ],
+            ..default()
+        }
+    }
+
+    fn level(obstacles: Vec<Obstacle>) -> Level {
+        Level {
+            terminuses: vec![Terminus {
+                point: Vec2::new(-48.0, 0.0),
+                emits: Default::default(),
+                collects: Default::default(),
+                ..default()
+            }],
+            obstacles,
+            ..crate::fixtures::level(2)
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let encoded = encode("level1", &solution());
+        let (id, decoded) = decode(&encoded).unwrap();
+
+        assert_eq!(id, "level1");
+        assert_eq!(decoded.segments.len(), 2);
+        assert_eq!(decoded.segments[1].points, solution().segments[1].points);
+        assert_eq!(decoded.segments[1].layer, 2);
+        assert!(decoded.segments[1].wide);
+        assert_eq!(decoded.segments[1].bias, RouteBias::Prefer);
+        assert_eq!(decoded.segments[0].bias, RouteBias::Normal);
+
+        // From before arcs, with the first road only.
+        let (_, old) = decode("AQZsZXZlbDH-AAAAAQ").unwrap();
+        assert_eq!(old.segments[0].points, solution().segments[0].points);
+        assert_eq!(old.segments[0].layer, 1);
+
+        assert_eq!(decode("").err(), Some(ShareError::Malformed));
+        assert_eq!(decode("not base64!").err(), Some(ShareError::Malformed));
+        assert_eq!(
+            decode(&encoded[..encoded.len() - 2]).err(),
+            Some(ShareError::Malformed)
+        );
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!(
+            validate(&level(vec![]), &solution()),
+            Err(ShareError::MidTerminusTouch)
+        );
+
+        let mut diagonal = solution();
+        diagonal.segments.remove(0);
+
+        assert_eq!(validate(&level(vec![]), &diagonal), Ok(()));
+
+        let obstacle = || Obstacle::Rect(Vec2::new(0.0, -96.0), Vec2::new(96.0, -24.0));
+        assert_eq!(
+            validate(&level(vec![obstacle()]), &diagonal),
+            Err(ShareError::ObstacleCollision)
+        );
+        assert_eq!(
+            validate(
+                &level(vec![Obstacle::Layers(vec![1], Box::new(obstacle()))]),
+                &diagonal
+            ),
+            Ok(())
+        );
+
+        let crooked = Solution {
+            segments: vec![RoadSegment {
+                points: (Vec2::ZERO, Vec2::new(96.0, 48.0)),
+                ..diagonal.segments[0].clone()
+            }],
+            ..default()
+        };
+        assert_eq!(
+            validate(&level(vec![]), &crooked),
+            Err(ShareError::BadAngle)
+        );
+
+        diagonal.segments[0].layer = 3;
+        assert_eq!(
+            validate(&level(vec![]), &diagonal),
+            Err(ShareError::BadLayer)
+        );
+    }
+
+    #[test]
+    fn arcs() {
+        let arc = possible_arcs(Vec2::ZERO, Vec2::new(96.0, 96.0), None).remove(0);
+        let road = |(a, b)| RoadSegment {
+            points: (a, b),
+            layer: 1,
+            wide: false,
+            bias: RouteBias::default(),
+        };
+        let solution = Solution {
+            segments: arc.into_iter().map(road).collect(),
+            ..default()
+        };
+
+        let (_, decoded) = decode(&encode("level1", &solution)).unwrap();
+        assert_eq!(decoded.segments.len(), solution.segments.len());
+        for (a, b) in decoded.segments.iter().zip(solution.segments.iter()) {
+            assert!(a.points.0.distance(b.points.0) < 0.1);
+            assert!(a.points.1.distance(b.points.1) < 0.1);
+        }
+        assert!(decoded
+            .segments
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| a.points.1 == b.points.0));
+
+        let level = Level {
+            terminuses: vec![],
+            ..level(vec![])
+        };
+        assert_eq!(validate(&level, &decoded), Ok(()));
+
+        // Half an arc ends off the grid.
+        let mut half = decoded;
+        half.segments.truncate(half.segments.len() / 2);
+        assert_eq!(validate(&level, &half), Err(ShareError::OffGrid));
+    }
+
+    #[test]
+    fn fine_angles() {
+        let line = possible_fine_lines(Vec2::ZERO, Vec2::new(144.0, 48.0), None).remove(0);
+        assert!(!on_grid(line[0].1));
+
+        let solution = Solution {
+            segments: line
+                .into_iter()
+                .map(|(a, b)| RoadSegment {
+                    points: (a, b),
+                    layer: 1,
+                    wide: false,
+                    bias: RouteBias::default(),
+                })
+                .collect(),
+            ..default()
+        };
+
+        let (_, decoded) = decode(&encode("level1", &solution)).unwrap();
+        assert_eq!(decoded.segments[0].points.1, decoded.segments[1].points.0);
+        assert!(
+            decoded.segments[0]
+                .points
+                .1
+                .distance(solution.segments[0].points.1)
+                < 0.1
+        );
+        assert_eq!(decoded.segments[1].points.1, Vec2::new(144.0, 48.0));
+
+        let level = Level {
+            terminuses: vec![],
+            ..level(vec![])
+        };
+        assert_eq!(validate(&level, &decoded), Ok(()));
+    }
+}
@@ -1,13 +1,21 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, time::Duration};
 
 use crate::{
+    capacity::{refill_capacity_system, TerminusCapacity},
+    congestion::Congestion,
+    controls::Action,
+    level::Terminus,
+    moving_obstacle::move_obstacles_system,
     pixie::{
         collide_pixies_system, emit_pixies_system, explode_pixies_system, move_pixies_system,
-        Pixie, PixieEmitter,
+        Pixie, PixieEmitter, PixieGrid,
     },
     pixie_button_system,
+    save::InputMap,
+    DrawingInteraction, GameState,
 };
-use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*, utils::HashMap};
+use rand::{rngs::StdRng, SeedableRng};
 
 pub struct SimulationPlugin;
 impl Plugin for SimulationPlugin {
@@ -18,6 +26,8 @@ impl Plugin for SimulationPlugin {
         schedule.add_systems(
             (
                 collide_pixies_system,
+                move_obstacles_system,
+                refill_capacity_system,
                 move_pixies_system,
                 emit_pixies_system,
                 explode_pixies_system,
@@ -30,19 +40,36 @@ impl Plugin for SimulationPlugin {
 
         app.init_resource::<SimulationSettings>();
         app.init_resource::<SimulationState>();
+        app.init_resource::<SimulationPaused>();
         app.init_resource::<SimulationSteps>();
+        app.init_resource::<SimulationTimeline>();
+        app.init_resource::<SimulationSeed>();
+        app.init_resource::<PixieGrid>();
+        app.init_resource::<TerminusCapacity>();
 
         // TODO this must run after buffers from pixie_button_system are applied
         // so that emitters are created on time. It might be nice to move sim entity
         // initialization into the sim schedule.
+        //
+        // Roads placed while pixies are running are applied before the simulation
+        // steps, rather than part way through a frame's worth of steps.
         app.add_systems(
             Update,
-            (apply_deferred.after(pixie_button_system), run_simulation).chain(),
+            (apply_deferred.after(pixie_button_system), run_simulation)
+                .chain()
+                .after(DrawingInteraction),
+        );
+        app.add_systems(
+            Update,
+            fast_forward_system
+                .before(run_simulation)
+                .run_if(in_state(GameState::Playing)),
         );
     }
 }
 
 pub const SIMULATION_TIMESTEP: f32 = 0.016_666_668;
+const DEFAULT_SEED: u64 = 0x5049_5849_4553;
 
 #[derive(ScheduleLabel, Debug, PartialEq, Eq, Clone, Hash)]
 pub struct SimulationSchedule;
@@ -54,6 +81,127 @@ pub enum SimulationState {
     Running,
     Finished,
 }
+impl SimulationState {
+    /// Roads can be drawn before a run, and during one, but not while the score is
+    /// being shown.
+    pub fn allows_drawing(&self) -> bool {
+        matches!(self, Self::NotStarted | Self::Running)
+    }
+}
+
+/// Seeds any randomness in a run, so that a road layout always plays out the same way.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimulationSeed(pub u64);
+impl Default for SimulationSeed {
+    fn default() -> Self {
+        Self(DEFAULT_SEED)
+    }
+}
+impl SimulationSeed {
+    /// A random number generator for the given step. Deriving it from the step rather
+    /// than keeping one around means that a rewound run plays out the same way again.
+    pub fn rng(&self, step: u32) -> StdRng {
+        StdRng::seed_from_u64(self.0 ^ u64::from(step).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+}
+
+/// While paused, the simulation does not advance, regardless of its state.
+#[derive(Resource, Default)]
+pub struct SimulationPaused(pub bool);
+
+/// Notable moments during a run, in seconds of simulation time.
+#[derive(Resource, Default, Clone)]
+pub struct SimulationTimeline {
+    /// When each emitter released its last pixie, along with that emitter's color.
+    pub emitters_finished: Vec<(u32, f32)>,
+    pub last_delivery: Option<f32>,
+    /// When each pixie was delivered.
+    pub deliveries: Vec<f32>,
+    /// Where each pixie exploded.
+    pub explosions: Vec<Vec2>,
+    /// What happened to the pixies of each color.
+    pub flavors: BTreeMap<u32, FlavorTally>,
+    /// Pixies emitted and collected by each terminus, keyed by its rounded position.
+    pub terminuses: HashMap<IVec2, TerminusTally>,
+}
+
+/// Counts of pixies of one color. A pixie that changes color on the way counts as
+/// emitted in one color and delivered or exploded in another.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct FlavorTally {
+    pub emitted: u32,
+    pub delivered: u32,
+    pub exploded: u32,
+}
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct TerminusTally {
+    pub emitted: u32,
+    pub collected: u32,
+}
+
+impl SimulationTimeline {
+    /// The tally for the terminus at `point`.
+    pub fn terminus(&mut self, point: Vec2) -> &mut TerminusTally {
+        self.terminuses.entry(point.round().as_ivec2()).or_default()
+    }
+
+    /// The number of deliveries in each of `buckets` equal slices of `duration`.
+    pub fn throughput(&self, duration: f32, buckets: usize) -> Vec<u32> {
+        let mut counts = vec![0; buckets];
+        if buckets == 0 || duration <= 0.0 {
+            return counts;
+        }
+
+        for t in self.deliveries.iter() {
+            let i = (t / duration * buckets as f32) as usize;
+            counts[i.min(buckets - 1)] += 1;
+        }
+
+        counts
+    }
+
+    /// The junction with the most explosions nearest to it, and how many there were.
+    pub fn worst_junction(&self, junctions: &[Vec2]) -> Option<(Vec2, usize)> {
+        self.losses(junctions)
+            .first()
+            .map(|l| (l.junction, l.explosions.len()))
+    }
+
+    /// Where pixies exploded, grouped by the junction nearest to each. Junctions where
+    /// the most pixies were lost come first, and ones where none were are left out.
+    pub fn losses(&self, junctions: &[Vec2]) -> Vec<JunctionLosses> {
+        let mut losses: Vec<_> = junctions
+            .iter()
+            .map(|j| JunctionLosses {
+                junction: *j,
+                explosions: vec![],
+            })
+            .collect();
+
+        for explosion in self.explosions.iter() {
+            let nearest = losses.iter_mut().min_by(|a, b| {
+                a.junction
+                    .distance_squared(*explosion)
+                    .total_cmp(&b.junction.distance_squared(*explosion))
+            });
+
+            if let Some(nearest) = nearest {
+                nearest.explosions.push(*explosion);
+            }
+        }
+
+        losses.retain(|l| !l.explosions.is_empty());
+        losses.sort_by_key(|l| std::cmp::Reverse(l.explosions.len()));
+        losses
+    }
+}
+
+/// The pixies that exploded closer to one junction than to any other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JunctionLosses {
+    pub junction: Vec2,
+    pub explosions: Vec<Vec2>,
+}
 
 #[derive(Resource)]
 pub struct SimulationSteps {
@@ -82,14 +230,32 @@ impl SimulationSteps {
         }
     }
 
-    fn tick(&mut self, delta: Duration) {
-        self.accumulator += delta;
+    /// Adds `delta` of real time, scaled by `scale`, to the accumulator. Slow speeds
+    /// may take several frames to add up to a step.
+    fn tick(&mut self, delta: Duration, scale: f32) {
+        self.accumulator += delta.mul_f32(scale);
     }
 
     fn reset(&mut self) {
         *self = Self::default();
     }
 
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    /// Counts a step without waiting for the time to add up, for running the
+    /// simulation as fast as possible.
+    pub fn advance(&mut self) {
+        self.step += 1;
+    }
+
+    /// Moves the simulation back to an earlier `step`, discarding any accumulated time.
+    pub fn rewind_to(&mut self, step: u32) {
+        self.step = step;
+        self.accumulator = Duration::ZERO;
+    }
+
     pub fn get_elapsed_f32(&self) -> f32 {
         self.step as f32 * SIMULATION_TIMESTEP
     }
@@ -97,37 +263,119 @@ impl SimulationSteps {
 
 #[derive(Clone, Copy, Default)]
 pub enum SimulationSpeed {
+    Slow,
     #[default]
     Normal,
     Fast,
+    VeryFast,
 }
 
 impl SimulationSpeed {
-    fn scale(&self) -> u32 {
+    fn scale(&self) -> f32 {
         match self {
-            Self::Normal => 1,
-            Self::Fast => 4,
+            Self::Slow => 0.5,
+            Self::Normal => 1.0,
+            Self::Fast => 4.0,
+            Self::VeryFast => 8.0,
         }
     }
     pub fn label(&self) -> String {
         match self {
+            Self::Slow => "0.5X".to_string(),
             Self::Normal => "1X".to_string(),
             Self::Fast => "4X".to_string(),
+            Self::VeryFast => "8X".to_string(),
         }
     }
     pub fn next(&self) -> Self {
         match self {
+            SimulationSpeed::Slow => SimulationSpeed::Normal,
             SimulationSpeed::Normal => SimulationSpeed::Fast,
-            SimulationSpeed::Fast => SimulationSpeed::Normal,
+            SimulationSpeed::Fast => SimulationSpeed::VeryFast,
+            SimulationSpeed::VeryFast => SimulationSpeed::Slow,
         }
     }
 }
+/// Pixies released back to back by each emitter in its turn, with `EmissionStrategy::Burst`.
+pub const BURST_SIZE: u32 = 5;
+/// How late each pixie may be released with `EmissionStrategy::Jitter`, as a fraction
+/// of the interval between pixies.
+pub const EMISSION_JITTER: f32 = 0.5;
+
+/// How emitters of several flavors leaving one terminus share it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmissionStrategy {
+    /// Each emitter takes a turn, one pixie at a time.
+    #[default]
+    RoundRobin,
+    /// Turns as above, with each pixie released a little late by a random amount.
+    Jitter,
+    /// Each emitter takes a turn, `BURST_SIZE` pixies at a time.
+    Burst,
+}
+
+impl EmissionStrategy {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::RoundRobin => "TURNS",
+            Self::Jitter => "JITTER",
+            Self::Burst => "BURST",
+        }
+    }
+    pub fn next(&self) -> Self {
+        match self {
+            Self::RoundRobin => Self::Jitter,
+            Self::Jitter => Self::Burst,
+            Self::Burst => Self::RoundRobin,
+        }
+    }
+    /// Seconds after its terminus starts that the `k`th pixie of the `i`th of `count`
+    /// emitters sharing it is released, with pixies `interval` seconds apart. Jitter is
+    /// left out.
+    pub fn release_time(&self, k: u32, i: u32, count: u32, interval: f32) -> f32 {
+        // The last emitter spawned goes first.
+        let turn = count - i - 1;
+
+        let slots = match self {
+            Self::RoundRobin | Self::Jitter => turn + k * count,
+            Self::Burst => (turn + k / BURST_SIZE * count) * BURST_SIZE + k % BURST_SIZE,
+        };
+
+        slots as f32 * interval
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct SimulationSettings {
     pub speed: SimulationSpeed,
+    /// While the fast forward key is held, the simulation runs as fast as it can,
+    /// whatever `speed` is.
+    pub fast_forward: bool,
+    pub emission: EmissionStrategy,
+}
+impl SimulationSettings {
+    /// The speed the simulation is actually running at.
+    pub fn current_speed(&self) -> SimulationSpeed {
+        if self.fast_forward {
+            SimulationSpeed::VeryFast
+        } else {
+            self.speed
+        }
+    }
+}
+
+fn fast_forward_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut settings: ResMut<SimulationSettings>,
+) {
+    let held = input_map.pressed(Action::FastForward, &keyboard_input);
+    if settings.fast_forward != held {
+        settings.fast_forward = held;
+    }
 }
 
-fn run_simulation(world: &mut World) {
+pub fn run_simulation(world: &mut World) {
     let state = world.resource_mut::<SimulationState>();
     if *state != SimulationState::Running {
         return;
@@ -135,14 +383,27 @@ fn run_simulation(world: &mut World) {
 
     if state.is_changed() {
         world.resource_mut::<SimulationSteps>().reset();
+        *world.resource_mut::<SimulationTimeline>() = SimulationTimeline::default();
+        world.resource_mut::<Congestion>().clear();
+
+        let capacities: Vec<_> = world
+            .query::<&Terminus>()
+            .iter(world)
+            .filter_map(|t| Some((t.point, t.capacity?)))
+            .collect();
+        world.resource_mut::<TerminusCapacity>().reset(capacities);
     }
 
-    let speed = world.resource::<SimulationSettings>().speed;
+    if world.resource::<SimulationPaused>().0 {
+        return;
+    }
+
+    let speed = world.resource::<SimulationSettings>().current_speed();
     let delta = world.resource::<Time>().delta();
 
     world
         .resource_mut::<SimulationSteps>()
-        .tick(delta * speed.scale());
+        .tick(delta, speed.scale());
 
     let mut check_again = true;
     while check_again {
@@ -163,7 +424,7 @@ fn run_simulation(world: &mut World) {
     }
 }
 
-fn update_sim_state_system(
+pub fn update_sim_state_system(
     mut sim_state: ResMut<SimulationState>,
     sim_steps: Res<SimulationSteps>,
     q_emitter: Query<&PixieEmitter>,
@@ -177,6 +438,11 @@ fn update_sim_state_system(
         return;
     }
 
+    // Sandbox runs go on until they're stopped.
+    if q_emitter.iter().any(|e| e.endless) {
+        return;
+    }
+
     for emitter in q_emitter.iter() {
         if emitter.remaining > 0 {
             return;
@@ -191,3 +457,124 @@ fn update_sim_state_system(
 
     *sim_state = SimulationState::Finished;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn throughput() {
+        let timeline = SimulationTimeline {
+            deliveries: vec![0.0, 0.5, 1.5, 4.0],
+            ..default()
+        };
+
+        assert_eq!(timeline.throughput(4.0, 4), vec![2, 1, 0, 1]);
+        assert_eq!(timeline.throughput(0.0, 4), vec![0; 4]);
+    }
+
+    #[test]
+    fn worst_junction() {
+        let junctions = [Vec2::ZERO, Vec2::new(100.0, 0.0)];
+
+        let timeline = SimulationTimeline {
+            explosions: vec![
+                Vec2::new(5.0, 0.0),
+                Vec2::new(90.0, 0.0),
+                Vec2::new(110.0, 5.0),
+            ],
+            ..default()
+        };
+        assert_eq!(timeline.worst_junction(&junctions), Some((junctions[1], 2)));
+
+        assert_eq!(
+            SimulationTimeline::default().worst_junction(&junctions),
+            None
+        );
+        assert_eq!(timeline.worst_junction(&[]), None);
+    }
+
+    #[test]
+    fn losses() {
+        let junctions = [Vec2::ZERO, Vec2::new(100.0, 0.0), Vec2::new(200.0, 0.0)];
+
+        let timeline = SimulationTimeline {
+            explosions: vec![
+                Vec2::new(5.0, 0.0),
+                Vec2::new(90.0, 0.0),
+                Vec2::new(110.0, 5.0),
+            ],
+            ..default()
+        };
+
+        assert_eq!(
+            timeline.losses(&junctions),
+            vec![
+                JunctionLosses {
+                    junction: junctions[1],
+                    explosions: vec![Vec2::new(90.0, 0.0), Vec2::new(110.0, 5.0)],
+                },
+                JunctionLosses {
+                    junction: junctions[0],
+                    explosions: vec![Vec2::new(5.0, 0.0)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn release_times() {
+        let turns = EmissionStrategy::RoundRobin;
+        assert_eq!(turns.release_time(0, 1, 2, 0.5), 0.0);
+        assert_eq!(turns.release_time(0, 0, 2, 0.5), 0.5);
+        assert_eq!(turns.release_time(1, 1, 2, 0.5), 1.0);
+
+        let burst = EmissionStrategy::Burst;
+        let last = BURST_SIZE - 1;
+        assert_eq!(burst.release_time(last, 1, 2, 1.0), last as f32);
+        assert_eq!(burst.release_time(0, 0, 2, 1.0), BURST_SIZE as f32);
+        assert_eq!(
+            burst.release_time(BURST_SIZE, 1, 2, 1.0),
+            2.0 * BURST_SIZE as f32
+        );
+
+        // Alone at a terminus, every strategy releases one pixie after another.
+        for k in 0..10 {
+            assert_eq!(burst.release_time(k, 0, 1, 1.0), k as f32);
+        }
+    }
+
+    #[test]
+    fn fractional_speed() {
+        let mut steps = SimulationSteps::default();
+        // A little over a step, so that float rounding can't come up short.
+        let frame = Duration::from_secs_f32(SIMULATION_TIMESTEP * 1.01);
+
+        // Half speed takes two frames to make a step.
+        steps.tick(frame, SimulationSpeed::Slow.scale());
+        assert!(!steps.expend());
+        steps.tick(frame, SimulationSpeed::Slow.scale());
+        assert!(steps.expend());
+        assert!(!steps.expend());
+
+        steps.tick(frame, SimulationSpeed::VeryFast.scale());
+        assert_eq!(
+            std::iter::from_fn(|| steps.expend().then_some(())).count(),
+            8
+        );
+        assert_eq!(steps.step(), 9);
+    }
+
+    #[test]
+    fn seeded_rng_is_repeatable() {
+        let seed = SimulationSeed::default();
+
+        let a: [u32; 4] = seed.rng(10).gen();
+        let b: [u32; 4] = seed.rng(10).gen();
+        let c: [u32; 4] = seed.rng(11).gen();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
@@ -0,0 +1,344 @@
+//! Named solution slots, so that several layouts can be kept for the same level.
+
+use crate::{
+    color,
+    controls::Action,
+    level::Level,
+    playing_enter_system,
+    restart::Restart,
+    save::{InputMap, NamedSolution, Solution, SolutionSlots, Solutions},
+    share::{ExportButton, ImportButton, ShareStatusText},
+    sim::SimulationState,
+    DrawingInput, DrawingInteraction, GameState, Handles, RoadSegment, SelectedLevel,
+};
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    ui::RelativeCursorPosition,
+};
+
+const MAX_NAME_LENGTH: usize = 16;
+
+pub struct SlotsPlugin;
+impl Plugin for SlotsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SlotRenaming>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_slots_panel_system.after(playing_enter_system),
+        );
+        app.add_systems(Update, slots_key_system.in_set(DrawingInput));
+        app.add_systems(
+            Update,
+            (slot_button_system, rename_system, update_slots_panel_system)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+
+        // Typing a name shouldn't trigger keyboard shortcuts, and clicking the panel
+        // shouldn't draw on the board underneath it.
+        app.configure_sets(Update, DrawingInput.run_if(not_renaming));
+        app.configure_sets(Update, DrawingInteraction.run_if(not_over_slots_panel));
+    }
+}
+
+/// The index of the slot whose name is being edited.
+#[derive(Resource, Default)]
+struct SlotRenaming(Option<usize>);
+
+#[derive(Component)]
+struct SlotsPanel;
+#[derive(Component)]
+struct SlotsList;
+#[derive(Component)]
+struct SlotNameButton(usize);
+#[derive(Component)]
+struct SlotLoadButton(usize);
+#[derive(Component)]
+struct SlotSaveButton(usize);
+#[derive(Component)]
+struct NewSlotButton;
+
+fn not_renaming(renaming: Res<SlotRenaming>) -> bool {
+    renaming.0.is_none()
+}
+
+fn not_over_slots_panel(
+    q_panel: Query<(&Node, &RelativeCursorPosition), With<SlotsPanel>>,
+) -> bool {
+    q_panel
+        .iter()
+        .all(|(node, cursor)| node.display == Display::None || !cursor.mouse_over())
+}
+
+fn slots_key_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut q_panel: Query<&mut Node, With<SlotsPanel>>,
+) {
+    if !input_map.just_pressed(Action::Slots, &keyboard_input) {
+        return;
+    }
+
+    for mut node in q_panel.iter_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+fn slot_button_system(
+    mut renaming: ResMut<SlotRenaming>,
+    mut slots: ResMut<SolutionSlots>,
+    mut solutions: ResMut<Solutions>,
+    mut restart: Restart,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    sim_state: Res<SimulationState>,
+    q_name: Query<(&Interaction, &SlotNameButton), Changed<Interaction>>,
+    q_load: Query<(&Interaction, &SlotLoadButton), Changed<Interaction>>,
+    q_save: Query<(&Interaction, &SlotSaveButton), Changed<Interaction>>,
+    q_new: Query<&Interaction, (Changed<Interaction>, With<NewSlotButton>)>,
+    q_segments: Query<&RoadSegment>,
+) {
+    let id = handles.level_id(selected_level.0);
+
+    let level_hash = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+        .map_or(0, Level::layout_hash);
+
+    let current = || Solution {
+        segments: q_segments.iter().cloned().collect(),
+        level_hash,
+    };
+
+    for (_, button) in q_name.iter().filter(|(i, _)| **i == Interaction::Pressed) {
+        renaming.0 = if renaming.0 == Some(button.0) {
+            None
+        } else {
+            Some(button.0)
+        };
+    }
+
+    for (_, button) in q_save.iter().filter(|(i, _)| **i == Interaction::Pressed) {
+        if let Some(slot) = slots.0.get_mut(&id).and_then(|s| s.get_mut(button.0)) {
+            slot.solution = current();
+        }
+    }
+
+    for _ in q_new.iter().filter(|i| **i == Interaction::Pressed) {
+        let level_slots = slots.0.entry(id.clone()).or_default();
+        level_slots.push(NamedSolution {
+            name: format!("SLOT {}", level_slots.len() + 1),
+            solution: current(),
+        });
+    }
+
+    if *sim_state != SimulationState::NotStarted {
+        return;
+    }
+
+    for (_, button) in q_load.iter().filter(|(i, _)| **i == Interaction::Pressed) {
+        let Some(slot) = slots.0.get(&id).and_then(|s| s.get(button.0)) else {
+            continue;
+        };
+
+        // Replace the working solution and start the level over, which will build it.
+        solutions.0.insert(id.clone(), slot.solution.clone());
+        restart.set(GameState::Playing);
+    }
+}
+
+fn rename_system(
+    mut events: EventReader<KeyboardInput>,
+    mut renaming: ResMut<SlotRenaming>,
+    mut slots: ResMut<SolutionSlots>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+) {
+    let Some(index) = renaming.0 else {
+        events.clear();
+        return;
+    };
+
+    let id = handles.level_id(selected_level.0);
+    if slots.0.get(&id).and_then(|s| s.get(index)).is_none() {
+        renaming.0 = None;
+        return;
+    }
+
+    for event in events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if event.logical_key == Key::Enter {
+            renaming.0 = None;
+            return;
+        }
+
+        // unwrap: we checked that the slot exists above
+        let name = &mut slots.0.get_mut(&id).unwrap()[index].name;
+
+        match &event.logical_key {
+            Key::Backspace => {
+                name.pop();
+            }
+            Key::Space if name.len() < MAX_NAME_LENGTH => {
+                name.push(' ');
+            }
+            Key::Character(c) if name.len() < MAX_NAME_LENGTH => {
+                name.extend(
+                    c.chars()
+                        .filter(|c| c.is_ascii_graphic())
+                        .map(|c| c.to_ascii_uppercase()),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+fn spawn_slots_panel_system(mut commands: Commands, mut renaming: ResMut<SlotRenaming>) {
+    renaming.0 = None;
+
+    commands
+        .spawn((
+            Node {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.),
+                left: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            BackgroundColor(color::DIALOG_BACKGROUND.with_alpha(0.8)),
+            RelativeCursorPosition::default(),
+            SlotsPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                SlotsList,
+            ));
+        });
+}
+
+fn update_slots_panel_system(
+    mut commands: Commands,
+    slots: Res<SolutionSlots>,
+    renaming: Res<SlotRenaming>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    q_list: Query<Entity, With<SlotsList>>,
+    q_added: Query<(), Added<SlotsList>>,
+) {
+    if !slots.is_changed() && !renaming.is_changed() && q_added.is_empty() {
+        return;
+    }
+
+    let Ok(list) = q_list.get_single() else {
+        return;
+    };
+
+    let text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 18.0,
+        ..default()
+    };
+
+    let id = handles.level_id(selected_level.0);
+    let level_slots = slots.0.get(&id).map(Vec::as_slice).unwrap_or_default();
+
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|parent| {
+        parent.spawn((
+            Text::new("SOLUTIONS (S)"),
+            text_font.clone(),
+            TextColor(color::UI_WHITE),
+        ));
+
+        for (i, slot) in level_slots.iter().enumerate() {
+            let name = if renaming.0 == Some(i) {
+                format!("{}_", slot.name)
+            } else {
+                slot.name.clone()
+            };
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(6.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_slot_button(parent, name, 180., SlotNameButton(i), &text_font);
+                    spawn_slot_button(parent, "LOAD".into(), 60., SlotLoadButton(i), &text_font);
+                    spawn_slot_button(parent, "SAVE".into(), 60., SlotSaveButton(i), &text_font);
+                });
+        }
+
+        spawn_slot_button(parent, "NEW SLOT".into(), 180., NewSlotButton, &text_font);
+
+        parent
+            .spawn(Node {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(6.),
+                ..default()
+            })
+            .with_children(|parent| {
+                spawn_slot_button(parent, "EXPORT".into(), 120., ExportButton, &text_font);
+                spawn_slot_button(parent, "IMPORT".into(), 120., ImportButton, &text_font);
+            });
+
+        parent.spawn((
+            Text::default(),
+            text_font.clone(),
+            TextColor(color::UI_WHITE),
+            ShareStatusText,
+        ));
+    });
+}
+
+fn spawn_slot_button(
+    parent: &mut ChildBuilder,
+    label: String,
+    width: f32,
+    marker: impl Component,
+    font: &TextFont,
+) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(width),
+                height: Val::Px(30.),
+                padding: UiRect::horizontal(Val::Px(6.)),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(color::UI_NORMAL_BUTTON),
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                font.clone(),
+                TextColor(color::UI_BUTTON_TEXT),
+            ));
+        });
+}
@@ -0,0 +1,165 @@
+//! Periodic snapshots of a running simulation, so that it can be rewound.
+
+use std::collections::VecDeque;
+
+use crate::{
+    capacity::TerminusCapacity,
+    congestion::Congestion,
+    pixie::{spawn_pixie, Pixie, PixieEmitter, PixieMeshes},
+    sim::{
+        update_sim_state_system, SimulationSchedule, SimulationState, SimulationSteps,
+        SimulationTimeline, SIMULATION_TIMESTEP,
+    },
+    GameState, PixieCount,
+};
+use bevy::prelude::*;
+
+/// Number of simulation ticks between snapshots.
+const SNAPSHOT_INTERVAL: u32 = 30;
+/// Number of snapshots kept, covering the most recent minute of simulation.
+const MAX_SNAPSHOTS: usize = 120;
+
+pub struct SnapshotPlugin;
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationSnapshots>();
+        app.add_event::<RewindSimulation>();
+
+        app.add_systems(
+            SimulationSchedule,
+            take_snapshot_system.after(update_sim_state_system),
+        );
+        app.add_systems(Update, rewind_system.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Requests that the running simulation be moved back by roughly `seconds`.
+#[derive(Event)]
+pub struct RewindSimulation {
+    pub seconds: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct SimulationSnapshots(VecDeque<Snapshot>);
+
+impl SimulationSnapshots {
+    /// Forgets every snapshot, for when the roads they were taken on have changed.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+struct Snapshot {
+    step: u32,
+    pixies: Vec<(Pixie, Transform)>,
+    emitters: Vec<(Entity, PixieEmitter)>,
+    pixie_count: u32,
+    timeline: SimulationTimeline,
+    congestion: Congestion,
+    capacity: TerminusCapacity,
+}
+
+fn take_snapshot_system(
+    sim_state: Res<SimulationState>,
+    steps: Res<SimulationSteps>,
+    mut snapshots: ResMut<SimulationSnapshots>,
+    pixie_count: Res<PixieCount>,
+    timeline: Res<SimulationTimeline>,
+    congestion: Res<Congestion>,
+    capacity: Res<TerminusCapacity>,
+    q_pixies: Query<(&Pixie, &Transform)>,
+    q_emitters: Query<(Entity, &PixieEmitter)>,
+) {
+    if *sim_state != SimulationState::Running {
+        return;
+    }
+
+    // A new run has started
+    if steps.step() == 1 {
+        snapshots.0.clear();
+    }
+
+    if steps.step() % SNAPSHOT_INTERVAL != 0 {
+        return;
+    }
+
+    snapshots.0.push_back(Snapshot {
+        step: steps.step(),
+        pixies: q_pixies
+            .iter()
+            .map(|(pixie, transform)| (pixie.clone(), *transform))
+            .collect(),
+        emitters: q_emitters
+            .iter()
+            .map(|(entity, emitter)| (entity, emitter.clone()))
+            .collect(),
+        pixie_count: pixie_count.0,
+        timeline: timeline.clone(),
+        congestion: congestion.clone(),
+        capacity: capacity.clone(),
+    });
+
+    if snapshots.0.len() > MAX_SNAPSHOTS {
+        snapshots.0.pop_front();
+    }
+}
+
+fn rewind_system(
+    mut commands: Commands,
+    mut events: EventReader<RewindSimulation>,
+    sim_state: Res<SimulationState>,
+    mut steps: ResMut<SimulationSteps>,
+    mut snapshots: ResMut<SimulationSnapshots>,
+    mut pixie_count: ResMut<PixieCount>,
+    mut timeline: ResMut<SimulationTimeline>,
+    mut congestion: ResMut<Congestion>,
+    mut capacity: ResMut<TerminusCapacity>,
+    q_pixies: Query<Entity, With<Pixie>>,
+    pixie_meshes: Res<PixieMeshes>,
+    mut q_emitters: Query<&mut PixieEmitter>,
+) {
+    // Pixies restored by an earlier request wouldn't be despawned by a later one in
+    // the same frame, so only honor the last.
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    if *sim_state != SimulationState::Running {
+        return;
+    }
+
+    let ticks = (event.seconds / SIMULATION_TIMESTEP).round() as u32;
+    let target = steps.step().saturating_sub(ticks);
+
+    // Use the latest snapshot at or before the target, or the earliest we have.
+    let Some(index) = snapshots
+        .0
+        .iter()
+        .rposition(|s| s.step <= target)
+        .or_else(|| (!snapshots.0.is_empty()).then_some(0))
+    else {
+        return;
+    };
+
+    snapshots.0.truncate(index + 1);
+    let snapshot = &snapshots.0[index];
+
+    for entity in q_pixies.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (pixie, transform) in snapshot.pixies.iter() {
+        spawn_pixie(&mut commands, &pixie_meshes, pixie.clone(), *transform);
+    }
+
+    for (entity, emitter) in snapshot.emitters.iter() {
+        if let Ok(mut current) = q_emitters.get_mut(*entity) {
+            *current = emitter.clone();
+        }
+    }
+
+    pixie_count.0 = snapshot.pixie_count;
+    *timeline = snapshot.timeline.clone();
+    *congestion = snapshot.congestion.clone();
+    *capacity = snapshot.capacity.clone();
+    steps.rewind_to(snapshot.step);
+}
@@ -0,0 +1,116 @@
+//! What a score is made of, and what it takes to earn each star. The thresholds are
+//! shown under the score while playing, and the score dialog spells out the formula
+//! with the numbers from the run.
+
+use crate::{
+    level::{Level, ScoreWeights},
+    AfterUpdate, Handles, Score, ScoreUi, SelectedLevel, SCORE_MULTIPLIER,
+};
+use bevy::prelude::*;
+use itertools::Itertools;
+
+pub struct StarsPlugin;
+impl Plugin for StarsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(AfterUpdate, star_threshold_text_system.in_set(ScoreUi));
+    }
+}
+
+#[derive(Component)]
+pub struct StarThresholdText;
+
+/// The score needed for each star after the first `earned`, like `★★Æ40 ★★★Æ60`.
+pub fn thresholds_label(thresholds: &[u32], earned: usize) -> String {
+    thresholds
+        .iter()
+        .enumerate()
+        .skip(earned)
+        .map(|(i, t)| format!("{}Æ{t}", "★".repeat(i + 1)))
+        .join(" ")
+}
+
+/// How a score was worked out, with the same symbols as the bottom bar. Parts that a
+/// level weights differently show their exponent.
+pub fn score_breakdown(pixies: u32, cost: u32, elapsed: f32, weights: &ScoreWeights) -> String {
+    let weighted = |term: String, weight: f32| {
+        if weight == 1.0 {
+            term
+        } else {
+            format!("{term}^{weight}")
+        }
+    };
+
+    format!(
+        "{} / {} / {} x {SCORE_MULTIPLIER}",
+        weighted(format!("₽{pixies}"), weights.pixies),
+        weighted(format!("§{cost}"), weights.cost),
+        weighted(format!("ŧ{elapsed:.1}"), weights.time),
+    )
+}
+
+fn star_threshold_text_system(
+    score: Res<Score>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    q_added: Query<(), Added<StarThresholdText>>,
+    mut q_text: Query<&mut Text, With<StarThresholdText>>,
+) {
+    if !score.is_changed() && q_added.is_empty() {
+        return;
+    }
+
+    let Some(level) = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+    else {
+        return;
+    };
+
+    // Once there's a score, only the stars still to earn are worth mentioning.
+    let earned = score.0.map_or(0, |score| {
+        level
+            .star_thresholds
+            .iter()
+            .filter(|t| **t <= score)
+            .count()
+    });
+
+    let label = if earned > 0 && earned == level.star_thresholds.len() {
+        "★".repeat(earned)
+    } else {
+        thresholds_label(&level.star_thresholds, earned)
+    };
+
+    for mut text in q_text.iter_mut() {
+        if text.0 != label {
+            text.0.clone_from(&label);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        assert_eq!(thresholds_label(&[20, 40, 60], 0), "★Æ20 ★★Æ40 ★★★Æ60");
+        assert_eq!(thresholds_label(&[20, 40, 60], 2), "★★★Æ60");
+        assert_eq!(thresholds_label(&[], 0), "");
+        let weights = ScoreWeights::default();
+        assert_eq!(
+            score_breakdown(30, 12, 4.5, &weights),
+            "₽30 / §12 / ŧ4.5 x 10000"
+        );
+        let weights = ScoreWeights {
+            time: 1.5,
+            ..weights
+        };
+        assert_eq!(
+            score_breakdown(30, 12, 4.5, &weights),
+            "₽30 / §12 / ŧ4.5^1.5 x 10000"
+        );
+    }
+}
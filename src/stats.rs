@@ -0,0 +1,176 @@
+//! Lifetime statistics, shown on the level select screen.
+//!
+//! Pixie deliveries and play time pile up quickly, so they're tallied here and only
+//! written to the save file now and then.
+
+use crate::{
+    color,
+    level_select::LevelSelectScreen,
+    save::{LevelMetadata, Statistics},
+    GameState, Handles, SelectedLevel,
+};
+use bevy::prelude::*;
+use itertools::Itertools;
+
+/// How often the tally is written to the save file while playing, in seconds.
+const FLUSH_INTERVAL: f32 = 30.0;
+
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StatisticsTally>();
+
+        app.add_systems(
+            Update,
+            tally_play_time_system.run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(OnEnter(GameState::Playing), start_tally_system);
+        app.add_systems(OnExit(GameState::Playing), flush_tally_system);
+        app.add_systems(OnEnter(GameState::LevelSelect), spawn_stats_panel_system);
+        app.add_systems(
+            Update,
+            stats_button_system.run_if(in_state(GameState::LevelSelect)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct StatsButton;
+#[derive(Component)]
+struct StatsPanel;
+
+/// Statistics that haven't been written to the save file yet.
+#[derive(Resource, Default)]
+pub struct StatisticsTally {
+    pub pixies_delivered: u64,
+    play_time: f32,
+    /// The level being played. `SelectedLevel` may already point at the next one
+    /// by the time the tally is flushed on the way out.
+    level_id: String,
+}
+
+impl Statistics {
+    /// Lines describing the player's history, for the stats panel.
+    pub fn lines(&self, metadata: &LevelMetadata) -> Vec<String> {
+        let attempts: u32 = metadata.0.values().map(|m| m.attempts).sum();
+        let total_time: f32 = self.play_time.values().sum();
+
+        let mut lines = vec![
+            format!("PIXIES DELIVERED {}", self.pixies_delivered),
+            format!("ROADS DRAWN {}", self.road_length.round()),
+            format!("NETS RIPPED {}", self.nets_ripped),
+            format!("ATTEMPTS {attempts}"),
+            format!("TIME PLAYED {}", duration(total_time)),
+        ];
+
+        for (id, seconds) in self
+            .play_time
+            .iter()
+            .sorted_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)))
+        {
+            let attempts = metadata.0.get(id).map_or(0, |m| m.attempts);
+            lines.push(format!("L{id} {} {attempts} ATTEMPTS", duration(*seconds)));
+        }
+
+        lines
+    }
+}
+
+fn duration(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    match seconds {
+        0..=59 => format!("{seconds}S"),
+        60..=3599 => format!("{}M", seconds / 60),
+        _ => format!("{}H {}M", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+fn start_tally_system(
+    mut tally: ResMut<StatisticsTally>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+) {
+    tally.level_id = handles.level_id(selected_level.0);
+}
+
+fn tally_play_time_system(
+    time: Res<Time>,
+    mut tally: ResMut<StatisticsTally>,
+    mut statistics: ResMut<Statistics>,
+) {
+    tally.play_time += time.delta_secs();
+
+    if tally.play_time >= FLUSH_INTERVAL {
+        flush(&mut tally, &mut statistics);
+    }
+}
+
+fn flush_tally_system(mut tally: ResMut<StatisticsTally>, mut statistics: ResMut<Statistics>) {
+    flush(&mut tally, &mut statistics);
+}
+
+fn flush(tally: &mut StatisticsTally, statistics: &mut Statistics) {
+    statistics.pixies_delivered += tally.pixies_delivered;
+    *statistics
+        .play_time
+        .entry(tally.level_id.clone())
+        .or_default() += tally.play_time;
+
+    tally.pixies_delivered = 0;
+    tally.play_time = 0.0;
+}
+
+fn spawn_stats_panel_system(
+    mut commands: Commands,
+    statistics: Res<Statistics>,
+    metadata: Res<LevelMetadata>,
+    handles: Res<Handles>,
+) {
+    commands.spawn((
+        Node {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(10.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        Text::new(statistics.lines(&metadata).join("\n")),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(color::UI_WHITE),
+        BackgroundColor(color::DIALOG_BACKGROUND),
+        GlobalZIndex(1),
+        LevelSelectScreen,
+        StatsPanel,
+    ));
+}
+
+fn stats_button_system(
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<StatsButton>)>,
+    mut q_panel: Query<&mut Node, With<StatsPanel>>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        for mut node in q_panel.iter_mut() {
+            node.display = match node.display {
+                Display::None => Display::Flex,
+                _ => Display::None,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(5.5), "5S");
+        assert_eq!(duration(150.0), "2M");
+        assert_eq!(duration(3600.0 * 2.0 + 60.0 * 5.0), "2H 5M");
+    }
+}
@@ -0,0 +1,247 @@
+//! A development tool that generates a synthetic worst-case board, automatically
+//! releases the pixies, and records how long each simulation tick takes.
+//!
+//! Enabled with the `stresstest` feature. Timings are written to
+//! `stress_test.csv` and the app exits when the simulation finishes.
+//!
+//! Note that the generated level is assigned the next free level number, so its
+//! solution and score will be written to the save file like any other level.
+
+use std::fmt::Write;
+
+use crate::{
+    bias::RouteBias,
+    level::{Level, Terminus},
+    pixie::{collide_pixies_system, explode_pixies_system, Pixie, PixieFlavor},
+    pixie_button_system,
+    save::{Solution, Solutions},
+    sim::{EmissionStrategy, SimulationSchedule, SimulationState},
+    spawn_emitters, GameState, Handles, PathfindingState, RoadSegment, SelectedLevel, GRID_SIZE,
+    PIXIES_PER_TERMINUS,
+};
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    utils::{Duration, HashSet, Instant},
+};
+
+/// Number of emitter/collector pairs. Each pair gets its own lane and flavor.
+const LANES: i32 = 31;
+/// Number of road segments making up each lane.
+const SEGMENTS_PER_LANE: i32 = 96;
+/// Half of the width of the board, in grid cells.
+const HALF_WIDTH: i32 = 24;
+
+const OUTPUT_PATH: &str = "stress_test.csv";
+
+pub struct StressTestPlugin;
+impl Plugin for StressTestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StressTest>();
+
+        app.add_systems(OnEnter(GameState::LevelSelect), stress_setup_system);
+        app.add_systems(
+            Update,
+            (
+                stress_release_system.before(pixie_button_system),
+                stress_finish_system,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+
+        app.add_systems(
+            SimulationSchedule,
+            (
+                stress_tick_start_system.before(collide_pixies_system),
+                stress_tick_end_system.after(explode_pixies_system),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct StressTest {
+    generated: bool,
+    released: bool,
+    finished: bool,
+    tick_start: Option<Instant>,
+    ticks: Vec<TickTiming>,
+}
+
+struct TickTiming {
+    duration: Duration,
+    pixies: usize,
+}
+
+fn stress_setup_system(
+    mut stress: ResMut<StressTest>,
+    mut handles: ResMut<Handles>,
+    mut levels: ResMut<Assets<Level>>,
+    mut solutions: ResMut<Solutions>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if stress.generated {
+        return;
+    }
+
+    let (level, segments) = generate();
+
+    info!(
+        "Generated stress test board with {} terminuses and {} segments",
+        level.terminuses.len(),
+        segments.len()
+    );
+
+    let level_hash = level.layout_hash();
+    handles.levels.push(levels.add(level));
+
+    let number = handles.levels.len() as u32;
+    solutions.0.insert(
+        handles.level_id(number),
+        Solution {
+            segments,
+            level_hash,
+        },
+    );
+    selected_level.0 = number;
+
+    stress.generated = true;
+    next_state.set(GameState::Playing);
+}
+
+/// Builds a board of parallel horizontal lanes, one per flavor, each made from many
+/// short collinear segments.
+fn generate() -> (Level, Vec<RoadSegment>) {
+    let mut terminuses = vec![];
+    let mut segments = vec![];
+
+    let start_x = -HALF_WIDTH as f32 * GRID_SIZE;
+    let segment_length = (HALF_WIDTH * 2) as f32 * GRID_SIZE / SEGMENTS_PER_LANE as f32;
+
+    for lane in 0..LANES {
+        let y = (lane - LANES / 2) as f32 * GRID_SIZE;
+
+        let flavor = PixieFlavor {
+            color: lane as u32 % 6,
+            net: lane as u32 / 6,
+        };
+
+        let mut emits = HashSet::default();
+        emits.insert(flavor);
+        let mut collects = HashSet::default();
+        collects.insert(flavor);
+
+        terminuses.push(Terminus {
+            point: Vec2::new(start_x, y),
+            emits,
+            collects: HashSet::default(),
+            ..default()
+        });
+        terminuses.push(Terminus {
+            point: Vec2::new(-start_x, y),
+            emits: HashSet::default(),
+            collects,
+            ..default()
+        });
+
+        for i in 0..SEGMENTS_PER_LANE {
+            segments.push(RoadSegment {
+                points: (
+                    Vec2::new(start_x + i as f32 * segment_length, y),
+                    Vec2::new(start_x + (i + 1) as f32 * segment_length, y),
+                ),
+                layer: 1,
+                wide: false,
+                bias: RouteBias::default(),
+            });
+        }
+    }
+
+    let level = Level {
+        name: "Stress Test".to_string(),
+        name_position: Vec2::new(-624.0, 336.0),
+        layers: 1,
+        terminuses,
+        obstacles: vec![],
+        star_thresholds: vec![1, 2, 3],
+        via_cost: 0.0,
+        max_segment_length: None,
+        scoring: default(),
+        pixies: PIXIES_PER_TERMINUS,
+        converters: vec![],
+        tutorial: vec![],
+    };
+
+    (level, segments)
+}
+
+fn stress_release_system(
+    mut commands: Commands,
+    mut stress: ResMut<StressTest>,
+    mut sim_state: ResMut<SimulationState>,
+    pathfinding: Res<PathfindingState>,
+) {
+    if !stress.generated || stress.released {
+        return;
+    }
+
+    if *sim_state != SimulationState::NotStarted || !pathfinding.valid {
+        return;
+    }
+
+    spawn_emitters(
+        &mut commands,
+        &pathfinding,
+        EmissionStrategy::default(),
+        false,
+        |_| Terminus::default().schedule(PIXIES_PER_TERMINUS),
+    );
+    *sim_state = SimulationState::Running;
+
+    stress.released = true;
+}
+
+fn stress_tick_start_system(mut stress: ResMut<StressTest>) {
+    stress.tick_start = Some(Instant::now());
+}
+
+fn stress_tick_end_system(mut stress: ResMut<StressTest>, q_pixies: Query<(), With<Pixie>>) {
+    let Some(start) = stress.tick_start.take() else {
+        return;
+    };
+
+    let timing = TickTiming {
+        duration: start.elapsed(),
+        pixies: q_pixies.iter().count(),
+    };
+
+    stress.ticks.push(timing);
+}
+
+fn stress_finish_system(
+    mut stress: ResMut<StressTest>,
+    sim_state: Res<SimulationState>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if !stress.released || stress.finished || *sim_state != SimulationState::Finished {
+        return;
+    }
+
+    let mut csv = "tick,micros,pixies\n".to_string();
+    for (i, tick) in stress.ticks.iter().enumerate() {
+        let _ = writeln!(csv, "{},{},{}", i, tick.duration.as_micros(), tick.pixies);
+    }
+
+    match std::fs::write(OUTPUT_PATH, csv) {
+        Ok(_) => info!(
+            "Wrote {} tick timings to {}",
+            stress.ticks.len(),
+            OUTPUT_PATH
+        ),
+        Err(e) => error!("Failed to write {}: {}", OUTPUT_PATH, e),
+    }
+
+    stress.finished = true;
+    exit.send(AppExit::Success);
+}
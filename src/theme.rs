@@ -0,0 +1,119 @@
+//! Palettes for the colors that carry meaning, like which pixies go where, so that
+//! they can be swapped for ones that are easier to tell apart.
+//!
+//! The built-in palette is always available. Others are loaded from `assets/themes`.
+
+use crate::{restart::Restart, save::ThemeChoice, GameState, Handles};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Themes loaded from `assets/themes`, in the order they're cycled through.
+pub const THEME_FILES: [&str; 1] = ["colorblind"];
+
+pub struct ThemePlugin;
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>();
+
+        app.add_systems(OnExit(GameState::Loading), apply_theme_choice_system);
+        app.add_systems(
+            Update,
+            (theme_button_system, theme_button_text_system)
+                .chain()
+                .run_if(in_state(GameState::LevelSelect)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct ThemeButton;
+
+#[derive(Asset, Resource, Clone, Debug, Deserialize, TypePath)]
+pub struct Theme {
+    pub name: String,
+    /// Pixie colors, indexed by flavor color, as sRGB components.
+    pixie: [(f32, f32, f32); 6],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "DEFAULT".to_string(),
+            pixie: [
+                (0.498, 1.0, 0.831),
+                (1.0, 0.078, 0.576),
+                (1.0, 0.647, 0.0),
+                (0.502, 0.0, 0.502),
+                (0.0, 0.392, 0.0),
+                (1.0, 1.0, 0.0),
+            ],
+        }
+    }
+}
+
+impl Theme {
+    pub fn pixie(&self, color: u32) -> Srgba {
+        let (r, g, b) = self.pixie[color as usize % self.pixie.len()];
+        Srgba::rgb(r, g, b)
+    }
+}
+
+/// The built-in theme, followed by any that loaded.
+fn available_themes(handles: &Handles, themes: &Assets<Theme>) -> Vec<Theme> {
+    std::iter::once(Theme::default())
+        .chain(handles.themes.iter().filter_map(|h| themes.get(h)).cloned())
+        .collect()
+}
+
+fn apply_theme_choice_system(
+    mut commands: Commands,
+    choice: Res<ThemeChoice>,
+    handles: Res<Handles>,
+    themes: Res<Assets<Theme>>,
+) {
+    if let Some(theme) = available_themes(&handles, &themes)
+        .into_iter()
+        .find(|t| t.name == choice.0)
+    {
+        commands.insert_resource(theme);
+    }
+}
+
+fn theme_button_system(
+    q_interaction: Query<&Interaction, (Changed<Interaction>, With<ThemeButton>)>,
+    mut theme: ResMut<Theme>,
+    mut choice: ResMut<ThemeChoice>,
+    mut restart: Restart,
+    handles: Res<Handles>,
+    themes: Res<Assets<Theme>>,
+) {
+    for _ in q_interaction.iter().filter(|i| **i == Interaction::Pressed) {
+        let available = available_themes(&handles, &themes);
+        let current = available
+            .iter()
+            .position(|t| t.name == theme.name)
+            .unwrap_or_default();
+
+        *theme = available[(current + 1) % available.len()].clone();
+        choice.0.clone_from(&theme.name);
+
+        // Rebuild the level select screen in the new colors.
+        restart.set(GameState::LevelSelect);
+    }
+}
+
+fn theme_button_text_system(
+    theme: Res<Theme>,
+    q_button: Query<&Children, With<ThemeButton>>,
+    mut q_text: Query<&mut Text>,
+) {
+    for children in q_button.iter() {
+        let mut iter = q_text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            let label = format!("THEME {}", theme.name);
+            if text.0 != label {
+                text.0 = label;
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! A panel beside the cursor describing the terminus under it: each flavor it emits or
+//! collects, by net and color, and once the pixies are released, how many it has
+//! emitted and collected so far. The labels on the board get hard to read on busy
+//! levels.
+
+use crate::{
+    color,
+    level::{Terminus, TERMINUS_LABEL_SPACING},
+    playing_enter_system,
+    sim::{SimulationPaused, SimulationState, SimulationTimeline, TerminusTally},
+    theme::Theme,
+    GameState, Handles, LineDrawingState, MouseState,
+};
+use bevy::prelude::*;
+use itertools::Itertools;
+
+/// How close to a terminus the cursor has to be for it to count as hovered.
+const TERMINUS_HOVER_RADIUS: f32 = 12.0;
+/// How far either side of a terminus its labels reach.
+const LABEL_HALF_WIDTH: f32 = 30.0;
+
+pub struct TooltipPlugin;
+impl Plugin for TooltipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_tooltip_system.after(playing_enter_system),
+        );
+        app.add_systems(
+            Update,
+            terminus_tooltip_system.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct TerminusTooltip;
+#[derive(Component)]
+struct TooltipCounts;
+
+/// Whether `cursor` is over the terminus or its labels, which hang below it.
+fn over_terminus(terminus: &Terminus, cursor: Vec2) -> bool {
+    let offset = cursor - terminus.point;
+    let labels = (terminus.emits.len() + terminus.collects.len()) as f32;
+
+    offset.length() <= TERMINUS_HOVER_RADIUS
+        || (offset.x.abs() <= LABEL_HALF_WIDTH
+            && offset.y <= 0.0
+            && offset.y >= -(labels + 0.5) * TERMINUS_LABEL_SPACING)
+}
+
+/// How many pixies the terminus has dealt with so far, one line for each thing it does.
+fn counts_label(terminus: &Terminus, tally: TerminusTally) -> String {
+    let mut lines = vec![];
+    if !terminus.emits.is_empty() {
+        lines.push(format!("EMITTED {}", tally.emitted));
+    }
+    if !terminus.collects.is_empty() {
+        lines.push(format!("COLLECTED {}", tally.collected));
+    }
+    lines.join("\n")
+}
+
+fn spawn_tooltip_system(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            padding: UiRect::all(Val::Px(8.)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.),
+            ..default()
+        },
+        BackgroundColor(color::DIALOG_BACKGROUND),
+        GlobalZIndex(1),
+        TerminusTooltip,
+    ));
+}
+
+fn terminus_tooltip_system(
+    mut commands: Commands,
+    mut shown: Local<Option<Entity>>,
+    mouse: Res<MouseState>,
+    line_state: Res<LineDrawingState>,
+    paused: Res<SimulationPaused>,
+    sim_state: Res<SimulationState>,
+    timeline: Res<SimulationTimeline>,
+    handles: Res<Handles>,
+    theme: Res<Theme>,
+    q_terminuses: Query<(Entity, &Terminus)>,
+    mut q_tooltip: Query<(Entity, &mut Node), With<TerminusTooltip>>,
+    mut q_counts: Query<&mut Text, With<TooltipCounts>>,
+) {
+    let Ok((tooltip, mut node)) = q_tooltip.get_single_mut() else {
+        return;
+    };
+
+    // Out of the way while drawing, and hidden behind the pause menu and score dialog.
+    let hovered = q_terminuses
+        .iter()
+        .find(|(_, t)| over_terminus(t, mouse.position))
+        .filter(|_| !line_state.drawing && !paused.0 && *sim_state != SimulationState::Finished);
+
+    let Some((entity, terminus)) = hovered else {
+        node.display = Display::None;
+        *shown = None;
+        return;
+    };
+
+    node.display = Display::Flex;
+    node.left = Val::Px(mouse.window_position.x + 16.);
+    node.top = Val::Px(mouse.window_position.y + 16.);
+
+    let counts = if *sim_state == SimulationState::NotStarted {
+        String::new()
+    } else {
+        let tally = timeline
+            .terminuses
+            .get(&terminus.point.round().as_ivec2())
+            .copied()
+            .unwrap_or_default();
+        counts_label(terminus, tally)
+    };
+
+    if *shown == Some(entity) {
+        for mut text in q_counts.iter_mut() {
+            if text.0 != counts {
+                text.0.clone_from(&counts);
+            }
+        }
+        return;
+    }
+    *shown = Some(entity);
+
+    let text_font = TextFont {
+        font: handles.fonts[0].clone(),
+        font_size: 18.0,
+        ..default()
+    };
+
+    let rows = terminus
+        .emits
+        .iter()
+        .sorted()
+        .map(|f| (f, "EMITS"))
+        .chain(terminus.collects.iter().sorted().map(|f| (f, "COLLECTS")));
+
+    commands.entity(tooltip).despawn_descendants();
+    commands.entity(tooltip).with_children(|parent| {
+        for (flavor, verb) in rows {
+            let mut label = format!("{verb} NET {}", flavor.net + 1);
+            if let Some(capacity) = terminus.capacity.filter(|_| verb == "COLLECTS") {
+                label.push_str(&format!(" AT {capacity}/S"));
+            }
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(8.),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Node {
+                            width: Val::Px(12.),
+                            height: Val::Px(12.),
+                            ..default()
+                        },
+                        BackgroundColor(theme.pixie(flavor.color).into()),
+                    ));
+                    parent.spawn((
+                        Text::new(label),
+                        text_font.clone(),
+                        TextColor(color::UI_WHITE),
+                    ));
+                });
+        }
+
+        parent.spawn((
+            Text::new(counts),
+            text_font.clone(),
+            TextColor(color::UI_GREY_RED),
+            TooltipCounts,
+        ));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixie::PixieFlavor;
+
+    #[test]
+    fn hover() {
+        let terminus = Terminus {
+            point: Vec2::new(48.0, 48.0),
+            emits: [PixieFlavor::default()].into_iter().collect(),
+            collects: [PixieFlavor { color: 1, net: 1 }].into_iter().collect(),
+            ..default()
+        };
+
+        assert!(over_terminus(&terminus, Vec2::new(52.0, 50.0)));
+        // On its second label.
+        assert!(over_terminus(
+            &terminus,
+            Vec2::new(60.0, 48.0 - 2.0 * TERMINUS_LABEL_SPACING)
+        ));
+        assert!(!over_terminus(&terminus, Vec2::new(48.0, 80.0)));
+        assert!(!over_terminus(
+            &terminus,
+            Vec2::new(48.0, 48.0 - 3.0 * TERMINUS_LABEL_SPACING)
+        ));
+
+        assert_eq!(
+            counts_label(
+                &terminus,
+                TerminusTally {
+                    emitted: 3,
+                    collected: 1
+                }
+            ),
+            "EMITTED 3\nCOLLECTED 1"
+        );
+    }
+}
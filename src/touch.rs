@@ -0,0 +1,264 @@
+//! Touch controls for tablets. A finger stands in for the mouse: dragging previews,
+//! tapping places or confirms, and a long press picks up the net ripping tool and
+//! rips whatever is under the finger. Two fingers pinch to zoom and pan.
+//!
+//! The camera can also be zoomed in on a point with `FocusCamera`. It zooms back out
+//! when the pixies are released.
+
+use crate::{
+    mouse_movement_system, playing_enter_system, radio_button::RadioButton, sim::SimulationState,
+    snap_to_grid, viewport::Viewport, DrawingInput, DrawingMode, DrawingState, GameState,
+    MainCamera, MouseState, NetRippingButton, GRID_SIZE,
+};
+use bevy::{input::touch::Touches, prelude::*};
+
+/// How far a finger may wander, in logical pixels, and still count as a tap.
+const TAP_SLOP: f32 = 12.0;
+/// How long a finger must be held still to count as a long press, in seconds.
+const LONG_PRESS: f32 = 0.6;
+/// The furthest the camera can zoom in, as a projection scale.
+const MIN_ZOOM: f32 = 0.4;
+/// How far `FocusCamera` zooms in, as a projection scale.
+const FOCUS_ZOOM: f32 = 0.5;
+
+pub struct TouchPlugin;
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchGesture>();
+        app.add_event::<FocusCamera>();
+
+        app.add_systems(
+            Update,
+            (touch_system, pinch_system)
+                .chain()
+                .after(mouse_movement_system)
+                .in_set(DrawingInput),
+        );
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            reset_zoom_system.after(playing_enter_system),
+        );
+        app.add_systems(
+            Update,
+            (
+                reset_zoom_system.run_if(
+                    resource_changed::<SimulationState>
+                        .and(resource_equals(SimulationState::Running)),
+                ),
+                focus_camera_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Zooms the camera in on a point on the board.
+#[derive(Event)]
+pub struct FocusCamera(pub Vec2);
+
+#[derive(Resource, Default)]
+struct TouchGesture {
+    /// The finger standing in for the mouse.
+    primary: Option<PrimaryTouch>,
+    pinch: Option<Pinch>,
+}
+
+struct PrimaryTouch {
+    id: u64,
+    start: Vec2,
+    /// When the finger went down, in seconds since startup.
+    started: f32,
+    /// Whether the finger has wandered too far to be a tap or long press.
+    moved: bool,
+    long_pressed: bool,
+}
+
+struct Pinch {
+    distance: f32,
+    scale: f32,
+    /// The point on the board that stays between the fingers.
+    anchor: Vec2,
+}
+
+fn touch_system(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    mut gesture: ResMut<TouchGesture>,
+    mut mouse: ResMut<MouseState>,
+    mut mouse_input: ResMut<ButtonInput<MouseButton>>,
+    mut drawing_state: ResMut<DrawingState>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    q_net_ripping_button: Query<Entity, With<NetRippingButton>>,
+    mut q_radio_button: Query<&mut RadioButton>,
+) {
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+
+    // A second finger starts a pinch, which shouldn't also draw.
+    if touches.iter().count() > 1 {
+        gesture.primary = None;
+        return;
+    }
+
+    if let Some(touch) = touches.iter_just_pressed().next() {
+        gesture.primary = Some(PrimaryTouch {
+            id: touch.id(),
+            start: touch.position(),
+            started: time.elapsed_secs(),
+            moved: false,
+            long_pressed: false,
+        });
+    }
+
+    let Some(primary) = gesture.primary.as_mut() else {
+        return;
+    };
+
+    let (position, released) = if let Some(touch) = touches.get_pressed(primary.id) {
+        (touch.position(), false)
+    } else if let Some(touch) = touches.get_released(primary.id) {
+        (touch.position(), true)
+    } else {
+        gesture.primary = None;
+        return;
+    };
+
+    if position.distance(primary.start) > TAP_SLOP {
+        primary.moved = true;
+    }
+
+    if let Ok(pos) = camera.viewport_to_world_2d(camera_transform, position) {
+        mouse.position = pos;
+
+        let new = snap_to_grid(pos, GRID_SIZE);
+        if mouse.snapped != new {
+            mouse.snapped = new;
+        }
+
+        mouse.window_position = position;
+    }
+
+    let still = !primary.moved && !primary.long_pressed;
+
+    if still && !released && time.elapsed_secs() - primary.started >= LONG_PRESS {
+        primary.long_pressed = true;
+
+        if !matches!(drawing_state.mode, DrawingMode::NetRipping) {
+            drawing_state.mode = DrawingMode::NetRipping;
+        }
+        if let Ok(ent) = q_net_ripping_button.get_single() {
+            if let Ok(mut radio) = q_radio_button.get_mut(ent) {
+                radio.selected = true;
+            }
+        }
+
+        click(&mut mouse_input);
+    }
+
+    if released {
+        if still {
+            click(&mut mouse_input);
+        }
+        gesture.primary = None;
+    }
+}
+
+/// Pretends that the left mouse button was clicked this frame.
+pub fn click(mouse_input: &mut ButtonInput<MouseButton>) {
+    mouse_input.press(MouseButton::Left);
+    mouse_input.release(MouseButton::Left);
+}
+
+fn pinch_system(
+    touches: Res<Touches>,
+    mut gesture: ResMut<TouchGesture>,
+    viewport: Res<Viewport>,
+    q_window: Query<&Window>,
+    mut q_camera: Query<
+        (
+            &Camera,
+            &GlobalTransform,
+            &mut Transform,
+            &mut OrthographicProjection,
+        ),
+        With<MainCamera>,
+    >,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform, mut transform, mut projection)) = q_camera.get_single_mut()
+    else {
+        return;
+    };
+
+    let mut fingers = touches.iter();
+    let (Some(a), Some(b)) = (fingers.next(), fingers.next()) else {
+        gesture.pinch = None;
+        return;
+    };
+
+    let distance = a.position().distance(b.position()).max(1.0);
+    let midpoint = (a.position() + b.position()) / 2.0;
+
+    let home = viewport.home;
+
+    let Some(pinch) = gesture.pinch.as_ref() else {
+        if let Ok(anchor) = camera.viewport_to_world_2d(camera_transform, midpoint) {
+            gesture.pinch = Some(Pinch {
+                distance,
+                scale: projection.scale,
+                anchor,
+            });
+        }
+        return;
+    };
+
+    let scale = (pinch.scale * pinch.distance / distance).clamp(MIN_ZOOM, 1.0);
+
+    // Keep the anchor under the fingers, without letting the view leave the board.
+    let half_size = window.size() / 2.0;
+    let offset = (midpoint - half_size) * Vec2::new(1.0, -1.0) * scale / viewport.pixels_per_unit;
+    let slack = half_size / viewport.pixels_per_unit * (1.0 - scale);
+    let target = (pinch.anchor - offset).clamp(home.truncate() - slack, home.truncate() + slack);
+
+    projection.scale = scale;
+    transform.translation = target.extend(home.z);
+}
+
+fn focus_camera_system(
+    mut events: EventReader<FocusCamera>,
+    viewport: Res<Viewport>,
+    q_window: Query<&Window>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let Some(FocusCamera(point)) = events.read().last() else {
+        return;
+    };
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    let Ok((mut transform, mut projection)) = q_camera.get_single_mut() else {
+        return;
+    };
+
+    let home = viewport.home;
+
+    let slack = window.size() / 2.0 / viewport.pixels_per_unit * (1.0 - FOCUS_ZOOM);
+    let target = point.clamp(home.truncate() - slack, home.truncate() + slack);
+
+    projection.scale = FOCUS_ZOOM;
+    transform.translation = target.extend(home.z);
+}
+
+fn reset_zoom_system(
+    viewport: Res<Viewport>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    for (mut transform, mut projection) in q_camera.iter_mut() {
+        transform.translation = viewport.home;
+        projection.scale = 1.0;
+    }
+}
@@ -0,0 +1,194 @@
+//! Hints for new players, scripted in the level file. Each step shows a bit of text
+//! next to a point on the board, and moves on to the next step once the player does
+//! what it asks.
+
+use crate::{
+    color, layer, level::Level, playing_enter_system, sim::SimulationState, GameState, Handles,
+    LineDrawingState, RoadSegment, SelectedLevel,
+};
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_prototype_lyon::prelude::*;
+use serde::Deserialize;
+
+/// Radius of the ring drawn around the point a hint is about.
+const RING_RADIUS: f32 = 14.0;
+
+pub struct TutorialPlugin;
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TutorialState>();
+
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            reset_tutorial_system.after(playing_enter_system),
+        );
+        app.add_systems(
+            Update,
+            (advance_tutorial_system, draw_tutorial_system)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TutorialStep {
+    pub text: String,
+    /// Where on the board the hint points.
+    pub point: Vec2,
+    pub until: TutorialGoal,
+}
+
+/// What the player needs to do to move on from a step.
+#[derive(Deserialize, Debug, Clone)]
+pub enum TutorialGoal {
+    /// Start drawing a road at this point.
+    StartAt(Vec2),
+    /// Finish a road at this point.
+    RoadTo(Vec2),
+    /// Switch to this layer.
+    Layer(u32),
+    /// Release the pixies.
+    Release,
+}
+
+impl TutorialGoal {
+    fn met(
+        &self,
+        line_drawing: &LineDrawingState,
+        segments: &[&RoadSegment],
+        sim_state: &SimulationState,
+    ) -> bool {
+        match self {
+            Self::StartAt(point) => line_drawing.drawing && line_drawing.start == *point,
+            Self::RoadTo(point) => segments
+                .iter()
+                .any(|s| s.points.0 == *point || s.points.1 == *point),
+            Self::Layer(layer) => line_drawing.layer == *layer,
+            Self::Release => *sim_state != SimulationState::NotStarted,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct TutorialState {
+    /// The step being shown, or past the end when the tutorial is over.
+    step: usize,
+}
+
+#[derive(Component)]
+struct TutorialHint;
+
+fn reset_tutorial_system(mut tutorial: ResMut<TutorialState>) {
+    tutorial.step = 0;
+}
+
+fn current_level<'a>(
+    levels: &'a Assets<Level>,
+    handles: &Handles,
+    selected_level: &SelectedLevel,
+) -> Option<&'a Level> {
+    handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+}
+
+fn advance_tutorial_system(
+    mut tutorial: ResMut<TutorialState>,
+    levels: Res<Assets<Level>>,
+    handles: Res<Handles>,
+    selected_level: Res<SelectedLevel>,
+    line_drawing: Res<LineDrawingState>,
+    sim_state: Res<SimulationState>,
+    q_segments: Query<&RoadSegment>,
+) {
+    let Some(level) = current_level(&levels, &handles, &selected_level) else {
+        return;
+    };
+
+    let Some(step) = level.tutorial.get(tutorial.step) else {
+        return;
+    };
+
+    let segments: Vec<_> = q_segments.iter().collect();
+
+    if step.until.met(&line_drawing, &segments, &sim_state) {
+        tutorial.step += 1;
+    }
+}
+
+fn draw_tutorial_system(
+    mut commands: Commands,
+    tutorial: Res<TutorialState>,
+    levels: Res<Assets<Level>>,
+    handles: Res<Handles>,
+    selected_level: Res<SelectedLevel>,
+    q_hints: Query<Entity, With<TutorialHint>>,
+) {
+    if !tutorial.is_changed() {
+        return;
+    }
+
+    for entity in q_hints.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(step) = current_level(&levels, &handles, &selected_level)
+        .and_then(|level| level.tutorial.get(tutorial.step))
+    else {
+        return;
+    };
+
+    commands.spawn((
+        ShapeBundle {
+            path: GeometryBuilder::build_as(&shapes::Circle {
+                radius: RING_RADIUS,
+                center: step.point,
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, layer::ROAD_OVERLAY),
+            ..default()
+        },
+        Stroke::new(color::UI_WHITE, 2.0),
+        TutorialHint,
+    ));
+
+    commands.spawn((
+        Text2d::new(step.text.clone()),
+        TextFont {
+            font: handles.fonts[0].clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(color::UI_WHITE),
+        Anchor::BottomCenter,
+        Transform::from_translation(
+            (step.point + Vec2::Y * (RING_RADIUS + 6.0)).extend(layer::ROAD_OVERLAY),
+        ),
+        TutorialHint,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_steps() {
+        let steps: Vec<TutorialStep> = ron::from_str(
+            r#"[
+                TutorialStep(
+                    text: "START HERE",
+                    point: Vec2(-240.0, 48.0),
+                    until: StartAt(Vec2(-240.0, 48.0)),
+                ),
+                TutorialStep(text: "GO", point: Vec2(0.0, 0.0), until: Release),
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(steps[0].until, TutorialGoal::StartAt(p) if p == Vec2::new(-240.0, 48.0)));
+        assert!(matches!(steps[1].until, TutorialGoal::Release));
+    }
+}
@@ -0,0 +1,230 @@
+//! Checking a solution from the command line, without a window, for level designers
+//! making sure that star thresholds can be reached and for automated checks on
+//! community levels.
+//!
+//! Enabled with the `validate` feature, and run with a level and a file holding a
+//! share code for it, like `cargo run --release --features validate -- 3 solution.txt`.
+//! The level can be a level number or a level id. The solution is checked the same
+//! way as an imported one, then run to the end as fast as it can go, and the result is printed to stdout as a single line of JSON.
+//! As with the benchmark, the solution replaces the one saved for the level.
+
+use crate::{
+    controls::{Action, ActionPressed},
+    level::Level,
+    save::Solutions,
+    share::{decode, validate},
+    sim::{
+        run_simulation, SimulationSchedule, SimulationState, SimulationSteps, SIMULATION_TIMESTEP,
+    },
+    AfterUpdate, Cost, GameState, Handles, PathfindingState, PixieCount, Score, ScoreCalc,
+    SelectedLevel,
+};
+use bevy::{app::AppExit, prelude::*};
+use itertools::Itertools;
+
+/// How many frames to wait for the roads to make a valid solution before giving up.
+const SETTLE_FRAMES: u32 = 30;
+/// Runs that haven't finished after this many seconds of simulation are given up on.
+const MAX_SECONDS: f32 = 600.0;
+
+pub struct ValidatePlugin;
+impl Plugin for ValidatePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Validation::from_args(std::env::args().skip(1)));
+
+        app.add_systems(OnEnter(GameState::LevelSelect), validate_setup_system);
+        app.add_systems(
+            Update,
+            (
+                validate_release_system.run_if(in_state(GameState::Playing)),
+                validate_run_system.after(run_simulation),
+            ),
+        );
+        app.add_systems(
+            AfterUpdate,
+            validate_report_system
+                .after(ScoreCalc)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Validation {
+    level: Option<String>,
+    solution: Option<String>,
+    started: bool,
+    frames: u32,
+    released: bool,
+    finished: bool,
+}
+
+impl Validation {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        Self {
+            level: args.next(),
+            solution: args.next(),
+            ..default()
+        }
+    }
+}
+
+/// Prints a failure as JSON and exits.
+fn fail(exit: &mut EventWriter<AppExit>, reason: &str) {
+    println!("{{\"valid\":false,\"error\":{reason:?}}}");
+    exit.send(AppExit::error());
+}
+
+fn validate_setup_system(
+    mut validation: ResMut<Validation>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    mut solutions: ResMut<Solutions>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if validation.started {
+        return;
+    }
+    validation.started = true;
+
+    let (Some(level), Some(path)) = (validation.level.clone(), validation.solution.clone()) else {
+        fail(&mut exit, "usage: <level number or id> <solution file>");
+        return;
+    };
+
+    let levels = 1..=handles.levels.len() as u32;
+    let Some(number) = level
+        .parse::<u32>()
+        .ok()
+        .filter(|n| levels.contains(n))
+        .or_else(|| levels.clone().find(|n| handles.level_id(*n) == level))
+    else {
+        fail(&mut exit, &format!("no such level: {level}"));
+        return;
+    };
+
+    let code = match std::fs::read_to_string(&path) {
+        Ok(code) => code,
+        Err(e) => {
+            fail(&mut exit, &format!("couldn't read {path}: {e}"));
+            return;
+        }
+    };
+
+    let (id, solution) = match decode(code.trim()) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            fail(&mut exit, e.reason());
+            return;
+        }
+    };
+
+    if id != handles.level_id(number) {
+        fail(&mut exit, &format!("the solution is for level {id}"));
+        return;
+    }
+
+    // Levels are loaded before leaving the loading screen.
+    if let Some(level) = levels.get(&handles.levels[number as usize - 1]) {
+        if let Err(e) = validate(level, &solution) {
+            fail(&mut exit, e.reason());
+            return;
+        }
+    }
+
+    solutions.0.insert(id, solution);
+    selected_level.0 = number;
+    next_state.set(GameState::Playing);
+}
+
+fn validate_release_system(
+    mut validation: ResMut<Validation>,
+    pathfinding: Res<PathfindingState>,
+    mut actions: EventWriter<ActionPressed>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if validation.released || validation.finished {
+        return;
+    }
+
+    if pathfinding.valid {
+        actions.send(ActionPressed(Action::Release));
+        validation.released = true;
+        return;
+    }
+
+    validation.frames += 1;
+    if validation.frames > SETTLE_FRAMES {
+        validation.finished = true;
+        fail(&mut exit, "the solution doesn't connect every terminus");
+    }
+}
+
+fn validate_run_system(world: &mut World) {
+    let validation = world.resource::<Validation>();
+    if !validation.released || validation.finished {
+        return;
+    }
+
+    let max_ticks = (MAX_SECONDS / SIMULATION_TIMESTEP) as u32;
+    let mut ticks = 0;
+
+    while *world.resource::<SimulationState>() == SimulationState::Running && ticks < max_ticks {
+        world.resource_mut::<SimulationSteps>().advance();
+        world.run_schedule(SimulationSchedule);
+        ticks += 1;
+    }
+
+    if *world.resource::<SimulationState>() == SimulationState::Running {
+        world.resource_mut::<Validation>().finished = true;
+        println!(
+            "{{\"valid\":false,\"error\":\"the run didn't finish in {MAX_SECONDS} seconds\"}}"
+        );
+        world.send_event(AppExit::error());
+    }
+}
+
+fn validate_report_system(
+    mut validation: ResMut<Validation>,
+    sim_state: Res<SimulationState>,
+    score: Res<Score>,
+    cost: Res<Cost>,
+    pixie_count: Res<PixieCount>,
+    sim_steps: Res<SimulationSteps>,
+    selected_level: Res<SelectedLevel>,
+    handles: Res<Handles>,
+    levels: Res<Assets<Level>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if validation.finished || *sim_state != SimulationState::Finished {
+        return;
+    }
+
+    let Some(score) = score.0 else {
+        return;
+    };
+    validation.finished = true;
+
+    let thresholds = handles
+        .levels
+        .get(selected_level.0 as usize - 1)
+        .and_then(|h| levels.get(h))
+        .map(|level| level.star_thresholds.clone())
+        .unwrap_or_default();
+    let stars = thresholds.iter().filter(|t| **t <= score).count();
+
+    println!(
+        "{{\"valid\":true,\"level\":{:?},\"score\":{},\"stars\":{},\"star_thresholds\":[{}],\"pixies\":{},\"cost\":{},\"elapsed\":{:.3}}}",
+        handles.level_id(selected_level.0),
+        score,
+        stars,
+        thresholds.iter().join(","),
+        pixie_count.0,
+        cost.0,
+        sim_steps.get_elapsed_f32()
+    );
+
+    exit.send(AppExit::Success);
+}
@@ -0,0 +1,104 @@
+//! Vias, where a road carries on from one layer to another. Roads on different layers
+//! that end at the same point are joined in the road graph, so the layer can be
+//! changed part way through drawing a line. Each via is marked on the board and adds
+//! the level's `via_cost` to the cost of a solution.
+
+use crate::{
+    color, layer, level::Terminus, AfterUpdate, GameState, RoadGraph, RoadSegment, ScoreCalc,
+};
+use bevy::{prelude::*, utils::HashMap};
+use bevy_prototype_lyon::prelude::*;
+
+pub struct ViasPlugin;
+impl Plugin for ViasPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            AfterUpdate,
+            via_marker_system
+                .after(ScoreCalc)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct ViaMarker;
+
+/// The points where road ends on more than one layer meet, given the end points of
+/// every road and the layer it's on. Terminuses are on every layer already, so they
+/// don't need vias.
+pub fn via_points(ends: impl IntoIterator<Item = (Vec2, u32)>, terminuses: &[Vec2]) -> Vec<Vec2> {
+    let mut layers: HashMap<IVec2, (Vec2, u32)> = HashMap::default();
+
+    for (point, layer) in ends {
+        layers
+            .entry(point.round().as_ivec2())
+            .or_insert((point, 0))
+            .1 |= 1 << layer;
+    }
+
+    let mut points: Vec<_> = layers
+        .into_values()
+        .filter(|(point, mask)| mask.count_ones() > 1 && !terminuses.contains(point))
+        .map(|(point, _)| point)
+        .collect();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+    points
+}
+
+fn via_marker_system(
+    mut commands: Commands,
+    graph: Res<RoadGraph>,
+    q_segments: Query<&RoadSegment>,
+    q_terminuses: Query<&Terminus>,
+    q_markers: Query<Entity, With<ViaMarker>>,
+) {
+    if !graph.is_changed() {
+        return;
+    }
+
+    for entity in q_markers.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let terminuses: Vec<_> = q_terminuses.iter().map(|t| t.point).collect();
+    let ends = q_segments
+        .iter()
+        .flat_map(|s| [(s.points.0, s.layer), (s.points.1, s.layer)]);
+
+    for point in via_points(ends, &terminuses) {
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Rectangle {
+                    extents: Vec2::splat(7.0),
+                    ..default()
+                }),
+                transform: Transform::from_translation(point.extend(layer::VIA))
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                ..default()
+            },
+            Fill::color(color::UI_WHITE),
+            Stroke::new(color::BACKGROUND, 1.5),
+            ViaMarker,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vias() {
+        let a = Vec2::new(0.0, 0.0);
+        let b = Vec2::new(48.0, 0.0);
+        let c = Vec2::new(96.0, 0.0);
+
+        // A line that goes under a bridge at b, and carries on at c.
+        let ends = [(a, 1), (b, 1), (b, 2), (c, 2), (c, 2), (a, 2)];
+        assert_eq!(via_points(ends, &[]), vec![a, b]);
+        assert_eq!(via_points(ends, &[a]), vec![b]);
+        assert!(via_points([(a, 1), (a, 1)], &[]).is_empty());
+    }
+}
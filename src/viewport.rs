@@ -0,0 +1,104 @@
+//! Fitting the board to the window. The camera is scaled so that the play area is all
+//! visible above the bottom bar, whatever the size and shape of the window, with the
+//! spare room on the sides or top left showing more of the grid.
+
+use crate::{MainCamera, BOTTOM_BAR_HEIGHT};
+use bevy::{prelude::*, render::camera::ScalingMode, window::WindowResized};
+
+/// The part of the board that levels are built in, which must always be visible.
+pub const PLAY_AREA: Rect = Rect {
+    min: Vec2::new(-640.0, -300.0),
+    max: Vec2::new(640.0, 350.0),
+};
+
+pub struct ViewportPlugin;
+impl Plugin for ViewportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Viewport>();
+        app.add_systems(Update, fit_viewport_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct Viewport {
+    /// Where the camera sits when it isn't zoomed in.
+    pub home: Vec3,
+    /// Logical pixels per unit on the board when the camera isn't zoomed in.
+    pub pixels_per_unit: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            home: Vec3::new(0.0, -10.0, 0.0),
+            pixels_per_unit: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    /// The viewport that fits the play area into a window of `size` with `bar_height`
+    /// of it covered by the bottom bar.
+    fn fit(size: Vec2, bar_height: f32) -> Self {
+        let room = Vec2::new(size.x, (size.y - bar_height).max(1.0));
+        let pixels_per_unit = (room / PLAY_AREA.size()).min_element().max(0.01);
+
+        // Center the play area in the room above the bar.
+        let home = PLAY_AREA.center() - Vec2::Y * bar_height / 2.0 / pixels_per_unit;
+
+        Self {
+            home: home.extend(0.0),
+            pixels_per_unit,
+        }
+    }
+}
+
+fn fit_viewport_system(
+    mut resized: EventReader<WindowResized>,
+    ui_scale: Res<UiScale>,
+    mut viewport: ResMut<Viewport>,
+    q_window: Query<&Window>,
+    q_added: Query<(), Added<MainCamera>>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let resized = resized.read().count() > 0;
+    if !resized && !ui_scale.is_changed() && q_added.is_empty() {
+        return;
+    }
+
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    *viewport = Viewport::fit(window.size(), BOTTOM_BAR_HEIGHT * ui_scale.0);
+
+    for (mut transform, mut projection) in q_camera.iter_mut() {
+        projection.scaling_mode = ScalingMode::WindowSize(viewport.pixels_per_unit);
+
+        // A zoomed in camera goes to the new home when it zooms back out.
+        if projection.scale == 1.0 {
+            transform.translation = viewport.home;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit() {
+        // The window the game was laid out for.
+        let viewport = Viewport::fit(Vec2::new(1280.0, 720.0), 70.0);
+        assert_eq!(viewport.pixels_per_unit, 1.0);
+        assert_eq!(viewport.home, Vec3::new(0.0, -10.0, 0.0));
+
+        // Twice as big, but only as tall.
+        let wide = Viewport::fit(Vec2::new(2560.0, 720.0), 70.0);
+        assert_eq!(wide.pixels_per_unit, 1.0);
+
+        let small = Viewport::fit(Vec2::new(640.0, 720.0), 70.0);
+        assert_eq!(small.pixels_per_unit, 0.5);
+        assert_eq!(small.home, Vec3::new(0.0, -45.0, 0.0));
+    }
+}